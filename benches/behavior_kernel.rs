@@ -0,0 +1,45 @@
+// behavior_kernel.rs
+// Scalar against vector neighbor sums, run with `cargo bench --features simd`
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+#[path = "../src/simd_kernel.rs"]
+mod simd_kernel;
+
+use simd_kernel::{flock_sums_scalar, flock_sums_simd, NeighborLanes};
+
+const MIN_DISTANCE: f32 = 16.0;
+
+// Neighbors scattered around the origin, within a typical visual range
+fn random_lanes(count: usize) -> NeighborLanes {
+    let mut rng = StdRng::seed_from_u64(count as u64);
+    let mut lanes = NeighborLanes::default();
+    for _ in 0..count {
+        lanes.push(
+            rng.gen_range(-32.0..32.0),
+            rng.gen_range(-32.0..32.0),
+            rng.gen_range(-400.0..400.0),
+            rng.gen_range(-400.0..400.0),
+        );
+    }
+    lanes
+}
+
+fn neighbor_sums(c: &mut Criterion) {
+    let mut group = c.benchmark_group("neighbor_sums");
+    for &count in &[8, 32, 128, 512] {
+        let lanes = random_lanes(count);
+        let pos = glam::vec2(1.0, -2.0);
+
+        group.bench_with_input(BenchmarkId::new("scalar", count), &lanes, |b, lanes| {
+            b.iter(|| flock_sums_scalar(black_box(pos), black_box(lanes), MIN_DISTANCE))
+        });
+        group.bench_with_input(BenchmarkId::new("simd", count), &lanes, |b, lanes| {
+            b.iter(|| flock_sums_simd(black_box(pos), black_box(lanes), MIN_DISTANCE))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, neighbor_sums);
+criterion_main!(benches);
@@ -0,0 +1,104 @@
+// simulation.rs
+// Neighbor index, behavior and whole-step timings at a few flock sizes, run
+// with `cargo bench --bench simulation`, against the boids_core library
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use rand::{rngs::StdRng, SeedableRng};
+
+use boids_core::behavior;
+use boids_core::boid::{Boid, VISUAL_RANGE};
+use boids_core::simulation::Simulation;
+use boids_core::spawn::SpawnPattern;
+use boids_core::spatial_grid::SpatialGrid;
+use boids_core::world::World;
+
+const FLOCK_SIZES: [usize; 3] = [1_000, 5_000, 20_000];
+
+// A world with the same density as the default window at 1000 boids
+fn world_for(count: usize) -> World {
+    let scale = (count as f32 / 1_000.0).sqrt();
+    World::new(1200.0 * scale, 900.0 * scale)
+}
+
+fn random_flock(count: usize, world: &World) -> Vec<Boid> {
+    let mut rng = StdRng::seed_from_u64(count as u64);
+    (0..count).map(|_| Boid::new(world.width, world.height, &mut rng)).collect()
+}
+
+fn spatial_grid(c: &mut Criterion) {
+    let mut group = c.benchmark_group("spatial_grid");
+    for &count in &FLOCK_SIZES {
+        let world = world_for(count);
+        let boids = random_flock(count, &world);
+        let mut grid = SpatialGrid::new(world.width, world.height, VISUAL_RANGE);
+
+        group.bench_with_input(BenchmarkId::new("build", count), &boids, |b, boids| {
+            b.iter(|| grid.build(black_box(boids)))
+        });
+
+        grid.build(&boids);
+        group.bench_with_input(BenchmarkId::new("items_near", count), &boids, |b, boids| {
+            b.iter(|| {
+                for boid in boids {
+                    black_box(grid.items_near(boid, VISUAL_RANGE));
+                }
+            })
+        });
+        group.bench_with_input(BenchmarkId::new("query_radius", count), &boids, |b, boids| {
+            b.iter(|| {
+                for boid in boids {
+                    black_box(grid.query_radius(glam::vec2(boid.x, boid.y), VISUAL_RANGE));
+                }
+            })
+        });
+    }
+    group.finish();
+}
+
+fn calculate_behaviors(c: &mut Criterion) {
+    let mut group = c.benchmark_group("calculate_behaviors");
+    let behaviors = behavior::default_behaviors();
+    for &count in &FLOCK_SIZES {
+        let world = world_for(count);
+        let boids = random_flock(count, &world);
+        let mut grid = SpatialGrid::new(world.width, world.height, VISUAL_RANGE);
+        grid.build(&boids);
+        let neighbor_lists: Vec<Vec<usize>> = boids
+            .iter()
+            .map(|boid| {
+                grid.query_radius(glam::vec2(boid.x, boid.y), VISUAL_RANGE)
+                    .into_iter()
+                    .map(|(i, _)| i)
+                    .collect()
+            })
+            .collect();
+
+        group.bench_with_input(BenchmarkId::from_parameter(count), &boids, |b, boids| {
+            b.iter(|| {
+                for (i, (boid, neighbors)) in boids.iter().zip(&neighbor_lists).enumerate() {
+                    let mut boid = *boid;
+                    black_box(boid.calculate_behaviors(i, neighbors, boids, &behaviors, &world));
+                }
+            })
+        });
+    }
+    group.finish();
+}
+
+fn simulation_step(c: &mut Criterion) {
+    let mut group = c.benchmark_group("simulation_step");
+    group.sample_size(20);
+    for &count in &FLOCK_SIZES {
+        let mut sim = Simulation::new(world_for(count), count as u64);
+        sim.respawn(count, SpawnPattern::Uniform);
+        let cursor = glam::vec2(-1000.0, -1000.0);
+
+        group.bench_function(BenchmarkId::from_parameter(count), |b| {
+            b.iter(|| sim.step(1.0 / 60.0, cursor))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, spatial_grid, calculate_behaviors, simulation_step);
+criterion_main!(benches);
@@ -0,0 +1,146 @@
+// ambient.rs
+use crate::boid::{Boid, SPEED_LIMIT};
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use cpal::{FromSample, SizedSample};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use tracing::warn;
+
+// Ambient sound settings
+const DENSE_NEIGHBORS: f32 = 12.0;   // Average neighbor count that counts as fully dense
+const NOISE_LEVEL: f32 = 0.15;       // Wind volume at full speed
+const QUIET_NOISE: f32 = 0.2;        // Fraction of NOISE_LEVEL left when the flock hovers
+const MIN_CUTOFF: f32 = 200.0;       // Hz, the wind is a low rumble when slow...
+const MAX_CUTOFF: f32 = 4000.0;      // ...and hisses at full speed
+const MAX_TICK_RATE: f32 = 60.0;     // Wing flaps per second in a dense flock
+const TICK_LEVEL: f32 = 0.25;
+const TICK_DECAY: f32 = 0.004;       // Seconds for a flap to fade to a third
+const TICK_CUTOFF: f32 = 900.0;      // Hz, flaps are soft thumps rather than clicks
+const SMOOTHING: f32 = 0.05;         // Seconds the sound takes to follow the flock
+
+// A generative soundscape of the flock: filtered noise for the wind, brighter
+// and louder as the boids speed up, with wing-flap ticks that get more frequent
+// where they crowd together. Toggled with <f12>.
+pub struct Ambient {
+    _stream: cpal::Stream, // Playback stops when dropped
+    density: Arc<AtomicU32>, // f32 bits, 0 to 1, read by the audio thread
+    speed: Arc<AtomicU32>,   // f32 bits, 0 to 1
+}
+
+impl Ambient {
+    pub fn open() -> Result<Ambient, String> {
+        let device = cpal::default_host().default_output_device().ok_or("no audio output device")?;
+        let supported = device.default_output_config().map_err(|err| err.to_string())?;
+        let config: cpal::StreamConfig = supported.config();
+        let density = Arc::new(AtomicU32::new(0.0f32.to_bits()));
+        let speed = Arc::new(AtomicU32::new(0.0f32.to_bits()));
+
+        let levels = (density.clone(), speed.clone());
+        let stream = match supported.sample_format() {
+            cpal::SampleFormat::F32 => play::<f32>(&device, &config, levels),
+            cpal::SampleFormat::I16 => play::<i16>(&device, &config, levels),
+            cpal::SampleFormat::U16 => play::<u16>(&device, &config, levels),
+            format => return Err(format!("unsupported sample format {}", format)),
+        }
+        .map_err(|err| err.to_string())?;
+        stream.play().map_err(|err| err.to_string())?;
+
+        Ok(Ambient { _stream: stream, density, speed })
+    }
+
+    // Measure the flock after an update and hand the levels to the audio thread
+    pub fn update(&self, boids: &[Boid], neighbor_lists: &[Vec<usize>]) {
+        let (density, speed) = if boids.is_empty() {
+            (0.0, 0.0)
+        } else {
+            let neighbors = neighbor_lists.iter().map(Vec::len).sum::<usize>() as f32 / boids.len() as f32;
+            let speed = boids.iter().map(|boid| boid.dx.hypot(boid.dy)).sum::<f32>() / boids.len() as f32;
+            (neighbors / DENSE_NEIGHBORS, speed / SPEED_LIMIT)
+        };
+        self.density.store(density.clamp(0.0, 1.0).to_bits(), Ordering::Relaxed);
+        self.speed.store(speed.clamp(0.0, 1.0).to_bits(), Ordering::Relaxed);
+    }
+}
+
+// The synth running on the audio thread
+struct Voice {
+    sample_rate: f32,
+    seed: u32,
+    density: f32, // Smoothed towards the shared levels every sample
+    speed: f32,
+    wind: f32,   // Lowpass state of the wind noise
+    flap: f32,   // Envelope of the current wing flap
+    thump: f32,  // Lowpass state of the flap noise
+}
+
+impl Voice {
+    // White noise from -1 to 1, an xorshift so the audio thread never locks
+    fn noise(&mut self) -> f32 {
+        self.seed ^= self.seed << 13;
+        self.seed ^= self.seed >> 17;
+        self.seed ^= self.seed << 5;
+        self.seed as f32 / u32::MAX as f32 * 2.0 - 1.0
+    }
+
+    fn sample(&mut self, density: f32, speed: f32) -> f32 {
+        let follow = 1.0 - (-1.0 / (SMOOTHING * self.sample_rate)).exp();
+        self.density += (density - self.density) * follow;
+        self.speed += (speed - self.speed) * follow;
+
+        let cutoff = MIN_CUTOFF + (MAX_CUTOFF - MIN_CUTOFF) * self.speed;
+        let noise = self.noise() * NOISE_LEVEL * (QUIET_NOISE + (1.0 - QUIET_NOISE) * self.speed);
+        self.wind += (noise - self.wind) * lowpass(cutoff, self.sample_rate);
+
+        // Flaps arrive at random, more often in a dense flock
+        let chance = MAX_TICK_RATE * self.density / self.sample_rate;
+        if (self.noise() * 0.5 + 0.5) < chance {
+            self.flap = 0.5 + 0.25 * (self.noise() + 1.0);
+        }
+        let burst = self.noise() * self.flap;
+        self.flap *= (-1.0 / (TICK_DECAY * self.sample_rate)).exp();
+        self.thump += (burst - self.thump) * lowpass(TICK_CUTOFF, self.sample_rate);
+
+        // Soft clip so a dense, fast flock can't distort
+        (self.wind + TICK_LEVEL * self.thump).tanh()
+    }
+}
+
+// Coefficient of a one-pole lowpass
+fn lowpass(cutoff: f32, sample_rate: f32) -> f32 {
+    1.0 - (-2.0 * std::f32::consts::PI * cutoff / sample_rate).exp()
+}
+
+fn play<T>(
+    device: &cpal::Device,
+    config: &cpal::StreamConfig,
+    (density, speed): (Arc<AtomicU32>, Arc<AtomicU32>),
+) -> Result<cpal::Stream, cpal::BuildStreamError>
+where
+    T: SizedSample + FromSample<f32>,
+{
+    let channels = config.channels.max(1) as usize;
+    let mut voice = Voice {
+        sample_rate: config.sample_rate.0 as f32,
+        seed: 0x9E37_79B9,
+        density: 0.0,
+        speed: 0.0,
+        wind: 0.0,
+        flap: 0.0,
+        thump: 0.0,
+    };
+    device.build_output_stream(
+        config,
+        move |data: &mut [T], _: &cpal::OutputCallbackInfo| {
+            let density = f32::from_bits(density.load(Ordering::Relaxed));
+            let speed = f32::from_bits(speed.load(Ordering::Relaxed));
+            for frame in data.chunks_mut(channels) {
+                let sample = T::from_sample(voice.sample(density, speed));
+                for channel in frame {
+                    *channel = sample;
+                }
+            }
+        },
+        |err| warn!("Ambient sound error: {}", err),
+        None,
+    )
+}
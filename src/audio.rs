@@ -0,0 +1,135 @@
+// audio.rs
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use cpal::{FromSample, Sample, SizedSample};
+use rustfft::num_complex::Complex;
+use rustfft::{Fft, FftPlanner};
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use tracing::warn;
+
+// Audio-reactive settings
+const FFT_SIZE: usize = 2048;           // Samples analysed per frame, about 45ms at 44.1kHz
+const BASS_MAX_HZ: f32 = 150.0;         // Kick drums and bass lines sit below this
+const SMOOTHING_RATE: f32 = 10.0;       // Per second, how fast the levels follow the music
+const PEAK_DECAY: f32 = 0.1;            // Fraction of the running peak lost per second
+const MIN_PEAK: f32 = 1e-4;             // Silence stays at zero instead of amplifying noise
+const BASS_SEPARATION_GAIN: f32 = 3.0;  // Separation is multiplied by up to 1 + this on the beat
+const QUIET_SPEED: f32 = 0.4;           // Speed limit multiplier in silence...
+const LOUD_SPEED: f32 = 1.6;            // ...and at full volume
+
+// Smoothed levels of the input, both 0 to 1 relative to the recent peaks
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AudioLevels {
+    pub bass: f32,
+    pub loudness: f32,
+}
+
+impl AudioLevels {
+    // Multiplier on the separation weight
+    pub fn separation_scale(self) -> f32 {
+        1.0 + BASS_SEPARATION_GAIN * self.bass
+    }
+
+    // Multiplier on the speed limit
+    pub fn speed_scale(self) -> f32 {
+        QUIET_SPEED + (LOUD_SPEED - QUIET_SPEED) * self.loudness
+    }
+}
+
+// Captures the default input device, a microphone or line-in, and measures
+// bass energy and loudness once per frame. Toggled with <f10>.
+pub struct AudioInput {
+    _stream: cpal::Stream, // Capture stops when dropped
+    samples: Arc<Mutex<VecDeque<f32>>>, // Newest FFT_SIZE mono samples
+    sample_rate: f32,
+    fft: Arc<dyn Fft<f32>>,
+    window: Vec<f32>, // Hann window, so the frame edges don't smear into the bass bins
+    peaks: AudioLevels, // Running maxima the raw levels are divided by
+    levels: AudioLevels,
+}
+
+impl AudioInput {
+    pub fn open() -> Result<AudioInput, String> {
+        let device = cpal::default_host().default_input_device().ok_or("no audio input device")?;
+        let supported = device.default_input_config().map_err(|err| err.to_string())?;
+        let config: cpal::StreamConfig = supported.config();
+        let samples = Arc::new(Mutex::new(VecDeque::with_capacity(FFT_SIZE)));
+
+        let stream = match supported.sample_format() {
+            cpal::SampleFormat::F32 => capture::<f32>(&device, &config, samples.clone()),
+            cpal::SampleFormat::I16 => capture::<i16>(&device, &config, samples.clone()),
+            cpal::SampleFormat::U16 => capture::<u16>(&device, &config, samples.clone()),
+            format => return Err(format!("unsupported sample format {}", format)),
+        }
+        .map_err(|err| err.to_string())?;
+        stream.play().map_err(|err| err.to_string())?;
+
+        let window = (0..FFT_SIZE)
+            .map(|i| 0.5 - 0.5 * (2.0 * std::f32::consts::PI * i as f32 / FFT_SIZE as f32).cos())
+            .collect();
+        Ok(AudioInput {
+            _stream: stream,
+            samples,
+            sample_rate: config.sample_rate.0 as f32,
+            fft: FftPlanner::new().plan_fft_forward(FFT_SIZE),
+            window,
+            peaks: AudioLevels { bass: MIN_PEAK, loudness: MIN_PEAK },
+            levels: AudioLevels::default(),
+        })
+    }
+
+    // Analyse the latest samples and move the smoothed levels towards them
+    pub fn update(&mut self, dt: f32) -> AudioLevels {
+        let samples: Vec<f32> = match self.samples.lock() {
+            Ok(samples) if samples.len() == FFT_SIZE => samples.iter().copied().collect(),
+            _ => return self.levels, // Still filling up
+        };
+
+        let loudness = (samples.iter().map(|sample| sample * sample).sum::<f32>() / FFT_SIZE as f32).sqrt();
+        let mut spectrum: Vec<Complex<f32>> =
+            samples.iter().zip(&self.window).map(|(&sample, &weight)| Complex::new(sample * weight, 0.0)).collect();
+        self.fft.process(&mut spectrum);
+        // Skip the DC bin, a microphone offset isn't bass
+        let bass_bins = ((BASS_MAX_HZ * FFT_SIZE as f32 / self.sample_rate) as usize).max(2);
+        let bass = spectrum[1..bass_bins].iter().map(|bin| bin.norm()).sum::<f32>();
+
+        // Normalize by slowly decaying peaks, so any input level fills the range
+        let decay = (1.0 - PEAK_DECAY * dt).max(0.0);
+        self.peaks.bass = (self.peaks.bass * decay).max(bass).max(MIN_PEAK);
+        self.peaks.loudness = (self.peaks.loudness * decay).max(loudness).max(MIN_PEAK);
+
+        let follow = 1.0 - (-SMOOTHING_RATE * dt).exp();
+        self.levels.bass += (bass / self.peaks.bass - self.levels.bass) * follow;
+        self.levels.loudness += (loudness / self.peaks.loudness - self.levels.loudness) * follow;
+        self.levels
+    }
+}
+
+// Mix every frame down to mono and keep the newest FFT_SIZE samples
+fn capture<T>(
+    device: &cpal::Device,
+    config: &cpal::StreamConfig,
+    samples: Arc<Mutex<VecDeque<f32>>>,
+) -> Result<cpal::Stream, cpal::BuildStreamError>
+where
+    T: SizedSample,
+    f32: FromSample<T>,
+{
+    let channels = config.channels.max(1) as usize;
+    device.build_input_stream(
+        config,
+        move |data: &[T], _: &cpal::InputCallbackInfo| {
+            if let Ok(mut samples) = samples.lock() {
+                for frame in data.chunks(channels) {
+                    let mono = frame.iter().map(|&sample| f32::from_sample(sample)).sum::<f32>() / channels as f32;
+                    if samples.len() == FFT_SIZE {
+                        samples.pop_front();
+                    }
+                    samples.push_back(mono);
+                }
+            }
+        },
+        |err| warn!("Audio capture error: {}", err),
+        None,
+    )
+}
@@ -0,0 +1,78 @@
+// autoscale.rs
+use tracing::info;
+
+// Auto-scaling settings
+pub const AUTOSCALE_STEP: usize = 100; // Boids added or removed per adjustment
+const MEASURE_SECONDS: f32 = 2.0;      // FPS is averaged over this long before adjusting
+const GROW_MARGIN: f32 = 1.1;          // Only add boids when this far above the target
+const SHRINK_MARGIN: f32 = 0.95;       // Remove boids when below this fraction of the target
+const SETTLE_REVERSALS: usize = 4;     // Direction changes before the count counts as settled
+const MIN_BOIDS: usize = 10;
+
+// Adds and removes boids to keep the frame rate near a target, which makes a
+// quick hardware benchmark: the count it settles at is what the machine can handle
+pub struct AutoScaler {
+    target_fps: f32,
+    step: usize,
+    elapsed: f32,
+    frames: usize,
+    last_direction: i8,            // 1 growing, -1 shrinking, 0 not yet adjusted
+    reversals: Vec<usize>,         // Boid counts at which the direction flipped
+    pub equilibrium: Option<usize>,
+}
+
+impl AutoScaler {
+    pub fn new(target_fps: u32, step: usize) -> Self {
+        AutoScaler {
+            target_fps: target_fps as f32,
+            step: step.max(1),
+            elapsed: 0.0,
+            frames: 0,
+            last_direction: 0,
+            reversals: Vec::new(),
+            equilibrium: None,
+        }
+    }
+
+    // Feed one frame of `dt` real seconds. Returns the new boid count when an
+    // adjustment is due.
+    pub fn update(&mut self, dt: f32, boid_count: usize) -> Option<usize> {
+        self.elapsed += dt;
+        self.frames += 1;
+        if self.elapsed < MEASURE_SECONDS {
+            return None;
+        }
+
+        let fps = self.frames as f32 / self.elapsed;
+        self.elapsed = 0.0;
+        self.frames = 0;
+
+        let direction = if fps > self.target_fps * GROW_MARGIN {
+            1
+        } else if fps < self.target_fps * SHRINK_MARGIN {
+            -1
+        } else {
+            // Inside the band, nothing to do
+            return None;
+        };
+
+        if self.last_direction != 0 && direction != self.last_direction {
+            self.reversals.push(boid_count);
+            if self.reversals.len() >= SETTLE_REVERSALS {
+                let settled = self.reversals.iter().sum::<usize>() / self.reversals.len();
+                if self.equilibrium != Some(settled) {
+                    info!("Auto-scale equilibrium: ~{} boids at {} FPS", settled, self.target_fps);
+                }
+                self.equilibrium = Some(settled);
+                self.reversals.remove(0);
+            }
+        }
+        self.last_direction = direction;
+
+        Some(if direction > 0 {
+            boid_count + self.step
+        } else {
+            boid_count.saturating_sub(self.step).max(MIN_BOIDS)
+        })
+    }
+}
@@ -0,0 +1,132 @@
+// barnes_hut.rs
+use crate::boid::{Boid, VISUAL_RANGE};
+
+// Long-range attraction settings
+const THETA: f32 = 0.7;                        // Nodes smaller than this fraction of their distance count as one mass
+const LEAF_SIZE: f32 = VISUAL_RANGE * 2.0;     // Nodes this small aren't split further
+const NEAR_RANGE: f32 = VISUAL_RANGE * 4.0;    // Closer mass is the local rules' business and is ignored
+
+#[derive(Clone)]
+struct Node {
+    center: glam::Vec2, // Middle of the square
+    size: f32,          // Side length
+    mass: f32,          // Boids inside
+    mass_center: glam::Vec2,
+    children: Option<usize>, // Index of the first of four consecutive children
+}
+
+// Weak attraction between distant sub-flocks so separated groups drift back
+// together, set with the long_range parameter. A Barnes-Hut quadtree sums
+// whole distant groups as single masses, so it costs O(n log n) rather than
+// comparing every pair. It is much coarser than the neighbor index: it only
+// splits down to LEAF_SIZE and ignores anything within NEAR_RANGE.
+#[derive(Clone)]
+pub struct BarnesHut {
+    pub strength: f32, // Velocity change per step at most, 0 turns it off
+    nodes: Vec<Node>,
+    total_mass: f32,
+}
+
+impl BarnesHut {
+    pub fn new() -> Self {
+        BarnesHut {
+            strength: 0.0,
+            nodes: Vec::new(),
+            total_mass: 0.0,
+        }
+    }
+
+    pub fn active(&self) -> bool {
+        self.strength > 0.0
+    }
+
+    // Rebuild the tree around the current positions
+    pub fn build(&mut self, boids: &[Boid]) {
+        self.nodes.clear();
+        self.total_mass = boids.len() as f32;
+        if boids.is_empty() {
+            return;
+        }
+
+        let mut positions: Vec<glam::Vec2> = boids.iter().map(|boid| glam::vec2(boid.x, boid.y)).collect();
+        let (min, max) = positions
+            .iter()
+            .fold((positions[0], positions[0]), |(min, max), &pos| (min.min(pos), max.max(pos)));
+        let size = (max - min).max_element().max(LEAF_SIZE);
+        self.nodes.push(Node {
+            center: (min + max) / 2.0,
+            size,
+            mass: 0.0,
+            mass_center: glam::Vec2::ZERO,
+            children: None,
+        });
+        self.fill(0, &mut positions);
+    }
+
+    // Sum up `positions` into the node, splitting it while it is large
+    fn fill(&mut self, node: usize, positions: &mut [glam::Vec2]) {
+        let mass = positions.len() as f32;
+        let mass_center = positions.iter().fold(glam::Vec2::ZERO, |sum, &pos| sum + pos) / mass.max(1.0);
+        self.nodes[node].mass = mass;
+        self.nodes[node].mass_center = mass_center;
+        let (center, size) = (self.nodes[node].center, self.nodes[node].size);
+        if positions.len() <= 1 || size <= LEAF_SIZE {
+            return;
+        }
+
+        // Quadrants ordered NW, NE, SW, SE, as in quadtree.rs
+        let quadrant = |pos: &glam::Vec2| (pos.y >= center.y) as usize * 2 + (pos.x >= center.x) as usize;
+        positions.sort_unstable_by_key(quadrant);
+
+        let first_child = self.nodes.len();
+        self.nodes[node].children = Some(first_child);
+        for i in 0..4 {
+            let offset = glam::vec2(if i % 2 == 0 { -0.25 } else { 0.25 }, if i < 2 { -0.25 } else { 0.25 });
+            self.nodes.push(Node {
+                center: center + offset * size,
+                size: size / 2.0,
+                mass: 0.0,
+                mass_center: glam::Vec2::ZERO,
+                children: None,
+            });
+        }
+        let mut rest = positions;
+        for i in 0..4 {
+            let count = rest.iter().take_while(|pos| quadrant(pos) == i).count();
+            let (inside, after) = std::mem::take(&mut rest).split_at_mut(count);
+            if !inside.is_empty() {
+                self.fill(first_child + i, inside);
+            }
+            rest = after;
+        }
+    }
+
+    // Pull towards the distant mass, weighted by closeness and at most `strength`
+    pub fn force(&self, boid: &Boid) -> glam::Vec2 {
+        if self.nodes.is_empty() {
+            return glam::Vec2::ZERO;
+        }
+
+        let pos = glam::vec2(boid.x, boid.y);
+        let mut pull = glam::Vec2::ZERO;
+        let mut stack = vec![0];
+        while let Some(idx) = stack.pop() {
+            let node = &self.nodes[idx];
+            if node.mass == 0.0 {
+                continue;
+            }
+            let offset = node.mass_center - pos;
+            let distance = offset.length();
+            match node.children {
+                // Too close for the node to count as one mass, look inside it
+                Some(first_child) if node.size >= THETA * distance => stack.extend(first_child..first_child + 4),
+                _ => {
+                    if distance > NEAR_RANGE {
+                        pull += offset / distance * node.mass * NEAR_RANGE / distance;
+                    }
+                }
+            }
+        }
+        pull / self.total_mass * self.strength
+    }
+}
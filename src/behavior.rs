@@ -0,0 +1,144 @@
+// behavior.rs
+use crate::boid::{Boid, AVOID_FACTOR, CENTERING_FACTOR, MATCHING_FACTOR, MIN_DISTANCE};
+use crate::math::{self, Real, Vector};
+use crate::world::World;
+use std::sync::Arc;
+
+// Number of behaviors whose contributions are kept for the inspector and
+// debug arrows. Any further registered behaviors still steer, they just
+// aren't reported.
+pub const MAX_TRACKED_BEHAVIORS: usize = 8;
+
+// A steering rule. `neighbors` indexes into `boids` and only contains the
+// other boids within visual range; the returned force is scaled by the
+// behavior's weight before being applied. Behaviors are shared between
+// copies of a simulation, so they can't hold mutable state.
+pub trait SteeringBehavior: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    fn force(&self, boid: &Boid, neighbors: &[usize], boids: &[Boid], world: &World) -> glam::Vec2;
+}
+
+// A registered behavior together with how strongly it steers
+#[derive(Clone)]
+pub struct WeightedBehavior {
+    pub behavior: Arc<dyn SteeringBehavior>,
+    pub weight: f32,
+}
+
+impl WeightedBehavior {
+    pub fn new(behavior: impl SteeringBehavior + 'static, weight: f32) -> Self {
+        WeightedBehavior {
+            behavior: Arc::new(behavior),
+            weight,
+        }
+    }
+}
+
+// Velocity change contributed by each registered behavior during one update,
+// in registration order
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SteeringForces {
+    pub contributions: [glam::Vec2; MAX_TRACKED_BEHAVIORS],
+}
+
+// The classic three flocking rules
+pub fn default_behaviors() -> Vec<WeightedBehavior> {
+    vec![
+        WeightedBehavior::new(Separation, AVOID_FACTOR),
+        WeightedBehavior::new(Cohesion, CENTERING_FACTOR),
+        WeightedBehavior::new(Alignment, MATCHING_FACTOR),
+    ]
+}
+
+// Change the weight of every registered behavior with the given name
+pub fn set_weight(behaviors: &mut [WeightedBehavior], name: &str, weight: f32) {
+    for weighted in behaviors.iter_mut().filter(|weighted| weighted.behavior.name() == name) {
+        weighted.weight = weight;
+    }
+}
+
+// Weight of the first registered behavior with the given name
+pub fn weight(behaviors: &[WeightedBehavior], name: &str) -> Option<f32> {
+    behaviors.iter().find(|weighted| weighted.behavior.name() == name).map(|weighted| weighted.weight)
+}
+
+// Weights of the classic three rules, for code that runs them without the
+// behavior objects, like the GPU kernel
+#[cfg(feature = "gpu")]
+#[derive(Debug, Clone, Copy)]
+pub struct Weights {
+    pub separation: f32,
+    pub cohesion: f32,
+    pub alignment: f32,
+}
+
+#[cfg(feature = "gpu")]
+impl Weights {
+    // Pick up the weights of the behaviors registered under the classic names
+    pub fn from_behaviors(behaviors: &[WeightedBehavior]) -> Weights {
+        Weights {
+            separation: weight(behaviors, "separation").unwrap_or(AVOID_FACTOR),
+            cohesion: weight(behaviors, "cohesion").unwrap_or(CENTERING_FACTOR),
+            alignment: weight(behaviors, "alignment").unwrap_or(MATCHING_FACTOR),
+        }
+    }
+}
+
+// Steer away from boids that are too close
+pub struct Separation;
+
+impl SteeringBehavior for Separation {
+    fn name(&self) -> &'static str {
+        "separation"
+    }
+
+    fn force(&self, boid: &Boid, neighbors: &[usize], boids: &[Boid], _world: &World) -> glam::Vec2 {
+        let pos = boid.position_real();
+        let min_distance = math::real(MIN_DISTANCE);
+        let mut avoid = Vector::ZERO;
+        for &idx in neighbors {
+            let away = pos - boids[idx].position_real();
+            if away.length_squared() < min_distance * min_distance {
+                avoid += away;
+            }
+        }
+        math::narrow(avoid)
+    }
+}
+
+// Steer towards the center of nearby boids
+pub struct Cohesion;
+
+impl SteeringBehavior for Cohesion {
+    fn name(&self) -> &'static str {
+        "cohesion"
+    }
+
+    fn force(&self, boid: &Boid, neighbors: &[usize], boids: &[Boid], _world: &World) -> glam::Vec2 {
+        if neighbors.is_empty() {
+            return glam::Vec2::ZERO;
+        }
+
+        let sum = neighbors.iter().fold(Vector::ZERO, |sum, &idx| sum + boids[idx].position_real());
+        math::narrow(sum / neighbors.len() as Real - boid.position_real())
+    }
+}
+
+// Steer towards the average velocity of nearby boids
+pub struct Alignment;
+
+impl SteeringBehavior for Alignment {
+    fn name(&self) -> &'static str {
+        "alignment"
+    }
+
+    fn force(&self, boid: &Boid, neighbors: &[usize], boids: &[Boid], _world: &World) -> glam::Vec2 {
+        if neighbors.is_empty() {
+            return glam::Vec2::ZERO;
+        }
+
+        let sum = neighbors.iter().fold(Vector::ZERO, |sum, &idx| sum + boids[idx].velocity_real());
+        math::narrow(sum / neighbors.len() as Real - boid.velocity_real())
+    }
+}
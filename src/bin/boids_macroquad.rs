@@ -0,0 +1,100 @@
+// boids_macroquad.rs
+// The flock drawn with macroquad, for machines where ggez's native
+// dependencies won't build:
+//
+//     cargo run --release --no-default-features --features render-macroquad --bin boids-macroquad
+//
+// The flock is stepped by the same Simulation as the ggez visualizer.
+use boids_core::render::{self, Renderer};
+use boids_core::simulation::Simulation;
+use boids_core::spawn::SpawnPattern;
+use boids_core::world::World;
+use macroquad::color::Color;
+use macroquad::input::{is_key_pressed, mouse_position, KeyCode};
+use macroquad::shapes::draw_triangle;
+use macroquad::window::{clear_background, next_frame, screen_height, screen_width};
+
+const NUM_BOIDS: usize = 1000;
+const BOID_STEP: usize = 100;  // Boids added or removed per key press
+const WORLD_WIDTH: f32 = 1600.0;
+const WORLD_HEIGHT: f32 = 1200.0;
+const BOID_SIZE: f32 = 10.0;   // Pixels from nose to tail at a scale of 1
+const MAX_TICK: f32 = 0.1;     // Longest step after a stall, seconds
+
+// Draws with macroquad's immediate mode shapes, scaling the world to fit the window
+struct MacroquadRenderer {
+    scale: f32,
+    offset: glam::Vec2,
+}
+
+impl MacroquadRenderer {
+    fn to_screen(&self, pos: glam::Vec2) -> macroquad::math::Vec2 {
+        let screen = self.offset + pos * self.scale;
+        macroquad::math::vec2(screen.x, screen.y)
+    }
+
+    fn to_world(&self, x: f32, y: f32) -> glam::Vec2 {
+        (glam::vec2(x, y) - self.offset) / self.scale
+    }
+}
+
+impl Renderer for MacroquadRenderer {
+    fn begin_frame(&mut self, world: &World) {
+        self.scale = (screen_width() / world.width).min(screen_height() / world.height);
+        self.offset = (glam::vec2(screen_width(), screen_height()) - glam::vec2(world.width, world.height) * self.scale) / 2.0;
+        clear_background(Color::new(0.05, 0.05, 0.1, 1.0));
+    }
+
+    fn draw_boid(&mut self, pos: glam::Vec2, heading: f32, color: [f32; 4]) {
+        let forward = glam::vec2(heading.cos(), heading.sin()) * BOID_SIZE / 2.0;
+        let side = forward.perp() * 0.5;
+        let nose = self.to_screen(pos + forward);
+        let left = self.to_screen(pos - forward + side);
+        let right = self.to_screen(pos - forward - side);
+        draw_triangle(nose, left, right, Color::new(color[0], color[1], color[2], 0.9));
+    }
+
+    fn draw_status(&mut self, text: &str) {
+        macroquad::text::draw_text(text, 10.0, 24.0, 24.0, Color::new(1.0, 1.0, 1.0, 0.8));
+    }
+
+    fn end_frame(&mut self) {}
+}
+
+#[macroquad::main("Boids")]
+async fn main() {
+    let mut sim = Simulation::new(World::new(WORLD_WIDTH, WORLD_HEIGHT), rand::random());
+    sim.respawn(NUM_BOIDS, SpawnPattern::Uniform);
+    let mut renderer = MacroquadRenderer { scale: 1.0, offset: glam::Vec2::ZERO };
+    let mut paused = false;
+
+    loop {
+        if is_key_pressed(KeyCode::Escape) || is_key_pressed(KeyCode::Q) {
+            break;
+        }
+        if is_key_pressed(KeyCode::Space) {
+            paused = !paused;
+        }
+        if is_key_pressed(KeyCode::Up) {
+            sim.resize_flock(sim.boids.len() + BOID_STEP);
+        }
+        if is_key_pressed(KeyCode::Down) {
+            sim.resize_flock(sim.boids.len().saturating_sub(BOID_STEP));
+        }
+
+        if !paused {
+            let (x, y) = mouse_position();
+            let cursor = renderer.to_world(x, y);
+            let tick = macroquad::time::get_frame_time().min(MAX_TICK);
+            sim.step(tick, cursor);
+        }
+
+        let status = format!(
+            "Boids: {}{} | <space> pause | <up>/<down> boids | <q> quit",
+            sim.boids.len(),
+            if paused { " (paused)" } else { "" }
+        );
+        render::draw_flock(&mut renderer, &sim.boids, &sim.world, &status);
+        next_frame().await;
+    }
+}
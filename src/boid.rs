@@ -1,107 +1,325 @@
-use ggez::mint;
+use crate::behavior::{SteeringForces, WeightedBehavior};
+use crate::math::{self, Vector};
+#[cfg(feature = "simd")]
+use crate::simd_kernel::{self, NeighborLanes};
+use crate::spatial_grid::Position2D;
+use crate::world::{Boundary, BoundaryMode, World};
+use rand::Rng;
+use std::f32::consts::PI;
 
 // Algorithm constants - exposed for easy tuning
 pub const SPEED_LIMIT: f32 = 400.0; // Pixels per second
 pub const VISUAL_RANGE: f32 = 32.0; // Pixels
 pub const MIN_DISTANCE: f32 = 16.0; // Pixels
-pub const AVOID_FACTOR: f32 = 0.5;
+pub const AVOID_FACTOR: f32 = 0.5;      // Default behavior weights, see behavior.rs
 pub const CENTERING_FACTOR: f32 = 0.05;
 pub const MATCHING_FACTOR: f32 = 0.1;
 pub const TURN_FACTOR: f32 = 16.0;
 pub const EDGE_BUFFER: f32 = 40.0;
+pub const MAX_FORCE: f32 = 60.0; // Max velocity change per step from all steering combined
+pub const MAX_TURN_RATE: f32 = 2.0 * PI; // Radians per second
+pub const WANDER_STRENGTH: f32 = 3.0;    // Velocity change per step from wandering
+pub const WANDER_JITTER: f32 = 0.3;      // Max change of the wander angle per step, radians
+pub const NUM_SPECIES: u8 = 3;
+pub const LEADER_SPEED_FACTOR: f32 = 1.25; // Leaders fly a little faster than the flock
+pub const WINGBEAT_LENGTH: f32 = 48.0;     // Pixels flown per cycle of an animated skin
+pub const TIRED_ENERGY: f32 = 0.3;         // Below this energy boids start slowing down
+pub const TIRED_SPEED: f32 = 0.4;          // Fraction of the speed limit left when exhausted
+pub const TRAIT_VARIANCE: f32 = 0.2;       // Default spread of per-boid traits, as a fraction either way
 
-#[derive(Debug, Clone, Copy)]
+// Infection state under the epidemic model, see epidemic.rs
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Health {
+    Susceptible,
+    Infected(f32), // Seconds until recovery
+    Recovered,
+}
+
+// Individual variation, as multipliers on the flock-wide settings. Boid::new
+// leaves them all at 1; Boid::vary_traits samples them.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Traits {
+    pub speed: f32,      // Speed limit
+    pub range: f32,      // Visual range
+    pub separation: f32, // Behavior weights
+    pub cohesion: f32,
+    pub alignment: f32,
+}
+
+impl Default for Traits {
+    fn default() -> Self {
+        Traits {
+            speed: 1.0,
+            range: 1.0,
+            separation: 1.0,
+            cohesion: 1.0,
+            alignment: 1.0,
+        }
+    }
+}
+
+impl Traits {
+    // Multiplier on the weight of the named behavior, 1 for those without a trait
+    pub fn weight(&self, behavior: &str) -> f32 {
+        match behavior {
+            "separation" => self.separation,
+            "cohesion" => self.cohesion,
+            "alignment" => self.alignment,
+            _ => 1.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Boid {
     pub x: f32,
     pub y: f32,
     pub dx: f32,
     pub dy: f32,
-    pub color: [f32; 4],
+    pub ax: f32,               // Steering accumulated since the last apply_acceleration
+    pub ay: f32,
+    pub max_force: f32,
+    pub heading: f32,          // Direction of travel in radians, as of the last update
+    pub max_turn_rate: f32,
+    pub wander_angle: f32,     // Offset from the heading the boid is drifting towards
+    pub color: [f32; 4],       // Random base color, see ColorMode for the others
+    pub species: u8,
+    pub neighbor_count: u32,   // Boids within visual range on the last update
+    pub leader: bool,
+    pub flock: u32,            // Connected group this boid belonged to when last labeled
+    pub wing_phase: f32,       // 0..1 through the skin animation, advances with distance flown
+    pub energy: f32,           // 0..1, only drained while the energy model is on, see energy.rs
+    pub health: Health,
+    pub traits: Traits,
+    pub fitness: f32,          // Score this generation under the evolution mode, see evolution.rs
+    #[cfg(feature = "deterministic")]
+    pub precise: glam::DVec2,  // Position as integrated in f64, x and y are it rounded
+    #[cfg(feature = "deterministic")]
+    pub precise_velocity: glam::DVec2, // Likewise for dx and dy
 }
 
 impl Boid {
-    pub fn new(win_width: f32, win_height: f32) -> Boid {
+    pub fn new(world_width: f32, world_height: f32, rng: &mut impl Rng) -> Boid {
+        let dx = (rng.gen::<f32>() - 0.5) * SPEED_LIMIT;
+        let dy = (rng.gen::<f32>() - 0.5) * SPEED_LIMIT;
+        
         Boid {
-            x: (rand::random::<f32>() * win_width / 2.0 + win_width / 4.0),
-            y: (rand::random::<f32>() * win_height / 2.0 + win_height / 4.0),
-            dx: (rand::random::<f32>() - 0.5) * SPEED_LIMIT,
-            dy: (rand::random::<f32>() - 0.5) * SPEED_LIMIT,
+            x: (rng.gen::<f32>() * world_width / 2.0 + world_width / 4.0),
+            y: (rng.gen::<f32>() * world_height / 2.0 + world_height / 4.0),
+            dx,
+            dy,
+            ax: 0.0,
+            ay: 0.0,
+            max_force: MAX_FORCE,
+            heading: math::atan2(dy, dx),
+            max_turn_rate: MAX_TURN_RATE,
+            wander_angle: 0.0,
             color: [
                 //rgb
-                (rand::random::<f32>() * 128.0 + 128.0) / 255.0,
-                (rand::random::<f32>() * 128.0 + 128.0) / 255.0,
-                (rand::random::<f32>() * 128.0 + 128.0) / 255.0,
+                (rng.gen::<f32>() * 128.0 + 128.0) / 255.0,
+                (rng.gen::<f32>() * 128.0 + 128.0) / 255.0,
+                (rng.gen::<f32>() * 128.0 + 128.0) / 255.0,
                 0.5,
             ],
+            species: rng.gen_range(0..NUM_SPECIES),
+            neighbor_count: 0,
+            leader: false,
+            flock: 0,
+            wing_phase: rng.gen(),
+            energy: 1.0,
+            health: Health::Susceptible,
+            traits: Traits::default(),
+            fitness: 0.0,
+            #[cfg(feature = "deterministic")]
+            precise: glam::DVec2::ZERO,
+            #[cfg(feature = "deterministic")]
+            precise_velocity: glam::DVec2::ZERO,
         }
     }
 
-    // Combined behavior calculation - reduces redundant distance calculations
-    // and neighbor finding operations
-    pub fn calculate_behaviors(&mut self, neighbor_indices: &[usize], boids: &[Boid]) {
-        // Initialize accumulators
-        let mut avoid_x = 0.0;
-        let mut avoid_y = 0.0;
-        
-        let mut center_x = 0.0;
-        let mut center_y = 0.0;
-        
-        let mut avg_dx = 0.0;
-        let mut avg_dy = 0.0;
-        
-        let mut num_neighbors = 0.0;
-        let mut num_close = 0;
+    // Give this boid its own traits, each uniform within `variance` either
+    // side of 1. No variance resets them without drawing from `rng`; too
+    // much is capped so no trait reaches zero.
+    pub fn vary_traits(&mut self, variance: f32, rng: &mut impl Rng) {
+        if variance <= 0.0 {
+            self.traits = Traits::default();
+            return;
+        }
+        let variance = variance.min(0.9);
+        let mut sample = || 1.0 + rng.gen_range(-variance..=variance);
+        self.traits = Traits {
+            speed: sample(),
+            range: sample(),
+            separation: sample(),
+            cohesion: sample(),
+            alignment: sample(),
+        };
+    }
+
+    // Add a steering force to this tick's acceleration
+    #[inline]
+    pub fn apply_force(&mut self, force: glam::Vec2) {
+        self.ax += force.x;
+        self.ay += force.y;
+    }
+
+    // Position in the precision the motion is integrated in. With the
+    // deterministic feature that is the f64 position kept alongside x and y,
+    // unless something has moved the boid since (wrapping, collisions,
+    // rewinding), in which case it restarts from where the boid is.
+    #[cfg(feature = "deterministic")]
+    pub fn position_real(&self) -> Vector {
+        if self.precise.as_vec2() == glam::vec2(self.x, self.y) {
+            self.precise
+        } else {
+            glam::dvec2(self.x as f64, self.y as f64)
+        }
+    }
+
+    #[cfg(not(feature = "deterministic"))]
+    pub fn position_real(&self) -> Vector {
+        glam::vec2(self.x, self.y)
+    }
+
+    #[cfg(feature = "deterministic")]
+    pub fn set_position_real(&mut self, position: Vector) {
+        self.precise = position;
+        self.x = position.x as f32;
+        self.y = position.y as f32;
+    }
+
+    #[cfg(not(feature = "deterministic"))]
+    pub fn set_position_real(&mut self, position: Vector) {
+        self.x = position.x;
+        self.y = position.y;
+    }
+
+    // Velocity in the same precision, kept the same way
+    #[cfg(feature = "deterministic")]
+    pub fn velocity_real(&self) -> Vector {
+        if self.precise_velocity.as_vec2() == glam::vec2(self.dx, self.dy) {
+            self.precise_velocity
+        } else {
+            glam::dvec2(self.dx as f64, self.dy as f64)
+        }
+    }
+
+    #[cfg(not(feature = "deterministic"))]
+    pub fn velocity_real(&self) -> Vector {
+        glam::vec2(self.dx, self.dy)
+    }
+
+    #[cfg(feature = "deterministic")]
+    pub fn set_velocity_real(&mut self, velocity: Vector) {
+        self.precise_velocity = velocity;
+        self.dx = velocity.x as f32;
+        self.dy = velocity.y as f32;
+    }
+
+    #[cfg(not(feature = "deterministic"))]
+    pub fn set_velocity_real(&mut self, velocity: Vector) {
+        self.dx = velocity.x;
+        self.dy = velocity.y;
+    }
+
+    // Turn the accumulated steering into a velocity change, capped at max_force
+    pub fn apply_acceleration(&mut self) {
+        let acceleration = math::widen(glam::vec2(self.ax, self.ay)).clamp_length_max(math::real(self.max_force));
+        self.set_velocity_real(self.velocity_real() + acceleration);
+        self.ax = 0.0;
+        self.ay = 0.0;
+    }
+
+    // Run every registered steering behavior against this boid's neighbors,
+    // which must already be within visual range (see NeighborIndex::query_radius).
+    // `index` is this boid's own position in `boids`, so it can be left out
+    // even when another boid sits at exactly the same spot. Steering is
+    // accumulated as acceleration; the return value reports what each
+    // behavior contributed for inspection.
+    pub fn calculate_behaviors(
+        &mut self,
+        index: usize,
+        neighbor_indices: &[usize],
+        boids: &[Boid],
+        behaviors: &[WeightedBehavior],
+        world: &World,
+    ) -> SteeringForces {
+        self.calculate_behaviors_capped(index, neighbor_indices, boids, behaviors, world, None)
+    }
+
+    // calculate_behaviors looking at no more than the `max_neighbors` nearest
+    // neighbors, which bounds the cost in dense clusters. The boids beyond the
+    // nearest few barely change the averages.
+    pub fn calculate_behaviors_capped(
+        &mut self,
+        index: usize,
+        neighbor_indices: &[usize],
+        boids: &[Boid],
+        behaviors: &[WeightedBehavior],
+        world: &World,
+        max_neighbors: Option<usize>,
+    ) -> SteeringForces {
+        let mut neighbors: Vec<usize> = neighbor_indices
+            .iter()
+            .copied()
+            .filter(|&idx| idx != index)
+            .collect();
+        self.neighbor_count = neighbors.len() as u32;
+        if let Some(cap) = max_neighbors.filter(|&cap| cap < neighbors.len()) {
+            let pos = glam::vec2(self.x, self.y);
+            let distance = |idx: usize| pos.distance_squared(glam::vec2(boids[idx].x, boids[idx].y));
+            neighbors.select_nth_unstable_by(cap, |&a, &b| distance(a).total_cmp(&distance(b)));
+            neighbors.truncate(cap);
+        }
         
-        // Process all neighbors in a single pass
-        for &idx in neighbor_indices {
-            let other = &boids[idx];
-            
-            // Don't process itself
-            if self.x == other.x && self.y == other.y {
-                continue;
+        // The classic three rules only need a few sums over the neighbors,
+        // which the vector kernel gathers in one pass
+        #[cfg(feature = "simd")]
+        let sums = {
+            let mut lanes = NeighborLanes::default();
+            for &idx in &neighbors {
+                let other = &boids[idx];
+                lanes.push(other.x, other.y, other.dx, other.dy);
             }
-            
-            // Fast squared distance check for performance
-            let dx = self.x - other.x;
-            let dy = self.y - other.y;
-            let squared_dist = dx * dx + dy * dy;
-            
-            // Avoidance (close range)
-            if squared_dist < MIN_DISTANCE * MIN_DISTANCE {
-                avoid_x += dx;
-                avoid_y += dy;
-                num_close += 1;
-            }
-            
-            // Attraction and velocity matching (visual range)
-            if squared_dist < VISUAL_RANGE * VISUAL_RANGE {
-                center_x += other.x;
-                center_y += other.y;
-                avg_dx += other.dx;
-                avg_dy += other.dy;
-                num_neighbors += 1.0;
+            simd_kernel::flock_sums_simd(glam::vec2(self.x, self.y), &lanes, MIN_DISTANCE)
+        };
+
+        let mut forces = SteeringForces::default();
+        for (i, weighted) in behaviors.iter().enumerate() {
+            #[cfg(feature = "simd")]
+            let force = match weighted.behavior.name() {
+                "separation" => sums.separation,
+                "cohesion" => sums.cohesion(glam::vec2(self.x, self.y)),
+                "alignment" => sums.alignment(glam::vec2(self.dx, self.dy)),
+                _ => weighted.behavior.force(self, &neighbors, boids, world),
+            } * weighted.weight * self.traits.weight(weighted.behavior.name());
+            #[cfg(not(feature = "simd"))]
+            let force = weighted.behavior.force(self, &neighbors, boids, world)
+                * weighted.weight
+                * self.traits.weight(weighted.behavior.name());
+            self.apply_force(force);
+            if let Some(contribution) = forces.contributions.get_mut(i) {
+                *contribution = force;
             }
         }
         
-        // Apply avoidance behavior
-        if num_close > 0 {
-            self.dx += avoid_x * AVOID_FACTOR;
-            self.dy += avoid_y * AVOID_FACTOR;
+        forces
+    }
+
+    // Random-walk steering so isolated boids don't fly in perfectly straight
+    // lines. The wander angle drifts a little every step and the boid is
+    // nudged towards heading + wander_angle.
+    pub fn wander(&mut self, rng: &mut impl Rng, strength: f32) {
+        if strength == 0.0 {
+            return;
         }
         
-        // Apply centering behavior
-        if num_neighbors > 0.0 {
-            center_x /= num_neighbors;
-            center_y /= num_neighbors;
-            self.dx += (center_x - self.x) * CENTERING_FACTOR;
-            self.dy += (center_y - self.y) * CENTERING_FACTOR;
-            
-            // Apply velocity matching
-            avg_dx /= num_neighbors;
-            avg_dy /= num_neighbors;
-            self.dx += (avg_dx - self.dx) * MATCHING_FACTOR;
-            self.dy += (avg_dy - self.dy) * MATCHING_FACTOR;
-        }
+        self.wander_angle += rng.gen_range(-WANDER_JITTER..=WANDER_JITTER);
+        self.wander_angle = self.wander_angle.max(-PI / 2.0).min(PI / 2.0);
+        
+        let angle = self.heading + self.wander_angle;
+        let (sin, cos) = math::sin_cos(angle);
+        self.apply_force(glam::vec2(cos, sin) * strength);
     }
 
     // Legacy methods kept for compatibility, but they delegate to calculate_behaviors
@@ -118,50 +336,61 @@ impl Boid {
         // This is now handled by calculate_behaviors
     }
 
+    // Fraction of the speed limit this boid can still reach, falling off once
+    // its energy drops below TIRED_ENERGY
+    pub fn stamina(&self) -> f32 {
+        if self.energy >= TIRED_ENERGY {
+            1.0
+        } else {
+            TIRED_SPEED + (1.0 - TIRED_SPEED) * self.energy / TIRED_ENERGY
+        }
+    }
+
     // Optimized speed limit check with fast square root approximation
     pub fn limit_speed(&mut self) {
-        let squared_speed = self.dx * self.dx + self.dy * self.dy;
-        if squared_speed > SPEED_LIMIT * SPEED_LIMIT {
-            let ratio = SPEED_LIMIT / squared_speed.sqrt();
-            self.dx *= ratio;
-            self.dy *= ratio;
-        }
+        self.limit_speed_scaled(1.0);
     }
 
-    // Optimized boundary check with early returns
-    pub fn keep_within_bounds(
-        &mut self,
-        cursor: mint::Point2<f32>,
-        win_width: f32,
-        win_height: f32,
-    ) {
-        let mut x_bounded = true;
-        let mut y_bounded = true;
-
-        // Check and adjust for x boundaries
-        if self.x < EDGE_BUFFER {
-            self.dx += TURN_FACTOR;
-            x_bounded = false;
-        } else if self.x > win_width - EDGE_BUFFER {
-            self.dx -= TURN_FACTOR;
-            x_bounded = false;
-        }
-        
-        // Check and adjust for y boundaries
-        if self.y < EDGE_BUFFER {
-            self.dy += TURN_FACTOR;
-            y_bounded = false;
-        } else if self.y > win_height - EDGE_BUFFER {
-            self.dy -= TURN_FACTOR;
-            y_bounded = false;
+    // Same with the flock-wide speed limit multiplied by `scale`
+    pub fn limit_speed_scaled(&mut self, scale: f32) {
+        let limit = (if self.leader { SPEED_LIMIT * LEADER_SPEED_FACTOR } else { SPEED_LIMIT })
+            * scale
+            * self.stamina()
+            * self.traits.speed;
+        let limit = math::real(limit);
+        let velocity = self.velocity_real();
+        let squared_speed = velocity.length_squared();
+        if squared_speed > limit * limit {
+            self.set_velocity_real(velocity * (limit / squared_speed.sqrt()));
         }
-        
-        // Apply damping if needed
-        if !x_bounded {
-            self.dx *= 0.8;
-        }
-        if !y_bounded {
-            self.dy *= 0.8;
+    }
+
+    // Turn back within EDGE_BUFFER of the boundary, or bounce off it, and
+    // avoid the cursor
+    pub fn keep_within_bounds(&mut self, cursor: mint::Point2<f32>, boundary: &dyn Boundary, mode: BoundaryMode) {
+        match mode {
+            BoundaryMode::Turn => {
+                let inward = boundary.inward(glam::vec2(self.x, self.y), EDGE_BUFFER);
+                self.ax += inward.x * TURN_FACTOR;
+                self.ay += inward.y * TURN_FACTOR;
+                
+                // Slow down along each axis that is being turned
+                if inward.x != 0.0 {
+                    self.dx *= 0.8;
+                }
+                if inward.y != 0.0 {
+                    self.dy *= 0.8;
+                }
+            }
+            BoundaryMode::Bounce { restitution } => {
+                let mut pos = glam::vec2(self.x, self.y);
+                let mut vel = glam::vec2(self.dx, self.dy);
+                boundary.bounce(&mut pos, &mut vel, restitution);
+                self.x = pos.x;
+                self.y = pos.y;
+                self.dx = vel.x;
+                self.dy = vel.y;
+            }
         }
         
         // Avoid mouse cursor with fast squared distance
@@ -170,8 +399,8 @@ impl Boid {
         let squared_dist_cursor = dx_cursor * dx_cursor + dy_cursor * dy_cursor;
         
         if squared_dist_cursor < 400.0 { // 20.0^2 = 400.0
-            self.dx += dx_cursor * 1.0;
-            self.dy += dy_cursor * 1.0;
+            self.ax += dx_cursor * 1.0;
+            self.ay += dy_cursor * 1.0;
         }
     }
     
@@ -187,11 +416,52 @@ impl Boid {
         self.squared_distance(boid).sqrt()
     }
     
-    // Update position based on velocity
+    // Update position based on velocity, first limiting how far the heading
+    // may have swung since the last update so boids bank instead of flipping
     #[inline]
     pub fn update_position(&mut self, tick: f32) {
-        self.x += self.dx * tick;
-        self.y += self.dy * tick;
+        self.limit_turn(tick);
+        // Small steps added to a large f32 position lose their low bits, which
+        // the deterministic feature avoids by integrating in f64
+        let velocity = self.velocity_real();
+        self.set_position_real(self.position_real() + velocity * math::real(tick));
+        
+        // Fast boids flap faster
+        let speed = math::single(velocity.length());
+        self.wing_phase = (self.wing_phase + speed * tick / WINGBEAT_LENGTH).fract();
+    }
+    
+    fn limit_turn(&mut self, tick: f32) {
+        let velocity = self.velocity_real();
+        let speed = velocity.length();
+        if speed == 0.0 {
+            return;
+        }
+        
+        // Signed angle between the old and new heading, wrapped to -PI..PI
+        let target = math::atan2_real(velocity.y, velocity.x);
+        let mut turn = target - math::real(self.heading);
+        if turn > math::PI {
+            turn -= 2.0 * math::PI;
+        } else if turn < -math::PI {
+            turn += 2.0 * math::PI;
+        }
+        
+        let max_turn = math::real(self.max_turn_rate * tick);
+        if turn.abs() > max_turn {
+            let heading = (math::real(self.heading) + max_turn.copysign(turn) + math::PI).rem_euclid(2.0 * math::PI) - math::PI;
+            let (sin, cos) = math::sin_cos_real(heading);
+            self.set_velocity_real(Vector::new(cos, sin) * speed);
+            self.heading = math::single(heading);
+        } else {
+            self.heading = math::single(target);
+        }
+    }
+}
+
+impl Position2D for Boid {
+    fn position(&self) -> glam::Vec2 {
+        glam::vec2(self.x, self.y)
     }
 }
 
@@ -199,3 +469,210 @@ impl Boid {
 // pub fn update_boids_parallel(boids: &mut [Boid], neighbor_lists: &[Vec<usize>], tick: f32) {
 //     // Implementation removed
 // }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::behavior::{Alignment, Cohesion, Separation};
+    use crate::world::{Circle, ConvexPolygon, Rectangle};
+    use rand::{rngs::StdRng, SeedableRng};
+
+    const WORLD: World = World { width: 800.0, height: 600.0 };
+
+    fn boid_at(x: f32, y: f32, dx: f32, dy: f32) -> Boid {
+        let mut boid = Boid::new(WORLD.width, WORLD.height, &mut StdRng::seed_from_u64(0));
+        boid.x = x;
+        boid.y = y;
+        boid.dx = dx;
+        boid.dy = dy;
+        boid.heading = dy.atan2(dx);
+        boid
+    }
+
+    // Steering on boid `index` from a single behavior, with every other boid as a neighbor
+    fn steering(boids: &[Boid], index: usize, behavior: WeightedBehavior) -> glam::Vec2 {
+        let mut boid = boids[index];
+        let neighbors: Vec<usize> = (0..boids.len()).collect();
+        boid.calculate_behaviors(index, &neighbors, boids, &[behavior], &WORLD);
+        glam::vec2(boid.ax, boid.ay)
+    }
+
+    #[test]
+    fn separation_pushes_close_boids_apart() {
+        let boids = [boid_at(400.0, 300.0, 0.0, 0.0), boid_at(405.0, 300.0, 0.0, 0.0)];
+
+        assert!(steering(&boids, 0, WeightedBehavior::new(Separation, 1.0)).x < 0.0);
+        assert!(steering(&boids, 1, WeightedBehavior::new(Separation, 1.0)).x > 0.0);
+    }
+
+    #[test]
+    fn separation_ignores_boids_beyond_the_minimum_distance() {
+        let boids = [boid_at(400.0, 300.0, 0.0, 0.0), boid_at(400.0, 300.0 + MIN_DISTANCE * 1.5, 0.0, 0.0)];
+
+        assert_eq!(steering(&boids, 0, WeightedBehavior::new(Separation, 1.0)), glam::Vec2::ZERO);
+    }
+
+    #[test]
+    fn cohesion_pulls_a_pair_together() {
+        let boids = [boid_at(400.0, 300.0, 0.0, 0.0), boid_at(400.0, 325.0, 0.0, 0.0)];
+
+        assert!(steering(&boids, 0, WeightedBehavior::new(Cohesion, 1.0)).y > 0.0);
+        assert!(steering(&boids, 1, WeightedBehavior::new(Cohesion, 1.0)).y < 0.0);
+    }
+
+    #[test]
+    fn alignment_converges_headings() {
+        let mut boids = [boid_at(400.0, 300.0, 100.0, 0.0), boid_at(410.0, 300.0, 0.0, 100.0)];
+        let angle = |boids: &[Boid]| {
+            glam::vec2(boids[0].dx, boids[0].dy).angle_between(glam::vec2(boids[1].dx, boids[1].dy)).abs()
+        };
+        let before = angle(&boids);
+
+        for _ in 0..5 {
+            let snapshot = boids;
+            for (i, boid) in boids.iter_mut().enumerate() {
+                boid.calculate_behaviors(i, &[0, 1], &snapshot, &[WeightedBehavior::new(Alignment, 0.2)], &WORLD);
+                boid.apply_acceleration();
+            }
+        }
+
+        assert!(angle(&boids) < before * 0.5);
+    }
+
+    #[test]
+    fn boids_at_the_same_spot_still_see_each_other() {
+        let boids = [boid_at(400.0, 300.0, 0.0, 0.0), boid_at(400.0, 300.0, 0.0, 0.0)];
+        let mut boid = boids[0];
+        boid.calculate_behaviors(0, &[0, 1], &boids, &[], &WORLD);
+
+        assert_eq!(boid.neighbor_count, 1);
+    }
+
+    #[test]
+    fn neighbor_cap_keeps_the_nearest() {
+        let boids = [
+            boid_at(400.0, 300.0, 0.0, 0.0),
+            boid_at(500.0, 300.0, 0.0, 0.0),
+            boid_at(400.0, 310.0, 0.0, 0.0),
+        ];
+        let mut boid = boids[0];
+        boid.calculate_behaviors_capped(0, &[0, 1, 2], &boids, &[WeightedBehavior::new(Cohesion, 1.0)], &WORLD, Some(1));
+
+        assert_eq!(boid.neighbor_count, 2);
+        assert_eq!(glam::vec2(boid.ax, boid.ay), glam::vec2(0.0, 10.0));
+    }
+
+    #[test]
+    fn limit_speed_clamps_the_magnitude() {
+        let mut boid = boid_at(400.0, 300.0, SPEED_LIMIT * 3.0, SPEED_LIMIT * 4.0);
+        boid.limit_speed();
+
+        let speed = glam::vec2(boid.dx, boid.dy).length();
+        assert!((speed - SPEED_LIMIT).abs() < 0.01);
+        assert!((boid.dy / boid.dx - 4.0 / 3.0).abs() < 0.001);
+    }
+
+    #[test]
+    fn limit_speed_leaves_slow_boids_alone() {
+        let mut boid = boid_at(400.0, 300.0, 30.0, -40.0);
+        boid.limit_speed();
+
+        assert_eq!((boid.dx, boid.dy), (30.0, -40.0));
+    }
+
+    #[test]
+    fn keep_within_bounds_turns_boids_at_the_edges() {
+        let far_away = mint::Point2 { x: -1000.0, y: -1000.0 };
+        let bounds = Rectangle::new(&WORLD);
+
+        let mut left = boid_at(EDGE_BUFFER / 2.0, 300.0, -100.0, 0.0);
+        left.keep_within_bounds(far_away, &bounds, BoundaryMode::Turn);
+        assert!(left.ax > 0.0);
+        assert!(left.dx > -100.0);
+
+        let mut bottom = boid_at(400.0, WORLD.height - EDGE_BUFFER / 2.0, 0.0, 100.0);
+        bottom.keep_within_bounds(far_away, &bounds, BoundaryMode::Turn);
+        assert!(bottom.ay < 0.0);
+
+        let mut middle = boid_at(400.0, 300.0, 100.0, 0.0);
+        middle.keep_within_bounds(far_away, &bounds, BoundaryMode::Turn);
+        assert_eq!((middle.ax, middle.ay), (0.0, 0.0));
+    }
+
+    #[test]
+    fn keep_within_bounds_turns_boids_towards_the_middle_of_a_circle() {
+        let far_away = mint::Point2 { x: -1000.0, y: -1000.0 };
+        let arena = Circle::new(&WORLD);
+
+        // Inside the world rectangle but outside the circle, near its top right
+        let mut corner = boid_at(700.0, 60.0, 100.0, 0.0);
+        corner.keep_within_bounds(far_away, &arena, BoundaryMode::Turn);
+        assert!(corner.ax < 0.0 && corner.ay > 0.0);
+
+        let mut middle = boid_at(400.0, 300.0, 100.0, 0.0);
+        middle.keep_within_bounds(far_away, &arena, BoundaryMode::Turn);
+        assert_eq!((middle.ax, middle.ay), (0.0, 0.0));
+    }
+
+    #[test]
+    fn bounce_flips_both_components_in_a_corner() {
+        let far_away = mint::Point2 { x: -1000.0, y: -1000.0 };
+        let bounds = Rectangle::new(&WORLD);
+
+        let mut boid = boid_at(-2.0, WORLD.height + 3.0, -100.0, 50.0);
+        boid.keep_within_bounds(far_away, &bounds, BoundaryMode::Bounce { restitution: 0.5 });
+        assert_eq!((boid.x, boid.y), (0.0, WORLD.height));
+        assert_eq!((boid.dx, boid.dy), (50.0, -25.0));
+    }
+
+    #[test]
+    fn bounce_leaves_boids_heading_back_inside_alone() {
+        let far_away = mint::Point2 { x: -1000.0, y: -1000.0 };
+        let bounds = Rectangle::new(&WORLD);
+
+        // Already turned around but not back over the edge yet
+        let mut boid = boid_at(-2.0, 300.0, 80.0, 10.0);
+        boid.keep_within_bounds(far_away, &bounds, BoundaryMode::Bounce { restitution: 0.5 });
+        assert_eq!(boid.x, 0.0);
+        assert_eq!((boid.dx, boid.dy), (80.0, 10.0));
+
+        // Inside, nothing happens even right at the edge
+        let mut inside = boid_at(0.0, 300.0, -80.0, 10.0);
+        inside.keep_within_bounds(far_away, &bounds, BoundaryMode::Bounce { restitution: 0.5 });
+        assert_eq!((inside.x, inside.dx), (0.0, -80.0));
+    }
+
+    #[test]
+    fn bounce_without_restitution_stops_motion_into_the_wall() {
+        let far_away = mint::Point2 { x: -1000.0, y: -1000.0 };
+        let bounds = Rectangle::new(&WORLD);
+
+        let mut boid = boid_at(WORLD.width + 1.0, 300.0, 100.0, 40.0);
+        boid.keep_within_bounds(far_away, &bounds, BoundaryMode::Bounce { restitution: 0.0 });
+        assert_eq!((boid.dx, boid.dy), (0.0, 40.0));
+    }
+
+    #[test]
+    fn bounce_off_a_circle_keeps_the_tangential_speed() {
+        let far_away = mint::Point2 { x: -1000.0, y: -1000.0 };
+        let arena = Circle::new(&WORLD);
+
+        // Leaving straight down through the bottom while drifting sideways
+        let mut boid = boid_at(400.0, 610.0, 30.0, 100.0);
+        boid.keep_within_bounds(far_away, &arena, BoundaryMode::Bounce { restitution: 1.0 });
+        assert!((boid.x - 400.0).abs() < 0.001 && (boid.y - 600.0).abs() < 0.001);
+        assert!((boid.dx - 30.0).abs() < 0.001 && (boid.dy + 100.0).abs() < 0.001);
+    }
+
+    #[test]
+    fn bounce_off_a_polygon_corner_returns_to_the_corner() {
+        let far_away = mint::Point2 { x: -1000.0, y: -1000.0 };
+        let corners = vec![glam::vec2(100.0, 100.0), glam::vec2(700.0, 100.0), glam::vec2(400.0, 500.0)];
+        let arena = ConvexPolygon::new(corners).unwrap();
+
+        let mut boid = boid_at(95.0, 95.0, -60.0, -60.0);
+        boid.keep_within_bounds(far_away, &arena, BoundaryMode::Bounce { restitution: 1.0 });
+        assert!((boid.x - 100.0).abs() < 0.001 && (boid.y - 100.0).abs() < 0.001);
+        assert!(boid.dx > 0.0 && boid.dy > 0.0);
+    }
+}
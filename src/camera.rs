@@ -0,0 +1,71 @@
+// camera.rs
+use ggez::graphics;
+
+// Camera settings
+pub const PAN_SPEED: f32 = 600.0; // Screen pixels per second
+pub const ZOOM_STEP: f32 = 1.1;   // Zoom factor per scroll wheel notch
+pub const MIN_ZOOM: f32 = 0.1;
+pub const MAX_ZOOM: f32 = 10.0;
+pub const FOLLOW_STIFFNESS: f32 = 8.0; // How quickly a following camera catches up
+
+// 2D camera mapping world coordinates onto the window
+pub struct Camera {
+    pub position: glam::Vec2, // World point shown at the center of the window
+    pub zoom: f32,
+    viewport: glam::Vec2,     // Window size in pixels
+}
+
+impl Camera {
+    pub fn new(viewport_width: f32, viewport_height: f32) -> Self {
+        let viewport = glam::vec2(viewport_width, viewport_height);
+
+        Camera {
+            position: viewport / 2.0,
+            zoom: 1.0,
+            viewport,
+        }
+    }
+
+    // Window size in pixels
+    pub fn viewport(&self) -> glam::Vec2 {
+        self.viewport
+    }
+
+    // Follow a window resize, keeping the same world point in the center
+    pub fn set_viewport(&mut self, viewport_width: f32, viewport_height: f32) {
+        self.viewport = glam::vec2(viewport_width, viewport_height);
+    }
+
+    pub fn world_to_screen(&self, point: glam::Vec2) -> glam::Vec2 {
+        (point - self.position) * self.zoom + self.viewport / 2.0
+    }
+
+    pub fn screen_to_world(&self, point: glam::Vec2) -> glam::Vec2 {
+        (point - self.viewport / 2.0) / self.zoom + self.position
+    }
+
+    // Move the camera by a distance given in screen pixels
+    pub fn pan(&mut self, screen_delta: glam::Vec2) {
+        self.position += screen_delta / self.zoom;
+    }
+
+    // Ease towards a moving target, used to track a single boid
+    pub fn follow(&mut self, target: glam::Vec2, dt: f32) {
+        let t = (FOLLOW_STIFFNESS * dt).min(1.0);
+        self.position += (target - self.position) * t;
+    }
+
+    // Zoom while keeping the world point under `screen_point` fixed
+    pub fn zoom_at(&mut self, screen_point: glam::Vec2, factor: f32) {
+        let anchor = self.screen_to_world(screen_point);
+        self.zoom = (self.zoom * factor).max(MIN_ZOOM).min(MAX_ZOOM);
+        self.position += anchor - self.screen_to_world(screen_point);
+    }
+
+    // DrawParam that renders world-space geometry through this camera
+    pub fn draw_param(&self) -> graphics::DrawParam {
+        graphics::DrawParam::new()
+            .dest(self.viewport / 2.0 - self.position * self.zoom)
+            .scale(glam::vec2(self.zoom, self.zoom))
+    }
+}
@@ -0,0 +1,235 @@
+// charts.rs
+#[cfg(feature = "render-ggez")]
+use ggez::{graphics, Context, GameResult};
+use std::collections::VecDeque;
+
+// Chart settings
+const CHART_SECONDS: f32 = 60.0;        // History shown in each chart
+const CHART_SAMPLE_INTERVAL: f32 = 0.25; // Seconds between samples
+const CHART_WIDTH: f32 = 240.0;          // Pixels
+const CHART_HEIGHT: f32 = 48.0;
+const CHART_SPACING: f32 = 8.0;
+
+// Rolling window of one metric
+pub struct MetricHistory {
+    pub label: &'static str,
+    samples: VecDeque<f32>,
+}
+
+impl MetricHistory {
+    pub fn new(label: &'static str) -> Self {
+        MetricHistory {
+            label,
+            samples: VecDeque::new(),
+        }
+    }
+
+    pub fn push(&mut self, value: f32) {
+        let capacity = (CHART_SECONDS / CHART_SAMPLE_INTERVAL) as usize;
+        while self.samples.len() >= capacity {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(value);
+    }
+
+    pub fn latest(&self) -> Option<f32> {
+        self.samples.back().copied()
+    }
+
+    // Smallest and largest sample, widened so a flat line still has a range
+    #[cfg(feature = "render-ggez")]
+    fn range(&self) -> (f32, f32) {
+        let min = self.samples.iter().copied().fold(f32::INFINITY, f32::min);
+        let max = self.samples.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+        if max - min < f32::EPSILON {
+            (min - 1.0, max + 1.0)
+        } else {
+            (min, max)
+        }
+    }
+}
+
+// Sparklines of FPS, boid count and polarization, toggled with <k>
+pub struct Charts {
+    pub fps: MetricHistory,
+    pub boid_count: MetricHistory,
+    pub polarization: MetricHistory,
+    since_sample: f32,
+}
+
+impl Charts {
+    pub fn new() -> Self {
+        Charts {
+            fps: MetricHistory::new("FPS"),
+            boid_count: MetricHistory::new("Boids"),
+            polarization: MetricHistory::new("Polarization"),
+            since_sample: CHART_SAMPLE_INTERVAL,
+        }
+    }
+
+    // Advance the clock by `dt` real seconds and report whether a sample is due
+    pub fn due(&mut self, dt: f32) -> bool {
+        self.since_sample += dt;
+        if self.since_sample >= CHART_SAMPLE_INTERVAL {
+            self.since_sample = 0.0;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn histories(&self) -> [&MetricHistory; 3] {
+        [&self.fps, &self.boid_count, &self.polarization]
+    }
+
+    // Size of the stacked charts in pixels at the given UI scale
+    pub fn size(&self, ui_scale: f32) -> glam::Vec2 {
+        let rows = self.histories().len() as f32;
+        glam::vec2(CHART_WIDTH, rows * (CHART_HEIGHT + CHART_SPACING) - CHART_SPACING) * ui_scale
+    }
+
+    // Draw the charts stacked downwards from `top_left`, in screen space
+    #[cfg(feature = "render-ggez")]
+    pub fn draw(&self, ctx: &Context, canvas: &mut graphics::Canvas, top_left: glam::Vec2, ui_scale: f32) -> GameResult {
+        let mb = &mut graphics::MeshBuilder::new();
+        let (width, height, spacing) = (CHART_WIDTH * ui_scale, CHART_HEIGHT * ui_scale, CHART_SPACING * ui_scale);
+        let step = width / ((CHART_SECONDS / CHART_SAMPLE_INTERVAL) as f32 - 1.0);
+        let mut labels = Vec::new();
+
+        for (row, history) in self.histories().iter().enumerate() {
+            let origin = top_left + glam::vec2(0.0, row as f32 * (height + spacing));
+            mb.rectangle(
+                graphics::DrawMode::fill(),
+                graphics::Rect::new(origin.x, origin.y, width, height),
+                [0.0, 0.0, 0.0, 0.4].into(),
+            )?;
+
+            // Newest sample sits at the right edge
+            let (min, max) = history.range();
+            let start_x = origin.x + width - (history.samples.len() as f32 - 1.0) * step;
+            let points: Vec<glam::Vec2> = history
+                .samples
+                .iter()
+                .enumerate()
+                .map(|(i, &value)| {
+                    let t = (value - min) / (max - min);
+                    glam::vec2(start_x + i as f32 * step, origin.y + height * (1.0 - t))
+                })
+                .collect();
+            if points.len() >= 2 {
+                mb.line(&points, 1.5 * ui_scale, [0.4, 0.9, 1.0, 0.9].into())?;
+            }
+
+            let label = match history.latest() {
+                Some(value) => format!("{}: {:.2} ({:.2} - {:.2})", history.label, value, min, max),
+                None => history.label.to_string(),
+            };
+            let text = graphics::Text::new(graphics::TextFragment {
+                text: label,
+                color: Some([1.0, 1.0, 1.0, 0.8].into()),
+                font: None,
+                scale: Some(graphics::PxScale::from(12.0 * ui_scale)),
+            });
+            labels.push((text, origin + glam::vec2(4.0, 2.0) * ui_scale));
+        }
+
+        // Labels go on top of the mesh, so they are drawn after it
+        canvas.draw(&graphics::Mesh::from_data(ctx, mb.build()), graphics::DrawParam::default());
+        for (text, pos) in labels {
+            canvas.draw(&text, graphics::DrawParam::default().dest(pos));
+        }
+        Ok(())
+    }
+}
+
+// Population settings
+const POPULATION_SAMPLE_INTERVAL: f32 = 0.25; // Simulated seconds between samples
+const POPULATION_SAMPLES: usize = 480;
+const POPULATION_HEIGHT: f32 = 80.0;
+
+// A few counts over simulated time drawn as lines on one shared scale, for
+// the epidemic and predator-prey curves
+#[derive(Clone)]
+pub struct PopulationChart {
+    series: Vec<(&'static str, [f32; 4], VecDeque<f32>)>,
+    since_sample: f32,
+}
+
+impl PopulationChart {
+    pub fn new(series: &[(&'static str, [f32; 4])]) -> Self {
+        PopulationChart {
+            series: series.iter().map(|&(label, color)| (label, color, VecDeque::new())).collect(),
+            since_sample: POPULATION_SAMPLE_INTERVAL,
+        }
+    }
+
+    pub fn clear(&mut self) {
+        for (_, _, samples) in &mut self.series {
+            samples.clear();
+        }
+        self.since_sample = POPULATION_SAMPLE_INTERVAL;
+    }
+
+    // Advance by `tick` simulated seconds, recording `values` (one per
+    // series) when a sample is due. The values are only computed then.
+    pub fn sample(&mut self, tick: f32, values: impl FnOnce() -> Vec<f32>) {
+        self.since_sample += tick;
+        if self.since_sample < POPULATION_SAMPLE_INTERVAL {
+            return;
+        }
+        self.since_sample = 0.0;
+        for ((_, _, samples), value) in self.series.iter_mut().zip(values()) {
+            if samples.len() >= POPULATION_SAMPLES {
+                samples.pop_front();
+            }
+            samples.push_back(value);
+        }
+    }
+
+    pub fn size(&self, ui_scale: f32) -> glam::Vec2 {
+        glam::vec2(CHART_WIDTH, POPULATION_HEIGHT) * ui_scale
+    }
+
+    // Draw the lines with the latest values as a legend, in screen space
+    #[cfg(feature = "render-ggez")]
+    pub fn draw(&self, ctx: &Context, canvas: &mut graphics::Canvas, top_left: glam::Vec2, ui_scale: f32) -> GameResult {
+        let mb = &mut graphics::MeshBuilder::new();
+        let (width, height) = (CHART_WIDTH * ui_scale, POPULATION_HEIGHT * ui_scale);
+        mb.rectangle(
+            graphics::DrawMode::fill(),
+            graphics::Rect::new(top_left.x, top_left.y, width, height),
+            [0.0, 0.0, 0.0, 0.4].into(),
+        )?;
+
+        let max = self
+            .series
+            .iter()
+            .flat_map(|(_, _, samples)| samples.iter().copied())
+            .fold(1.0, f32::max);
+        let step = width / (POPULATION_SAMPLES as f32 - 1.0);
+        let mut legend = Vec::new();
+        for (label, color, samples) in &self.series {
+            let start_x = top_left.x + width - (samples.len() as f32 - 1.0) * step;
+            let points: Vec<glam::Vec2> = samples
+                .iter()
+                .enumerate()
+                .map(|(i, &value)| glam::vec2(start_x + i as f32 * step, top_left.y + height * (1.0 - value / max)))
+                .collect();
+            if points.len() >= 2 {
+                mb.line(&points, 1.5 * ui_scale, (*color).into())?;
+            }
+            legend.push(format!("{} {:.0}", label, samples.back().copied().unwrap_or(0.0)));
+        }
+
+        let text = graphics::Text::new(graphics::TextFragment {
+            text: legend.join(" | "),
+            color: Some([1.0, 1.0, 1.0, 0.8].into()),
+            font: None,
+            scale: Some(graphics::PxScale::from(12.0 * ui_scale)),
+        });
+
+        canvas.draw(&graphics::Mesh::from_data(ctx, mb.build()), graphics::DrawParam::default());
+        canvas.draw(&text, graphics::DrawParam::default().dest(top_left + glam::vec2(4.0, 2.0) * ui_scale));
+        Ok(())
+    }
+}
@@ -0,0 +1,219 @@
+// cli.rs
+use crate::autoscale::AUTOSCALE_STEP;
+use crate::config::CONFIG_PATH;
+use crate::epidemic::INFECTION_PROBABILITY;
+use crate::food::FOOD_RATE;
+use crate::rewind::{REWIND_MEMORY_MB, REWIND_SECONDS};
+use crate::stress::STRESS_THRESHOLD;
+use crate::boid::{TRAIT_VARIANCE, WANDER_STRENGTH};
+use crate::telemetry::TELEMETRY_INTERVAL;
+use crate::waypoints::GOAL_WEIGHT;
+use crate::zones::EMIT_RATE;
+use std::env;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+// Command line options, falling back to the compiled-in defaults
+pub struct Options {
+    pub world_width: f32,
+    pub world_height: f32,
+    pub fixed_world: bool,     // World size given with --world, otherwise it tracks the window
+    pub rewind_seconds: f32,
+    pub rewind_memory_mb: usize,
+    pub goal_weight: f32,
+    pub wander_strength: f32,
+    pub seed: Option<u64>,     // Random when not given
+    pub demo: bool,            // Hands-off mode for unattended displays
+    pub screensaver: bool,     // Fullscreen demo that exits on any input
+    pub telemetry: Option<PathBuf>, // CSV, or JSON lines for .json/.jsonl
+    pub telemetry_interval: f32,
+    pub trajectories: Option<PathBuf>, // Binary per-step log of every boid
+    pub autoscale: Option<usize>,   // Step size when adjusting the count to hold the FPS target
+    pub stress: Option<f32>,        // FPS threshold that ends the stress test
+    pub emit_rate: f32,             // Boids per second from each emitter
+    pub food_rate: f32,             // Food items per second while food is on
+    pub infection_probability: f32, // Chance per 1/60s of passing on an infection
+    pub trait_variance: f32,        // Spread of per-boid traits either side of the flock settings
+    pub config: PathBuf,            // TOML file with key bindings
+    pub gpu: bool,                  // Run the flocking rules in compute shaders, --backend gpu
+    pub sim_thread: bool,           // Step the flock on a worker thread
+    pub record_input: Option<PathBuf>, // Log of every input event and frame time
+    pub replay_input: Option<PathBuf>, // Input log to play back, exits at the end
+    pub log_file: Option<PathBuf>,  // Copy of the log output for the session
+    pub obstacles: Option<PathBuf>, // Obstacle layout to start with, and save edits to
+    pub evolution_log: Option<PathBuf>, // CSV of generation stats while evolving
+    pub scenario: Option<PathBuf>,  // Timed events to play out, see scenario.rs
+    pub tui: bool,                  // Draw in the terminal instead of a window
+    pub serve: Option<SocketAddr>,  // Address to broadcast live state on over WebSocket
+    pub osc: Option<SocketAddr>,    // UDP address to take OSC parameter changes on
+    pub metrics: Option<SocketAddr>, // HTTP address to serve Prometheus metrics on
+}
+
+pub const USAGE: &str = "usage: boids [--world <width>x<height>] [--rewind-seconds <s>] [--rewind-memory <mb>]\n             [--goal-weight <w>] [--wander <strength>] [--seed <n>] [--demo]\n             [--screensaver] [--telemetry <file.csv|file.jsonl>] [--telemetry-interval <s>]\n             [--trajectories <file>] [--autoscale [<step>]]\n             [--stress [<min fps>]] [--emit-rate <boids/s>]\n             [--food-rate <items/s>] [--infection-probability <p>]\n             [--trait-variance <fraction>] [--config <file.toml>] [--backend <cpu|gpu>]\n             [--sim-thread] [--record-input <file>] [--replay-input <file>]\n             [--log-file <file>] [--obstacles <file>] [--evolution-log <file>]\n             [--tui] [--serve <address:port>] [--osc <address:port>]\n             [--metrics <address:port>] [--scenario <file>]";
+
+impl Options {
+    pub fn from_args(default_width: f32, default_height: f32) -> Result<Options, String> {
+        let mut options = Options {
+            world_width: default_width,
+            world_height: default_height,
+            fixed_world: false,
+            rewind_seconds: REWIND_SECONDS,
+            rewind_memory_mb: REWIND_MEMORY_MB,
+            goal_weight: GOAL_WEIGHT,
+            wander_strength: WANDER_STRENGTH,
+            seed: None,
+            demo: false,
+            screensaver: false,
+            telemetry: None,
+            telemetry_interval: TELEMETRY_INTERVAL,
+            trajectories: None,
+            autoscale: None,
+            stress: None,
+            emit_rate: EMIT_RATE,
+            food_rate: FOOD_RATE,
+            infection_probability: INFECTION_PROBABILITY,
+            trait_variance: TRAIT_VARIANCE,
+            config: PathBuf::from(CONFIG_PATH),
+            gpu: false,
+            sim_thread: false,
+            record_input: None,
+            replay_input: None,
+            log_file: None,
+            obstacles: None,
+            evolution_log: None,
+            scenario: None,
+            tui: false,
+            serve: None,
+            osc: None,
+            metrics: None,
+        };
+
+        let mut args = env::args().skip(1).peekable();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--world" => {
+                    let value = args.next().ok_or("--world needs a value like 2560x1440")?;
+                    let (width, height) = parse_size(&value)?;
+                    options.world_width = width;
+                    options.world_height = height;
+                    options.fixed_world = true;
+                }
+                "--rewind-seconds" => options.rewind_seconds = parse_value(&arg, args.next())?,
+                "--rewind-memory" => options.rewind_memory_mb = parse_value(&arg, args.next())?,
+                "--goal-weight" => options.goal_weight = parse_value(&arg, args.next())?,
+                "--wander" => options.wander_strength = parse_value(&arg, args.next())?,
+                "--seed" => options.seed = Some(parse_value(&arg, args.next())?),
+                "--demo" => options.demo = true,
+                "--screensaver" => {
+                    options.screensaver = true;
+                    options.demo = true;
+                }
+                "--telemetry" => {
+                    let value = args.next().ok_or("--telemetry needs a file name")?;
+                    options.telemetry = Some(PathBuf::from(value));
+                }
+                "--telemetry-interval" => options.telemetry_interval = parse_value(&arg, args.next())?,
+                "--trajectories" => {
+                    let value = args.next().ok_or("--trajectories needs a file name")?;
+                    options.trajectories = Some(PathBuf::from(value));
+                }
+                "--autoscale" => {
+                    // The step size is optional
+                    let step = match args.peek().and_then(|value| value.parse().ok()) {
+                        Some(step) => {
+                            args.next();
+                            step
+                        }
+                        None => AUTOSCALE_STEP,
+                    };
+                    options.autoscale = Some(step);
+                }
+                "--stress" => {
+                    let threshold = match args.peek().and_then(|value| value.parse().ok()) {
+                        Some(threshold) => {
+                            args.next();
+                            threshold
+                        }
+                        None => STRESS_THRESHOLD,
+                    };
+                    options.stress = Some(threshold);
+                }
+                "--emit-rate" => options.emit_rate = parse_value(&arg, args.next())?,
+                "--food-rate" => options.food_rate = parse_value(&arg, args.next())?,
+                "--infection-probability" => options.infection_probability = parse_value(&arg, args.next())?,
+                "--trait-variance" => options.trait_variance = parse_value(&arg, args.next())?,
+                "--config" => {
+                    let value = args.next().ok_or("--config needs a file name")?;
+                    options.config = PathBuf::from(value);
+                }
+                "--sim-thread" => options.sim_thread = true,
+                "--tui" => options.tui = true,
+                "--serve" => options.serve = Some(parse_value(&arg, args.next())?),
+                "--osc" => options.osc = Some(parse_value(&arg, args.next())?),
+                "--metrics" => options.metrics = Some(parse_value(&arg, args.next())?),
+                "--record-input" => {
+                    let value = args.next().ok_or("--record-input needs a file name")?;
+                    options.record_input = Some(PathBuf::from(value));
+                }
+                "--replay-input" => {
+                    let value = args.next().ok_or("--replay-input needs a file name")?;
+                    options.replay_input = Some(PathBuf::from(value));
+                }
+                "--obstacles" => {
+                    let value = args.next().ok_or("--obstacles needs a file name")?;
+                    options.obstacles = Some(PathBuf::from(value));
+                }
+                "--evolution-log" => {
+                    let value = args.next().ok_or("--evolution-log needs a file name")?;
+                    options.evolution_log = Some(PathBuf::from(value));
+                }
+                "--scenario" => {
+                    let value = args.next().ok_or("--scenario needs a file name")?;
+                    options.scenario = Some(PathBuf::from(value));
+                }
+                "--log-file" => {
+                    let value = args.next().ok_or("--log-file needs a file name")?;
+                    options.log_file = Some(PathBuf::from(value));
+                }
+                "--backend" => match args.next().as_deref() {
+                    Some("cpu") => options.gpu = false,
+                    Some("gpu") => options.gpu = true,
+                    _ => return Err("--backend needs 'cpu' or 'gpu'".to_string()),
+                },
+                "-h" | "--help" => return Err(USAGE.to_string()),
+                other => return Err(format!("unknown argument '{}'\n{}", other, USAGE)),
+            }
+        }
+
+        // The worker steps on the CPU and has no device to dispatch to
+        if options.gpu && options.sim_thread {
+            return Err("--backend gpu can't be combined with --sim-thread".to_string());
+        }
+
+        Ok(options)
+    }
+}
+
+// Parse a "<width>x<height>" pair of positive numbers
+fn parse_size(value: &str) -> Result<(f32, f32), String> {
+    let invalid = || format!("invalid size '{}', expected <width>x<height>", value);
+
+    let mut parts = value.splitn(2, 'x');
+    let width = parts.next().and_then(|w| w.parse::<f32>().ok()).ok_or_else(invalid)?;
+    let height = parts.next().and_then(|h| h.parse::<f32>().ok()).ok_or_else(invalid)?;
+
+    if width <= 0.0 || height <= 0.0 {
+        return Err(invalid());
+    }
+
+    Ok((width, height))
+}
+
+// Parse the value following a flag
+fn parse_value<T: FromStr>(flag: &str, value: Option<String>) -> Result<T, String> {
+    let value = value.ok_or_else(|| format!("{} needs a value", flag))?;
+    value
+        .parse()
+        .map_err(|_| format!("invalid value '{}' for {}", value, flag))
+}
@@ -0,0 +1,67 @@
+// collisions.rs
+use crate::boid::Boid;
+use crate::spatial_grid::SpatialGrid;
+use crate::world::World;
+
+// Collision settings
+pub const COLLISION_RADIUS: f32 = 8.0; // Pixels, a quarter of the drawn boid size
+const PASSES: usize = 2;               // Relaxation passes per step, dense clumps settle faster with more
+
+// Keeps boids from overlapping by treating each as a solid disc, toggled
+// with <f6>. Overlapping pairs are pushed apart after the boids move.
+#[derive(Clone)]
+pub struct Collisions {
+    pub active: bool,
+    grid: SpatialGrid,
+}
+
+impl Collisions {
+    pub fn new(world: &World) -> Self {
+        Collisions {
+            active: false,
+            grid: SpatialGrid::new(world.width, world.height, 2.0 * COLLISION_RADIUS),
+        }
+    }
+
+    pub fn resize(&mut self, world: &World) {
+        self.grid = SpatialGrid::new(world.width, world.height, 2.0 * COLLISION_RADIUS);
+    }
+
+    // Move every overlapping pair apart along the line between them, each by
+    // half the overlap. Boids on the exact same spot split in a direction
+    // picked from their index so the result stays deterministic.
+    pub fn resolve(&mut self, boids: &mut [Boid]) {
+        let contact = 2.0 * COLLISION_RADIUS;
+        let mut pushes = vec![glam::Vec2::ZERO; boids.len()];
+        for _ in 0..PASSES {
+            self.grid.build(boids);
+            for push in &mut pushes {
+                *push = glam::Vec2::ZERO;
+            }
+
+            for (i, boid) in boids.iter().enumerate() {
+                let pos = glam::vec2(boid.x, boid.y);
+                for (j, squared_distance) in self.grid.query_radius(pos, contact) {
+                    if j <= i {
+                        continue;
+                    }
+                    let distance = squared_distance.sqrt();
+                    let normal = if distance > 0.0 {
+                        (glam::vec2(boids[j].x, boids[j].y) - pos) / distance
+                    } else {
+                        let angle = i as f32;
+                        glam::vec2(angle.cos(), angle.sin())
+                    };
+                    let half_overlap = (contact - distance) / 2.0;
+                    pushes[i] -= normal * half_overlap;
+                    pushes[j] += normal * half_overlap;
+                }
+            }
+
+            for (boid, push) in boids.iter_mut().zip(&pushes) {
+                boid.x += push.x;
+                boid.y += push.y;
+            }
+        }
+    }
+}
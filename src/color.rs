@@ -0,0 +1,117 @@
+// color.rs
+use crate::boid::{Boid, NUM_SPECIES, SPEED_LIMIT};
+use crate::epidemic::Epidemic;
+use std::f32::consts::PI;
+
+// Neighbor count at which the NeighborCount gradient saturates
+const MAX_NEIGHBOR_SHADE: f32 = 20.0;
+const BOID_ALPHA: f32 = 0.5;
+const LEADER_COLOR: [f32; 4] = [1.0, 1.0, 0.9, 1.0]; // Leaders stand out in every mode
+
+// Palette used by ColorMode::Species, one entry per species
+const SPECIES_COLORS: [[f32; 3]; NUM_SPECIES as usize] = [
+    [0.95, 0.55, 0.45],
+    [0.45, 0.8, 0.95],
+    [0.7, 0.95, 0.5],
+];
+
+// How boids are colored when drawn, cycled with <c>
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorMode {
+    Random,
+    Heading,
+    Speed,
+    NeighborCount,
+    Species,
+    Flock,
+    Energy,
+    Infection,
+}
+
+impl ColorMode {
+    pub fn next(self) -> ColorMode {
+        match self {
+            ColorMode::Random => ColorMode::Heading,
+            ColorMode::Heading => ColorMode::Speed,
+            ColorMode::Speed => ColorMode::NeighborCount,
+            ColorMode::NeighborCount => ColorMode::Species,
+            ColorMode::Species => ColorMode::Flock,
+            ColorMode::Flock => ColorMode::Energy,
+            ColorMode::Energy => ColorMode::Infection,
+            ColorMode::Infection => ColorMode::Random,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ColorMode::Random => "Random",
+            ColorMode::Heading => "Heading",
+            ColorMode::Speed => "Speed",
+            ColorMode::NeighborCount => "Neighbors",
+            ColorMode::Species => "Species",
+            ColorMode::Flock => "Flock",
+            ColorMode::Energy => "Energy",
+            ColorMode::Infection => "Infection",
+        }
+    }
+
+    // Color of a boid under this mode, computed fresh every frame
+    pub fn boid_color(self, boid: &Boid) -> [f32; 4] {
+        if boid.leader {
+            return LEADER_COLOR;
+        }
+        
+        let [r, g, b] = match self {
+            ColorMode::Random => return boid.color,
+            ColorMode::Heading => {
+                let hue = (boid.dx.atan2(boid.dy) + PI) / (2.0 * PI);
+                hsv_to_rgb(hue, 0.6, 1.0)
+            }
+            ColorMode::Speed => {
+                // Slow boids are blue, fast boids red
+                let speed = (boid.dx * boid.dx + boid.dy * boid.dy).sqrt();
+                let t = (speed / SPEED_LIMIT).min(1.0);
+                hsv_to_rgb(0.66 * (1.0 - t), 0.7, 1.0)
+            }
+            ColorMode::NeighborCount => {
+                let t = (boid.neighbor_count as f32 / MAX_NEIGHBOR_SHADE).min(1.0);
+                hsv_to_rgb(0.15, 0.8 * t, 0.4 + 0.6 * t)
+            }
+            ColorMode::Species => SPECIES_COLORS[boid.species as usize % SPECIES_COLORS.len()],
+            ColorMode::Flock => {
+                // Golden ratio steps keep neighboring flock IDs far apart in hue
+                let hue = boid.flock as f32 * 0.618_034;
+                hsv_to_rgb(hue, 0.65, 1.0)
+            }
+            ColorMode::Energy => {
+                // Exhausted boids are red, rested ones green
+                hsv_to_rgb(0.33 * boid.energy, 0.75, 1.0)
+            }
+            ColorMode::Infection => {
+                let [r, g, b, _] = Epidemic::color(boid.health);
+                [r, g, b]
+            }
+        };
+
+        [r, g, b, BOID_ALPHA]
+    }
+}
+
+// Convert hue/saturation/value (all 0..1) to rgb
+pub fn hsv_to_rgb(h: f32, s: f32, v: f32) -> [f32; 3] {
+    let h = (h.fract() + 1.0).fract() * 6.0;
+    let c = v * s;
+    let x = c * (1.0 - ((h % 2.0) - 1.0).abs());
+    let m = v - c;
+
+    let (r, g, b) = match h as u32 {
+        0 => (c, x, 0.0),
+        1 => (x, c, 0.0),
+        2 => (0.0, c, x),
+        3 => (0.0, x, c),
+        4 => (x, 0.0, c),
+        _ => (c, 0.0, x),
+    };
+
+    [r + m, g + m, b + m]
+}
@@ -0,0 +1,248 @@
+// config.rs
+use crate::scoring::ScoringArea;
+use crate::theme::Theme;
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver};
+
+pub const CONFIG_PATH: &str = "boids.toml";
+
+// Settings read from boids.toml. Every section is optional.
+#[derive(Debug, Default, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub keys: HashMap<String, String>, // Action name -> key name, see input_map.rs
+    pub parameters: Parameters,
+    pub display: Display,
+    pub arena: Arena,
+    pub midi: Midi,
+    pub scoring: Vec<ScoringArea>,
+}
+
+// MIDI controller input, see midi.rs
+#[derive(Debug, Default, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Midi {
+    pub port: Option<String>,  // Part of the input port name, only read at startup
+    pub channel: Option<u8>,   // 1-16, any channel when unset
+    pub controls: HashMap<String, MidiControl>, // Parameter name -> knob
+}
+
+// A CC knob or fader and the parameter range it sweeps
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MidiControl {
+    pub cc: u8,
+    pub min: f32,
+    pub max: f32,
+}
+
+// Shape the boids are kept inside, see world.rs
+#[derive(Debug, Default, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Arena {
+    pub shape: Option<ArenaShape>, // The world rectangle when unset
+    pub points: Vec<[f32; 2]>,     // Polygon corners as fractions of the world size
+    pub mode: Option<ArenaMode>,   // Turn when unset
+    pub restitution: Option<f32>,  // Speed kept by a bounce, see world.rs for the default
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ArenaShape {
+    Rectangle,
+    Circle,
+    Polygon,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ArenaMode {
+    Turn,
+    Bounce,
+}
+
+// Window and frame pacing options
+#[derive(Debug, Default, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Display {
+    pub vsync: Option<bool>,   // On unless disabled, only read at startup
+    pub fps_cap: Option<u32>,  // Frames per second to sleep down to, uncapped when unset
+    pub ui_scale: Option<f32>, // Multiplier on top of the display's scale factor
+    pub skin: Option<PathBuf>, // PNG to draw boids with instead of the polygon
+    pub skin_frames: Option<u32>, // Animation frames side by side in the skin, 1 when unset
+    pub theme: Option<Theme>,
+}
+
+// Simulation parameters that can be changed while running. Unset values keep
+// whatever the command line or the last preset chose.
+#[derive(Debug, Default, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Parameters {
+    pub separation: Option<f32>,
+    pub cohesion: Option<f32>,
+    pub alignment: Option<f32>,
+    pub wander: Option<f32>,
+    pub goal_weight: Option<f32>,
+    pub emit_rate: Option<f32>,
+    pub food_rate: Option<f32>,
+    pub infection_probability: Option<f32>,
+    pub trait_variance: Option<f32>,
+    pub speed: Option<f32>,        // Multiplier on the speed limit
+    pub visual_range: Option<f32>, // Multiplier on how far boids see
+    pub long_range: Option<f32>,   // Pull between distant sub-flocks
+    pub neighbor_cap: Option<f32>, // Most neighbors each boid looks at, 0 for all
+}
+
+impl Parameters {
+    // Name and new value of every parameter set here that differs from `old`
+    pub fn changes(&self, old: &Parameters) -> Vec<(&'static str, f32)> {
+        let pairs = [
+            ("separation", self.separation, old.separation),
+            ("cohesion", self.cohesion, old.cohesion),
+            ("alignment", self.alignment, old.alignment),
+            ("wander", self.wander, old.wander),
+            ("goal_weight", self.goal_weight, old.goal_weight),
+            ("emit_rate", self.emit_rate, old.emit_rate),
+            ("food_rate", self.food_rate, old.food_rate),
+            ("infection_probability", self.infection_probability, old.infection_probability),
+            ("trait_variance", self.trait_variance, old.trait_variance),
+            ("speed", self.speed, old.speed),
+            ("visual_range", self.visual_range, old.visual_range),
+            ("long_range", self.long_range, old.long_range),
+            ("neighbor_cap", self.neighbor_cap, old.neighbor_cap),
+        ];
+        pairs
+            .iter()
+            .filter_map(|&(name, new, old)| match new {
+                Some(value) if new != old => Some((name, value)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    // Set one parameter by its boids.toml name, false for an unknown name
+    pub fn set(&mut self, name: &str, value: f32) -> bool {
+        let field = match name {
+            "separation" => &mut self.separation,
+            "cohesion" => &mut self.cohesion,
+            "alignment" => &mut self.alignment,
+            "wander" => &mut self.wander,
+            "goal_weight" => &mut self.goal_weight,
+            "emit_rate" => &mut self.emit_rate,
+            "food_rate" => &mut self.food_rate,
+            "infection_probability" => &mut self.infection_probability,
+            "trait_variance" => &mut self.trait_variance,
+            "speed" => &mut self.speed,
+            "visual_range" => &mut self.visual_range,
+            "long_range" => &mut self.long_range,
+            "neighbor_cap" => &mut self.neighbor_cap,
+            _ => return false,
+        };
+        *field = Some(value);
+        true
+    }
+
+    // These parameters with every value set in `changes` replaced
+    pub fn overlay(&self, changes: &Parameters) -> Parameters {
+        Parameters {
+            separation: changes.separation.or(self.separation),
+            cohesion: changes.cohesion.or(self.cohesion),
+            alignment: changes.alignment.or(self.alignment),
+            wander: changes.wander.or(self.wander),
+            goal_weight: changes.goal_weight.or(self.goal_weight),
+            emit_rate: changes.emit_rate.or(self.emit_rate),
+            food_rate: changes.food_rate.or(self.food_rate),
+            infection_probability: changes.infection_probability.or(self.infection_probability),
+            trait_variance: changes.trait_variance.or(self.trait_variance),
+            speed: changes.speed.or(self.speed),
+            visual_range: changes.visual_range.or(self.visual_range),
+            long_range: changes.long_range.or(self.long_range),
+            neighbor_cap: changes.neighbor_cap.or(self.neighbor_cap),
+        }
+    }
+}
+
+impl Config {
+    // A missing file is fine and gives the defaults
+    pub fn load(path: &Path) -> Result<Config, String> {
+        match fs::read_to_string(path) {
+            Ok(text) => toml::from_str(&text).map_err(|err| format!("{}: {}", path.display(), err)),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Config::default()),
+            Err(err) => Err(format!("{}: {}", path.display(), err)),
+        }
+    }
+}
+
+// Notices when a file changes on disk
+pub struct FileWatcher {
+    path: PathBuf,
+    events: Receiver<notify::Result<notify::Event>>,
+    _watcher: RecommendedWatcher, // Stops watching when dropped
+}
+
+impl FileWatcher {
+    pub fn new(path: &Path) -> notify::Result<FileWatcher> {
+        let (sender, events) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(move |event| {
+            let _ = sender.send(event);
+        })?;
+
+        // Editors often save by replacing the file, which ends a watch on the
+        // file itself, so watch the directory and filter by name
+        let dir = match path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+        watcher.watch(dir, RecursiveMode::NonRecursive)?;
+
+        Ok(FileWatcher {
+            path: path.to_path_buf(),
+            events,
+            _watcher: watcher,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    // Whether the file changed since the last poll
+    pub fn changed(&self) -> bool {
+        let file_name = match self.path.file_name() {
+            Some(file_name) => file_name,
+            None => return false,
+        };
+        self.events.try_iter().any(|event| match event {
+            Ok(event) => {
+                !event.kind.is_access()
+                    && event.paths.iter().any(|path| path.file_name() == Some(file_name))
+            }
+            Err(_) => false,
+        })
+    }
+}
+
+// Reloads the config file whenever it changes on disk
+pub struct ConfigWatcher {
+    file: FileWatcher,
+}
+
+impl ConfigWatcher {
+    pub fn new(path: &Path) -> notify::Result<ConfigWatcher> {
+        Ok(ConfigWatcher { file: FileWatcher::new(path)? })
+    }
+
+    // The freshly loaded config if the file changed since the last poll
+    pub fn poll(&self) -> Option<Result<Config, String>> {
+        if self.file.changed() {
+            Some(Config::load(self.file.path()))
+        } else {
+            None
+        }
+    }
+}
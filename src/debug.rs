@@ -0,0 +1,45 @@
+// debug.rs
+
+// Set of debug overlays that can be switched on at runtime
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DebugFlags(u8);
+
+impl DebugFlags {
+    pub const NONE: DebugFlags = DebugFlags(0);
+    pub const SPATIAL_GRID: DebugFlags = DebugFlags(1 << 0); // Grid lines and cell occupancy
+
+    pub fn contains(self, flags: DebugFlags) -> bool {
+        self.0 & flags.0 == flags.0
+    }
+
+    pub fn toggle(&mut self, flags: DebugFlags) {
+        self.0 ^= flags.0;
+    }
+}
+
+// How much of the HUD is drawn, cycled with <home>. Minimal keeps just the
+// frame rate; hidden leaves the flock alone for screenshots and recordings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HudMode {
+    Full,    // Text, panels and debug overlays
+    Minimal,
+    Hidden,
+}
+
+impl HudMode {
+    pub fn next(self) -> HudMode {
+        match self {
+            HudMode::Full => HudMode::Minimal,
+            HudMode::Minimal => HudMode::Hidden,
+            HudMode::Hidden => HudMode::Full,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            HudMode::Full => "Full",
+            HudMode::Minimal => "FPS only",
+            HudMode::Hidden => "Hidden",
+        }
+    }
+}
@@ -0,0 +1,82 @@
+// energy.rs
+use crate::boid::{Boid, SPEED_LIMIT};
+
+// Energy settings. Energy runs from 0 (exhausted) to 1 (fully rested).
+const BASE_DRAIN: f32 = 0.01;       // Per second, just for being alive
+const SPEED_DRAIN: f32 = 0.08;      // Per second at the speed limit, grows with speed squared
+const REST_SPEED: f32 = 0.25;       // Boids below this fraction of the speed limit are resting
+const REST_GAIN: f32 = 0.05;        // Per second while resting
+const FOOD_ENERGY: f32 = 0.4;       // Per item eaten
+const DRAFT_DISTANCE: f32 = 12.0;   // Pixels behind the boid being drafted
+const DRAFT_SAVING: f32 = 0.5;      // Fraction of the speed drain saved in a slipstream
+const DRAFT_WEIGHT: f32 = 2.0;      // Velocity change per step towards the slipstream
+
+// The spot just behind the nearest flockmate ahead that flies the same way.
+// The boid itself is skipped by having no offset, so `neighbors` may include it.
+fn drafting_target(boid: &Boid, neighbors: &[usize], boids: &[Boid]) -> Option<glam::Vec2> {
+    let pos = glam::vec2(boid.x, boid.y);
+    let velocity = glam::vec2(boid.dx, boid.dy);
+    neighbors
+        .iter()
+        .map(|&idx| &boids[idx])
+        .filter(|other| {
+            let offset = glam::vec2(other.x, other.y) - pos;
+            offset != glam::Vec2::ZERO
+                && offset.dot(velocity) > 0.0
+                && glam::vec2(other.dx, other.dy).dot(velocity) > 0.0
+        })
+        .min_by(|a, b| boid.squared_distance(a).partial_cmp(&boid.squared_distance(b)).unwrap())
+        .map(|leader| {
+            let heading = glam::vec2(leader.dx, leader.dy).normalize_or_zero();
+            glam::vec2(leader.x, leader.y) - heading * DRAFT_DISTANCE
+        })
+}
+
+// Tired boids tuck in behind the nearest flockmate ahead of them
+pub fn drafting_force(boid: &Boid, neighbors: &[usize], boids: &[Boid]) -> glam::Vec2 {
+    if boid.stamina() >= 1.0 {
+        return glam::Vec2::ZERO;
+    }
+    match drafting_target(boid, neighbors, boids) {
+        Some(target) => (target - glam::vec2(boid.x, boid.y)).normalize_or_zero() * DRAFT_WEIGHT,
+        None => glam::Vec2::ZERO,
+    }
+}
+
+// Drain energy by speed, less in a slipstream, and recover it while resting.
+// `neighbor_lists` must match `boids`, as built at the start of the step.
+pub fn update(boids: &mut [Boid], neighbor_lists: &[Vec<usize>], tick: f32) {
+    let changes: Vec<f32> = boids
+        .iter()
+        .enumerate()
+        .map(|(i, boid)| {
+            let speed = (boid.dx * boid.dx + boid.dy * boid.dy).sqrt() / SPEED_LIMIT;
+            if speed < REST_SPEED {
+                return REST_GAIN * tick;
+            }
+
+            let in_slipstream = drafting_target(boid, &neighbor_lists[i], boids)
+                .map_or(false, |target| (target - glam::vec2(boid.x, boid.y)).length() < DRAFT_DISTANCE);
+            let saving = if in_slipstream { DRAFT_SAVING } else { 0.0 };
+            -(BASE_DRAIN + SPEED_DRAIN * speed * speed * (1.0 - saving)) * tick
+        })
+        .collect();
+
+    for (boid, change) in boids.iter_mut().zip(changes) {
+        boid.energy = (boid.energy + change).max(0.0).min(1.0);
+    }
+}
+
+// Top up the boids that just ate
+pub fn feed(boids: &mut [Boid], eaters: &[usize]) {
+    for &i in eaters {
+        boids[i].energy = (boids[i].energy + FOOD_ENERGY).min(1.0);
+    }
+}
+
+// Everyone back to full, used when the energy model is switched off
+pub fn restore(boids: &mut [Boid]) {
+    for boid in boids {
+        boid.energy = 1.0;
+    }
+}
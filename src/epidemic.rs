@@ -0,0 +1,120 @@
+// epidemic.rs
+use crate::boid::{Boid, Health, MIN_DISTANCE};
+use crate::charts::PopulationChart;
+use crate::math;
+use rand::Rng;
+
+// Epidemic settings
+pub const INFECTION_PROBABILITY: f32 = 0.05; // Chance per 1/60s that a close contact passes it on
+const PROBABILITY_RATE: f32 = 60.0;          // Steps per second the probability is given for
+const RECOVERY_SECONDS: f32 = 8.0;           // Simulated time until an infected boid recovers
+
+const SUSCEPTIBLE_COLOR: [f32; 4] = [0.5, 0.7, 1.0, 0.9];
+const INFECTED_COLOR: [f32; 4] = [1.0, 0.35, 0.3, 0.9];
+const RECOVERED_COLOR: [f32; 4] = [0.6, 0.6, 0.6, 0.9];
+
+// Boids in each state at one moment
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SirCounts {
+    pub susceptible: usize,
+    pub infected: usize,
+    pub recovered: usize,
+}
+
+impl SirCounts {
+    pub fn count(boids: &[Boid]) -> Self {
+        let mut counts = SirCounts::default();
+        for boid in boids {
+            match boid.health {
+                Health::Susceptible => counts.susceptible += 1,
+                Health::Infected(_) => counts.infected += 1,
+                Health::Recovered => counts.recovered += 1,
+            }
+        }
+        counts
+    }
+}
+
+// SIR infection model, toggled with <i>. Starting it infects one random boid;
+// it then spreads to boids within MIN_DISTANCE of an infected one, and each
+// infected boid recovers for good after a while.
+#[derive(Clone)]
+pub struct Epidemic {
+    pub active: bool,
+    pub probability: f32,
+    pub chart: PopulationChart, // S, I and R counts over time
+}
+
+impl Epidemic {
+    pub fn new(probability: f32) -> Self {
+        Epidemic {
+            active: false,
+            probability,
+            chart: PopulationChart::new(&[
+                ("S", SUSCEPTIBLE_COLOR),
+                ("I", INFECTED_COLOR),
+                ("R", RECOVERED_COLOR),
+            ]),
+        }
+    }
+
+    // Start a new outbreak from a single random boid, or end the current one
+    pub fn toggle(&mut self, boids: &mut [Boid], rng: &mut impl Rng) {
+        self.active = !self.active;
+        self.chart.clear();
+        for boid in boids.iter_mut() {
+            boid.health = Health::Susceptible;
+        }
+        if self.active && !boids.is_empty() {
+            let patient_zero = rng.gen_range(0..boids.len());
+            boids[patient_zero].health = Health::Infected(RECOVERY_SECONDS);
+        }
+    }
+
+    // Spread the infection along close contacts and advance recovery.
+    // `neighbor_lists` must match `boids`, as built at the start of the step.
+    pub fn step(&mut self, boids: &mut [Boid], neighbor_lists: &[Vec<usize>], tick: f32, rng: &mut impl Rng) {
+        // Compounded over however many of those steps this one spans, so the
+        // spread doesn't depend on the frame rate
+        let chance = 1.0 - math::powf(1.0 - self.probability, tick * PROBABILITY_RATE);
+        
+        // Infections from this step only become contagious on the next one
+        let mut infected = Vec::new();
+        for (i, boid) in boids.iter().enumerate() {
+            if let Health::Infected(_) = boid.health {
+                for &j in &neighbor_lists[i] {
+                    let other = &boids[j];
+                    if other.health == Health::Susceptible
+                        && boid.squared_distance(other) < MIN_DISTANCE * MIN_DISTANCE
+                        && rng.gen::<f32>() < chance
+                    {
+                        infected.push(j);
+                    }
+                }
+            }
+        }
+
+        for boid in boids.iter_mut() {
+            if let Health::Infected(remaining) = boid.health {
+                boid.health = if remaining > tick { Health::Infected(remaining - tick) } else { Health::Recovered };
+            }
+        }
+        for j in infected {
+            boids[j].health = Health::Infected(RECOVERY_SECONDS);
+        }
+
+        self.chart.sample(tick, || {
+            let counts = SirCounts::count(boids);
+            vec![counts.susceptible as f32, counts.infected as f32, counts.recovered as f32]
+        });
+    }
+
+    // Color of a boid by its state, for the infection color mode
+    pub fn color(health: Health) -> [f32; 4] {
+        match health {
+            Health::Susceptible => SUSCEPTIBLE_COLOR,
+            Health::Infected(_) => INFECTED_COLOR,
+            Health::Recovered => RECOVERED_COLOR,
+        }
+    }
+}
@@ -0,0 +1,232 @@
+// evolution.rs
+use crate::boid::{Boid, Traits};
+use rand::Rng;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+// Evolution settings
+pub const EVOLUTION_LOG: &str = "evolution.csv"; // Where generation stats go without --evolution-log
+const GENERATION_SECONDS: f32 = 30.0;  // Simulated time each generation is scored over
+const ELITE_FRACTION: f32 = 0.2;       // The fittest boids keep their genome and parent the rest
+const MUTATION_RATE: f32 = 0.2;        // Chance per gene of a mutation
+const MUTATION_SIZE: f32 = 0.15;       // Largest change a mutation makes
+const MIN_GENE: f32 = 0.2;             // Weight multipliers are kept in this range
+const MAX_GENE: f32 = 3.0;
+const DANGER_RANGE: f32 = 120.0;       // Pixels, predators this close make survival count extra
+const CROWD_SIZE: f32 = 8.0;           // Neighbors that count fully towards cohesion
+
+// Behavior weights a boid passes on, stored as its separation, cohesion and
+// alignment traits
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Genome {
+    pub separation: f32,
+    pub cohesion: f32,
+    pub alignment: f32,
+}
+
+impl Genome {
+    fn of(traits: &Traits) -> Self {
+        Genome {
+            separation: traits.separation,
+            cohesion: traits.cohesion,
+            alignment: traits.alignment,
+        }
+    }
+
+    fn apply(&self, traits: &mut Traits) {
+        traits.separation = self.separation;
+        traits.cohesion = self.cohesion;
+        traits.alignment = self.alignment;
+    }
+
+    // Each gene from either parent, then maybe nudged
+    fn breed(a: &Genome, b: &Genome, rng: &mut impl Rng) -> Genome {
+        let mut gene = |x: f32, y: f32| {
+            let inherited = if rng.gen::<bool>() { x } else { y };
+            let mutation = if rng.gen::<f32>() < MUTATION_RATE {
+                rng.gen_range(-MUTATION_SIZE..=MUTATION_SIZE)
+            } else {
+                0.0
+            };
+            (inherited + mutation).max(MIN_GENE).min(MAX_GENE)
+        };
+        Genome {
+            separation: gene(a.separation, b.separation),
+            cohesion: gene(a.cohesion, b.cohesion),
+            alignment: gene(a.alignment, b.alignment),
+        }
+    }
+}
+
+// What a fitness function gets to look at besides the boid
+pub struct FitnessContext<'a> {
+    pub tick: f32,
+    pub predators: &'a [glam::Vec2],
+}
+
+// Scores a boid for one step; the scores add up over a generation
+pub trait Fitness: Sync {
+    fn name(&self) -> &'static str;
+    fn score(&self, boid: &Boid, context: &FitnessContext) -> f32;
+}
+
+// Seconds alive, doubled while a predator is close. Caught boids are gone
+// before breeding, so their genomes die out.
+pub struct Survival;
+
+impl Fitness for Survival {
+    fn name(&self) -> &'static str {
+        "Survival"
+    }
+
+    fn score(&self, boid: &Boid, context: &FitnessContext) -> f32 {
+        let pos = glam::vec2(boid.x, boid.y);
+        let in_danger = context
+            .predators
+            .iter()
+            .any(|predator| predator.distance_squared(pos) < DANGER_RANGE * DANGER_RANGE);
+        if in_danger {
+            2.0 * context.tick
+        } else {
+            context.tick
+        }
+    }
+}
+
+// Time spent among flockmates, up to CROWD_SIZE of them
+pub struct Cohesion;
+
+impl Fitness for Cohesion {
+    fn name(&self) -> &'static str {
+        "Cohesion"
+    }
+
+    fn score(&self, boid: &Boid, context: &FitnessContext) -> f32 {
+        (boid.neighbor_count as f32).min(CROWD_SIZE) / CROWD_SIZE * context.tick
+    }
+}
+
+// How a generation did, as written to the log
+#[derive(Debug, Clone, Copy)]
+pub struct GenerationStats {
+    pub generation: usize,
+    pub best_fitness: f32,
+    pub mean_fitness: f32,
+    pub best: Genome,
+}
+
+// Appends one CSV row per generation
+pub struct EvolutionLog {
+    writer: BufWriter<File>,
+}
+
+impl EvolutionLog {
+    pub fn create(path: &Path) -> io::Result<EvolutionLog> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        writeln!(writer, "generation,best_fitness,mean_fitness,best_separation,best_cohesion,best_alignment")?;
+        Ok(EvolutionLog { writer })
+    }
+
+    pub fn write(&mut self, stats: &GenerationStats) -> io::Result<()> {
+        writeln!(
+            self.writer,
+            "{},{:.3},{:.3},{:.4},{:.4},{:.4}",
+            stats.generation,
+            stats.best_fitness,
+            stats.mean_fitness,
+            stats.best.separation,
+            stats.best.cohesion,
+            stats.best.alignment,
+        )?;
+        // Generations are far apart, so keep the file current
+        self.writer.flush()
+    }
+}
+
+// Genetic algorithm over the boids' behavior weights, cycled through the
+// fitness functions with <f7>. Every GENERATION_SECONDS the fittest boids
+// keep their genome and the others take a crossover of two of them.
+#[derive(Clone)]
+pub struct Evolution {
+    functions: Vec<&'static dyn Fitness>,
+    selected: Option<usize>, // Index into `functions`, None while off
+    elapsed: f32,            // Simulated time into the current generation
+    pub generation: usize,
+}
+
+impl Evolution {
+    pub fn new() -> Self {
+        Evolution {
+            functions: vec![&Survival, &Cohesion],
+            selected: None,
+            elapsed: 0.0,
+            generation: 0,
+        }
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.selected.is_some()
+    }
+
+    // Name of the fitness function in use, or "Off"
+    pub fn name(&self) -> &'static str {
+        self.selected.map_or("Off", |i| self.functions[i].name())
+    }
+
+    // Switch to the next fitness function, or off after the last one. Every
+    // switch starts over from generation 0.
+    pub fn cycle(&mut self, boids: &mut [Boid]) {
+        self.selected = match self.selected {
+            None if !self.functions.is_empty() => Some(0),
+            Some(i) if i + 1 < self.functions.len() => Some(i + 1),
+            _ => None,
+        };
+        self.elapsed = 0.0;
+        self.generation = 0;
+        for boid in boids {
+            boid.fitness = 0.0;
+        }
+    }
+
+    // Score every boid for this step, and breed when the generation is up
+    pub fn step(&mut self, boids: &mut [Boid], predators: &[glam::Vec2], tick: f32, rng: &mut impl Rng) -> Option<GenerationStats> {
+        let fitness = &self.functions[self.selected?];
+        let context = FitnessContext { tick, predators };
+        for boid in boids.iter_mut() {
+            boid.fitness += fitness.score(boid, &context);
+        }
+
+        self.elapsed += tick;
+        if self.elapsed < GENERATION_SECONDS || boids.len() < 2 {
+            return None;
+        }
+        self.elapsed = 0.0;
+        Some(self.breed(boids, rng))
+    }
+
+    fn breed(&mut self, boids: &mut [Boid], rng: &mut impl Rng) -> GenerationStats {
+        let mut order: Vec<usize> = (0..boids.len()).collect();
+        order.sort_by(|&a, &b| boids[b].fitness.partial_cmp(&boids[a].fitness).unwrap());
+        let elite = ((boids.len() as f32 * ELITE_FRACTION) as usize).max(2);
+        let parents: Vec<Genome> = order[..elite].iter().map(|&i| Genome::of(&boids[i].traits)).collect();
+
+        let stats = GenerationStats {
+            generation: self.generation,
+            best_fitness: boids[order[0]].fitness,
+            mean_fitness: boids.iter().map(|boid| boid.fitness).sum::<f32>() / boids.len() as f32,
+            best: parents[0],
+        };
+
+        for &i in &order[elite..] {
+            let a = &parents[rng.gen_range(0..parents.len())];
+            let b = &parents[rng.gen_range(0..parents.len())];
+            Genome::breed(a, b, rng).apply(&mut boids[i].traits);
+        }
+        for boid in boids.iter_mut() {
+            boid.fitness = 0.0;
+        }
+        self.generation += 1;
+        stats
+    }
+}
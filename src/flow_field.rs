@@ -0,0 +1,140 @@
+// flow_field.rs
+#[cfg(feature = "render-ggez")]
+use ggez::{graphics, GameResult};
+
+// Flow field settings
+pub const FLOW_CELL_SIZE: f32 = 32.0; // Pixels
+pub const FLOW_STRENGTH: f32 = 20.0;  // Velocity change per step at full field strength
+pub const BRUSH_RADIUS: f32 = 48.0;   // Pixels, in world space
+const BRUSH_AMOUNT: f32 = 0.35;       // How much one drag event bends a cell
+
+// Grid of direction vectors painted by the user that pushes boids along
+#[derive(Clone)]
+pub struct FlowField {
+    cells: Vec<glam::Vec2>,
+    cell_size: f32,
+    width: usize,
+    height: usize,
+    painted: bool,
+}
+
+impl FlowField {
+    pub fn new(world_width: f32, world_height: f32, cell_size: f32) -> Self {
+        let width = (world_width / cell_size).ceil() as usize;
+        let height = (world_height / cell_size).ceil() as usize;
+
+        FlowField {
+            cells: vec![glam::Vec2::ZERO; width * height],
+            cell_size,
+            width,
+            height,
+            painted: false,
+        }
+    }
+
+    pub fn clear(&mut self) {
+        for cell in &mut self.cells {
+            *cell = glam::Vec2::ZERO;
+        }
+        self.painted = false;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        !self.painted
+    }
+
+    // Fit the field to a new world size. Each cell takes the direction from
+    // where it was before the world was scaled by `scale`, so the painted flow
+    // stays put with a scale of one and stretches with the world otherwise.
+    pub fn resize(&mut self, world_width: f32, world_height: f32, scale: glam::Vec2) {
+        let mut resized = FlowField::new(world_width, world_height, self.cell_size);
+        if self.painted {
+            for cell_y in 0..resized.height {
+                for cell_x in 0..resized.width {
+                    let center = (glam::vec2(cell_x as f32, cell_y as f32) + 0.5) * self.cell_size / scale;
+                    resized.cells[cell_y * resized.width + cell_x] = self.sample(center.x, center.y);
+                }
+            }
+            resized.painted = true;
+        }
+        *self = resized;
+    }
+
+    fn cell_index(&self, x: f32, y: f32) -> Option<usize> {
+        if x < 0.0 || y < 0.0 {
+            return None;
+        }
+        let cell_x = (x / self.cell_size) as usize;
+        let cell_y = (y / self.cell_size) as usize;
+        if cell_x >= self.width || cell_y >= self.height {
+            return None;
+        }
+        Some(cell_y * self.width + cell_x)
+    }
+
+    // Field direction at a world position, zero outside the field
+    pub fn sample(&self, x: f32, y: f32) -> glam::Vec2 {
+        self.cell_index(x, y).map_or(glam::Vec2::ZERO, |idx| self.cells[idx])
+    }
+
+    // Blend `direction` into every cell within `radius` of `center`, strongest in the middle
+    pub fn paint(&mut self, center: glam::Vec2, direction: glam::Vec2, radius: f32) {
+        if direction == glam::Vec2::ZERO {
+            return;
+        }
+        let direction = direction.normalize();
+
+        let min_x = ((center.x - radius) / self.cell_size).floor().max(0.0) as usize;
+        let min_y = ((center.y - radius) / self.cell_size).floor().max(0.0) as usize;
+        let max_x = (((center.x + radius) / self.cell_size).ceil().max(0.0) as usize).min(self.width);
+        let max_y = (((center.y + radius) / self.cell_size).ceil().max(0.0) as usize).min(self.height);
+
+        for cell_y in min_y..max_y {
+            for cell_x in min_x..max_x {
+                let cell_center = glam::vec2(
+                    (cell_x as f32 + 0.5) * self.cell_size,
+                    (cell_y as f32 + 0.5) * self.cell_size,
+                );
+                let dist = (cell_center - center).length();
+                if dist > radius {
+                    continue;
+                }
+
+                let falloff = 1.0 - dist / radius;
+                let cell = &mut self.cells[cell_y * self.width + cell_x];
+                *cell = (*cell + direction * BRUSH_AMOUNT * falloff).clamp_length_max(1.0);
+                self.painted = true;
+            }
+        }
+    }
+
+    // Short strokes showing the direction and strength of every painted cell
+    #[cfg(feature = "render-ggez")]
+    pub fn draw(&self, mb: &mut graphics::MeshBuilder, line_width: f32) -> GameResult {
+        if !self.painted {
+            return Ok(());
+        }
+
+        for (idx, &flow) in self.cells.iter().enumerate() {
+            if flow.length_squared() < 0.01 {
+                continue;
+            }
+
+            let center = glam::vec2(
+                ((idx % self.width) as f32 + 0.5) * self.cell_size,
+                ((idx / self.width) as f32 + 0.5) * self.cell_size,
+            );
+            let half = flow * self.cell_size * 0.4;
+            mb.line(&[center - half, center + half], line_width, [0.6, 0.8, 1.0, 0.25].into())?;
+            mb.circle(
+                graphics::DrawMode::fill(),
+                center + half,
+                1.5 * line_width,
+                0.1,
+                [0.6, 0.8, 1.0, 0.4].into(),
+            )?;
+        }
+
+        Ok(())
+    }
+}
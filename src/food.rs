@@ -0,0 +1,148 @@
+// food.rs
+use crate::boid::Boid;
+use crate::spatial_grid::SpatialGrid;
+use crate::world::World;
+#[cfg(feature = "render-ggez")]
+use ggez::{graphics, GameResult};
+use rand::Rng;
+
+// Foraging settings
+pub const FOOD_RATE: f32 = 5.0;     // Items per second respawned while food is on
+const INITIAL_FOOD: usize = 60;     // Scattered when food is switched on
+const MAX_FOOD: usize = 300;        // Respawning pauses at this many items
+const SENSE_RANGE: f32 = 120.0;     // Boids notice food this close
+const EAT_RADIUS: f32 = 6.0;        // Pixels, touching an item eats it
+const FORAGE_WEIGHT: f32 = 0.3;     // Velocity change per step towards the nearest item
+const FOOD_CELL_SIZE: f32 = SENSE_RANGE;
+
+// Food items scattered over the world, toggled with <h>. Boids steer for the
+// nearest item they can sense and eat it on contact, and new items appear at
+// random spots at a steady rate. The items live in their own spatial grid, so
+// each boid only looks at the cells around it.
+#[derive(Clone)]
+pub struct Food {
+    pub active: bool,
+    pub items: Vec<glam::Vec2>,
+    grid: SpatialGrid,
+    pub rate: f32,
+    pending: f32,  // Fractional items owed from previous steps
+}
+
+impl Food {
+    pub fn new(world: &World, rate: f32) -> Self {
+        Food {
+            active: false,
+            items: Vec::new(),
+            grid: SpatialGrid::new(world.width, world.height, FOOD_CELL_SIZE),
+            rate,
+            pending: 0.0,
+        }
+    }
+
+    // Switching on covers the world with a first batch of items, switching
+    // off removes them all
+    pub fn toggle(&mut self, world: &World, rng: &mut impl Rng) {
+        self.active = !self.active;
+        self.items.clear();
+        self.pending = 0.0;
+        if self.active {
+            for _ in 0..INITIAL_FOOD {
+                self.add_random(world, rng);
+            }
+        }
+        self.grid.build(&self.items);
+    }
+
+    // Fit the grid to new world bounds, dropping items that ended up outside
+    // Move the items with a world rescaled by `scale`, resize rebuilds the grid
+    pub fn rescale(&mut self, scale: glam::Vec2) {
+        for item in &mut self.items {
+            *item *= scale;
+        }
+    }
+
+    pub fn resize(&mut self, world: &World) {
+        self.items.retain(|item| item.x <= world.width && item.y <= world.height);
+        self.grid = SpatialGrid::new(world.width, world.height, FOOD_CELL_SIZE);
+        self.grid.build(&self.items);
+    }
+
+    // Pull towards the nearest item within sensing range
+    pub fn steer(&self, boid: &Boid) -> glam::Vec2 {
+        let pos = glam::vec2(boid.x, boid.y);
+        let nearest = self
+            .grid
+            .query_radius(pos, SENSE_RANGE)
+            .into_iter()
+            .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap());
+
+        match nearest {
+            Some((i, squared_distance)) if squared_distance > 0.0 => {
+                (self.items[i] - pos).normalize() * FORAGE_WEIGHT
+            }
+            _ => glam::Vec2::ZERO,
+        }
+    }
+
+    // Remove every item a boid is touching and return the indices of the
+    // boids that ate. An item shared by several boids goes to the first.
+    pub fn eat(&mut self, boids: &[Boid]) -> Vec<usize> {
+        if self.items.is_empty() {
+            return Vec::new();
+        }
+
+        let mut taken = vec![false; self.items.len()];
+        let mut eaters = Vec::new();
+        for (i, boid) in boids.iter().enumerate() {
+            let found = self
+                .grid
+                .query_radius(glam::vec2(boid.x, boid.y), EAT_RADIUS)
+                .into_iter()
+                .find(|&(item, _)| !taken[item]);
+            if let Some((item, _)) = found {
+                taken[item] = true;
+                eaters.push(i);
+            }
+        }
+
+        if !eaters.is_empty() {
+            let mut index = 0;
+            self.items.retain(|_| {
+                index += 1;
+                !taken[index - 1]
+            });
+            self.grid.build(&self.items);
+        }
+        eaters
+    }
+
+    // Add the items due over `tick` seconds
+    pub fn respawn(&mut self, tick: f32, world: &World, rng: &mut impl Rng) {
+        if !self.active {
+            return;
+        }
+        self.pending += self.rate * tick;
+        // Whole items only, carrying the fraction over to the next step
+        let due = self.pending.floor();
+        self.pending -= due;
+        let count = (due as usize).min(MAX_FOOD.saturating_sub(self.items.len()));
+        for _ in 0..count {
+            self.add_random(world, rng);
+        }
+        if count > 0 {
+            self.grid.build(&self.items);
+        }
+    }
+
+    fn add_random(&mut self, world: &World, rng: &mut impl Rng) {
+        self.items.push(glam::vec2(rng.gen::<f32>() * world.width, rng.gen::<f32>() * world.height));
+    }
+
+    #[cfg(feature = "render-ggez")]
+    pub fn draw(&self, mb: &mut graphics::MeshBuilder) -> GameResult {
+        for &item in &self.items {
+            mb.circle(graphics::DrawMode::fill(), item, 3.0, 0.1, [0.5, 0.9, 0.3, 0.9].into())?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,126 @@
+// gif_capture.rs
+use crate::recorder::{Frame, RECORDINGS_DIR};
+use std::collections::VecDeque;
+use std::fs::{self, File};
+use std::io;
+use std::path::Path;
+use std::thread;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::{error, info};
+
+// GIF settings
+pub const GIF_SECONDS: f32 = 5.0;
+const GIF_FPS: u32 = 15;
+const GIF_DOWNSCALE: u32 = 3; // Keeps the buffer small and the file shareable
+const GIF_ENCODE_SPEED: i32 = 10; // 1 (best quality) - 30 (fastest)
+
+// Rolling buffer of the last few seconds of downscaled frames
+pub struct GifBuffer {
+    frames: VecDeque<Frame>,
+    since_capture: f32,
+}
+
+impl GifBuffer {
+    pub fn new() -> Self {
+        GifBuffer {
+            frames: VecDeque::new(),
+            since_capture: 0.0,
+        }
+    }
+
+    // Whether enough time has passed that the next rendered frame should be kept
+    pub fn wants_frame(&mut self, dt: f32) -> bool {
+        self.since_capture += dt;
+        if self.since_capture >= 1.0 / GIF_FPS as f32 {
+            self.since_capture = 0.0;
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn push(&mut self, frame: &Frame) {
+        let max_frames = (GIF_SECONDS * GIF_FPS as f32) as usize;
+        while self.frames.len() >= max_frames {
+            self.frames.pop_front();
+        }
+        self.frames.push_back(downscale(frame, GIF_DOWNSCALE));
+    }
+
+    // Encode the buffered frames to a GIF on a background thread
+    pub fn save(&self) {
+        if self.frames.is_empty() {
+            info!("No frames captured yet");
+            return;
+        }
+
+        let frames: Vec<Frame> = self.frames.iter().map(|frame| Frame {
+            width: frame.width,
+            height: frame.height,
+            rgba: frame.rgba.clone(),
+        }).collect();
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs())
+            .unwrap_or(0);
+        let path = Path::new(RECORDINGS_DIR).join(format!("boids-{}.gif", timestamp));
+
+        thread::spawn(move || match encode_gif(&path, frames) {
+            Ok(()) => info!("Saved {}", path.display()),
+            Err(err) => error!("Failed to save {}: {}", path.display(), err),
+        });
+    }
+}
+
+// Box-filter a frame down by an integer factor
+fn downscale(frame: &Frame, factor: u32) -> Frame {
+    let width = (frame.width / factor).max(1);
+    let height = (frame.height / factor).max(1);
+    let mut rgba = Vec::with_capacity((width * height * 4) as usize);
+
+    for y in 0..height {
+        for x in 0..width {
+            let mut sum = [0u32; 4];
+            for sy in y * factor..((y + 1) * factor).min(frame.height) {
+                for sx in x * factor..((x + 1) * factor).min(frame.width) {
+                    let idx = ((sy * frame.width + sx) * 4) as usize;
+                    for (channel, total) in sum.iter_mut().enumerate() {
+                        *total += frame.rgba[idx + channel] as u32;
+                    }
+                }
+            }
+            for total in &sum {
+                rgba.push((total / (factor * factor)) as u8);
+            }
+        }
+    }
+
+    Frame { width, height, rgba }
+}
+
+fn encode_gif(path: &Path, frames: Vec<Frame>) -> io::Result<()> {
+    fs::create_dir_all(RECORDINGS_DIR)?;
+
+    let (width, height) = (frames[0].width as u16, frames[0].height as u16);
+    let file = File::create(path)?;
+    let mut encoder = gif::Encoder::new(file, width, height, &[])
+        .map_err(|err| io::Error::new(io::ErrorKind::Other, err.to_string()))?;
+    encoder
+        .set_repeat(gif::Repeat::Infinite)
+        .map_err(|err| io::Error::new(io::ErrorKind::Other, err.to_string()))?;
+
+    for mut frame in frames {
+        // Skip frames captured before a window resize
+        if frame.width as u16 != width || frame.height as u16 != height {
+            continue;
+        }
+
+        let mut gif_frame = gif::Frame::from_rgba_speed(width, height, &mut frame.rgba, GIF_ENCODE_SPEED);
+        gif_frame.delay = (100 / GIF_FPS) as u16; // Hundredths of a second
+        encoder
+            .write_frame(&gif_frame)
+            .map_err(|err| io::Error::new(io::ErrorKind::Other, err.to_string()))?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,398 @@
+// gpu.rs
+use crate::behavior::Weights;
+use crate::boid::{Boid, MIN_DISTANCE, VISUAL_RANGE};
+use crate::world::World;
+use bytemuck::{Pod, Zeroable};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use wgpu::util::DeviceExt;
+
+// GPU settings
+const WORKGROUP_SIZE: u32 = 64; // Must match @workgroup_size in gpu.wgsl
+const READBACKS: usize = 2;     // Steps in flight, so a copy can be queued while the last is read
+
+// Boid state as stored on the GPU
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod, Zeroable)]
+struct GpuBoid {
+    pos: [f32; 2],
+    vel: [f32; 2],
+}
+
+// What the flock pass works out for each boid. Must match Steering in
+// gpu.wgsl.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod, Zeroable)]
+struct GpuSteering {
+    force: [f32; 2],
+    neighbors: u32, // Other boids within visual range
+    _pad: u32,
+}
+
+// Uniforms for both passes, padded to a multiple of 16 bytes. Must match
+// Params in gpu.wgsl.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod, Zeroable)]
+struct Params {
+    count: u32,
+    grid_width: u32,
+    grid_height: u32,
+    cell_capacity: u32,
+    cell_size: f32,
+    visual_range: f32,
+    min_distance: f32,
+    separation: f32,
+    cohesion: f32,
+    alignment: f32,
+    _pad: [f32; 2],
+}
+
+// A buffer the steering is copied into and mapped from without waiting
+struct Readback {
+    buffer: wgpu::Buffer,
+    mapped: Arc<AtomicBool>, // Set by wgpu once the copy can be read
+    pending: bool,           // Copied into and not read yet
+    count: usize,            // Boids in the copy
+    population: u64,         // Simulation::population of those boids
+    step: u64,               // When it was queued, so results are read in order
+}
+
+// Buffers sized for a number of boids, grid cells and boids per cell, kept
+// from step to step and only recreated when the flock outgrows them. The
+// bind group keeps the cell item buffer alive.
+struct Buffers {
+    params: wgpu::Buffer,
+    boids_in: wgpu::Buffer,
+    steering_out: wgpu::Buffer,
+    cell_counts: wgpu::Buffer,
+    readbacks: Vec<Readback>,
+    bind_group: wgpu::BindGroup,
+    capacity: usize,
+    cells: usize,
+    cell_capacity: u32,
+}
+
+// Experimental backend, selected with --backend gpu. Neighbor search and the
+// classic rules run in compute shaders, which work out each boid's steering
+// and neighbor count; the rest of the step runs on the CPU as usual, without
+// a CPU neighbor search unless something else needs the lists. Results are
+// read back without stalling the frame, so the steering is a step or two
+// behind the boids. Per-boid trait weights and scripted behaviors only apply
+// on the CPU.
+pub struct GpuFlock {
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    bind_group_layout: wgpu::BindGroupLayout,
+    bin_pipeline: wgpu::ComputePipeline,
+    flock_pipeline: wgpu::ComputePipeline,
+    buffers: Option<Buffers>,
+    steering: Vec<glam::Vec2>, // Latest steering read back, by boid
+    neighbor_counts: Vec<u32>, // And how many neighbors each boid had
+    steps: u64,
+    read_step: u64,            // When the latest steering was queued
+    read_population: u64,      // And for which population
+    pub adapter_name: String,
+}
+
+impl GpuFlock {
+    pub fn new() -> Result<GpuFlock, String> {
+        let instance = wgpu::Instance::new(wgpu::InstanceDescriptor::default());
+        let adapter = pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
+            power_preference: wgpu::PowerPreference::HighPerformance,
+            compatible_surface: None,
+            force_fallback_adapter: false,
+        }))
+        .ok_or("no GPU adapter found")?;
+        let adapter_name = adapter.get_info().name;
+
+        let (device, queue) = pollster::block_on(adapter.request_device(
+            &wgpu::DeviceDescriptor {
+                label: Some("boids"),
+                required_features: wgpu::Features::empty(),
+                required_limits: wgpu::Limits::default(),
+            },
+            None,
+        ))
+        .map_err(|err| err.to_string())?;
+
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("flock"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("gpu.wgsl").into()),
+        });
+
+        let storage = |binding: u32, read_only: bool| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Storage { read_only },
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("flock"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                storage(1, true),
+                storage(2, false),
+                storage(3, false),
+                storage(4, false),
+            ],
+        });
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("flock"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = |entry_point: &str| {
+            device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                label: Some(entry_point),
+                layout: Some(&layout),
+                module: &shader,
+                entry_point,
+            })
+        };
+        let bin_pipeline = pipeline("bin");
+        let flock_pipeline = pipeline("flock");
+
+        Ok(GpuFlock {
+            device,
+            queue,
+            bind_group_layout,
+            bin_pipeline,
+            flock_pipeline,
+            buffers: None,
+            steering: Vec::new(),
+            neighbor_counts: Vec::new(),
+            steps: 0,
+            read_step: 0,
+            read_population: 0,
+            adapter_name,
+        })
+    }
+
+    // Queue the steering for `boids`, numbered `population` as in
+    // Simulation::population, and return the latest steering and neighbor
+    // counts that have come back, if they are for the same boids
+    pub fn steering(
+        &mut self,
+        boids: &[Boid],
+        population: u64,
+        weights: Weights,
+        world: &World,
+        visual_range: f32,
+    ) -> Option<(&[glam::Vec2], &[u32])> {
+        self.collect();
+        if !boids.is_empty() {
+            self.queue_step(boids, population, weights, world, visual_range);
+        }
+        // The same number of boids doesn't mean the same boids, as one could
+        // have left through a sink while another was emitted
+        if self.read_population == population && self.steering.len() == boids.len() && !boids.is_empty() {
+            Some((&self.steering, &self.neighbor_counts))
+        } else {
+            None
+        }
+    }
+
+    // Read any copies that have finished, oldest first, without waiting
+    fn collect(&mut self) {
+        self.device.poll(wgpu::Maintain::Poll);
+        let buffers = match self.buffers.as_mut() {
+            Some(buffers) => buffers,
+            None => return,
+        };
+        buffers.readbacks.sort_by_key(|readback| readback.step);
+        for readback in &mut buffers.readbacks {
+            if !readback.pending || !readback.mapped.load(Ordering::Acquire) {
+                continue;
+            }
+            if readback.step > self.read_step {
+                let size = (readback.count * std::mem::size_of::<GpuSteering>()) as wgpu::BufferAddress;
+                let data = readback.buffer.slice(..size).get_mapped_range();
+                let steering: &[GpuSteering] = bytemuck::cast_slice(&data);
+                self.steering.clear();
+                self.steering.extend(steering.iter().map(|steering| glam::Vec2::from(steering.force)));
+                self.neighbor_counts.clear();
+                self.neighbor_counts.extend(steering.iter().map(|steering| steering.neighbors));
+                self.read_step = readback.step;
+                self.read_population = readback.population;
+            }
+            readback.buffer.unmap();
+            readback.mapped.store(false, Ordering::Release);
+            readback.pending = false;
+        }
+    }
+
+    // Upload the boids and run both passes, copying the result into a free
+    // readback buffer. Skipped while every readback is still in flight.
+    fn queue_step(&mut self, boids: &[Boid], population: u64, weights: Weights, world: &World, visual_range: f32) {
+        // Cells at least as wide as the visual range, so the 3x3 block covers it
+        let cell_size = visual_range.max(VISUAL_RANGE);
+        let grid_width = ((world.width / cell_size).ceil() as u32).max(1);
+        let grid_height = ((world.height / cell_size).ceil() as u32).max(1);
+
+        // Size the cells for the most crowded one, so no boid is left out
+        let mut counts = vec![0u32; (grid_width * grid_height) as usize];
+        for boid in boids {
+            let x = ((boid.x / cell_size).floor().max(0.0) as u32).min(grid_width - 1);
+            let y = ((boid.y / cell_size).floor().max(0.0) as u32).min(grid_height - 1);
+            counts[(y * grid_width + x) as usize] += 1;
+        }
+        let most_crowded = counts.iter().copied().max().unwrap_or(1);
+        self.reserve(boids.len(), counts.len(), most_crowded);
+
+        self.steps += 1;
+        let step = self.steps;
+        let buffers = match self.buffers.as_mut() {
+            Some(buffers) => buffers,
+            None => return,
+        };
+        let readback = match buffers.readbacks.iter_mut().find(|readback| !readback.pending) {
+            Some(readback) => readback,
+            None => return,
+        };
+
+        let params = Params {
+            count: boids.len() as u32,
+            grid_width,
+            grid_height,
+            cell_capacity: buffers.cell_capacity,
+            cell_size,
+            visual_range,
+            min_distance: MIN_DISTANCE,
+            separation: weights.separation,
+            cohesion: weights.cohesion,
+            alignment: weights.alignment,
+            _pad: [0.0; 2],
+        };
+        let state: Vec<GpuBoid> = boids
+            .iter()
+            .map(|boid| GpuBoid {
+                pos: [boid.x, boid.y],
+                vel: [boid.dx, boid.dy],
+            })
+            .collect();
+        self.queue.write_buffer(&buffers.params, 0, bytemuck::bytes_of(&params));
+        self.queue.write_buffer(&buffers.boids_in, 0, bytemuck::cast_slice(&state));
+
+        let size = (boids.len() * std::mem::size_of::<GpuSteering>()) as wgpu::BufferAddress;
+        let workgroups = (boids.len() as u32 + WORKGROUP_SIZE - 1) / WORKGROUP_SIZE;
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: Some("flock step") });
+        encoder.clear_buffer(&buffers.cell_counts, 0, None);
+        {
+            let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("flock step"),
+                timestamp_writes: None,
+            });
+            pass.set_bind_group(0, &buffers.bind_group, &[]);
+            pass.set_pipeline(&self.bin_pipeline);
+            pass.dispatch_workgroups(workgroups, 1, 1);
+            pass.set_pipeline(&self.flock_pipeline);
+            pass.dispatch_workgroups(workgroups, 1, 1);
+        }
+        encoder.copy_buffer_to_buffer(&buffers.steering_out, 0, &readback.buffer, 0, size);
+        self.queue.submit(Some(encoder.finish()));
+
+        // Picked up by collect once the copy is done
+        let mapped = Arc::clone(&readback.mapped);
+        readback.buffer.slice(..size).map_async(wgpu::MapMode::Read, move |result| {
+            if result.is_ok() {
+                mapped.store(true, Ordering::Release);
+            }
+        });
+        readback.pending = true;
+        readback.count = boids.len();
+        readback.population = population;
+        readback.step = step;
+    }
+
+    // Make sure the buffers hold at least `count` boids, exactly `cells` cells
+    // and at least `cell_capacity` boids per cell
+    fn reserve(&mut self, count: usize, cells: usize, cell_capacity: u32) {
+        if let Some(buffers) = &self.buffers {
+            if buffers.capacity >= count && buffers.cells == cells && buffers.cell_capacity >= cell_capacity {
+                return;
+            }
+        }
+
+        let capacity = count.next_power_of_two();
+        let cell_capacity = cell_capacity.next_power_of_two();
+        let boid_bytes = (capacity * std::mem::size_of::<GpuBoid>()) as wgpu::BufferAddress;
+        let steering_bytes = (capacity * std::mem::size_of::<GpuSteering>()) as wgpu::BufferAddress;
+        let buffer = |label: &str, size: wgpu::BufferAddress, usage: wgpu::BufferUsages| {
+            self.device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some(label),
+                size,
+                usage,
+                mapped_at_creation: false,
+            })
+        };
+
+        let params = self.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("params"),
+            contents: bytemuck::bytes_of(&Params::zeroed()),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+        let storage = wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST;
+        let boids_in = buffer("boids in", boid_bytes, storage);
+        let steering_out = buffer("steering out", steering_bytes, storage | wgpu::BufferUsages::COPY_SRC);
+        let cell_counts = buffer("cell counts", (cells * 4) as wgpu::BufferAddress, storage);
+        let cell_items = buffer(
+            "cell items",
+            (cells * cell_capacity as usize * 4) as wgpu::BufferAddress,
+            storage,
+        );
+        let readbacks = (0..READBACKS)
+            .map(|_| Readback {
+                buffer: buffer(
+                    "readback",
+                    steering_bytes,
+                    wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+                ),
+                mapped: Arc::new(AtomicBool::new(false)),
+                pending: false,
+                count: 0,
+                population: 0,
+                step: 0,
+            })
+            .collect();
+
+        let bind_group = self.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("flock"),
+            layout: &self.bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry { binding: 0, resource: params.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 1, resource: boids_in.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 2, resource: steering_out.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 3, resource: cell_counts.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 4, resource: cell_items.as_entire_binding() },
+            ],
+        });
+
+        self.buffers = Some(Buffers {
+            params,
+            boids_in,
+            steering_out,
+            cell_counts,
+            readbacks,
+            bind_group,
+            capacity,
+            cells,
+            cell_capacity,
+        });
+    }
+}
@@ -0,0 +1,122 @@
+// gpu.wgsl
+// Flocking on the GPU, driven by gpu.rs. Each step runs `bin` to sort the
+// boids into grid cells, then `flock` to work out the steering from the
+// classic rules and count the neighbors. The rest of the step happens on
+// the CPU.
+
+struct Boid {
+    pos: vec2<f32>,
+    vel: vec2<f32>,
+};
+
+// Must match GpuSteering in gpu.rs
+struct Steering {
+    force: vec2<f32>,
+    neighbors: u32,
+    _pad: u32,
+};
+
+// Must match Params in gpu.rs
+struct Params {
+    count: u32,
+    grid_width: u32,
+    grid_height: u32,
+    cell_capacity: u32,
+    cell_size: f32,
+    visual_range: f32,
+    min_distance: f32,
+    separation: f32,
+    cohesion: f32,
+    alignment: f32,
+    _pad0: f32,
+    _pad1: f32,
+};
+
+@group(0) @binding(0) var<uniform> params: Params;
+@group(0) @binding(1) var<storage, read> boids_in: array<Boid>;
+@group(0) @binding(2) var<storage, read_write> steering_out: array<Steering>;
+@group(0) @binding(3) var<storage, read_write> cell_counts: array<atomic<u32>>;
+@group(0) @binding(4) var<storage, read_write> cell_items: array<u32>;
+
+// Cell containing `pos`, clamped to the grid like SpatialGrid::cell_of
+fn cell_of(pos: vec2<f32>) -> vec2<i32> {
+    let x = clamp(i32(floor(pos.x / params.cell_size)), 0, i32(params.grid_width) - 1);
+    let y = clamp(i32(floor(pos.y / params.cell_size)), 0, i32(params.grid_height) - 1);
+    return vec2<i32>(x, y);
+}
+
+// Claim a slot in the boid's cell. gpu.rs sizes the cells for the most
+// crowded one, so every boid gets a slot.
+@compute @workgroup_size(64)
+fn bin(@builtin(global_invocation_id) id: vec3<u32>) {
+    let i = id.x;
+    if (i >= params.count) {
+        return;
+    }
+
+    let cell = cell_of(boids_in[i].pos);
+    let c = u32(cell.y) * params.grid_width + u32(cell.x);
+    let slot = atomicAdd(&cell_counts[c], 1u);
+    if (slot < params.cell_capacity) {
+        cell_items[c * params.cell_capacity + slot] = i;
+    }
+}
+
+// Separation, cohesion and alignment over the boids in the surrounding cells,
+// weighted like the behaviors in behavior.rs
+@compute @workgroup_size(64)
+fn flock(@builtin(global_invocation_id) id: vec3<u32>) {
+    let i = id.x;
+    if (i >= params.count) {
+        return;
+    }
+
+    let me = boids_in[i];
+    let cell = cell_of(me.pos);
+    var separation = vec2<f32>(0.0, 0.0);
+    var center = vec2<f32>(0.0, 0.0);
+    var average = vec2<f32>(0.0, 0.0);
+    var count = 0u;
+
+    // Cells are as wide as the visual range, so the 3x3 block covers it
+    for (var dy = -1; dy <= 1; dy = dy + 1) {
+        for (var dx = -1; dx <= 1; dx = dx + 1) {
+            let x = cell.x + dx;
+            let y = cell.y + dy;
+            if (x < 0 || y < 0 || x >= i32(params.grid_width) || y >= i32(params.grid_height)) {
+                continue;
+            }
+
+            let c = u32(y) * params.grid_width + u32(x);
+            let stored = min(atomicLoad(&cell_counts[c]), params.cell_capacity);
+            for (var s = 0u; s < stored; s = s + 1u) {
+                let j = cell_items[c * params.cell_capacity + s];
+                if (j == i) {
+                    continue;
+                }
+
+                let other = boids_in[j];
+                let away = me.pos - other.pos;
+                let squared_distance = dot(away, away);
+                if (squared_distance >= params.visual_range * params.visual_range) {
+                    continue;
+                }
+                if (squared_distance < params.min_distance * params.min_distance) {
+                    separation = separation + away;
+                }
+                center = center + other.pos;
+                average = average + other.vel;
+                count = count + 1u;
+            }
+        }
+    }
+
+    var steer = separation * params.separation;
+    if (count > 0u) {
+        let n = f32(count);
+        steer = steer + (center / n - me.pos) * params.cohesion;
+        steer = steer + (average / n - me.vel) * params.alignment;
+    }
+
+    steering_out[i] = Steering(steer, count, 0u);
+}
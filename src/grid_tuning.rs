@@ -0,0 +1,77 @@
+// grid_tuning.rs
+use crate::spatial_grid::{QueryStats, SpatialGrid};
+
+// Tuning settings
+const TUNE_INTERVAL: f32 = 2.0;    // Seconds of queries averaged per decision
+const CELL_VISIT_COST: f32 = 4.0;  // Cost of visiting a cell relative to checking one candidate
+const MIN_CELL_SIZE: f32 = 8.0;    // Pixels
+const MAX_CELL_SIZE: f32 = 256.0;
+const SIZE_STEPS: usize = 24;      // Candidate sizes tried between the limits
+const MIN_IMPROVEMENT: f32 = 0.85; // Only rebuild when the estimated cost drops below this share
+
+// Picks the grid cell size with the lowest estimated query cost. Small cells
+// mean visiting many cells, large cells mean checking many far away boids.
+// The local density seen by the queries decides where the balance lies.
+pub struct CellSizeTuner {
+    elapsed: f32,
+    pub last: QueryStats,          // Counters from the last decision, for the HUD
+    pub occupancy: f32,            // Average boids per non-empty cell at the last decision
+}
+
+impl CellSizeTuner {
+    pub fn new() -> Self {
+        CellSizeTuner {
+            elapsed: 0.0,
+            last: QueryStats::default(),
+            occupancy: 0.0,
+        }
+    }
+
+    // Average candidates looked at per query over the last interval
+    pub fn candidates_per_query(&self) -> f32 {
+        self.last.candidates as f32 / self.last.queries.max(1) as f32
+    }
+
+    // Average boids found per query over the last interval
+    pub fn neighbors_per_query(&self) -> f32 {
+        self.last.matches as f32 / self.last.queries.max(1) as f32
+    }
+
+    // Call once per frame with the grid being queried. Returns a new cell
+    // size when rebuilding the grid is worth it.
+    pub fn update(&mut self, dt: f32, grid: &SpatialGrid, radius: f32) -> Option<f32> {
+        self.elapsed += dt;
+        if self.elapsed < TUNE_INTERVAL {
+            return None;
+        }
+        self.elapsed = 0.0;
+
+        self.last = grid.take_stats();
+        self.occupancy = grid.average_occupancy();
+        if self.last.queries == 0 {
+            return None;
+        }
+
+        // Boids per square pixel around the typical query
+        let current = grid.cell_size();
+        let scanned = SpatialGrid::scan_width(current, radius).powi(2);
+        let density = self.candidates_per_query() / scanned;
+
+        let cost = |cell_size: f32| {
+            let width = SpatialGrid::scan_width(cell_size, radius);
+            let cells = (width / cell_size).powi(2);
+            cells * CELL_VISIT_COST + density * width * width
+        };
+
+        let ratio = (MAX_CELL_SIZE / MIN_CELL_SIZE).powf(1.0 / (SIZE_STEPS - 1) as f32);
+        let best = (0..SIZE_STEPS)
+            .map(|step| (MIN_CELL_SIZE * ratio.powi(step as i32)).round())
+            .min_by(|a, b| cost(*a).partial_cmp(&cost(*b)).unwrap_or(std::cmp::Ordering::Equal))?;
+
+        if cost(best) < cost(current) * MIN_IMPROVEMENT {
+            Some(best)
+        } else {
+            None
+        }
+    }
+}
@@ -0,0 +1,196 @@
+// input_log.rs
+use crate::input_map::{key_name, parse_key};
+use ggez::input::keyboard::{KeyCode, KeyMods};
+use ggez::input::mouse::MouseButton;
+use std::collections::{HashSet, VecDeque};
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::time::Duration;
+
+// An input event as seen by the event handlers
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum InputEvent {
+    KeyDown(KeyCode, KeyMods),
+    KeyUp(KeyCode),
+    MouseDown(MouseButton, glam::Vec2, KeyMods),
+    MouseUp(MouseButton, glam::Vec2),
+    MouseMove(glam::Vec2, glam::Vec2), // Position and movement since the last event
+    Wheel(f32),
+}
+
+// One line of the log. A "frame" line carries the length of a frame; the
+// events listed before it arrived ahead of that frame's update. Every other
+// line is one event.
+impl InputEvent {
+    fn to_line(self) -> Option<String> {
+        Some(match self {
+            InputEvent::KeyDown(key, mods) => format!("key_down {} {}", key_name(key)?, mods.bits()),
+            InputEvent::KeyUp(key) => format!("key_up {}", key_name(key)?),
+            InputEvent::MouseDown(button, pos, mods) => {
+                format!("mouse_down {} {} {} {}", button_name(button), pos.x, pos.y, mods.bits())
+            }
+            InputEvent::MouseUp(button, pos) => format!("mouse_up {} {} {}", button_name(button), pos.x, pos.y),
+            InputEvent::MouseMove(pos, delta) => format!("mouse_move {} {} {} {}", pos.x, pos.y, delta.x, delta.y),
+            InputEvent::Wheel(y) => format!("wheel {}", y),
+        })
+    }
+
+    fn parse(fields: &[&str]) -> Option<InputEvent> {
+        let number = |i: usize| fields.get(i)?.parse::<f32>().ok();
+        let point = |i: usize| Some(glam::vec2(number(i)?, number(i + 1)?));
+        let mods = |i: usize| KeyMods::from_bits(fields.get(i)?.parse().ok()?);
+
+        Some(match *fields.first()? {
+            "key_down" => InputEvent::KeyDown(parse_key(fields.get(1)?)?, mods(2)?),
+            "key_up" => InputEvent::KeyUp(parse_key(fields.get(1)?)?),
+            "mouse_down" => InputEvent::MouseDown(parse_button(fields.get(1)?)?, point(2)?, mods(4)?),
+            "mouse_up" => InputEvent::MouseUp(parse_button(fields.get(1)?)?, point(2)?),
+            "mouse_move" => InputEvent::MouseMove(point(1)?, point(3)?),
+            "wheel" => InputEvent::Wheel(number(1)?),
+            _ => return None,
+        })
+    }
+}
+
+fn button_name(button: MouseButton) -> String {
+    match button {
+        MouseButton::Left => "Left".to_string(),
+        MouseButton::Right => "Right".to_string(),
+        MouseButton::Middle => "Middle".to_string(),
+        MouseButton::Other(number) => number.to_string(),
+    }
+}
+
+fn parse_button(name: &str) -> Option<MouseButton> {
+    match name {
+        "Left" => Some(MouseButton::Left),
+        "Right" => Some(MouseButton::Right),
+        "Middle" => Some(MouseButton::Middle),
+        other => other.parse().ok().map(MouseButton::Other),
+    }
+}
+
+// Writes every input event and frame length to a file, started with
+// --record-input. Together with the seed in the header this is enough to
+// replay the session exactly.
+pub struct InputRecorder {
+    writer: BufWriter<File>,
+}
+
+impl InputRecorder {
+    pub fn create(path: &Path, seed: u64) -> io::Result<InputRecorder> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        writeln!(writer, "# boids input log")?;
+        writeln!(writer, "seed {}", seed)?;
+        Ok(InputRecorder { writer })
+    }
+
+    pub fn event(&mut self, event: InputEvent) -> io::Result<()> {
+        match event.to_line() {
+            Some(line) => writeln!(self.writer, "{}", line),
+            None => Ok(()), // Keys without a name can't be bound to anything
+        }
+    }
+
+    // Frame lengths are stored in nanoseconds so the replay sees exactly the same ticks
+    pub fn frame(&mut self, dt: Duration) -> io::Result<()> {
+        writeln!(self.writer, "frame {}", dt.as_nanos())
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+// A recorded frame: the events delivered before it, and its length
+struct Frame {
+    events: Vec<InputEvent>,
+    dt: Duration,
+}
+
+// Feeds a recorded session back in, started with --replay-input. Live input
+// is ignored meanwhile, and the keys, buttons and mouse position the
+// simulation polls come from the recording instead of the window.
+pub struct InputPlayback {
+    pub seed: u64,
+    frames: VecDeque<Frame>,
+    held_keys: HashSet<KeyCode>,
+    held_buttons: HashSet<MouseButton>,
+    pub mouse: glam::Vec2,                 // Screen position of the recorded cursor
+    pub frames_played: usize,
+}
+
+impl InputPlayback {
+    pub fn load(path: &Path) -> Result<InputPlayback, String> {
+        let text = fs::read_to_string(path).map_err(|err| err.to_string())?;
+        let mut seed = None;
+        let mut frames = VecDeque::new();
+        let mut events = Vec::new();
+
+        for (number, line) in text.lines().enumerate() {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let invalid = || format!("line {}: can't read '{}'", number + 1, line);
+            match fields.first() {
+                None => {}
+                Some(first) if first.starts_with('#') => {}
+                Some(&"seed") => seed = Some(fields.get(1).and_then(|s| s.parse().ok()).ok_or_else(invalid)?),
+                Some(&"frame") => {
+                    let nanos = fields.get(1).and_then(|s| s.parse().ok()).ok_or_else(invalid)?;
+                    frames.push_back(Frame {
+                        events: std::mem::take(&mut events),
+                        dt: Duration::from_nanos(nanos),
+                    });
+                }
+                Some(_) => events.push(InputEvent::parse(&fields).ok_or_else(invalid)?),
+            }
+        }
+
+        Ok(InputPlayback {
+            seed: seed.ok_or("missing seed line")?,
+            frames,
+            held_keys: HashSet::new(),
+            held_buttons: HashSet::new(),
+            mouse: glam::Vec2::ZERO,
+            frames_played: 0,
+        })
+    }
+
+    // Events for the next frame and its length, or None once the recording is over
+    pub fn next_frame(&mut self) -> Option<(Vec<InputEvent>, Duration)> {
+        let frame = self.frames.pop_front()?;
+        self.frames_played += 1;
+        Some((frame.events, frame.dt))
+    }
+
+    // Update the held keys, buttons and cursor. Call just before handling
+    // each event, so the handlers see the state as it was live.
+    pub fn track(&mut self, event: InputEvent) {
+        match event {
+            InputEvent::KeyDown(key, _) => {
+                self.held_keys.insert(key);
+            }
+            InputEvent::KeyUp(key) => {
+                self.held_keys.remove(&key);
+            }
+            InputEvent::MouseDown(button, pos, _) => {
+                self.held_buttons.insert(button);
+                self.mouse = pos;
+            }
+            InputEvent::MouseUp(button, pos) => {
+                self.held_buttons.remove(&button);
+                self.mouse = pos;
+            }
+            InputEvent::MouseMove(pos, _) => self.mouse = pos,
+            InputEvent::Wheel(_) => {}
+        }
+    }
+
+    pub fn is_key_held(&self, key: KeyCode) -> bool {
+        self.held_keys.contains(&key)
+    }
+
+    pub fn is_button_held(&self, button: MouseButton) -> bool {
+        self.held_buttons.contains(&button)
+    }
+}
@@ -0,0 +1,608 @@
+// input_map.rs
+use crate::presets::PRESETS;
+use ggez::input::keyboard::KeyCode;
+use ggez::Context;
+use std::collections::HashMap;
+use std::iter;
+
+// Everything the keyboard can do. Most actions fire once per key press; the
+// ones marked held are polled every frame while their key is down.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Action {
+    Play,
+    Pause,
+    Reset,
+    AddBoids,
+    RemoveBoids,
+    Quit,
+    CycleIndex,
+    CycleColor,
+    CycleTheme,
+    ToggleFollow,
+    CycleForces,
+    ToggleGrid,
+    ToggleTrails,
+    ToggleMotionBlur,
+    ToggleStats,
+    ToggleCharts,
+    ToggleProfiler,
+    CycleLeaders,
+    CycleSpawnPattern,
+    SlowDown,
+    SpeedUp,
+    ClearFlow,
+    ClearWaypoints,
+    AddEmitter,
+    AddSink,
+    ClearZones,
+    ToggleFood,
+    ToggleEnergy,
+    ToggleEpidemic,
+    TogglePredators,
+    CyclePheromones,
+    ToggleEditor,
+    ToggleCollisions,
+    CycleEvolution,
+    #[cfg(feature = "audio")]
+    ToggleAudio,
+    #[cfg(feature = "audio")]
+    ToggleAmbient,
+    ToggleLod,
+    ToggleShepherd,
+    CycleHud,
+    ShrinkBrush,
+    GrowBrush,
+    SlowerBrush,
+    FasterBrush,
+    SaveGif,
+    ToggleRecording,
+    ToggleFullscreen,
+    NextWorld,
+    NewWorld,
+    Preset(usize), // Index into PRESETS
+    DeleteSelection,      // Selection layer
+    RecolorSelection,     // Selection layer
+    SelectionToPredators, // Selection layer
+    RaiseWeight(usize),   // Ctrl layer, index into NUDGED_WEIGHTS
+    LowerWeight(usize),   // Ctrl layer
+    PanUp,      // Held
+    PanDown,    // Held
+    PanLeft,    // Held
+    PanRight,   // Held
+    Rewind,     // Held
+    SpawnBrush, // Held
+}
+
+impl Action {
+    // Every action in menu order, one per preset and nudged weight where
+    // they are numbered
+    pub fn all() -> impl Iterator<Item = Action> {
+        let presets = (0..PRESETS.len()).map(Action::Preset);
+        let weights = (0..NUDGED_WEIGHTS.len())
+            .flat_map(|index| iter::once(Action::RaiseWeight(index)).chain(iter::once(Action::LowerWeight(index))));
+        MAIN_ACTIONS
+            .iter()
+            .copied()
+            .chain(presets)
+            .chain(SELECTION_ACTIONS.iter().copied())
+            .chain(weights)
+            .chain(HELD_ACTIONS.iter().copied())
+    }
+
+    // Name used in the [keys] section of boids.toml
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::Play => "play",
+            Action::Pause => "pause",
+            Action::Reset => "reset",
+            Action::AddBoids => "add_boids",
+            Action::RemoveBoids => "remove_boids",
+            Action::Quit => "quit",
+            Action::CycleIndex => "cycle_index",
+            Action::CycleColor => "cycle_color",
+            Action::CycleTheme => "cycle_theme",
+            Action::ToggleFollow => "toggle_follow",
+            Action::CycleForces => "cycle_forces",
+            Action::ToggleGrid => "toggle_grid",
+            Action::ToggleTrails => "toggle_trails",
+            Action::ToggleMotionBlur => "toggle_motion_blur",
+            Action::ToggleStats => "toggle_stats",
+            Action::ToggleCharts => "toggle_charts",
+            Action::ToggleProfiler => "toggle_profiler",
+            Action::CycleLeaders => "cycle_leaders",
+            Action::CycleSpawnPattern => "cycle_spawn_pattern",
+            Action::SlowDown => "slow_down",
+            Action::SpeedUp => "speed_up",
+            Action::ClearFlow => "clear_flow",
+            Action::ClearWaypoints => "clear_waypoints",
+            Action::AddEmitter => "add_emitter",
+            Action::AddSink => "add_sink",
+            Action::ClearZones => "clear_zones",
+            Action::ToggleFood => "toggle_food",
+            Action::ToggleEnergy => "toggle_energy",
+            Action::ToggleEpidemic => "toggle_epidemic",
+            Action::TogglePredators => "toggle_predators",
+            Action::CyclePheromones => "cycle_pheromones",
+            Action::ToggleEditor => "toggle_editor",
+            Action::ToggleCollisions => "toggle_collisions",
+            Action::CycleEvolution => "cycle_evolution",
+            #[cfg(feature = "audio")]
+            Action::ToggleAudio => "toggle_audio",
+            #[cfg(feature = "audio")]
+            Action::ToggleAmbient => "toggle_ambient",
+            Action::ToggleLod => "toggle_lod",
+            Action::ToggleShepherd => "toggle_shepherd",
+            Action::CycleHud => "cycle_hud",
+            Action::ShrinkBrush => "shrink_brush",
+            Action::GrowBrush => "grow_brush",
+            Action::SlowerBrush => "slower_brush",
+            Action::FasterBrush => "faster_brush",
+            Action::SaveGif => "save_gif",
+            Action::ToggleRecording => "toggle_recording",
+            Action::ToggleFullscreen => "toggle_fullscreen",
+            Action::NextWorld => "next_world",
+            Action::NewWorld => "new_world",
+            Action::Preset(index) => PRESET_NAMES[index],
+            Action::DeleteSelection => "delete_selection",
+            Action::RecolorSelection => "recolor_selection",
+            Action::SelectionToPredators => "selection_to_predators",
+            Action::RaiseWeight(index) => RAISE_NAMES[index],
+            Action::LowerWeight(index) => LOWER_NAMES[index],
+            Action::PanUp => "pan_up",
+            Action::PanDown => "pan_down",
+            Action::PanLeft => "pan_left",
+            Action::PanRight => "pan_right",
+            Action::Rewind => "rewind",
+            Action::SpawnBrush => "spawn_brush",
+        }
+    }
+
+    // Short description for the setup menu
+    pub fn description(self) -> &'static str {
+        match self {
+            Action::Play => "play",
+            Action::Pause => "pause",
+            Action::Reset => "reset",
+            Action::AddBoids => "add boids",
+            Action::RemoveBoids => "reduce boids",
+            Action::Quit => "quit",
+            Action::CycleIndex => "neighbor index",
+            Action::CycleColor => "color mode",
+            Action::CycleTheme => "theme",
+            Action::ToggleFollow => "follow selected",
+            Action::CycleForces => "force vectors",
+            Action::ToggleGrid => "spatial grid",
+            Action::ToggleTrails => "trails",
+            Action::ToggleMotionBlur => "motion blur",
+            Action::ToggleStats => "stats",
+            Action::ToggleCharts => "charts",
+            Action::ToggleProfiler => "profiler",
+            Action::CycleLeaders => "leaders",
+            Action::CycleSpawnPattern => "spawn pattern",
+            Action::SlowDown => "slower",
+            Action::SpeedUp => "faster",
+            Action::ClearFlow => "clear flow",
+            Action::ClearWaypoints => "clear waypoints",
+            Action::AddEmitter => "emitter at cursor",
+            Action::AddSink => "sink at cursor",
+            Action::ClearZones => "clear emitters / sinks",
+            Action::ToggleFood => "food",
+            Action::ToggleEnergy => "energy model",
+            Action::ToggleEpidemic => "epidemic",
+            Action::TogglePredators => "predators and prey",
+            Action::CyclePheromones => "pheromones",
+            Action::ToggleEditor => "obstacle editor",
+            Action::ToggleCollisions => "collisions",
+            Action::CycleEvolution => "evolution",
+            #[cfg(feature = "audio")]
+            Action::ToggleAudio => "audio reactive",
+            #[cfg(feature = "audio")]
+            Action::ToggleAmbient => "ambient sound",
+            Action::ToggleLod => "distant boids update less",
+            Action::ToggleShepherd => "shepherding game",
+            Action::CycleHud => "hud mode",
+            Action::ShrinkBrush => "smaller brush",
+            Action::GrowBrush => "larger brush",
+            Action::SlowerBrush => "slower brush",
+            Action::FasterBrush => "faster brush",
+            Action::SaveGif => "gif of last 5s",
+            Action::ToggleRecording => "record",
+            Action::ToggleFullscreen => "fullscreen",
+            Action::NextWorld => "next world",
+            Action::NewWorld => "new world",
+            Action::Preset(_) => "presets",
+            Action::DeleteSelection => "delete group",
+            Action::RecolorSelection => "recolor group",
+            Action::SelectionToPredators => "group to predators",
+            Action::RaiseWeight(index) | Action::LowerWeight(index) => NUDGED_WEIGHTS[index],
+            Action::PanUp => "pan up",
+            Action::PanDown => "pan down",
+            Action::PanLeft => "pan left",
+            Action::PanRight => "pan right",
+            Action::Rewind => "rewind (hold)",
+            Action::SpawnBrush => "spawn brush (hold)",
+        }
+    }
+
+    pub fn layer(self) -> Layer {
+        match self {
+            Action::DeleteSelection | Action::RecolorSelection | Action::SelectionToPredators => Layer::Selection,
+            Action::RaiseWeight(_) | Action::LowerWeight(_) => Layer::Ctrl,
+            _ => Layer::Main,
+        }
+    }
+
+    fn default_key(self) -> KeyCode {
+        match self {
+            Action::Play => KeyCode::Space,
+            Action::Pause => KeyCode::P,
+            Action::Reset => KeyCode::R,
+            Action::AddBoids => KeyCode::Up,
+            Action::RemoveBoids => KeyCode::Down,
+            Action::Quit => KeyCode::Escape,
+            Action::CycleIndex => KeyCode::N,
+            Action::CycleColor => KeyCode::C,
+            Action::CycleTheme => KeyCode::Y,
+            Action::ToggleFollow => KeyCode::F,
+            Action::CycleForces => KeyCode::V,
+            Action::ToggleGrid => KeyCode::G,
+            Action::ToggleTrails => KeyCode::T,
+            Action::ToggleMotionBlur => KeyCode::U,
+            Action::ToggleStats => KeyCode::M,
+            Action::ToggleCharts => KeyCode::K,
+            Action::ToggleProfiler => KeyCode::F3,
+            Action::CycleLeaders => KeyCode::L,
+            Action::CycleSpawnPattern => KeyCode::O,
+            Action::SlowDown => KeyCode::LBracket,
+            Action::SpeedUp => KeyCode::RBracket,
+            Action::ClearFlow => KeyCode::X,
+            Action::ClearWaypoints => KeyCode::Back,
+            Action::AddEmitter => KeyCode::Insert,
+            Action::AddSink => KeyCode::Q,
+            Action::ClearZones => KeyCode::Delete,
+            Action::ToggleFood => KeyCode::H,
+            Action::ToggleEnergy => KeyCode::J,
+            Action::ToggleEpidemic => KeyCode::I,
+            Action::TogglePredators => KeyCode::F4,
+            Action::CyclePheromones => KeyCode::F5,
+            Action::ToggleEditor => KeyCode::E,
+            Action::ToggleCollisions => KeyCode::F6,
+            Action::CycleEvolution => KeyCode::F7,
+            #[cfg(feature = "audio")]
+            Action::ToggleAudio => KeyCode::F10,
+            #[cfg(feature = "audio")]
+            Action::ToggleAmbient => KeyCode::F12,
+            Action::ToggleLod => KeyCode::End,
+            Action::ToggleShepherd => KeyCode::F1,
+            Action::CycleHud => KeyCode::Home,
+            Action::ShrinkBrush => KeyCode::Comma,
+            Action::GrowBrush => KeyCode::Period,
+            Action::SlowerBrush => KeyCode::Minus,
+            Action::FasterBrush => KeyCode::Equals,
+            Action::SaveGif => KeyCode::F8,
+            Action::ToggleRecording => KeyCode::F9,
+            Action::ToggleFullscreen => KeyCode::F11,
+            Action::NextWorld => KeyCode::Tab,
+            Action::NewWorld => KeyCode::F2,
+            Action::Preset(index) => PRESET_KEYS[index],
+            Action::DeleteSelection => KeyCode::Delete,
+            Action::RecolorSelection => KeyCode::C,
+            Action::SelectionToPredators => KeyCode::F4,
+            Action::RaiseWeight(index) => RAISE_KEYS[index],
+            Action::LowerWeight(index) => LOWER_KEYS[index],
+            Action::PanUp => KeyCode::W,
+            Action::PanDown => KeyCode::S,
+            Action::PanLeft => KeyCode::A,
+            Action::PanRight => KeyCode::D,
+            Action::Rewind => KeyCode::B,
+            Action::SpawnBrush => KeyCode::Z,
+        }
+    }
+}
+
+// Bindings are unique within a layer. The selection layer is only consulted
+// while a group is selected and the ctrl layer while <ctrl> is held, so their
+// actions can reuse keys from the main one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Layer {
+    Main,
+    Selection,
+    Ctrl,
+}
+
+// The actions that aren't numbered, in menu order around the presets and
+// weights that Action::all puts between them
+const MAIN_ACTIONS: &[Action] = &[
+    Action::Play,
+    Action::Pause,
+    Action::Reset,
+    Action::AddBoids,
+    Action::RemoveBoids,
+    Action::Quit,
+    Action::CycleIndex,
+    Action::CycleColor,
+    Action::CycleTheme,
+    Action::ToggleFollow,
+    Action::CycleForces,
+    Action::ToggleGrid,
+    Action::ToggleTrails,
+    Action::ToggleMotionBlur,
+    Action::ToggleStats,
+    Action::ToggleCharts,
+    Action::ToggleProfiler,
+    Action::CycleLeaders,
+    Action::CycleSpawnPattern,
+    Action::SlowDown,
+    Action::SpeedUp,
+    Action::ClearFlow,
+    Action::ClearWaypoints,
+    Action::AddEmitter,
+    Action::AddSink,
+    Action::ClearZones,
+    Action::ToggleFood,
+    Action::ToggleEnergy,
+    Action::ToggleEpidemic,
+    Action::TogglePredators,
+    Action::CyclePheromones,
+    Action::ToggleEditor,
+    Action::ToggleCollisions,
+    Action::CycleEvolution,
+    #[cfg(feature = "audio")]
+    Action::ToggleAudio,
+    #[cfg(feature = "audio")]
+    Action::ToggleAmbient,
+    Action::ToggleLod,
+    Action::ToggleShepherd,
+    Action::CycleHud,
+    Action::ShrinkBrush,
+    Action::GrowBrush,
+    Action::SlowerBrush,
+    Action::FasterBrush,
+    Action::SaveGif,
+    Action::ToggleRecording,
+    Action::ToggleFullscreen,
+    Action::NextWorld,
+    Action::NewWorld,
+];
+const SELECTION_ACTIONS: &[Action] = &[
+    Action::DeleteSelection,
+    Action::RecolorSelection,
+    Action::SelectionToPredators,
+];
+const HELD_ACTIONS: &[Action] = &[
+    Action::PanUp,
+    Action::PanDown,
+    Action::PanLeft,
+    Action::PanRight,
+    Action::Rewind,
+    Action::SpawnBrush,
+];
+
+// Names and default keys of the preset actions, one per entry in PRESETS
+const PRESET_NAMES: [&str; PRESETS.len()] = ["preset_1", "preset_2", "preset_3", "preset_4", "preset_5"];
+const PRESET_KEYS: [KeyCode; PRESETS.len()] = [KeyCode::Key1, KeyCode::Key2, KeyCode::Key3, KeyCode::Key4, KeyCode::Key5];
+
+// Behavior weights nudged by the weight actions, with their names and
+// default keys, raising then lowering
+pub const NUDGED_WEIGHTS: [&str; 3] = ["cohesion", "separation", "alignment"];
+const RAISE_NAMES: [&str; NUDGED_WEIGHTS.len()] = ["raise_cohesion", "raise_separation", "raise_alignment"];
+const LOWER_NAMES: [&str; NUDGED_WEIGHTS.len()] = ["lower_cohesion", "lower_separation", "lower_alignment"];
+const RAISE_KEYS: [KeyCode; NUDGED_WEIGHTS.len()] = [KeyCode::Q, KeyCode::W, KeyCode::E];
+const LOWER_KEYS: [KeyCode; NUDGED_WEIGHTS.len()] = [KeyCode::A, KeyCode::S, KeyCode::D];
+
+// Key names accepted in boids.toml, matching winit's key names
+const KEY_NAMES: [(&str, KeyCode); 68] = [
+    ("A", KeyCode::A), ("B", KeyCode::B), ("C", KeyCode::C), ("D", KeyCode::D),
+    ("E", KeyCode::E), ("F", KeyCode::F), ("G", KeyCode::G), ("H", KeyCode::H),
+    ("I", KeyCode::I), ("J", KeyCode::J), ("K", KeyCode::K), ("L", KeyCode::L),
+    ("M", KeyCode::M), ("N", KeyCode::N), ("O", KeyCode::O), ("P", KeyCode::P),
+    ("Q", KeyCode::Q), ("R", KeyCode::R), ("S", KeyCode::S), ("T", KeyCode::T),
+    ("U", KeyCode::U), ("V", KeyCode::V), ("W", KeyCode::W), ("X", KeyCode::X),
+    ("Y", KeyCode::Y), ("Z", KeyCode::Z),
+    ("Key0", KeyCode::Key0), ("Key1", KeyCode::Key1), ("Key2", KeyCode::Key2),
+    ("Key3", KeyCode::Key3), ("Key4", KeyCode::Key4), ("Key5", KeyCode::Key5),
+    ("Key6", KeyCode::Key6), ("Key7", KeyCode::Key7), ("Key8", KeyCode::Key8),
+    ("Key9", KeyCode::Key9),
+    ("F1", KeyCode::F1), ("F2", KeyCode::F2), ("F3", KeyCode::F3), ("F4", KeyCode::F4),
+    ("F5", KeyCode::F5), ("F6", KeyCode::F6), ("F7", KeyCode::F7), ("F8", KeyCode::F8),
+    ("F9", KeyCode::F9), ("F10", KeyCode::F10), ("F11", KeyCode::F11), ("F12", KeyCode::F12),
+    ("Space", KeyCode::Space), ("Escape", KeyCode::Escape), ("Tab", KeyCode::Tab),
+    ("Return", KeyCode::Return), ("Back", KeyCode::Back), ("Delete", KeyCode::Delete),
+    ("Insert", KeyCode::Insert), ("Home", KeyCode::Home), ("End", KeyCode::End),
+    ("Up", KeyCode::Up), ("Down", KeyCode::Down), ("Left", KeyCode::Left),
+    ("Right", KeyCode::Right),
+    ("LBracket", KeyCode::LBracket), ("RBracket", KeyCode::RBracket),
+    ("Comma", KeyCode::Comma), ("Period", KeyCode::Period), ("Minus", KeyCode::Minus),
+    ("Equals", KeyCode::Equals), ("Slash", KeyCode::Slash),
+];
+
+pub fn parse_key(name: &str) -> Option<KeyCode> {
+    KEY_NAMES
+        .iter()
+        .find(|(key_name, _)| key_name.eq_ignore_ascii_case(name))
+        .map(|&(_, key)| key)
+}
+
+// Name of a key as accepted by parse_key
+pub fn key_name(key: KeyCode) -> Option<&'static str> {
+    KEY_NAMES.iter().find(|&&(_, code)| code == key).map(|&(name, _)| name)
+}
+
+// How a key is shown in the menu, e.g. "space" or "["
+pub fn key_label(key: KeyCode) -> String {
+    match key {
+        KeyCode::LBracket => "[".to_string(),
+        KeyCode::RBracket => "]".to_string(),
+        KeyCode::Comma => ",".to_string(),
+        KeyCode::Period => ".".to_string(),
+        KeyCode::Minus => "-".to_string(),
+        KeyCode::Equals => "=".to_string(),
+        KeyCode::Slash => "/".to_string(),
+        KeyCode::Back => "backspace".to_string(),
+        KeyCode::Key0 => "0".to_string(),
+        KeyCode::Key1 => "1".to_string(),
+        KeyCode::Key2 => "2".to_string(),
+        KeyCode::Key3 => "3".to_string(),
+        KeyCode::Key4 => "4".to_string(),
+        KeyCode::Key5 => "5".to_string(),
+        KeyCode::Key6 => "6".to_string(),
+        KeyCode::Key7 => "7".to_string(),
+        KeyCode::Key8 => "8".to_string(),
+        KeyCode::Key9 => "9".to_string(),
+        _ => KEY_NAMES
+            .iter()
+            .find(|&&(_, code)| code == key)
+            .map_or_else(|| format!("{:?}", key), |(name, _)| name.to_lowercase()),
+    }
+}
+
+// Resolves keys to actions, starting from the defaults and applying any
+// remapping from the config file
+pub struct InputMap {
+    actions: HashMap<(Layer, KeyCode), Action>,
+    keys: HashMap<Action, KeyCode>,
+}
+
+impl InputMap {
+    // `overrides` maps action names to key names
+    pub fn new(overrides: &HashMap<String, String>) -> Result<InputMap, String> {
+        let mut keys: HashMap<Action, KeyCode> = Action::all()
+            .map(|action| (action, action.default_key()))
+            .collect();
+
+        for (action_name, key_name) in overrides {
+            let action = Action::all()
+                .find(|action| action.name() == action_name)
+                .ok_or_else(|| format!("unknown action '{}' in [keys]", action_name))?;
+            let key = parse_key(key_name)
+                .ok_or_else(|| format!("unknown key '{}' for {}", key_name, action_name))?;
+            keys.insert(action, key);
+        }
+
+        // Two actions on one key in the same layer would make one of them unreachable
+        let mut actions = HashMap::new();
+        for action in Action::all() {
+            let key = keys[&action];
+            if let Some(other) = actions.insert((action.layer(), key), action) {
+                return Err(format!(
+                    "{} and {} are both bound to {}",
+                    other.name(),
+                    action.name(),
+                    key_label(key)
+                ));
+            }
+        }
+
+        Ok(InputMap { actions, keys })
+    }
+
+    pub fn action(&self, layer: Layer, key: KeyCode) -> Option<Action> {
+        self.actions.get(&(layer, key)).copied()
+    }
+
+    pub fn key(&self, action: Action) -> KeyCode {
+        self.keys[&action]
+    }
+
+    // For held actions, whether their key is currently down
+    pub fn is_held(&self, ctx: &Context, action: Action) -> bool {
+        ctx.keyboard.is_key_pressed(self.key(action))
+    }
+
+    // One "<description> : <key>" line per action for the setup menu. Actions
+    // sharing a description, like the presets, share a line listing their keys.
+    pub fn help_lines(&self) -> Vec<String> {
+        let mut lines: Vec<(&str, Layer, Vec<String>)> = Vec::new();
+        for action in Action::all() {
+            let key = format!("<{}>", key_label(self.key(action)));
+            match lines.last_mut() {
+                Some((description, _, keys)) if *description == action.description() => keys.push(key),
+                _ => lines.push((action.description(), action.layer(), vec![key])),
+            }
+        }
+        lines
+            .into_iter()
+            .map(|(description, layer, keys)| match layer {
+                Layer::Main => format!("{} : {}", description, keys.join("/")),
+                Layer::Selection => format!("{} : {} with a group selected", description, keys.join("/")),
+                Layer::Ctrl => format!("{} : <ctrl> + {}", description, keys.join("/")),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn overrides(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs.iter().map(|&(action, key)| (action.to_string(), key.to_string())).collect()
+    }
+
+    #[test]
+    fn defaults_have_no_conflicts() {
+        let map = InputMap::new(&HashMap::new()).unwrap();
+
+        assert_eq!(map.action(Layer::Main, KeyCode::Space), Some(Action::Play));
+        assert_eq!(map.action(Layer::Selection, KeyCode::Delete), Some(Action::DeleteSelection));
+        assert_eq!(map.key(Action::RaiseWeight(0)), KeyCode::Q);
+    }
+
+    #[test]
+    fn overrides_rebind_actions() {
+        let map = InputMap::new(&overrides(&[("play", "return"), ("preset_5", "Key0")])).unwrap();
+
+        assert_eq!(map.key(Action::Play), KeyCode::Return);
+        assert_eq!(map.action(Layer::Main, KeyCode::Space), None);
+        assert_eq!(map.action(Layer::Main, KeyCode::Key0), Some(Action::Preset(4)));
+    }
+
+    #[test]
+    fn unknown_actions_are_rejected() {
+        let err = InputMap::new(&overrides(&[("fly", "Space")])).err().unwrap();
+        assert!(err.contains("unknown action 'fly'"), "{}", err);
+    }
+
+    #[test]
+    fn unknown_keys_are_rejected() {
+        let err = InputMap::new(&overrides(&[("play", "Hyper")])).err().unwrap();
+        assert!(err.contains("unknown key 'Hyper'"), "{}", err);
+    }
+
+    #[test]
+    fn duplicate_bindings_are_rejected() {
+        let err = InputMap::new(&overrides(&[("play", "P")])).err().unwrap();
+        assert!(err.contains("play") && err.contains("pause"), "{}", err);
+
+        // Other layers may reuse the key
+        assert!(InputMap::new(&overrides(&[("delete_selection", "P")])).is_ok());
+    }
+
+    #[test]
+    fn key_names_round_trip() {
+        for &(name, key) in KEY_NAMES.iter() {
+            assert_eq!(key_name(key), Some(name));
+            assert_eq!(parse_key(key_name(key).unwrap()), Some(key));
+        }
+        for action in Action::all() {
+            assert!(key_name(action.default_key()).is_some(), "{} has no key name", action.name());
+        }
+    }
+
+    #[test]
+    fn every_preset_and_weight_has_actions() {
+        let actions: Vec<Action> = Action::all().collect();
+        for index in 0..PRESETS.len() {
+            assert!(actions.contains(&Action::Preset(index)));
+        }
+        for index in 0..NUDGED_WEIGHTS.len() {
+            assert!(actions.contains(&Action::RaiseWeight(index)));
+            assert!(actions.contains(&Action::LowerWeight(index)));
+        }
+
+        let mut names: Vec<&str> = actions.iter().map(|action| action.name()).collect();
+        names.sort_unstable();
+        names.dedup();
+        assert_eq!(names.len(), actions.len());
+    }
+}
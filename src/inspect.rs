@@ -0,0 +1,213 @@
+// inspect.rs
+use crate::behavior::{SteeringForces, WeightedBehavior};
+use crate::boid::Boid;
+#[cfg(feature = "render-ggez")]
+use crate::boid::VISUAL_RANGE;
+#[cfg(feature = "render-ggez")]
+use crate::neighbor_index::NeighborIndex;
+#[cfg(feature = "render-ggez")]
+use ggez::{graphics, GameResult};
+
+// Clicks further than this from any boid clear the selection
+pub const SELECT_RADIUS: f32 = 16.0; // Pixels, in world space
+
+// Force arrow settings
+#[cfg(feature = "render-ggez")]
+const FORCE_VECTOR_SCALE: f32 = 2.0;           // Arrow pixels per unit of velocity change
+#[cfg(feature = "render-ggez")]
+const MAX_ARROW_LENGTH: f32 = VISUAL_RANGE;
+// Arrow colors in behavior registration order: separation, cohesion, alignment, then custom
+#[cfg(feature = "render-ggez")]
+const FORCE_COLORS: [[f32; 4]; 6] = [
+    [1.0, 0.3, 0.3, 0.9],
+    [0.3, 1.0, 0.3, 0.9],
+    [0.3, 0.5, 1.0, 0.9],
+    [1.0, 0.9, 0.3, 0.9],
+    [1.0, 0.3, 1.0, 0.9],
+    [0.3, 1.0, 1.0, 0.9],
+];
+
+// Which boids get steering force arrows, cycled with <v>
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ForceDisplay {
+    Off,
+    Selected,
+    All,
+}
+
+impl ForceDisplay {
+    pub fn next(self) -> ForceDisplay {
+        match self {
+            ForceDisplay::Off => ForceDisplay::Selected,
+            ForceDisplay::Selected => ForceDisplay::All,
+            ForceDisplay::All => ForceDisplay::Off,
+        }
+    }
+}
+
+// Tracks the boid picked with the mouse
+#[derive(Clone)]
+pub struct Inspector {
+    pub selected: Option<usize>,
+}
+
+impl Inspector {
+    pub fn new() -> Self {
+        Inspector { selected: None }
+    }
+
+    pub fn clear(&mut self) {
+        self.selected = None;
+    }
+
+    // Select the boid closest to `point`, or nothing if none is close enough
+    pub fn select_at(&mut self, boids: &[Boid], point: glam::Vec2, radius: f32) {
+        self.selected = nearest_boid(boids, point, radius);
+    }
+
+    // The selected boid, if the selection is still valid
+    pub fn boid<'a>(&self, boids: &'a [Boid]) -> Option<&'a Boid> {
+        self.selected.and_then(|i| boids.get(i))
+    }
+
+    // Highlight, visual range and neighbor links for the selected boid
+    #[cfg(feature = "render-ggez")]
+    pub fn draw(
+        &self,
+        mb: &mut graphics::MeshBuilder,
+        boids: &[Boid],
+        index: &dyn NeighborIndex,
+        line_width: f32,
+    ) -> GameResult {
+        let (i, boid) = match self.selected.zip(self.boid(boids)) {
+            Some(selected) => selected,
+            None => return Ok(()),
+        };
+        let pos = glam::vec2(boid.x, boid.y);
+
+        // Lines to every neighbor within visual range
+        // The index may predate boids being added or removed this frame
+        for idx in index.get_neighbors(boid, VISUAL_RANGE) {
+            let other = match boids.get(idx) {
+                Some(other) => other,
+                None => continue,
+            };
+            if idx == i || boid.squared_distance(other) >= VISUAL_RANGE * VISUAL_RANGE {
+                continue;
+            }
+            mb.line(
+                &[pos, glam::vec2(other.x, other.y)],
+                line_width,
+                [1.0, 1.0, 0.4, 0.6].into(),
+            )?;
+        }
+
+        mb.circle(
+            graphics::DrawMode::stroke(line_width),
+            pos,
+            VISUAL_RANGE,
+            0.1,
+            [1.0, 1.0, 0.4, 0.4].into(),
+        )?;
+        mb.circle(
+            graphics::DrawMode::stroke(2.0 * line_width),
+            pos,
+            SELECT_RADIUS,
+            0.1,
+            [1.0, 1.0, 1.0, 0.9].into(),
+        )?;
+
+        Ok(())
+    }
+
+    // Multi-line description of the selected boid for the overlay
+    pub fn overlay_text(
+        &self,
+        boids: &[Boid],
+        forces: &[SteeringForces],
+        behaviors: &[WeightedBehavior],
+    ) -> Option<String> {
+        let i = self.selected?;
+        let boid = self.boid(boids)?;
+        let forces = forces.get(i).copied().unwrap_or_default();
+        let speed = (boid.dx * boid.dx + boid.dy * boid.dy).sqrt();
+
+        let mut text = format!(
+            "Boid #{}\npos: ({:.1}, {:.1})\nvel: ({:.1}, {:.1}) |{:.1}|\nneighbors: {}",
+            i,
+            boid.x, boid.y,
+            boid.dx, boid.dy, speed,
+            boid.neighbor_count,
+        );
+        for (weighted, force) in behaviors.iter().zip(&forces.contributions) {
+            text.push_str(&format!(
+                "\n{}: ({:.1}, {:.1})",
+                weighted.behavior.name(),
+                force.x,
+                force.y
+            ));
+        }
+
+        Some(text)
+    }
+}
+
+// Index of the boid closest to `point` within `radius`
+pub fn nearest_boid(boids: &[Boid], point: glam::Vec2, radius: f32) -> Option<usize> {
+    let mut nearest = None;
+    let mut best_dist = radius * radius;
+    for (i, boid) in boids.iter().enumerate() {
+        let dist = (glam::vec2(boid.x, boid.y) - point).length_squared();
+        if dist < best_dist {
+            best_dist = dist;
+            nearest = Some(i);
+        }
+    }
+    nearest
+}
+
+// Draw one arrow per behavior contribution starting at a boid
+#[cfg(feature = "render-ggez")]
+pub fn draw_force_arrows(
+    mb: &mut graphics::MeshBuilder,
+    boid: &Boid,
+    forces: &SteeringForces,
+    line_width: f32,
+) -> GameResult {
+    let pos = glam::vec2(boid.x, boid.y);
+
+    for (i, &force) in forces.contributions.iter().enumerate() {
+        let color = FORCE_COLORS[i % FORCE_COLORS.len()];
+        draw_arrow(mb, pos, force * FORCE_VECTOR_SCALE, color, line_width)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(feature = "render-ggez")]
+fn draw_arrow(
+    mb: &mut graphics::MeshBuilder,
+    from: glam::Vec2,
+    vector: glam::Vec2,
+    color: [f32; 4],
+    line_width: f32,
+) -> GameResult {
+    let length = vector.length();
+    if length < 0.5 {
+        return Ok(());
+    }
+
+    let direction = vector / length;
+    let tip = from + direction * length.min(MAX_ARROW_LENGTH);
+
+    // Two short strokes angled back from the tip form the head
+    let head = 4.0 * line_width.max(0.5);
+    let normal = glam::vec2(-direction.y, direction.x);
+    let left = tip - direction * head + normal * head * 0.5;
+    let right = tip - direction * head - normal * head * 0.5;
+
+    mb.line(&[from, tip], line_width, color.into())?;
+    mb.line(&[left, tip, right], line_width, color.into())?;
+
+    Ok(())
+}
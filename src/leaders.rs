@@ -0,0 +1,94 @@
+// leaders.rs
+use crate::boid::Boid;
+use rand::seq::index;
+use rand::Rng;
+
+// Leader settings
+pub const NUM_LEADERS: usize = 3;
+const FOLLOW_WEIGHT: f32 = 6.0;    // Velocity change per step towards the nearest leader
+const FOLLOW_RANGE: f32 = 400.0;   // Followers ignore leaders further away than this
+const WANDER_JITTER: f32 = 40.0;   // Random velocity change per step for wandering leaders
+const MOUSE_WEIGHT: f32 = 20.0;    // Velocity change per step for leaders chasing the cursor
+
+// What the leaders do, cycled with <l>
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LeaderMode {
+    Off,
+    Wander,
+    FollowMouse,
+}
+
+impl LeaderMode {
+    pub fn next(self) -> LeaderMode {
+        match self {
+            LeaderMode::Off => LeaderMode::Wander,
+            LeaderMode::Wander => LeaderMode::FollowMouse,
+            LeaderMode::FollowMouse => LeaderMode::Off,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            LeaderMode::Off => "Off",
+            LeaderMode::Wander => "Wander",
+            LeaderMode::FollowMouse => "Follow mouse",
+        }
+    }
+
+    // Steering for a leader boid under this mode
+    pub fn leader_force(self, boid: &Boid, mouse_pos: glam::Vec2, rng: &mut impl Rng) -> glam::Vec2 {
+        match self {
+            LeaderMode::Off => glam::Vec2::ZERO,
+            LeaderMode::Wander => glam::vec2(
+                rng.gen_range(-WANDER_JITTER..=WANDER_JITTER),
+                rng.gen_range(-WANDER_JITTER..=WANDER_JITTER),
+            ),
+            LeaderMode::FollowMouse => {
+                let offset = mouse_pos - glam::vec2(boid.x, boid.y);
+                if offset == glam::Vec2::ZERO {
+                    glam::Vec2::ZERO
+                } else {
+                    offset.normalize() * MOUSE_WEIGHT
+                }
+            }
+        }
+    }
+}
+
+// Promote `count` random boids to leaders, demoting everyone else
+pub fn assign_leaders(boids: &mut [Boid], count: usize, rng: &mut impl Rng) {
+    clear_leaders(boids);
+    let count = count.min(boids.len());
+    for i in index::sample(rng, boids.len(), count) {
+        boids[i].leader = true;
+    }
+}
+
+pub fn clear_leaders(boids: &mut [Boid]) {
+    for boid in boids {
+        boid.leader = false;
+    }
+}
+
+pub fn leader_positions(boids: &[Boid]) -> Vec<glam::Vec2> {
+    boids
+        .iter()
+        .filter(|boid| boid.leader)
+        .map(|boid| glam::vec2(boid.x, boid.y))
+        .collect()
+}
+
+// Pull of a follower towards the nearest leader in range
+pub fn follow_force(boid: &Boid, leaders: &[glam::Vec2]) -> glam::Vec2 {
+    let pos = glam::vec2(boid.x, boid.y);
+    let nearest = leaders
+        .iter()
+        .map(|&leader| leader - pos)
+        .filter(|offset| offset.length_squared() < FOLLOW_RANGE * FOLLOW_RANGE)
+        .min_by(|a, b| a.length_squared().total_cmp(&b.length_squared()));
+
+    match nearest {
+        Some(offset) if offset != glam::Vec2::ZERO => offset.normalize() * FOLLOW_WEIGHT,
+        _ => glam::Vec2::ZERO,
+    }
+}
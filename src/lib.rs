@@ -0,0 +1,45 @@
+// lib.rs
+// The headless simulation core as a library, so it can be used without the
+// ggez front end. Simulation in simulation.rs holds one world and steps it.
+// The macroquad visualizer in src/bin builds on it, and with --features
+// boids-py so does the Python module; the ggez visualizer in main.rs includes
+// the same modules directly.
+
+pub mod barnes_hut;
+pub mod behavior;
+pub mod boid;
+pub mod charts;
+pub mod collisions;
+pub mod energy;
+pub mod epidemic;
+pub mod evolution;
+pub mod flow_field;
+pub mod food;
+pub mod inspect;
+pub mod leaders;
+pub mod lod;
+pub mod math;
+pub mod metrics;
+pub mod neighbor_index;
+pub mod obstacles;
+pub mod pheromone;
+pub mod predators;
+pub mod quadtree;
+pub mod render;
+pub mod scoring;
+pub mod selection;
+pub mod shepherd;
+pub mod shockwave;
+pub mod sim_thread;
+#[cfg(feature = "simd")]
+pub mod simd_kernel;
+pub mod simulation;
+pub mod spatial_grid;
+pub mod spawn;
+pub mod trails;
+pub mod waypoints;
+pub mod world;
+pub mod zones;
+
+#[cfg(feature = "boids-py")]
+mod python;
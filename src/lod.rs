@@ -0,0 +1,91 @@
+// lod.rs
+
+// Level of detail settings. Intervals are in simulation steps.
+const OFFSCREEN_INTERVAL: usize = 2; // Boids outside the view...
+const DISTANT_INTERVAL: usize = 4;   // ...and more than a view's width or height beyond it
+
+// Level of detail for the simulation, toggled with <end>. Boids far from the
+// camera only run their steering behaviors every few steps, reusing the
+// steering from their last full update in between, while still moving every
+// step. Their neighbors are still looked up, as the energy model, epidemic
+// and metrics need them. The skipped boids are staggered so each step does
+// a similar amount of work.
+#[derive(Clone)]
+pub struct Lod {
+    pub active: bool,
+    view_center: glam::Vec2,   // Middle of the part of the world on screen
+    view_half: glam::Vec2,     // Half its size
+    step: usize,
+    due: Vec<bool>,            // Whether each boid runs its behaviors this step
+    steering: Vec<glam::Vec2>, // Behavior acceleration from each boid's last full update
+}
+
+impl Lod {
+    pub fn new() -> Self {
+        Lod {
+            active: false,
+            view_center: glam::Vec2::ZERO,
+            view_half: glam::Vec2::ONE,
+            step: 0,
+            due: Vec::new(),
+            steering: Vec::new(),
+        }
+    }
+
+    // Follow the camera, `center` and `half` describe the part of the world
+    // on screen
+    pub fn set_view(&mut self, center: glam::Vec2, half: glam::Vec2) {
+        self.view_center = center;
+        self.view_half = half;
+    }
+
+    // Decide which boids update fully this step
+    pub fn plan(&mut self, positions: impl ExactSizeIterator<Item = glam::Vec2>) {
+        self.step = self.step.wrapping_add(1);
+        self.steering.resize(positions.len(), glam::Vec2::ZERO);
+        self.due.clear();
+        let (step, view_center, view_half) = (self.step, self.view_center, self.view_half);
+        self.due.extend(positions.enumerate().map(|(i, pos)| {
+            // How many half views beyond the edge of the view the boid is
+            let beyond = (((pos - view_center).abs() - view_half) / view_half.max(glam::Vec2::ONE)).max_element();
+            let interval = if beyond <= 0.0 {
+                1
+            } else if beyond <= 2.0 {
+                OFFSCREEN_INTERVAL
+            } else {
+                DISTANT_INTERVAL
+            };
+            (step + i) % interval == 0
+        }));
+    }
+
+    // Forget the stored steering, for a flock that was replaced
+    pub fn clear(&mut self) {
+        self.due.clear();
+        self.steering.clear();
+    }
+
+    // Follow boids being removed, `kept` has one flag per boid before the removal
+    pub fn retain_kept(&mut self, kept: &[bool]) {
+        let mut flags = kept.iter();
+        self.due.retain(|_| flags.next().copied().unwrap_or(false));
+        let mut flags = kept.iter();
+        self.steering.retain(|_| flags.next().copied().unwrap_or(false));
+    }
+
+    // Whether boid `i` runs its behaviors this step; always when LOD is off
+    pub fn due(&self, i: usize) -> bool {
+        !self.active || self.due.get(i).copied().unwrap_or(true)
+    }
+
+    pub fn store(&mut self, i: usize, steering: glam::Vec2) {
+        if let Some(slot) = self.steering.get_mut(i) {
+            *slot = steering;
+        }
+    }
+
+    // Steering from boid `i`'s last full update
+    pub fn steering(&self, i: usize) -> glam::Vec2 {
+        self.steering.get(i).copied().unwrap_or(glam::Vec2::ZERO)
+    }
+}
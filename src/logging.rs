@@ -0,0 +1,28 @@
+// logging.rs
+use std::fs::File;
+use std::io;
+use std::path::Path;
+use std::sync::Mutex;
+use tracing_subscriber::{fmt, prelude::*, EnvFilter};
+
+// Used when RUST_LOG isn't set. The per-second summaries are at info, boids
+// coming and going at debug and the profiling spans at trace. ggez and wgpu
+// log through the `log` crate, which ends up here as well; wgpu is chatty.
+const DEFAULT_FILTER: &str = "info,wgpu_core=warn,wgpu_hal=warn,naga=warn";
+
+// Send log events to stdout, filtered by RUST_LOG, and mirror them to
+// `log_file` without colors if one was given with --log-file
+pub fn init(log_file: Option<&Path>) -> io::Result<()> {
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(DEFAULT_FILTER));
+    let file_layer = match log_file {
+        Some(path) => Some(fmt::layer().with_ansi(false).with_writer(Mutex::new(File::create(path)?))),
+        None => None,
+    };
+
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(fmt::layer().with_target(false))
+        .with(file_layer)
+        .init();
+    Ok(())
+}
@@ -1,40 +1,192 @@
+#[cfg(feature = "audio")]
+mod ambient;
+#[cfg(feature = "audio")]
+mod audio;
+mod autoscale;
+mod barnes_hut;
+mod behavior;
 mod boid;
+mod camera;
+mod charts;
+mod cli;
+mod collisions;
+mod color;
+mod config;
+mod debug;
+mod energy;
+mod epidemic;
+mod evolution;
+mod flow_field;
+mod food;
+mod gif_capture;
+#[cfg(feature = "gpu")]
+mod gpu;
+mod grid_tuning;
+mod input_log;
+mod input_map;
+mod inspect;
+mod leaders;
+mod lod;
+mod logging;
+mod math;
+mod metrics;
+#[cfg(feature = "midi")]
+mod midi;
+mod neighbor_index;
+mod obstacles;
+mod osc;
+mod pheromone;
+mod predators;
+mod presets;
+mod profiler;
+mod prometheus;
+mod quadtree;
+mod recorder;
+mod render;
+mod rewind;
+mod scenario;
+mod scoring;
+#[cfg(feature = "scripting")]
+mod script;
+mod selection;
+#[cfg(feature = "server")]
+mod server;
+mod shepherd;
+mod shockwave;
+mod sim_thread;
+#[cfg(feature = "simd")]
+mod simd_kernel;
+mod simulation;
+mod skin;
 mod spatial_grid;
+mod spawn;
+mod spawn_brush;
+mod stress;
+mod telemetry;
+mod theme;
+mod toast;
+mod trails;
+mod trajectories;
+mod tui;
+mod waypoints;
+mod world;
+mod zones;
 
-use boid::{Boid, VISUAL_RANGE};
+#[cfg(feature = "audio")]
+use ambient::Ambient;
+#[cfg(feature = "audio")]
+use audio::AudioInput;
+use autoscale::AutoScaler;
+use boid::VISUAL_RANGE;
+use camera::{Camera, PAN_SPEED, ZOOM_STEP};
+use charts::Charts;
+#[cfg(feature = "scripting")]
+use config::FileWatcher;
+use config::{ArenaMode, ArenaShape, Config, ConfigWatcher, Parameters};
+use color::ColorMode;
+use debug::{DebugFlags, HudMode};
+use evolution::{EvolutionLog, EVOLUTION_LOG};
+use flow_field::BRUSH_RADIUS;
+use gif_capture::{GifBuffer, GIF_SECONDS};
+#[cfg(feature = "gpu")]
+use gpu::GpuFlock;
+use grid_tuning::CellSizeTuner;
+use input_log::{InputEvent, InputPlayback, InputRecorder};
+use input_map::{Action, InputMap, Layer, NUDGED_WEIGHTS};
+use inspect::{ForceDisplay, SELECT_RADIUS};
+use leaders::LeaderMode;
+use metrics::FlockStats;
+#[cfg(feature = "midi")]
+use midi::MidiKnobs;
+use neighbor_index::{IndexKind, NeighborIndex};
+use obstacles::{ObstacleEditor, OBSTACLES_PATH};
+use osc::{OscCommand, OscListener};
+use pheromone::PheromoneMode;
+use presets::PRESETS;
+use profiler::{Profiler, Section};
+use prometheus::MetricsEndpoint;
+use recorder::Recorder;
+use render::GgezRenderer;
+use rewind::RewindBuffer;
+use scenario::{Scenario, ScenarioEvent};
+#[cfg(feature = "server")]
+use server::Server;
+use sim_thread::SimThread;
+use simulation::{Simulation, StepReport};
+use skin::Skin;
 use spatial_grid::SpatialGrid;
+use spawn::SpawnPattern;
+use spawn_brush::SpawnBrush;
+use stress::{StressAction, StressTest, STRESS_START_COUNT};
+use telemetry::Telemetry;
+use theme::Theme;
+use toast::Toast;
+use trajectories::TrajectoryWriter;
+use world::{Arena, BoundaryMode, ConvexPolygon, ResizeMode, World, DEFAULT_RESTITUTION};
 
 use ggez::{
     conf,
     event,
     graphics,
-    input,
-    timer,
+    input::keyboard::{KeyCode, KeyInput, KeyMods},
+    input::mouse::MouseButton,
     Context,
     ContextBuilder,
     GameResult,
 };
+use rand::Rng;
+use std::collections::VecDeque;
+use std::mem;
+use std::path::PathBuf;
 use std::time::Instant;
+use tracing::{debug, error, info, warn};
 
 // Window dimensions
 const HEIGHT: f32 = 720.0;
 const WIDTH: f32 = HEIGHT * (16.0 / 9.0);
 
+// Default world dimensions relative to the window, overridable with --world
+const WORLD_SCALE: f32 = 2.0;
+const WORLD_WIDTH: f32 = WIDTH * WORLD_SCALE;
+const WORLD_HEIGHT: f32 = HEIGHT * WORLD_SCALE;
+
 // Boid settings
 const NUM_BOIDS: usize = 1000; // Starting with 1000 boids
-const BOID_SIZE: f32 = 32.0;   // Pixels
+const BOID_COUNT_STEP: usize = 500; // Added or removed with <up> and <down>
+const BOID_SIZE: f32 = 32.0;   // Pixels at a UI scale of 1
+const MOTION_BLUR_FADE: f32 = 0.12; // Background alpha painted over the streaks each frame
 
 // Performance settings
-const CELL_SIZE: f32 = VISUAL_RANGE; // Cell size for spatial partitioning
-const FPS_TARGET: u32 = 30;          // Target fps
+const FPS_TARGET: u32 = 30;          // Target fps for --autoscale, see fps_cap in boids.toml for a hard limit
+
+// Demo mode settings
+const DEMO_INTERVAL: f32 = 30.0; // Seconds between preset/color changes
+const SCREENSAVER_MOTION_THRESHOLD: f32 = 10.0; // Pixels of mouse travel that wake the screen
+
+// Simulation speed multipliers selectable with <[> and <]>
+const TIME_SCALES: [f32; 9] = [0.1, 0.25, 0.5, 1.0, 2.0, 3.0, 5.0, 7.5, 10.0];
+const DEFAULT_TIME_SCALE_INDEX: usize = 3;
+
+// A right button press that moves less than this is a click, not a flow stroke
+const CLICK_DRAG_THRESHOLD: f32 = 4.0; // Pixels
+
+// Behavior weight nudges with <ctrl>, see NUDGED_WEIGHTS
+const WEIGHT_STEP: f32 = 1.1;      // Factor per nudge
+const WEIGHT_MIN_STEP: f32 = 0.01; // Smallest change per nudge, so a weight of zero can be raised
 
 // Rendering settings
-const DRAW_SPATIAL_GRID: bool = false; // Set to true to visualize the spatial grid
+const MENU_FONT_SIZE: f32 = 20.0;
+const MENU_COLUMN_GAP: f32 = 60.0;   // Pixels between menu columns
+const OCCUPANCY_MIN_ZOOM: f32 = 2.0; // Zoom at which cell occupancy numbers appear
 
-fn get_boids(count: usize) -> Vec<Boid> {
-    std::iter::repeat_with(|| Boid::new(WIDTH, HEIGHT))
-        .take(count)
-        .collect()
+// Outline of a boid pointing up, `size` pixels long
+fn boid_shape(size: f32) -> Vec<glam::Vec2> {
+    vec![
+        glam::vec2(0.0, -size / 2.0),
+        glam::vec2(size / 4.0, size / 2.0),
+        glam::vec2(0.0, size / 3.0),
+        glam::vec2(-size / 4.0, size / 2.0),
+    ]
 }
 
 enum PlayState {
@@ -43,93 +195,1722 @@ enum PlayState {
     Pause,
 }
 
+// A world waiting off screen, paused, until switched to with <tab>, along
+// with the front end state that belongs to it
+struct Tab {
+    sim: Simulation,
+    state: PlayState,
+    boid_count: usize,
+    spawn_pattern: SpawnPattern,
+    stats: FlockStats,
+    charts: Charts,
+    time_scale_index: usize,
+    rewind: RewindBuffer,
+    preset_index: usize,
+}
+
 struct State {
     state: PlayState,
+    sim: Simulation,                    // The world on screen
+    worlds: VecDeque<Tab>,              // Worlds not on screen, next one first
+    world_number: usize,                // Position of the one on screen, for the HUD
+    input_map: InputMap,                // Keyboard bindings, remappable in boids.toml
+    config: Config,                     // Contents of boids.toml as last loaded
+    config_watcher: Option<ConfigWatcher>, // Reloads boids.toml when it is saved
+    #[cfg(feature = "scripting")]
+    script_watcher: Option<FileWatcher>, // Reloads custom_behavior.rhai when it is saved
+    toast: Toast,                       // Briefly lists what a config reload changed
+    fullscreen: bool,                   // Borderless fullscreen, toggled with <f11>
+    fps_cap: Option<u32>,               // Frame limiter from boids.toml
+    frame_start: Instant,               // When the current frame began, for the frame limiter
+    scale_factor: f32,                  // Display scale factor reported by the OS
+    ui_scale: f32,                      // scale_factor times the ui_scale from boids.toml
     dt: std::time::Duration,
-    boids: Vec<Boid>,
-    spatial_grid: SpatialGrid,
+    spawn_pattern: SpawnPattern,        // Used on reset, cycled with <o>
+    fixed_world: bool,                  // Set by --world or OSC and scenarios, otherwise the world follows the window
+    camera: Camera,
+    show_trails: bool,                  // Toggled with <t>
+    motion_blur: bool,                  // Streak boids by fading instead of clearing, toggled with <u>
+    blur_image: Option<graphics::Image>, // Holds the streaks between frames
+    color_mode: ColorMode,
+    theme: Theme,
+    follow_selected: bool,              // Camera tracks the selected boid, toggled with <f>
+    stats: FlockStats,                  // Flock metrics from the last update
+    show_stats: bool,                   // Stats panel, toggled with <m>
+    charts: Charts,                     // Metric history for the sparklines
+    show_charts: bool,                  // Toggled with <k>
+    force_display: ForceDisplay,        // Debug arrows for those forces, cycled with <v>
+    debug_flags: DebugFlags,
+    time_scale_index: usize,            // Index into TIME_SCALES
+    rewind: RewindBuffer,               // Recent history, scrubbed by holding <b>
+    evolution_log: Option<EvolutionLog>, // Opened when evolution first starts
+    evolution_log_path: PathBuf,        // Set with --evolution-log
+    #[cfg(feature = "audio")]
+    audio: Option<AudioInput>,          // Music driving separation and speed, toggled with <f10>
+    #[cfg(feature = "audio")]
+    ambient: Option<Ambient>,           // Soundscape driven by the flock, toggled with <f12>
+    obstacle_editor: ObstacleEditor,    // Toggled with <e>
+    obstacles_path: PathBuf,            // Loaded with --obstacles, saved when leaving the editor
+    right_drag_distance: f32,           // Mouse travel since the right button went down
+    spawn_brush: SpawnBrush,            // Paints boids while <z> is held
+    preset_index: usize,                // Index into PRESETS, picked with <1>-<5>
+    hud: HudMode,                       // Cycled with <home>
+    demo: bool,                         // Started with --demo
+    demo_timer: f32,                    // Seconds since the demo last changed preset
+    screensaver: bool,                  // Started with --screensaver, quits on input
+    screensaver_motion: f32,            // Mouse travel seen so far
+    recorder: Option<Recorder>,         // Frame export, toggled with <f9>
+    gif_buffer: GifBuffer,              // Last few seconds, saved as a GIF with <f8>
+    telemetry: Option<Telemetry>,       // Flock statistics log, enabled with --telemetry
+    trajectories: Option<TrajectoryWriter>, // Every boid every step, enabled with --trajectories
+    #[cfg(feature = "server")]
+    server: Option<Server>,             // Live state for dashboards, enabled with --serve
+    osc: Option<OscListener>,           // Parameter control from OSC controllers, enabled with --osc
+    metrics_endpoint: Option<MetricsEndpoint>, // Prometheus scrape target, enabled with --metrics
+    #[cfg(feature = "midi")]
+    midi: Option<MidiKnobs>,            // Knobs mapped in the [midi] section of boids.toml
+    input_recorder: Option<InputRecorder>, // Input log, enabled with --record-input
+    playback: Option<InputPlayback>,    // Recorded input being replayed, enabled with --replay-input
+    scenario: Option<Scenario>,         // Timed events, enabled with --scenario
+    autoscaler: Option<AutoScaler>,     // Holds FPS_TARGET by changing the count, enabled with --autoscale
+    stress_test: Option<StressTest>,    // Ramps up the count until FPS drops, enabled with --stress
+    #[cfg(feature = "gpu")]
+    gpu: Option<GpuFlock>,              // Steps the flock in compute shaders, enabled with --backend gpu
+    sim_thread: Option<SimThread>,      // Steps the flock on a worker thread, enabled with --sim-thread
+    queued_steps: Option<(glam::Vec2, f32, usize)>, // Cursor, tick and count for the worker at the end of update
+    pending_input: Vec<InputEvent>,     // Arrived while the worker was stepping, handled once it is done
+    grid_tuner: CellSizeTuner,          // Resizes the grid cells as the density changes
+    index_time: std::time::Duration,    // Time spent building and querying the index
+    index_timings: [Option<f32>; 2],    // Last average μs per frame, indexed by IndexKind
     points: Vec<glam::Vec2>,
     fps_display: graphics::Text,
+    fps: f32,                           // Frame rate over the last second, for the minimal HUD
     frames: usize,
     frame_time: std::time::Duration,
     boid_count: usize,
-    mesh_cache: Option<graphics::Mesh>, // Cache for static parts of the mesh
-    last_update_time: Instant,          // For measuring time spent in update
-    last_draw_time: Instant,            // For measuring time spent in draw
+    mesh_cache: Option<graphics::Mesh>, // One boid mesh instanced per boid, rebuilt when `points` change
+    boid_instances: graphics::InstanceArray, // Position, rotation and color of every drawn boid
+    skin: Option<Skin>,                 // Image drawn instead of the polygon, set in boids.toml
+    profiler: Profiler,                 // Per-section frame times, shown with <f3>
+    show_profiler: bool,
 }
 
 impl State {
-    pub fn new(_ctx: &mut Context) -> State {
+    pub fn new(ctx: &mut Context, options: &cli::Options, input_map: InputMap) -> State {
+        let world = World::new(options.world_width, options.world_height);
+        
+        // All simulation randomness comes from one seeded generator so runs can be reproduced
+        let seed = options.seed.unwrap_or_else(rand::random);
+        info!("Seed: {}", seed);
+        let mut sim = Simulation::new(world, seed);
+        sim.set_parameter("goal_weight", options.goal_weight);
+        sim.set_parameter("emit_rate", options.emit_rate);
+        sim.set_parameter("food_rate", options.food_rate);
+        sim.set_parameter("infection_probability", options.infection_probability);
+        sim.set_parameter("wander", options.wander_strength);
+        sim.set_parameter("trait_variance", options.trait_variance);
+        
         // Create initial boids
-        let boids = get_boids(NUM_BOIDS);
+        let boid_count = if options.stress.is_some() { STRESS_START_COUNT } else { NUM_BOIDS };
+        sim.respawn(boid_count, SpawnPattern::Blob);
+        
+        let telemetry = options.telemetry.as_ref().and_then(|path| {
+            match Telemetry::create(path, options.telemetry_interval) {
+                Ok(telemetry) => {
+                    info!("Logging telemetry to {}", path.display());
+                    Some(telemetry)
+                }
+                Err(err) => {
+                    error!("Failed to open {}: {}", path.display(), err);
+                    None
+                }
+            }
+        });
+        
+        let input_recorder = options.record_input.as_ref().and_then(|path| {
+            match InputRecorder::create(path, seed) {
+                Ok(recorder) => {
+                    info!("Recording input to {}", path.display());
+                    Some(recorder)
+                }
+                Err(err) => {
+                    error!("Failed to open {}: {}", path.display(), err);
+                    None
+                }
+            }
+        });
+        
+        let trajectories = options.trajectories.as_ref().and_then(|path| {
+            match TrajectoryWriter::create(path) {
+                Ok(writer) => {
+                    info!("Writing trajectories to {}", path.display());
+                    Some(writer)
+                }
+                Err(err) => {
+                    error!("Failed to open {}: {}", path.display(), err);
+                    None
+                }
+            }
+        });
+        
+        #[cfg(feature = "server")]
+        let server = options.serve.and_then(|address| match Server::bind(address) {
+            Ok(server) => {
+                info!("Serving live state on ws://{}", address);
+                Some(server)
+            }
+            Err(err) => {
+                error!("Failed to serve on {}: {}", address, err);
+                None
+            }
+        });
+        
+        let osc = options.osc.and_then(|address| match OscListener::bind(address) {
+            Ok(osc) => {
+                info!("Listening for OSC on {}", address);
+                Some(osc)
+            }
+            Err(err) => {
+                error!("Failed to listen for OSC on {}: {}", address, err);
+                None
+            }
+        });
+        
+        let metrics_endpoint = options.metrics.and_then(|address| match MetricsEndpoint::bind(address) {
+            Ok(endpoint) => {
+                info!("Serving metrics on http://{}/metrics", address);
+                Some(endpoint)
+            }
+            Err(err) => {
+                error!("Failed to serve metrics on {}: {}", address, err);
+                None
+            }
+        });
+        
+        // High-DPI displays get proportionally larger boids and text
+        let scale_factor = ctx.gfx.window().scale_factor() as f32;
+        let ui_scale = scale_factor;
+        
+        let config_watcher = match ConfigWatcher::new(&options.config) {
+            Ok(watcher) => Some(watcher),
+            Err(err) => {
+                warn!("Not watching {} for changes: {}", options.config.display(), err);
+                None
+            }
+        };
+        
+        #[cfg(feature = "scripting")]
+        let script_watcher = match FileWatcher::new(std::path::Path::new(script::SCRIPT_PATH)) {
+            Ok(watcher) => Some(watcher),
+            Err(err) => {
+                warn!("Not watching {} for changes: {}", script::SCRIPT_PATH, err);
+                None
+            }
+        };
+        
+        #[cfg(feature = "scripting")]
+        match script::reload(&mut sim.behaviors, std::path::Path::new(script::SCRIPT_PATH)) {
+            Ok(true) => info!("Steering with {}", script::SCRIPT_PATH),
+            Ok(false) => {}
+            Err(message) => warn!("Script not loaded: {}", message),
+        }
+        
+        let sim_thread = if options.sim_thread { Some(SimThread::spawn()) } else { None };
+        
+        // Start with the camera looking at the middle of the world
+        let mut camera = Camera::new(WIDTH, HEIGHT);
+        camera.position = world.center();
+        
+        State {
+            // Demo and stress modes skip the menu and go straight to the flock
+            state: if options.demo || options.stress.is_some() { PlayState::Play } else { PlayState::Setup },
+            sim,
+            worlds: VecDeque::new(),
+            world_number: 0,
+            input_map,
+            config: Config::default(),
+            config_watcher,
+            #[cfg(feature = "scripting")]
+            script_watcher,
+            toast: Toast::new(),
+            fullscreen: options.screensaver,
+            fps_cap: None,
+            frame_start: Instant::now(),
+            scale_factor,
+            ui_scale,
+            dt: std::time::Duration::new(0, 0),
+            spawn_pattern: SpawnPattern::Blob,
+            fixed_world: options.fixed_world,
+            camera,
+            show_trails: false,
+            motion_blur: false,
+            blur_image: None,
+            color_mode: ColorMode::Random,
+            theme: Theme::default(),
+            follow_selected: false,
+            stats: FlockStats::default(),
+            show_stats: false,
+            charts: Charts::new(),
+            show_charts: false,
+            force_display: ForceDisplay::Off,
+            debug_flags: DebugFlags::NONE,
+            time_scale_index: DEFAULT_TIME_SCALE_INDEX,
+            rewind: RewindBuffer::new(options.rewind_seconds, options.rewind_memory_mb),
+            evolution_log: None,
+            evolution_log_path: options.evolution_log.clone().unwrap_or_else(|| PathBuf::from(EVOLUTION_LOG)),
+            #[cfg(feature = "audio")]
+            audio: None,
+            #[cfg(feature = "audio")]
+            ambient: None,
+            obstacle_editor: ObstacleEditor::default(),
+            obstacles_path: options.obstacles.clone().unwrap_or_else(|| PathBuf::from(OBSTACLES_PATH)),
+            right_drag_distance: 0.0,
+            spawn_brush: SpawnBrush::new(),
+            preset_index: 0,
+            hud: if options.demo { HudMode::Hidden } else { HudMode::Full },
+            demo: options.demo,
+            demo_timer: 0.0,
+            screensaver: options.screensaver,
+            screensaver_motion: 0.0,
+            recorder: None,
+            gif_buffer: GifBuffer::new(),
+            telemetry,
+            trajectories,
+            #[cfg(feature = "server")]
+            server,
+            osc,
+            metrics_endpoint,
+            #[cfg(feature = "midi")]
+            midi: None,
+            input_recorder,
+            playback: None,
+            scenario: None,
+            autoscaler: options.autoscale.map(|step| AutoScaler::new(FPS_TARGET, step)),
+            stress_test: options.stress.map(StressTest::new),
+            #[cfg(feature = "gpu")]
+            gpu: if options.gpu { start_gpu() } else { None },
+            sim_thread,
+            queued_steps: None,
+            pending_input: Vec::new(),
+            grid_tuner: CellSizeTuner::new(),
+            index_time: std::time::Duration::new(0, 0),
+            index_timings: [None; 2],
+            points: boid_shape(BOID_SIZE * ui_scale),
+            fps_display: graphics::Text::new(graphics::TextFragment {
+                text: "FPS: 0".to_string(),
+                color: Some(graphics::Color::WHITE),
+                font: None,
+                scale: Some(graphics::PxScale::from(20.0 * ui_scale)),
+            }),
+            fps: 0.0,
+            frames: 0,
+            frame_time: std::time::Duration::new(0, 0),
+            boid_count,
+            mesh_cache: None,
+            boid_instances: graphics::InstanceArray::new(ctx, None),
+            skin: None,
+            profiler: Profiler::new(),
+            show_profiler: false,
+        }
+    }
+    
+    // Add boids where the flock is sparsest or remove random ones, leaving
+    // the rest flying as they were
+    fn adjust_boid_count(&mut self, increase: bool) {
+        if increase {
+            self.resize_flock(self.sim.boids.len() + BOID_COUNT_STEP);
+        } else if self.sim.boids.len() > BOID_COUNT_STEP {
+            self.resize_flock(self.sim.boids.len() - BOID_COUNT_STEP);
+        }
+        
+        info!(boids = self.boid_count, "Boid count changed");
+    }
+    
+    // Grow or shrink the population to `count`. Every count change goes
+    // through here: the arrow keys, the autoscaler, stress tests and scenarios.
+    fn resize_flock(&mut self, count: usize) {
+        self.sim.resize_flock(count);
+        self.boid_count = self.sim.boids.len();
+    }
+    
+    // Remove every boid in the group selection
+    fn delete_selection(&mut self) {
+        self.sim.delete_selection();
+        self.boid_count = self.sim.boids.len();
+        info!(boids = self.boid_count, "Deleted group");
+    }
+    
+    // Give the group selection one new random color, shown in the random color mode
+    fn recolor_selection(&mut self) {
+        self.sim.recolor_selection();
+        self.color_mode = ColorMode::Random;
+        info!(boids = self.sim.selection.len(), "Recolored group");
+    }
+    
+    // Replace the group selection with predators, as many as there is room for
+    fn selection_to_predators(&mut self) {
+        let converted = self.sim.selection_to_predators();
+        self.boid_count = self.sim.boids.len();
+        info!(converted, predators = self.sim.predator_prey.predators.len(), "Turned group into predators");
+    }
+    
+    // Rebuild the grid with a different cell size when the tuner finds a cheaper one
+    fn tune_grid(&mut self, dt: f32) {
+        if let Some(cell_size) = self.grid_tuner.update(dt, &self.sim.spatial_grid, VISUAL_RANGE) {
+            info!(
+                "Grid cell size: {:.0}px -> {:.0}px ({:.1} candidates / query)",
+                self.sim.spatial_grid.cell_size(),
+                cell_size,
+                self.grid_tuner.candidates_per_query()
+            );
+            self.sim.spatial_grid = SpatialGrid::new(self.sim.world.width, self.sim.world.height, cell_size);
+        }
+    }
+    
+    // Whether <ctrl> is down, as recorded in the log when replaying input
+    fn is_ctrl_held(&self, ctx: &Context) -> bool {
+        match &self.playback {
+            Some(playback) => playback.is_key_held(KeyCode::LControl) || playback.is_key_held(KeyCode::RControl),
+            None => ctx.keyboard.active_mods().contains(KeyMods::CTRL),
+        }
+    }
+    
+    // Unit direction of the pan keys being held, zero if none are. With
+    // <ctrl> down they nudge behavior weights instead.
+    fn pan_direction(&self, ctx: &Context) -> glam::Vec2 {
+        let mut direction = glam::Vec2::ZERO;
+        if self.is_ctrl_held(ctx) {
+            return direction;
+        }
+        if self.is_held(ctx, Action::PanUp) {
+            direction.y -= 1.0;
+        }
+        if self.is_held(ctx, Action::PanDown) {
+            direction.y += 1.0;
+        }
+        if self.is_held(ctx, Action::PanLeft) {
+            direction.x -= 1.0;
+        }
+        if self.is_held(ctx, Action::PanRight) {
+            direction.x += 1.0;
+        }
+        direction.normalize_or_zero()
+    }
+    
+    // Pan the camera with WASD, or track the selected boid
+    fn update_camera(&mut self, ctx: &Context) {
+        // The pan keys steer the sheepdog during the herding game
+        let direction = if self.sim.shepherd.active { glam::Vec2::ZERO } else { self.pan_direction(ctx) };
+        if direction != glam::Vec2::ZERO {
+            // Manual panning takes over from the follow camera
+            self.follow_selected = false;
+            self.camera.pan(direction * PAN_SPEED * self.dt.as_secs_f32());
+        }
+        
+        if self.follow_selected {
+            match self.sim.inspector.boid(&self.sim.boids) {
+                Some(boid) => {
+                    let target = glam::vec2(boid.x, boid.y);
+                    self.camera.follow(target, self.dt.as_secs_f32());
+                }
+                None => self.follow_selected = false,
+            }
+        }
+    }
+    
+    fn toggle_follow(&mut self) {
+        if self.sim.inspector.boid(&self.sim.boids).is_some() {
+            self.follow_selected = !self.follow_selected;
+        } else {
+            info!("Select a boid to follow first");
+        }
+    }
+    
+    // Mouse position in world coordinates
+    fn mouse_world_position(&self, ctx: &Context) -> glam::Vec2 {
+        self.camera.screen_to_world(self.mouse_screen_position(ctx))
+    }
+    
+    // The input the simulation polls comes from the recording during playback
+    fn mouse_screen_position(&self, ctx: &Context) -> glam::Vec2 {
+        match &self.playback {
+            Some(playback) => playback.mouse,
+            None => {
+                let mouse = ctx.mouse.position();
+                glam::vec2(mouse.x, mouse.y)
+            }
+        }
+    }
+    
+    fn is_held(&self, ctx: &Context, action: Action) -> bool {
+        match &self.playback {
+            Some(playback) => playback.is_key_held(self.input_map.key(action)),
+            None => self.input_map.is_held(ctx, action),
+        }
+    }
+    
+    fn is_button_held(&self, ctx: &Context, button: MouseButton) -> bool {
+        match &self.playback {
+            Some(playback) => playback.is_button_held(button),
+            None => ctx.mouse.button_pressed(button),
+        }
+    }
+    
+    // Live input is logged when recording and ignored while a recording plays
+    fn live_input(&mut self, ctx: &mut Context, input: InputEvent) {
+        if self.playback.is_some() {
+            return;
+        }
+        if let Some(recorder) = self.input_recorder.as_mut() {
+            if let Err(err) = recorder.event(input) {
+                error!("Input recording stopped: {}", err);
+                self.input_recorder = None;
+            }
+        }
+        if self.sim_thread.as_mut().map_or(false, SimThread::busy) {
+            self.pending_input.push(input);
+            return;
+        }
+        self.handle_input(ctx, input);
+    }
+    
+    // Feed the next recorded frame in, replacing the measured frame time
+    fn replay_frame(&mut self, ctx: &mut Context) {
+        let frame = match self.playback.as_mut() {
+            Some(playback) => playback.next_frame(),
+            None => return,
+        };
+        
+        match frame {
+            Some((events, dt)) => {
+                self.dt = dt;
+                for input in events {
+                    if let Some(playback) = self.playback.as_mut() {
+                        playback.track(input);
+                    }
+                    self.handle_input(ctx, input);
+                }
+            }
+            None => {
+                let frames = self.playback.as_ref().map_or(0, |playback| playback.frames_played);
+                info!("Playback finished after {} frames with {} boids", frames, self.sim.boids.len());
+                ctx.request_quit();
+            }
+        }
+    }
+    
+    // Act on an input event, live or replayed
+    fn handle_input(&mut self, ctx: &mut Context, input: InputEvent) {
+        self.join_sim_thread();
+        match input {
+            // The editor takes <return> and <backspace> while it is open
+            InputEvent::KeyDown(KeyCode::Return, _) if self.obstacle_editor.active => self.finish_wall(),
+            InputEvent::KeyDown(KeyCode::Back, _) if self.obstacle_editor.active => {
+                self.sim.obstacles.pop();
+            }
+            InputEvent::KeyDown(keycode, mods) => {
+                // Held <ctrl>, then a group selection holding boids, get first pick of the keys
+                let ctrl = Some(Layer::Ctrl).filter(|_| mods.contains(KeyMods::CTRL));
+                let selection = Some(Layer::Selection).filter(|_| !self.sim.selection.is_empty());
+                let layers = ctrl.into_iter().chain(selection).chain(Some(Layer::Main));
+                if let Some(action) = layers.into_iter().find_map(|layer| self.input_map.action(layer, keycode)) {
+                    self.perform(ctx, action);
+                }
+            }
+            InputEvent::KeyUp(_) => {}
+            InputEvent::MouseDown(button, pos, mods) => self.mouse_pressed(button, pos, mods),
+            InputEvent::MouseUp(button, pos) => self.mouse_released(button, pos),
+            InputEvent::MouseMove(pos, delta) => self.mouse_moved(ctx, pos, delta),
+            InputEvent::Wheel(y) => {
+                let mouse = self.mouse_screen_position(ctx);
+                self.camera.zoom_at(mouse, ZOOM_STEP.powf(y));
+            }
+        }
+    }
+    
+    fn mouse_pressed(&mut self, button: MouseButton, pos: glam::Vec2, mods: KeyMods) {
+        if button == MouseButton::Right {
+            self.right_drag_distance = 0.0;
+        }
+        
+        if button == MouseButton::Left {
+            let point = self.camera.screen_to_world(pos);
+            if self.obstacle_editor.active {
+                self.obstacle_editor.press(point, mods.contains(KeyMods::SHIFT));
+            } else if mods.contains(KeyMods::CTRL) {
+                self.sim.waypoints.add(point);
+            } else if mods.contains(KeyMods::ALT) {
+                self.sim.shockwaves.start(point);
+            } else if mods.contains(KeyMods::SHIFT) {
+                if let Some(nearest) = inspect::nearest_boid(&self.sim.boids, point, SELECT_RADIUS) {
+                    self.sim.remove_boids(|i, _| i == nearest);
+                    self.boid_count = self.sim.boids.len();
+                }
+            } else {
+                // Pick the boid under the cursor, or clear the selection. A
+                // drag boxes a group instead, see mouse_released.
+                self.sim.inspector.select_at(&self.sim.boids, point, SELECT_RADIUS);
+                self.sim.selection.start_drag(point);
+            }
+        }
+    }
+    
+    // End the wall being drawn in the editor, keeping it if it has a segment
+    fn finish_wall(&mut self) {
+        if let Some(wall) = self.obstacle_editor.finish() {
+            self.sim.obstacles.push(wall);
+        }
+    }
+    
+    fn mouse_released(&mut self, button: MouseButton, pos: glam::Vec2) {
+        if self.obstacle_editor.active {
+            match button {
+                MouseButton::Left => {
+                    let point = self.camera.screen_to_world(pos);
+                    if let Some(circle) = self.obstacle_editor.release(point) {
+                        self.sim.obstacles.push(circle);
+                    }
+                }
+                MouseButton::Right => self.finish_wall(),
+                _ => {}
+            }
+            return;
+        }
+        
+        if button == MouseButton::Left && self.sim.selection.is_dragging() {
+            self.sim.rebuild_index();
+            let min_size = CLICK_DRAG_THRESHOLD / self.camera.zoom;
+            let index = match self.sim.index_kind {
+                IndexKind::Grid => &self.sim.spatial_grid as &dyn NeighborIndex,
+                IndexKind::QuadTree => &self.sim.quadtree,
+            };
+            if self.sim.selection.finish_drag(&self.sim.boids, index, min_size) {
+                info!(boids = self.sim.selection.len(), "Selected group");
+            } else {
+                self.sim.selection.clear();
+            }
+        }
+        
+        // A right click that didn't paint anything spawns a boid, or sends
+        // the group selection there
+        if button == MouseButton::Right && self.right_drag_distance < CLICK_DRAG_THRESHOLD {
+            if let PlayState::Setup = self.state {
+                return;
+            }
+            let point = self.camera.screen_to_world(pos);
+            if self.sim.selection.is_empty() {
+                self.sim.spawn_boid_at(point);
+                self.boid_count = self.sim.boids.len();
+            } else {
+                self.sim.selection.set_goal(point);
+                debug!(x = point.x, y = point.y, boids = self.sim.selection.len(), "Group goal set");
+            }
+        }
+    }
+    
+    fn mouse_moved(&mut self, ctx: &Context, pos: glam::Vec2, delta: glam::Vec2) {
+        // Drag the world around with the middle mouse button
+        if self.is_button_held(ctx, MouseButton::Middle) {
+            self.follow_selected = false;
+            self.camera.pan(-delta);
+        }
+        
+        // Stretch the selection box
+        if self.sim.selection.is_dragging() {
+            self.sim.selection.drag_to(self.camera.screen_to_world(pos));
+        }
+        
+        // Paint the flow field in the direction of the drag
+        if self.is_button_held(ctx, MouseButton::Right) && !self.obstacle_editor.active {
+            self.right_drag_distance += delta.x.abs() + delta.y.abs();
+            let point = self.camera.screen_to_world(pos);
+            self.sim.flow_field.paint(point, delta, BRUSH_RADIUS);
+        }
+    }
+    
+    // Controls listed in two columns, built from the current key bindings
+    fn draw_menu(&self, ctx: &Context, canvas: &mut graphics::Canvas) -> GameResult {
+        let mut lines = self.input_map.help_lines();
+        lines.extend(
+            [
+                "pan : <middle drag>",
+                "zoom : <scroll>",
+                "inspect boid : <left click>",
+                "select group : <left drag>",
+                "group goal : <right click> with a group selected",
+                "spawn / remove boid : <right click> / <shift> + <left click>",
+                "shockwave : <alt> + <left click>",
+                "paint flow : <right drag>",
+                "waypoints : <ctrl> + <left click>",
+                "walls / circles (editor) : <left click> / <shift> + <left drag>",
+                "sheepdog (herding game) : pan keys / gamepad left stick",
+            ]
+            .iter()
+            .map(|line| line.to_string()),
+        );
+        lines.push(format!("spawn pattern : {}", self.spawn_pattern.name()));
+        
+        let half = (lines.len() + 1) / 2;
+        let columns: Vec<graphics::Text> = lines
+            .chunks(half)
+            .map(|column| {
+                graphics::Text::new(graphics::TextFragment {
+                    text: column.join("\n"),
+                    color: Some(self.theme.hud_color()),
+                    font: None,
+                    scale: Some(graphics::PxScale::from(MENU_FONT_SIZE * self.ui_scale)),
+                })
+            })
+            .collect();
+        
+        let gap = MENU_COLUMN_GAP * self.ui_scale;
+        let sizes = columns.iter().map(|column| column.measure(ctx)).collect::<GameResult<Vec<_>>>()?;
+        let total_width: f32 = sizes.iter().map(|size| size.x).sum::<f32>() + gap * (columns.len() as f32 - 1.0);
+        let screen = self.camera.viewport();
+        let mut x = (screen.x - total_width) / 2.0;
+        for (column, size) in columns.iter().zip(&sizes) {
+            let y = (screen.y - size.y) / 2.0;
+            canvas.draw(column, graphics::DrawParam::default().dest(glam::vec2(x, y)));
+            x += size.x + gap;
+        }
+        
+        Ok(())
+    }
+    
+    // Screen-space text drawn on top of the world
+    fn draw_hud(&self, ctx: &Context, canvas: &mut graphics::Canvas, draw_grid: bool) -> GameResult {
+        let screen = self.camera.viewport();
+        let margin = 10.0 * self.ui_scale;
+        
+        // Draw the FPS display in the top-left corner
+        canvas.draw(&self.fps_display, graphics::DrawParam::default().dest(glam::vec2(margin, margin)));
+        
+        // Knob positions and scoring zone counts just below it, when in use
+        let mut y = 1.5 * margin + self.fps_display.measure(ctx)?.y;
+        #[cfg(feature = "midi")]
+        let midi = self.midi.as_ref().and_then(MidiKnobs::hud_text);
+        #[cfg(not(feature = "midi"))]
+        let midi: Option<String> = None;
+        for lines in midi.into_iter().chain(self.sim.scoring.hud_text()) {
+            let text = graphics::Text::new(graphics::TextFragment {
+                text: lines,
+                color: Some(self.theme.hud_color()),
+                font: None,
+                scale: Some(graphics::PxScale::from(16.0 * self.ui_scale)),
+            });
+            canvas.draw(&text, graphics::DrawParam::default().dest(glam::vec2(margin, y)));
+            y += text.measure(ctx)?.y + 0.5 * margin;
+        }
+        
+        // Herding game score along the top
+        if self.sim.shepherd.active {
+            let score = graphics::Text::new(graphics::TextFragment {
+                text: self.sim.shepherd.hud_text(),
+                color: Some(self.theme.hud_color()),
+                font: None,
+                scale: Some(graphics::PxScale::from(24.0 * self.ui_scale)),
+            });
+            let pos = glam::vec2((screen.x - score.measure(ctx)?.x) / 2.0, 4.0 * margin);
+            canvas.draw(&score, graphics::DrawParam::default().dest(pos));
+        }
+        
+        // Flock metrics in the top-right corner
+        if self.show_stats {
+            let panel = graphics::Text::new(graphics::TextFragment {
+                text: format!(
+                    "{}\nCell size: {:.0}px\nCandidates / query: {:.1}\nNeighbors / query: {:.1}\nCell occupancy: {:.1}",
+                    self.stats.panel_text(),
+                    self.sim.spatial_grid.cell_size(),
+                    self.grid_tuner.candidates_per_query(),
+                    self.grid_tuner.neighbors_per_query(),
+                    self.grid_tuner.occupancy,
+                ),
+                color: Some(self.theme.hud_color()),
+                font: None,
+                scale: Some(graphics::PxScale::from(16.0 * self.ui_scale)),
+            });
+            let panel_pos = glam::vec2(screen.x - panel.measure(ctx)?.x - margin, 4.0 * margin);
+            canvas.draw(&panel, graphics::DrawParam::default().dest(panel_pos));
+        }
+        
+        // Frame time breakdown in the bottom-left corner
+        if self.show_profiler {
+            let origin = glam::vec2(margin, screen.y - self.profiler.size(self.ui_scale).y - margin);
+            self.profiler.draw(ctx, canvas, origin, self.ui_scale)?;
+        }
+        
+        // Population curves along the bottom while an epidemic or the
+        // predator-prey mode runs, side by side if both do
+        let mut population_charts = Vec::new();
+        if self.sim.epidemic.active {
+            population_charts.push(&self.sim.epidemic.chart);
+        }
+        if self.sim.predator_prey.active {
+            population_charts.push(&self.sim.predator_prey.chart);
+        }
+        let charts_width: f32 = population_charts.iter().map(|chart| chart.size(self.ui_scale).x + margin).sum::<f32>() - margin;
+        let mut x = (screen.x - charts_width) / 2.0;
+        for chart in population_charts {
+            let size = chart.size(self.ui_scale);
+            chart.draw(ctx, canvas, glam::vec2(x, screen.y - size.y - margin), self.ui_scale)?;
+            x += size.x + margin;
+        }
+        
+        // Metric history in the bottom-right corner
+        if self.show_charts {
+            let origin = screen - self.charts.size(self.ui_scale) - glam::vec2(margin, margin);
+            self.charts.draw(ctx, canvas, origin, self.ui_scale)?;
+        }
+        
+        // Cell occupancy numbers once cells are big enough on screen
+        if draw_grid && self.camera.zoom >= OCCUPANCY_MIN_ZOOM {
+            self.draw_cell_occupancy(canvas);
+        }
+        
+        // Details of the selected boid in the bottom-left corner
+        if let Some(details) = self.sim.inspector.overlay_text(&self.sim.boids, &self.sim.forces, &self.sim.behaviors) {
+            let overlay = graphics::Text::new(graphics::TextFragment {
+                text: details,
+                color: Some(self.theme.hud_color()),
+                font: None,
+                scale: Some(graphics::PxScale::from(16.0 * self.ui_scale)),
+            });
+            let overlay_pos = glam::vec2(margin, screen.y - overlay.measure(ctx)?.y - margin);
+            canvas.draw(&overlay, graphics::DrawParam::default().dest(overlay_pos));
+        }
+        
+        Ok(())
+    }
+    
+    // Label each visible grid cell with the number of boids in it
+    fn draw_cell_occupancy(&self, canvas: &mut graphics::Canvas) {
+        // The grid is only filled while it is the active index
+        if self.sim.index_kind != IndexKind::Grid {
+            return;
+        }
+        
+        let cell_size = self.sim.spatial_grid.cell_size();
+        let (columns, rows) = self.sim.spatial_grid.dimensions();
+        let top_left = self.camera.screen_to_world(glam::vec2(0.0, 0.0));
+        let bottom_right = self.camera.screen_to_world(self.camera.viewport());
+        
+        let first_x = (top_left.x / cell_size).floor().max(0.0) as usize;
+        let first_y = (top_left.y / cell_size).floor().max(0.0) as usize;
+        let last_x = ((bottom_right.x / cell_size).ceil().max(0.0) as usize).min(columns);
+        let last_y = ((bottom_right.y / cell_size).ceil().max(0.0) as usize).min(rows);
+        
+        for cell_y in first_y..last_y {
+            for cell_x in first_x..last_x {
+                let count = self.sim.spatial_grid.occupancy(cell_x, cell_y);
+                if count == 0 {
+                    continue;
+                }
+                
+                let corner = glam::vec2(cell_x as f32 * cell_size, cell_y as f32 * cell_size);
+                let label = graphics::Text::new(graphics::TextFragment {
+                    text: count.to_string(),
+                    color: Some([0.8, 0.8, 0.8, 0.8].into()),
+                    font: None,
+                    scale: Some(graphics::PxScale::from(12.0 * self.ui_scale)),
+                });
+                let pos = self.camera.world_to_screen(corner) + glam::vec2(2.0, 2.0);
+                canvas.draw(&label, graphics::DrawParam::default().dest(pos));
+            }
+        }
+    }
+    
+    // Advance the simulation by a single step of `tick` seconds
+    fn step(&mut self, tick: f32, mouse_pos: glam::Vec2) {
+        let view_half = self.camera.viewport() / (2.0 * self.camera.zoom);
+        self.sim.lod.set_view(self.camera.position, view_half);
+        
+        // The GPU backend works out the classic rules in compute shaders, and
+        // the CPU steers until its first result comes back
+        #[cfg(feature = "gpu")]
+        let report = match self.gpu.as_mut() {
+            Some(gpu) => {
+                let mut weights = behavior::Weights::from_behaviors(&self.sim.behaviors);
+                weights.separation *= self.sim.separation_gain;
+                let range = VISUAL_RANGE * self.sim.range_scale;
+                match gpu.steering(&self.sim.boids, self.sim.population, weights, &self.sim.world, range) {
+                    Some((steering, neighbor_counts)) => {
+                        self.sim.step_with_steering(tick, mouse_pos, steering, neighbor_counts)
+                    }
+                    None => self.sim.step(tick, mouse_pos),
+                }
+            }
+            None => self.sim.step(tick, mouse_pos),
+        };
+        #[cfg(not(feature = "gpu"))]
+        let report = self.sim.step(tick, mouse_pos);
+        self.record_step(report);
+    }
+    
+    // Hand the simulation to the worker for the steps queued this frame,
+    // drawing the view of its last result in the meantime
+    fn send_to_sim_thread(&mut self) {
+        let (sim_thread, (mouse_pos, tick, steps)) = match (self.sim_thread.as_mut(), self.queued_steps.take()) {
+            (Some(sim_thread), Some(queued)) => (sim_thread, queued),
+            _ => return,
+        };
+        let view_half = self.camera.viewport() / (2.0 * self.camera.zoom);
+        self.sim.lod.set_view(self.camera.position, view_half);
+        
+        let keep_flocks = self.trajectories.is_some();
+        if !sim_thread.send(&mut self.sim, mouse_pos, tick, steps, keep_flocks) {
+            error!("Simulation thread stopped, stepping on the main thread");
+            self.sim_thread = None;
+        }
+    }
+    
+    // Take back the simulation the worker stepped, before anything reads or
+    // edits it
+    fn join_sim_thread(&mut self) {
+        if let Some(stepped) = self.sim_thread.as_mut().and_then(SimThread::take) {
+            self.sim = stepped.sim;
+            let elapsed = stepped.tick * stepped.reports.len() as f32;
+            for report in stepped.reports {
+                self.record_step(report);
+            }
+            if let Some(trajectories) = self.trajectories.as_mut() {
+                for flock in &stepped.flocks {
+                    trajectories.record(flock, stepped.tick);
+                }
+            }
+            self.rewind.record(&self.sim.boids, elapsed);
+        }
+    }
+    
+    // Profile and log what happened in a step
+    fn record_step(&mut self, report: StepReport) {
+        self.profiler.add(Section::GridRebuild, report.grid_rebuild);
+        self.profiler.add(Section::Neighbors, report.neighbors);
+        self.profiler.add(Section::Behaviors, report.behaviors);
+        self.index_time += report.grid_rebuild + report.neighbors;
+        self.boid_count = self.sim.boids.len();
+        
+        if let (Some(stats), Some(log)) = (report.bred, self.evolution_log.as_mut()) {
+            if let Err(err) = log.write(&stats) {
+                error!("Failed to write {}: {}", self.evolution_log_path.display(), err);
+                self.evolution_log = None;
+            }
+        }
+    }
+    
+    // Append the last flock statistics to the telemetry log
+    fn log_telemetry(&mut self) {
+        if let Some(telemetry) = self.telemetry.as_mut() {
+            if let Err(err) = telemetry.write(&self.stats, self.sim.scoring.zones()) {
+                error!("Telemetry disabled: {}", err);
+                self.telemetry = None;
+            }
+        }
+    }
+    
+    // Restore an older snapshot while the rewind key is held
+    fn rewind_step(&mut self) {
+        if let Some(boids) = self.rewind.rewind(self.dt.as_secs_f32()) {
+            self.sim.boids = boids;
+            self.sim.new_population();
+            self.boid_count = self.sim.boids.len();
+            self.sim.selection.truncate(self.boid_count);
+            self.sim.trails.clear();
+            self.sim.lod.clear();
+        }
+    }
+    
+    // Raise or lower a behavior weight by WEIGHT_STEP and flash all three
+    fn nudge_weight(&mut self, name: &str, raise: bool) {
+        let factor = if raise { WEIGHT_STEP } else { 1.0 / WEIGHT_STEP };
+        let weight = match behavior::weight(&self.sim.behaviors, name) {
+            Some(weight) if raise => (weight * factor).max(weight + WEIGHT_MIN_STEP),
+            Some(weight) => (weight * factor).min(weight - WEIGHT_MIN_STEP).max(0.0),
+            None => return,
+        };
+        self.sim.set_parameter(name, weight);
+        
+        let weights: Vec<String> = NUDGED_WEIGHTS
+            .iter()
+            .filter_map(|&name| behavior::weight(&self.sim.behaviors, name).map(|weight| format!("{} {:.3}", name, weight)))
+            .collect();
+        self.toast.show(weights.join(" | "));
+        debug!(behavior = name, weight, "Nudged weight");
+    }
+    
+    // Switch to one of the named parameter presets
+    fn select_preset(&mut self, index: usize) {
+        if let Some(preset) = PRESETS.get(index) {
+            preset.apply(&mut self.sim.behaviors, &mut self.sim.wander_strength);
+            self.preset_index = index;
+            info!("Preset: {}", preset.name);
+        }
+    }
+    
+    // Rotate through presets and color modes while running unattended
+    fn update_demo(&mut self) {
+        self.demo_timer += self.dt.as_secs_f32();
+        if self.demo_timer >= DEMO_INTERVAL {
+            self.demo_timer = 0.0;
+            self.select_preset((self.preset_index + 1) % PRESETS.len());
+            self.color_mode = self.color_mode.next();
+        }
+    }
+    
+    // Start or stop exporting frames
+    fn toggle_recording(&mut self) {
+        match self.recorder.take() {
+            Some(mut recorder) => {
+                recorder.stop();
+                info!(
+                    "Recording stopped: {} frames written, {} dropped",
+                    recorder.frames, recorder.dropped
+                );
+            }
+            None => self.recorder = Some(Recorder::start()),
+        }
+    }
+    
+    // Take over the parameters and key bindings of a newly loaded config,
+    // returning a description of each change
+    fn apply_config(&mut self, ctx: &mut Context, config: Config) -> Vec<String> {
+        let mut changes = Vec::new();
+        
+        // Parameters belong to the world on screen, like the keys that change them
+        for (name, value) in config.parameters.changes(&self.config.parameters) {
+            if self.sim.set_parameter(name, value) {
+                changes.push(format!("{} = {}", name, value));
+            }
+        }
+        
+        if config.display.fps_cap != self.config.display.fps_cap {
+            self.fps_cap = config.display.fps_cap.filter(|&cap| cap > 0);
+            match self.fps_cap {
+                Some(cap) => changes.push(format!("fps_cap = {}", cap)),
+                None => changes.push("fps_cap off".to_string()),
+            }
+        }
+        if config.display.ui_scale != self.config.display.ui_scale {
+            self.set_ui_scale(self.scale_factor, config.display.ui_scale.unwrap_or(1.0));
+            changes.push(format!("ui_scale = {}", config.display.ui_scale.unwrap_or(1.0)));
+        }
+        if config.display.skin != self.config.display.skin
+            || config.display.skin_frames != self.config.display.skin_frames
+        {
+            // A skin that fails to load falls back to the polygon
+            let frames = config.display.skin_frames.unwrap_or(1);
+            self.skin = config.display.skin.as_ref().and_then(|path| match Skin::load(ctx, path, frames) {
+                Ok(skin) => Some(skin),
+                Err(err) => {
+                    warn!("Failed to load skin: {}", err);
+                    None
+                }
+            });
+            match (&config.display.skin, &self.skin) {
+                (Some(path), Some(_)) => changes.push(format!("skin = {}", path.display())),
+                (Some(path), None) => changes.push(format!("skin {} failed to load", path.display())),
+                (None, _) => changes.push("skin off".to_string()),
+            }
+        }
+        if config.display.theme != self.config.display.theme {
+            self.theme = config.display.theme.unwrap_or_default();
+            changes.push(format!("theme = {}", self.theme.name()));
+        }
+        if config.display.vsync != self.config.display.vsync {
+            changes.push("vsync (applies after a restart)".to_string());
+        }
+        
+        // The arena and scoring zones are the same in every world
+        if config.arena.mode != self.config.arena.mode || config.arena.restitution != self.config.arena.restitution {
+            let restitution = config.arena.restitution.unwrap_or(DEFAULT_RESTITUTION).max(0.0).min(1.0);
+            let mode = match config.arena.mode.unwrap_or(ArenaMode::Turn) {
+                ArenaMode::Turn => BoundaryMode::Turn,
+                ArenaMode::Bounce => BoundaryMode::Bounce { restitution },
+            };
+            for sim in self.all_worlds() {
+                sim.boundary_mode = mode;
+            }
+            changes.push(match mode {
+                BoundaryMode::Turn => "arena mode = turn".to_string(),
+                BoundaryMode::Bounce { restitution } => format!("arena mode = bounce, restitution {}", restitution),
+            });
+        }
+        if config.arena.shape != self.config.arena.shape || config.arena.points != self.config.arena.points {
+            let shape = config.arena.shape.unwrap_or(ArenaShape::Rectangle);
+            let corners: Vec<glam::Vec2> = config.arena.points.iter().map(|&[x, y]| glam::vec2(x, y)).collect();
+            if shape == ArenaShape::Polygon && ConvexPolygon::new(corners.clone()).is_none() {
+                changes.push("arena polygon is not convex, using the world rectangle".to_string());
+            } else {
+                changes.push(format!("arena = {:?}", shape).to_lowercase());
+            }
+            let arena = match shape {
+                ArenaShape::Rectangle => Arena::Rectangle,
+                ArenaShape::Circle => Arena::Circle,
+                ArenaShape::Polygon => Arena::Polygon(corners),
+            };
+            for sim in self.all_worlds() {
+                sim.set_arena(arena.clone());
+            }
+        }
+        
+        if config.scoring != self.config.scoring {
+            for sim in self.all_worlds() {
+                sim.scoring.set_areas(&config.scoring, &sim.world);
+            }
+            changes.push(format!("{} scoring zones", config.scoring.len()));
+        }
+        
+        if config.keys != self.config.keys {
+            // Bindings were validated before the config was accepted
+            if let Ok(input_map) = InputMap::new(&config.keys) {
+                self.input_map = input_map;
+                changes.push("key bindings".to_string());
+            }
+        }
+        
+        // The port is opened once there is something to map, and kept after that
+        #[cfg(feature = "midi")]
+        if config.midi != self.config.midi {
+            match self.midi.as_mut() {
+                Some(midi) => {
+                    midi.set_mapping(&config.midi);
+                    changes.push("MIDI controls".to_string());
+                }
+                None if !config.midi.controls.is_empty() => match MidiKnobs::connect(&config.midi) {
+                    Ok(midi) => {
+                        self.midi = Some(midi);
+                        changes.push("MIDI controls".to_string());
+                    }
+                    Err(message) => warn!("MIDI controls unavailable: {}", message),
+                },
+                None => {}
+            }
+        }
+        #[cfg(not(feature = "midi"))]
+        if config.midi != self.config.midi && !config.midi.controls.is_empty() {
+            warn!("MIDI controls need a build with --features midi");
+        }
+        
+        self.config = config;
+        changes
+    }
+    
+    // Pick up edits to boids.toml
+    fn reload_config(&mut self, ctx: &mut Context) {
+        let reloaded = match self.config_watcher.as_ref().and_then(|watcher| watcher.poll()) {
+            Some(reloaded) => reloaded,
+            None => return,
+        };
+        
+        // A broken file leaves the running settings alone
+        let config = match reloaded.and_then(|config| InputMap::new(&config.keys).map(|_| config)) {
+            Ok(config) => config,
+            Err(message) => {
+                warn!("Invalid config: {}", message);
+                self.toast.show(format!("Config not reloaded:\n{}", message));
+                return;
+            }
+        };
+        
+        let changes = self.apply_config(ctx, config);
+        if !changes.is_empty() {
+            info!("Config reloaded: {}", changes.join(", "));
+            self.toast.show(format!("Config reloaded\n{}", changes.join("\n")));
+        }
+    }
+    
+    // Swap in the custom behavior script after it is saved
+    #[cfg(feature = "scripting")]
+    fn reload_script(&mut self) {
+        let path = match self.script_watcher.as_ref() {
+            Some(watcher) if watcher.changed() => watcher.path().to_path_buf(),
+            _ => return,
+        };
+        match script::reload(&mut self.sim.behaviors, &path) {
+            Ok(true) => {
+                info!("Reloaded {}", path.display());
+                self.toast.show(format!("Reloaded {}", path.display()));
+            }
+            Ok(false) => info!("{} removed, custom behavior off", path.display()),
+            Err(message) => {
+                warn!("Script not reloaded: {}", message);
+                self.toast.show(format!("Script not reloaded:\n{}", message));
+            }
+        }
+    }
+    
+    // Parameter changes from outside the window, applied like a config reload
+    fn apply_parameters(&mut self, ctx: &mut Context, parameters: &Parameters) -> Vec<String> {
+        let mut config = self.config.clone();
+        config.parameters = config.parameters.overlay(parameters);
+        self.apply_config(ctx, config)
+    }
+    
+    #[cfg(feature = "server")]
+    fn poll_server(&mut self, ctx: &mut Context) {
+        let commands = match self.server.as_mut() {
+            Some(server) => server.poll(),
+            None => return,
+        };
+        for parameters in commands {
+            let changes = self.apply_parameters(ctx, &parameters);
+            if !changes.is_empty() {
+                info!("Changed by a server client: {}", changes.join(", "));
+                self.toast.show(format!("Changed remotely\n{}", changes.join("\n")));
+            }
+        }
+    }
+    
+    // Knob movements are shown in the HUD rather than as toasts
+    #[cfg(feature = "midi")]
+    fn poll_midi(&mut self, ctx: &mut Context) {
+        if let Some(parameters) = self.midi.as_mut().and_then(MidiKnobs::poll) {
+            self.apply_parameters(ctx, &parameters);
+        }
+    }
+    
+    // OSC controllers send a stream of values while a fader moves, so these
+    // are only logged, a toast for each would flicker
+    fn poll_osc(&mut self, ctx: &mut Context) {
+        let commands = match self.osc.as_mut() {
+            Some(osc) => osc.poll(),
+            None => return,
+        };
+        for command in commands {
+            match command {
+                OscCommand::Parameters(parameters) => {
+                    for change in self.apply_parameters(ctx, &parameters) {
+                        debug!("Changed by OSC: {}", change);
+                    }
+                }
+                OscCommand::Count(count) => {
+                    if count != self.sim.boids.len() {
+                        self.resize_flock(count);
+                    }
+                }
+                OscCommand::World(width, height) => self.set_world_size(width, height),
+            }
+        }
+    }
+    
+    // A world size asked for from outside the window. It stays fixed from
+    // then on, as with --world, instead of following the window.
+    fn set_world_size(&mut self, width: f32, height: f32) {
+        match self.sim.resize(width, height, ResizeMode::Rescale) {
+            Ok(()) => {
+                self.fixed_world = true;
+                info!("World size: {}x{}", width, height);
+            }
+            Err(message) => warn!("World not resized: {}", message),
+        }
+    }
+    
+    // Keep the world sized to the window, unless it was fixed. A minimized
+    // window has no size, so the world stays as it was until it comes back.
+    fn follow_window(&mut self, viewport: glam::Vec2) {
+        if self.fixed_world {
+            return;
+        }
+        if let Err(message) = self.sim.resize(viewport.x * WORLD_SCALE, viewport.y * WORLD_SCALE, ResizeMode::Clamp) {
+            debug!("World not resized: {}", message);
+        }
+    }
+    
+    // Carry out the scenario events due after `sim_dt` more simulated seconds
+    fn run_scenario(&mut self, ctx: &mut Context, sim_dt: f32) {
+        let (events, time) = match self.scenario.as_mut() {
+            Some(scenario) => (scenario.advance(sim_dt), scenario.time()),
+            None => return,
+        };
+        for event in events {
+            info!("Scenario at {:.1}s: {:?}", time, event);
+            match event {
+                ScenarioEvent::SpawnPredators(count) => self.sim.predator_prey.spawn(count, &self.sim.world, &mut self.sim.rng),
+                ScenarioEvent::SpawnBoids(count) => self.resize_flock(self.sim.boids.len() + count),
+                ScenarioEvent::Count(count) => self.resize_flock(count),
+                ScenarioEvent::World(width, height) => self.set_world_size(width, height),
+                ScenarioEvent::Wind(wind) => self.sim.wind = wind,
+                ScenarioEvent::Set(name, value) => {
+                    let mut parameters = Parameters::default();
+                    parameters.set(&name, value);
+                    self.apply_parameters(ctx, &parameters);
+                }
+                ScenarioEvent::Reset => self.start_simulation(),
+                ScenarioEvent::Pause => self.state = PlayState::Pause,
+                ScenarioEvent::Quit => ctx.request_quit(),
+            }
+        }
+    }
+    
+    // Leave the setup menu with a freshly spawned flock
+    fn start_simulation(&mut self) {
+        self.sim.respawn(self.boid_count, self.spawn_pattern);
+        self.rewind.clear();
+        self.state = PlayState::Play;
+    }
+    
+    // Carry out a key press
+    fn perform(&mut self, ctx: &mut Context, action: Action) {
+        match action {
+            Action::Play => match self.state {
+                PlayState::Setup => self.start_simulation(),
+                PlayState::Pause => self.state = PlayState::Play,
+                PlayState::Play => {}
+            },
+            Action::Pause => {
+                if let PlayState::Play = self.state {
+                    self.state = PlayState::Pause;
+                }
+            }
+            Action::Reset => self.state = PlayState::Setup,
+            Action::AddBoids | Action::RemoveBoids => {
+                if let PlayState::Setup = self.state {
+                    return;
+                }
+                self.adjust_boid_count(action == Action::AddBoids);
+            }
+            Action::Quit => ctx.request_quit(),
+            Action::CycleIndex => self.cycle_neighbor_index(),
+            Action::CycleTheme => {
+                self.theme = self.theme.next();
+                info!("Theme: {}", self.theme.name());
+            }
+            Action::CycleColor => {
+                self.color_mode = self.color_mode.next();
+                info!("Color mode: {}", self.color_mode.name());
+            }
+            Action::ToggleFollow => self.toggle_follow(),
+            Action::CycleForces => self.force_display = self.force_display.next(),
+            Action::ToggleGrid => self.debug_flags.toggle(DebugFlags::SPATIAL_GRID),
+            Action::ToggleTrails => {
+                self.show_trails = !self.show_trails;
+                self.sim.trails.clear();
+            }
+            Action::ToggleMotionBlur => {
+                self.motion_blur = !self.motion_blur;
+                self.blur_image = None;
+            }
+            Action::NextWorld => self.next_world(),
+            Action::NewWorld => self.new_world(),
+            Action::ToggleStats => self.show_stats = !self.show_stats,
+            Action::ToggleCharts => self.show_charts = !self.show_charts,
+            Action::ToggleProfiler => self.show_profiler = !self.show_profiler,
+            Action::CycleLeaders => {
+                self.sim.leader_mode = self.sim.leader_mode.next();
+                if self.sim.leader_mode == LeaderMode::Off {
+                    leaders::clear_leaders(&mut self.sim.boids);
+                }
+                info!("Leaders: {}", self.sim.leader_mode.name());
+            }
+            Action::CycleSpawnPattern => {
+                self.spawn_pattern = self.spawn_pattern.next();
+                info!("Spawn pattern: {}", self.spawn_pattern.name());
+            }
+            Action::SlowDown => self.adjust_time_scale(false),
+            Action::SpeedUp => self.adjust_time_scale(true),
+            Action::ClearFlow => self.sim.flow_field.clear(),
+            Action::ClearWaypoints => self.sim.waypoints.clear(),
+            Action::AddEmitter => {
+                let point = self.mouse_world_position(ctx);
+                self.sim.zones.add_emitter(point, &self.sim.world);
+            }
+            Action::AddSink => {
+                let point = self.mouse_world_position(ctx);
+                self.sim.zones.add_sink(point);
+            }
+            Action::ClearZones => self.sim.zones.clear(),
+            Action::ToggleFood => {
+                self.sim.food.toggle(&self.sim.world, &mut self.sim.rng);
+                info!("Food: {}", if self.sim.food.active { "on" } else { "off" });
+            }
+            Action::ToggleEnergy => {
+                self.sim.energy_model = !self.sim.energy_model;
+                if !self.sim.energy_model {
+                    energy::restore(&mut self.sim.boids);
+                }
+                info!("Energy model: {}", if self.sim.energy_model { "on" } else { "off" });
+            }
+            Action::ToggleEpidemic => {
+                self.sim.epidemic.toggle(&mut self.sim.boids, &mut self.sim.rng);
+                if self.sim.epidemic.active {
+                    self.color_mode = ColorMode::Infection;
+                }
+                info!("Epidemic: {}", if self.sim.epidemic.active { "started" } else { "off" });
+            }
+            Action::ToggleEditor => {
+                self.obstacle_editor.active = !self.obstacle_editor.active;
+                if self.obstacle_editor.active {
+                    info!("Editing obstacles");
+                } else {
+                    self.finish_wall();
+                    match self.sim.obstacles.save(&self.obstacles_path) {
+                        Ok(()) => info!("Saved {} obstacles to {}", self.sim.obstacles.items().len(), self.obstacles_path.display()),
+                        Err(err) => error!("Failed to save {}: {}", self.obstacles_path.display(), err),
+                    }
+                }
+            }
+            Action::ToggleCollisions => {
+                self.sim.collisions.active = !self.sim.collisions.active;
+                info!("Collisions: {}", if self.sim.collisions.active { "on" } else { "off" });
+            }
+            Action::CycleEvolution => {
+                self.sim.evolution.cycle(&mut self.sim.boids);
+                if self.sim.evolution.is_active() && self.evolution_log.is_none() {
+                    match EvolutionLog::create(&self.evolution_log_path) {
+                        Ok(log) => self.evolution_log = Some(log),
+                        Err(err) => error!("Failed to create {}: {}", self.evolution_log_path.display(), err),
+                    }
+                }
+                info!("Evolution: {}", self.sim.evolution.name());
+            }
+            #[cfg(feature = "audio")]
+            Action::ToggleAudio => match self.audio.take() {
+                Some(_) => {
+                    for sim in self.all_worlds() {
+                        sim.separation_gain = 1.0;
+                        sim.speed_gain = 1.0;
+                    }
+                    info!("Audio reactive: off");
+                }
+                None => match AudioInput::open() {
+                    Ok(audio) => {
+                        self.audio = Some(audio);
+                        info!("Audio reactive: on");
+                    }
+                    Err(message) => {
+                        warn!("No audio input: {}", message);
+                        self.toast.show(format!("No audio input: {}", message));
+                    }
+                },
+            },
+            Action::ToggleLod => {
+                self.sim.lod.active = !self.sim.lod.active;
+                info!("Level of detail: {}", if self.sim.lod.active { "on" } else { "off" });
+            }
+            Action::ToggleShepherd => {
+                self.sim.shepherd.toggle(&self.sim.world);
+                info!("Herding game: {}", if self.sim.shepherd.active { "on" } else { "off" });
+            }
+            Action::CycleHud => {
+                self.hud = self.hud.next();
+                info!("HUD: {}", self.hud.name());
+            }
+            #[cfg(feature = "audio")]
+            Action::ToggleAmbient => match self.ambient.take() {
+                Some(_) => info!("Ambient sound: off"),
+                None => match Ambient::open() {
+                    Ok(ambient) => {
+                        self.ambient = Some(ambient);
+                        info!("Ambient sound: on");
+                    }
+                    Err(message) => {
+                        warn!("No audio output: {}", message);
+                        self.toast.show(format!("No audio output: {}", message));
+                    }
+                },
+            },
+            Action::CyclePheromones => {
+                self.sim.pheromones.mode = self.sim.pheromones.mode.next();
+                if self.sim.pheromones.mode == PheromoneMode::Off {
+                    self.sim.pheromones.clear();
+                }
+                info!("Pheromones: {}", self.sim.pheromones.mode.name());
+            }
+            Action::TogglePredators => {
+                self.sim.predator_prey.toggle(&self.sim.world, &mut self.sim.rng);
+                info!("Predator-prey mode: {}", if self.sim.predator_prey.active { "on" } else { "off" });
+            }
+            Action::ShrinkBrush => self.spawn_brush.adjust_radius(false),
+            Action::GrowBrush => self.spawn_brush.adjust_radius(true),
+            Action::SlowerBrush => self.spawn_brush.adjust_rate(false),
+            Action::FasterBrush => self.spawn_brush.adjust_rate(true),
+            Action::SaveGif => {
+                info!("Saving the last {} seconds as a GIF", GIF_SECONDS);
+                self.gif_buffer.save();
+            }
+            Action::ToggleRecording => self.toggle_recording(),
+            Action::ToggleFullscreen => self.toggle_fullscreen(ctx),
+            Action::Preset(index) => self.select_preset(index),
+            Action::DeleteSelection => self.delete_selection(),
+            Action::RecolorSelection => self.recolor_selection(),
+            Action::SelectionToPredators => self.selection_to_predators(),
+            Action::RaiseWeight(index) => self.nudge_weight(NUDGED_WEIGHTS[index], true),
+            Action::LowerWeight(index) => self.nudge_weight(NUDGED_WEIGHTS[index], false),
+            // Held actions are polled in update
+            Action::PanUp
+            | Action::PanDown
+            | Action::PanLeft
+            | Action::PanRight
+            | Action::Rewind
+            | Action::SpawnBrush => {}
+        }
+    }
+    
+    // Indices of the boids that can be on screen, or every boid when the
+    // whole world is in view
+    fn visible_boids(&mut self) -> Vec<usize> {
+        // Pad by a boid so ones straddling the edge still get drawn
+        let padding = glam::Vec2::splat(BOID_SIZE * self.ui_scale / self.camera.zoom);
+        let min = self.camera.screen_to_world(glam::Vec2::ZERO) - padding;
+        let max = self.camera.screen_to_world(self.camera.viewport()) + padding;
+        if min.x <= 0.0 && min.y <= 0.0 && max.x >= self.sim.world.width && max.y >= self.sim.world.height {
+            return (0..self.sim.boids.len()).collect();
+        }
         
-        // Create spatial grid for efficient neighbor lookups
-        let spatial_grid = SpatialGrid::new(WIDTH, HEIGHT, CELL_SIZE);
+        // The index is rebuilt because boids may have moved since update filled it,
+        // e.g. while paused, rewinding or painting
+        self.sim.rebuild_index();
+        let mut visible = self.sim.active_index().query_rect(min, max);
+        visible.retain(|&i| {
+            let boid = &self.sim.boids[i];
+            boid.x >= min.x && boid.x <= max.x && boid.y >= min.y && boid.y <= max.y
+        });
+        visible
+    }
+    
+    // Draw the given boids through the camera with a single instanced draw call
+    fn draw_boids(
+        &mut self,
+        ctx: &Context,
+        canvas: &mut graphics::Canvas,
+        visible: &[usize],
+        colors: &[[f32; 4]],
+    ) -> GameResult {
+        if visible.is_empty() {
+            return Ok(());
+        }
+        if let Some(skin) = self.skin.as_mut() {
+            let colors: Vec<[f32; 4]> = visible.iter().map(|&i| colors[i]).collect();
+            let boids = &self.sim.boids;
+            let sprites = visible.iter().map(|&i| {
+                let boid = &boids[i];
+                (glam::vec2(boid.x, boid.y), boid.dx.atan2(-boid.dy), boid.wing_phase)
+            });
+            return skin.draw(canvas, sprites, &colors, BOID_SIZE * self.ui_scale, self.camera.draw_param());
+        }
         
-        State {
-            state: PlayState::Setup,
-            dt: std::time::Duration::new(0, 0),
-            boids,
-            spatial_grid,
-            points: vec![
-                glam::vec2(0.0, -BOID_SIZE / 2.0),
-                glam::vec2(BOID_SIZE / 4.0, BOID_SIZE / 2.0),
-                glam::vec2(0.0, BOID_SIZE / 3.0),
-                glam::vec2(-BOID_SIZE / 4.0, BOID_SIZE / 2.0),
-            ],
-            fps_display: graphics::Text::new(graphics::TextFragment {
-                text: "FPS: 0".to_string(),
-                color: Some(graphics::Color::WHITE),
-                font: Some(graphics::Font::default()),
-                scale: Some(graphics::PxScale::from(20.0)),
-            }),
-            frames: 0,
-            frame_time: std::time::Duration::new(0, 0),
-            boid_count: NUM_BOIDS,
-            mesh_cache: None,
-            last_update_time: Instant::now(),
-            last_draw_time: Instant::now(),
+        let mesh = match &self.mesh_cache {
+            Some(mesh) => mesh.clone(),
+            None => {
+                let mesh = graphics::Mesh::new_polygon(
+                    ctx,
+                    graphics::DrawMode::fill(),
+                    &self.points,
+                    graphics::Color::WHITE,
+                )?;
+                self.mesh_cache = Some(mesh.clone());
+                mesh
+            }
+        };
+        
+        let mut renderer = GgezRenderer { instances: &mut self.boid_instances };
+        let boids = &self.sim.boids;
+        render::draw_boids(&mut renderer, boids, visible.iter().copied(), |i| colors[i], &self.sim.world, "");
+        canvas.draw_instanced_mesh(mesh, &self.boid_instances, self.camera.draw_param());
+        Ok(())
+    }
+    
+    // Paint the boids into the blur image over a faded copy of the previous
+    // frames, then draw it in place of the background
+    fn draw_motion_blur(
+        &mut self,
+        ctx: &mut Context,
+        canvas: &mut graphics::Canvas,
+        visible: &[usize],
+        colors: &[[f32; 4]],
+    ) -> GameResult {
+        let screen = self.camera.viewport();
+        let fresh = self.blur_image.is_none();
+        let image = match &self.blur_image {
+            Some(image) => image.clone(),
+            None => {
+                let (width, height) = ctx.gfx.drawable_size();
+                let format = ctx.gfx.surface_format();
+                let image = graphics::Image::new_canvas_image(ctx, format, width as u32, height as u32, 1);
+                self.blur_image = Some(image.clone());
+                image
+            }
+        };
+        
+        // Only a new image is cleared, the old streaks are faded instead
+        let clear = if fresh { Some(self.theme.clear_color()) } else { None };
+        let mut blur = graphics::Canvas::from_image(ctx, image.clone(), clear);
+        if fresh {
+            self.theme.draw_background(ctx, &mut blur, screen.x, screen.y)?;
+        } else {
+            self.theme.fade_background(ctx, &mut blur, screen.x, screen.y, MOTION_BLUR_FADE)?;
         }
+        self.draw_boids(ctx, &mut blur, visible, colors)?;
+        blur.finish(ctx)?;
+        
+        canvas.draw(&image, graphics::DrawParam::new());
+        Ok(())
     }
     
-    // Helper function to adjust the number of boids
-    fn adjust_boid_count(&mut self, increase: bool, _ctx: &mut Context) {
-        if increase {
-            self.boid_count += 500; // Increase by 500 instead of 100
-        } else if self.boid_count > 500 {
-            self.boid_count -= 500; // Decrease by 500 instead of 100
+    // Recompute everything sized by the UI scale
+    fn set_ui_scale(&mut self, scale_factor: f32, multiplier: f32) {
+        self.scale_factor = scale_factor;
+        self.ui_scale = scale_factor * multiplier;
+        self.points = boid_shape(BOID_SIZE * self.ui_scale);
+        self.mesh_cache = None;
+        // The FPS text picks up the new size on its next refresh
+    }
+    
+    // Switch between a window and borderless fullscreen; resize_event follows
+    fn toggle_fullscreen(&mut self, ctx: &mut Context) {
+        let mode = if self.fullscreen {
+            conf::FullscreenType::Windowed
+        } else {
+            conf::FullscreenType::Desktop
+        };
+        match ctx.gfx.set_fullscreen(mode) {
+            Ok(()) => self.fullscreen = !self.fullscreen,
+            Err(err) => warn!("Failed to change fullscreen mode: {}", err),
+        }
+    }
+    
+    // Sleep off whatever is left of the frame budget when a cap is set
+    fn limit_frame_rate(&mut self) {
+        if let Some(cap) = self.fps_cap {
+            let budget = std::time::Duration::from_secs_f32(1.0 / cap as f32);
+            let elapsed = self.frame_start.elapsed();
+            if elapsed < budget {
+                std::thread::sleep(budget - elapsed);
+            }
+        }
+        self.frame_start = Instant::now();
+    }
+    
+    // Step through TIME_SCALES, clamping at either end
+    fn adjust_time_scale(&mut self, faster: bool) {
+        if faster {
+            self.time_scale_index = (self.time_scale_index + 1).min(TIME_SCALES.len() - 1);
+        } else {
+            self.time_scale_index = self.time_scale_index.saturating_sub(1);
+        }
+        info!("Time scale: {}x", TIME_SCALES[self.time_scale_index]);
+    }
+    
+    // Switch to the next neighbor index, keeping the timings of the old one
+    fn cycle_neighbor_index(&mut self) {
+        self.sim.index_kind = self.sim.index_kind.next();
+        self.index_time = std::time::Duration::new(0, 0);
+        info!("Neighbor index: {}", self.sim.index_kind.name());
+    }
+    
+    // Exchange the world on screen with `other`, returning the old one
+    fn swap_world(&mut self, mut other: Tab) -> Tab {
+        mem::swap(&mut self.sim, &mut other.sim);
+        if let Some(sim_thread) = self.sim_thread.as_mut() {
+            sim_thread.discard_view();
         }
+        mem::swap(&mut self.state, &mut other.state);
+        mem::swap(&mut self.boid_count, &mut other.boid_count);
+        mem::swap(&mut self.spawn_pattern, &mut other.spawn_pattern);
+        mem::swap(&mut self.stats, &mut other.stats);
+        mem::swap(&mut self.charts, &mut other.charts);
+        mem::swap(&mut self.time_scale_index, &mut other.time_scale_index);
+        mem::swap(&mut self.rewind, &mut other.rewind);
+        mem::swap(&mut self.preset_index, &mut other.preset_index);
+        other
+    }
+    
+    // Every world, the one on screen first
+    fn all_worlds(&mut self) -> impl Iterator<Item = &mut Simulation> {
+        std::iter::once(&mut self.sim).chain(self.worlds.iter_mut().map(|tab| &mut tab.sim))
+    }
+    
+    // Show the next simulation, keeping the current one as it is
+    fn next_world(&mut self) {
+        let next = match self.worlds.pop_front() {
+            Some(next) => next,
+            None => return,
+        };
+        let previous = self.swap_world(next);
+        self.worlds.push_back(previous);
+        self.world_number = (self.world_number + 1) % (self.worlds.len() + 1);
+        self.blur_image = None;
         
-        // Update boids
-        self.boids = get_boids(self.boid_count);
-            
-        println!("Boid count: {}", self.boid_count);
+        // The window may have changed size while this world was hidden
+        self.follow_window(self.camera.viewport());
+        self.camera.position = self.sim.world.center();
+        info!("World {}/{}", self.world_number + 1, self.worlds.len() + 1);
     }
     
-    // Update the spatial grid with current boid positions
-    fn update_spatial_grid(&mut self) {
-        self.spatial_grid.clear();
+    // Start another simulation with the current parameters and a fresh flock,
+    // and switch to it
+    fn new_world(&mut self) {
+        let seed = self.sim.rng.gen();
+        let mut sim = self.sim.empty_copy(seed);
+        sim.respawn(self.boid_count, self.spawn_pattern);
+        let tab = Tab {
+            sim,
+            state: PlayState::Play,
+            boid_count: self.boid_count,
+            spawn_pattern: self.spawn_pattern,
+            stats: FlockStats::default(),
+            charts: Charts::new(),
+            time_scale_index: self.time_scale_index,
+            rewind: self.rewind.empty_copy(),
+            preset_index: self.preset_index,
+        };
         
-        for (i, boid) in self.boids.iter().enumerate() {
-            self.spatial_grid.insert(i, boid);
-        }
+        // Slot it in right after the current world and move to it
+        self.worlds.push_front(tab);
+        self.next_world();
     }
     
-    // Get neighbor lists for all boids using spatial partitioning
-    // Fixed to not use parallelism due to Sync trait issues
-    fn get_all_neighbor_lists(&self) -> Vec<Vec<usize>> {
-        self.boids.iter()
-            .map(|boid| self.spatial_grid.get_neighbors(boid, VISUAL_RANGE))
-            .collect()
+    // Timing comparison line for the HUD, e.g. "Grid: 120.0μs | QuadTree: 95.0μs"
+    fn index_timing_summary(&self) -> String {
+        IndexKind::ALL
+            .iter()
+            .map(|&kind| {
+                let marker = if kind == self.sim.index_kind { "*" } else { "" };
+                match self.index_timings[kind as usize] {
+                    Some(us) => format!("{}{}: {:.1}μs", marker, kind.name(), us),
+                    None => format!("{}{}: -", marker, kind.name()),
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(" | ")
     }
 }
 
 impl event::EventHandler for State {
     fn update(&mut self, ctx: &mut Context) -> GameResult {
-        let _update_start = Instant::now();
-        self.dt = timer::delta(ctx);
+        let update_start = Instant::now();
+        self.dt = ctx.time.delta();
+        if let Some(recorder) = self.input_recorder.as_mut() {
+            if let Err(err) = recorder.frame(self.dt) {
+                error!("Input recording stopped: {}", err);
+                self.input_recorder = None;
+            }
+        }
+        
+        // While the worker is still stepping, draw its last view again rather
+        // than wait. Replayed input waits, so every logged frame gets a step.
+        if self.playback.is_none() && self.sim_thread.as_mut().map_or(false, SimThread::busy) {
+            self.frames += 1;
+            self.frame_time += self.dt;
+            self.toast.update(self.dt.as_secs_f32());
+            self.update_camera(ctx);
+            self.profiler.add(Section::Update, update_start.elapsed());
+            return Ok(());
+        }
+        self.join_sim_thread();
+        for input in mem::take(&mut self.pending_input) {
+            self.handle_input(ctx, input);
+        }
+        self.replay_frame(ctx);
+        self.reload_config(ctx);
+        #[cfg(feature = "scripting")]
+        self.reload_script();
+        #[cfg(feature = "server")]
+        self.poll_server(ctx);
+        self.poll_osc(ctx);
+        #[cfg(feature = "midi")]
+        self.poll_midi(ctx);
+        self.toast.update(self.dt.as_secs_f32());
         let tick = (self.dt.subsec_millis() as f32) / 1000.0;
-        let pressed_keys = input::keyboard::pressed_keys(ctx);
         
         // Update frame counter for FPS calculation
         self.frames += 1;
@@ -138,199 +1919,650 @@ impl event::EventHandler for State {
         // Update FPS display every second
         if self.frame_time.as_secs_f32() >= 1.0 {
             let fps = self.frames as f32 / self.frame_time.as_secs_f32();
-            let update_time = self.last_update_time.elapsed().as_micros() as f32 / self.frames as f32;
-            let draw_time = self.last_draw_time.elapsed().as_micros() as f32 / self.frames as f32;
+            self.fps = fps;
+            let update_time = self.profiler.stats(Section::Update);
+            let draw_time = self.profiler.stats(Section::Draw);
+            
+            // Only record index timings while the simulation is actually running
+            if let PlayState::Play = self.state {
+                let index_time = self.index_time.as_micros() as f32 / self.frames as f32;
+                self.index_timings[self.sim.index_kind as usize] = Some(index_time);
+            }
             
             self.fps_display = graphics::Text::new(graphics::TextFragment {
                 text: format!(
-                    "FPS: {:.1} | Boids: {} | Update: {:.1}μs | Draw: {:.1}μs", 
-                    fps, self.boid_count, update_time, draw_time
+                    "FPS: {:.1} | Boids: {} | Update (mean/p95/p99): {} | Draw: {} | Speed: {}x | Rewind: {:.1}s\nIndex <n>: {} | Color <c>: {} | Preset: {} | World: {}/{}", 
+                    fps, self.boid_count, update_time, draw_time, TIME_SCALES[self.time_scale_index],
+                    self.rewind.seconds(), self.index_timing_summary(), self.color_mode.name(),
+                    PRESETS[self.preset_index].name, self.world_number + 1, self.worlds.len() + 1
                 ),
-                color: Some(graphics::Color::WHITE),
-                font: Some(graphics::Font::default()),
-                scale: Some(graphics::PxScale::from(20.0)),
+                color: Some(self.theme.hud_color()),
+                font: None,
+                scale: Some(graphics::PxScale::from(20.0 * self.ui_scale)),
             });
             
             self.frames = 0;
             self.frame_time = std::time::Duration::new(0, 0);
-            self.last_update_time = Instant::now();
-            self.last_draw_time = Instant::now();
+            self.index_time = std::time::Duration::new(0, 0);
+            
+            // Per-second performance summary
+            info!(
+                fps,
+                boids = self.boid_count,
+                update_us = update_time.mean,
+                update_p99_us = update_time.p99,
+                draw_us = draw_time.mean,
+                draw_p99_us = draw_time.p99,
+                "Frame summary"
+            );
             
-            // Print performance information
-            println!("Current FPS: {:.1} with {} boids | Update: {:.1}μs | Draw: {:.1}μs", 
-                     fps, self.boid_count, update_time, draw_time);
+            if let Some(endpoint) = self.metrics_endpoint.as_ref() {
+                self.sim.refresh_neighbor_lists();
+                let flock = FlockStats::compute(&self.sim.boids, &self.sim.neighbor_lists);
+                endpoint.publish(&prometheus::Sample {
+                    fps,
+                    boids: self.boid_count,
+                    update: update_time,
+                    draw: draw_time,
+                    flock: &flock,
+                });
+            }
         }
 
         match self.state {
             PlayState::Setup => {
-                self.boids.drain(..);
-                if pressed_keys.contains(&event::KeyCode::Space) {
-                    self.boids = get_boids(self.boid_count);
-                    self.state = PlayState::Play;
-                }
+                self.sim.boids.drain(..);
             }
 
             PlayState::Pause => {
-                if pressed_keys.contains(&event::KeyCode::Space) {
-                    self.state = PlayState::Play;
-                } else if pressed_keys.contains(&event::KeyCode::R) {
-                    self.state = PlayState::Setup;
-                } else if pressed_keys.contains(&event::KeyCode::Up) {
-                    self.adjust_boid_count(true, ctx);
-                } else if pressed_keys.contains(&event::KeyCode::Down) {
-                    self.adjust_boid_count(false, ctx);
+                self.update_camera(ctx);
+                
+                if self.is_held(ctx, Action::Rewind) {
+                    self.rewind_step();
                 }
             }
 
             PlayState::Play => {
-                if pressed_keys.contains(&event::KeyCode::P) {
-                    self.state = PlayState::Pause;
-                } else if pressed_keys.contains(&event::KeyCode::R) {
-                    self.state = PlayState::Setup;
-                } else if pressed_keys.contains(&event::KeyCode::Up) {
-                    self.adjust_boid_count(true, ctx);
-                } else if pressed_keys.contains(&event::KeyCode::Down) {
-                    self.adjust_boid_count(false, ctx);
+                self.update_camera(ctx);
+                if self.sim.shepherd.active {
+                    self.sim.shepherd.steer(self.pan_direction(ctx) + shepherd::gamepad_stick(ctx));
+                }
+                
+                if self.demo {
+                    self.update_demo();
+                }
+                
+                // Bass pushes the boids apart and loudness speeds them up
+                #[cfg(feature = "audio")]
+                if let Some(audio) = self.audio.as_mut() {
+                    let levels = audio.update(self.dt.as_secs_f32());
+                    self.sim.separation_gain = levels.separation_scale();
+                    self.sim.speed_gain = levels.speed_scale();
                 }
-
-                // Update spatial grid
-                self.update_spatial_grid();
                 
-                // Get neighbor lists for all boids
-                let neighbor_lists = self.get_all_neighbor_lists();
+                self.run_scenario(ctx, tick * TIME_SCALES[self.time_scale_index]);
+                
+                let (frame_time, count) = (self.dt.as_secs_f32(), self.sim.boids.len());
+                if let Some(count) = self.autoscaler.as_mut().and_then(|scaler| scaler.update(frame_time, count)) {
+                    self.resize_flock(count);
+                }
+                
+                // Holding the brush key paints boids along the mouse path
+                if self.is_held(ctx, Action::SpawnBrush) {
+                    let point = self.mouse_world_position(ctx);
+                    let before = self.sim.boids.len();
+                    self.spawn_brush.paint(&mut self.sim.boids, point, self.dt.as_secs_f32(), &self.sim.world, &mut self.sim.rng);
+                    self.sim.vary_traits_from(before);
+                    self.boid_count = self.sim.boids.len();
+                } else {
+                    self.spawn_brush.release();
+                }
+                
+                // Holding the rewind key scrubs backwards instead of simulating
+                if self.is_held(ctx, Action::Rewind) {
+                    self.rewind_step();
+                    self.profiler.add(Section::Update, update_start.elapsed());
+                    return Ok(());
+                }
+                
+                // Fast-forward runs several normal-sized steps instead of one big one
+                let time_scale = TIME_SCALES[self.time_scale_index];
+                let steps = time_scale.ceil().max(1.0) as usize;
+                let step_tick = tick * time_scale / steps as f32;
+                
+                let mouse_pos = self.mouse_world_position(ctx);
+                if self.sim_thread.is_some() {
+                    // Handed to the worker once the rest of the frame is done
+                    self.queued_steps = Some((mouse_pos, step_tick, steps));
+                } else {
+                    for _ in 0..steps {
+                        self.step(step_tick, mouse_pos);
+                        if let Some(trajectories) = self.trajectories.as_mut() {
+                            trajectories.record(&self.sim.boids, step_tick);
+                        }
+                    }
+                    self.rewind.record(&self.sim.boids, tick * time_scale);
+                }
+                
+                if self.sim.index_kind == IndexKind::Grid {
+                    self.tune_grid(self.dt.as_secs_f32());
+                }
+                
+                let sample_due = match self.telemetry.as_mut() {
+                    Some(telemetry) => telemetry.due(tick * time_scale),
+                    None => false,
+                };
+                let chart_due = self.charts.due(self.dt.as_secs_f32());
+                #[cfg(feature = "server")]
+                let serving = self.server.as_ref().map_or(false, Server::has_clients);
+                #[cfg(not(feature = "server"))]
+                let serving = false;
+                if self.show_stats || sample_due || chart_due || serving {
+                    self.sim.refresh_neighbor_lists();
+                    self.stats = FlockStats::compute(&self.sim.boids, &self.sim.neighbor_lists);
+                }
+                if sample_due {
+                    self.log_telemetry();
+                }
+                #[cfg(feature = "server")]
+                if let Some(server) = self.server.as_mut() {
+                    server.broadcast(&self.sim.boids, &self.stats, tick * time_scale);
+                }
+                #[cfg(feature = "audio")]
+                if let Some(ambient) = self.ambient.as_ref() {
+                    self.sim.refresh_neighbor_lists();
+                    ambient.update(&self.sim.boids, &self.sim.neighbor_lists);
+                }
+                if chart_due {
+                    self.charts.fps.push(ctx.time.fps() as f32);
+                    self.charts.boid_count.push(self.sim.boids.len() as f32);
+                    self.charts.polarization.push(self.stats.polarization);
+                }
                 
-                // Update boids movement - non-parallel version
-                for i in 0..self.boids.len() {
-                    // Make a copy of the boid to work with
-                    let mut boid = self.boids[i];
-                    boid.calculate_behaviors(&neighbor_lists[i], &self.boids);
-                    boid.limit_speed();
-                    boid.update_position(tick);
-                    // Store the modified boid back in the collection
-                    self.boids[i] = boid;
+                if self.show_trails {
+                    self.sim.trails.record(&self.sim.boids);
                 }
                 
-                // Handle boundary checks and mouse interactions
-                let mouse_pos = input::mouse::position(ctx);
-                for boid in &mut self.boids {
-                    boid.keep_within_bounds(mouse_pos, WIDTH, HEIGHT);
+                if let Some(stress_test) = self.stress_test.as_mut() {
+                    match stress_test.update(self.dt.as_secs_f32(), update_start.elapsed()) {
+                        StressAction::Continue => {}
+                        StressAction::Resize(count) => self.resize_flock(count),
+                        StressAction::Finished => {
+                            print!("{}", stress_test.report());
+                            match stress_test.write_report() {
+                                Ok(path) => info!("Stress report written to {}", path),
+                                Err(err) => error!("Failed to write stress report: {}", err),
+                            }
+                            ctx.request_quit();
+                        }
+                    }
                 }
             }
         };
         
+        // The worker steps while this frame is drawn
+        self.send_to_sim_thread();
+        self.profiler.add(Section::Update, update_start.elapsed());
         Ok(())
     }
 
     fn draw(&mut self, ctx: &mut Context) -> GameResult {
         let draw_start = Instant::now();
-        graphics::clear(ctx, [0.15, 0.2, 0.22, 1.0].into());
+        let _span = tracing::trace_span!("draw").entered();
+        let screen = self.camera.viewport();
+        let mut canvas = graphics::Canvas::from_frame(ctx, self.theme.clear_color());
+        self.theme.draw_background(ctx, &mut canvas, screen.x, screen.y)?;
 
         match self.state {
             PlayState::Setup => {
-                let menu_text = graphics::Text::new(graphics::TextFragment {
-                    text: "play : <space>\npause : <p>\nreset : <r>\nadd boids : <up>\nreduce boids : <down>".to_string(),
-                    color: Some(graphics::Color::WHITE),
-                    font: Some(graphics::Font::default()),
-                    scale: Some(graphics::PxScale::from(60.0)),
-                });
-
-                let text_pos = glam::vec2(
-                    (WIDTH - menu_text.width(ctx) as f32) / 2.0,
-                    (HEIGHT - menu_text.height(ctx) as f32) / 2.0,
-                );
-
-                graphics::draw(ctx, &menu_text,
-                               graphics::DrawParam::default().dest(text_pos))?;
+                self.draw_menu(ctx, &mut canvas)?;
             }
 
             _ => {
                 let mb = &mut graphics::MeshBuilder::new();
+                let line_width = 1.0 / self.camera.zoom;
                 
-                // Draw boids using instanced rendering if possible, otherwise fallback to individual draws
-                if self.boids.len() > 0 {
-                    // For each boid, compute its transform matrix and add it to the mesh
-                    for boid in &self.boids {
-                        let rot = glam::Mat2::from_angle(boid.dx.atan2(-boid.dy));
-                        let pos = glam::vec2(boid.x, boid.y);
-                        mb.polygon(
-                            graphics::DrawMode::fill(),
-                            &[
-                                (rot * self.points[0]) + pos,
-                                (rot * self.points[1]) + pos,
-                                (rot * self.points[2]) + pos,
-                                (rot * self.points[3]) + pos,
-                            ],
-                            boid.color.into(),
-                        )?;
-                    }
+                // Colors depend on the current mode, so they are computed per frame
+                let colors: Vec<[f32; 4]> = self.sim.boids
+                    .iter()
+                    .map(|boid| self.theme.boid_color(self.color_mode.boid_color(boid)))
+                    .collect();
+                
+                // Draw the pheromones, flow field and trails underneath the boids
+                if self.sim.pheromones.mode != PheromoneMode::Off {
+                    self.sim.pheromones.draw(mb)?;
+                }
+                self.sim.flow_field.draw(mb, line_width)?;
+                self.sim.waypoints.draw(mb, line_width)?;
+                self.sim.zones.draw(mb, line_width)?;
+                self.sim.scoring.draw(mb, line_width)?;
+                self.sim.food.draw(mb)?;
+                self.sim.obstacles.draw(mb, line_width)?;
+                if self.obstacle_editor.active {
+                    let cursor = self.camera.screen_to_world(self.mouse_screen_position(ctx));
+                    self.obstacle_editor.draw(mb, cursor, line_width)?;
+                }
+                self.sim.predator_prey.draw(mb)?;
+                if self.sim.shepherd.active {
+                    self.sim.shepherd.draw(mb, line_width)?;
+                }
+                self.sim.shockwaves.draw(mb, line_width)?;
+                
+                if self.show_trails {
+                    self.sim.trails.draw(mb, &colors, line_width)?;
                 }
                 
+                // Outline the arena so the edges are visible when zoomed out
+                mb.polygon(
+                    graphics::DrawMode::stroke(2.0 * line_width),
+                    &self.sim.boundary.outline(),
+                    self.theme.overlay_color(0.3),
+                )?;
+                
+                let visible = self.visible_boids();
+                if self.motion_blur {
+                    self.draw_motion_blur(ctx, &mut canvas, &visible, &colors)?;
+                }
+                
+                canvas.draw(&graphics::Mesh::from_data(ctx, mb.build()), self.camera.draw_param());
+                
+                // Draw boids as instances of one cached mesh, tinted per boid
+                if !self.motion_blur {
+                    self.draw_boids(ctx, &mut canvas, &visible, &colors)?;
+                }
+                
+                // Everything else goes on top of the boids, unless the HUD is cut down
+                let mb = &mut graphics::MeshBuilder::new();
+                let overlays = self.hud == HudMode::Full;
+                
                 // Draw spatial grid for debugging if enabled
-                if DRAW_SPATIAL_GRID {
-                    for x in 0..=(WIDTH / CELL_SIZE) as usize {
-                        let x_pos = x as f32 * CELL_SIZE;
+                let draw_grid = overlays && self.debug_flags.contains(DebugFlags::SPATIAL_GRID);
+                if draw_grid {
+                    let cell_size = self.sim.spatial_grid.cell_size();
+                    for x in 0..=(self.sim.world.width / cell_size) as usize {
+                        let x_pos = x as f32 * cell_size;
                         mb.line(
                             &[
                                 glam::vec2(x_pos, 0.0),
-                                glam::vec2(x_pos, HEIGHT),
+                                glam::vec2(x_pos, self.sim.world.height),
                             ],
-                            1.0,
+                            line_width,
                             [0.5, 0.5, 0.5, 0.3].into(),
                         )?;
                     }
                     
-                    for y in 0..=(HEIGHT / CELL_SIZE) as usize {
-                        let y_pos = y as f32 * CELL_SIZE;
+                    for y in 0..=(self.sim.world.height / cell_size) as usize {
+                        let y_pos = y as f32 * cell_size;
                         mb.line(
                             &[
                                 glam::vec2(0.0, y_pos),
-                                glam::vec2(WIDTH, y_pos),
+                                glam::vec2(self.sim.world.width, y_pos),
                             ],
-                            1.0,
+                            line_width,
                             [0.5, 0.5, 0.5, 0.3].into(),
                         )?;
                     }
                 }
                 
+                // Highlight the selected boid and its neighborhood
+                if overlays {
+                    self.sim.inspector.draw(mb, &self.sim.boids, self.sim.active_index(), line_width)?;
+                    self.sim.selection.draw(mb, &self.sim.boids, line_width)?;
+                }
+                
+                // Steering force debug arrows
+                let force_display = if overlays { self.force_display } else { ForceDisplay::Off };
+                match force_display {
+                    ForceDisplay::Off => {}
+                    ForceDisplay::Selected => {
+                        if let Some(i) = self.sim.inspector.selected {
+                            if let (Some(boid), Some(forces)) = (self.sim.boids.get(i), self.sim.forces.get(i)) {
+                                inspect::draw_force_arrows(mb, boid, forces, line_width)?;
+                            }
+                        }
+                    }
+                    ForceDisplay::All => {
+                        for (boid, forces) in self.sim.boids.iter().zip(&self.sim.forces) {
+                            inspect::draw_force_arrows(mb, boid, forces, line_width)?;
+                        }
+                    }
+                }
+                
+                // Show the brush outline while painting boids
+                if overlays && self.is_held(ctx, Action::SpawnBrush) {
+                    self.spawn_brush.draw(mb, self.mouse_world_position(ctx), line_width)?;
+                }
+                
                 // Draw cursor highlight
-                mb.circle(
-                    graphics::DrawMode::fill(),
-                    input::mouse::position(ctx),
-                    10.0,
-                    0.1,
-                    [1.0, 1.0, 1.0, 0.5].into(),
-                )?;
+                if overlays {
+                    mb.circle(
+                        graphics::DrawMode::fill(),
+                        self.mouse_world_position(ctx),
+                        10.0 * self.ui_scale,
+                        0.1,
+                        self.theme.overlay_color(0.5),
+                    )?;
+                }
                 
-                // Build and draw the mesh
-                let m = mb.build(ctx)?;
-                graphics::draw(ctx, &m, graphics::DrawParam::new())?;
+                // Build and draw the mesh through the camera, unless nothing was added
+                let overlay = mb.build();
+                if !overlay.indices.is_empty() {
+                    canvas.draw(&graphics::Mesh::from_data(ctx, overlay), self.camera.draw_param());
+                }
                 
-                // Draw the FPS display in the top-left corner
-                graphics::draw(
-                    ctx,
-                    &self.fps_display,
-                    graphics::DrawParam::default().dest(glam::vec2(10.0, 10.0)),
-                )?;
+                match self.hud {
+                    HudMode::Full => self.draw_hud(ctx, &mut canvas, draw_grid)?,
+                    HudMode::Minimal => {
+                        let margin = 10.0 * self.ui_scale;
+                        let fps = graphics::Text::new(graphics::TextFragment {
+                            text: format!("FPS: {:.1}", self.fps),
+                            color: Some(self.theme.hud_color()),
+                            font: None,
+                            scale: Some(graphics::PxScale::from(20.0 * self.ui_scale)),
+                        });
+                        canvas.draw(&fps, graphics::DrawParam::default().dest(glam::vec2(margin, margin)));
+                    }
+                    HudMode::Hidden => {}
+                }
             }
         };
 
+        if self.hud == HudMode::Full {
+            self.toast.draw(ctx, &mut canvas, self.camera.viewport().x, 60.0 * self.ui_scale, self.ui_scale)?;
+        }
+        canvas.finish(ctx)?;
+        
+        // Capture the finished frame before the recording indicator goes on top.
+        // The GIF buffer only needs a frame every so often.
+        let gif_wants_frame = self.gif_buffer.wants_frame(self.dt.as_secs_f32());
+        if self.recorder.is_some() || gif_wants_frame {
+            let image = ctx.gfx.frame().clone();
+            let mut rgba = image.to_pixels(ctx)?;
+            // The frame is in the surface format, which is BGRA on most platforms
+            if let graphics::ImageFormat::Bgra8Unorm | graphics::ImageFormat::Bgra8UnormSrgb = image.format() {
+                for pixel in rgba.chunks_exact_mut(4) {
+                    pixel.swap(0, 2);
+                }
+            }
+            let frame = recorder::Frame {
+                width: image.width(),
+                height: image.height(),
+                rgba,
+            };
+            
+            if gif_wants_frame {
+                self.gif_buffer.push(&frame);
+            }
+            if let Some(recorder) = self.recorder.as_mut() {
+                recorder.push(frame);
+            }
+        }
+        
+        if self.recorder.is_some() {
+            let indicator = graphics::Mesh::new_circle(
+                ctx,
+                graphics::DrawMode::fill(),
+                glam::vec2(self.camera.viewport().x - 20.0 * self.ui_scale, 20.0 * self.ui_scale),
+                8.0 * self.ui_scale,
+                0.1,
+                [1.0, 0.2, 0.2, 0.9].into(),
+            )?;
+            let mut canvas = graphics::Canvas::from_frame(ctx, None);
+            canvas.draw(&indicator, graphics::DrawParam::new());
+            canvas.finish(ctx)?;
+        }
+        
         // Track time spent in draw
-        self.last_draw_time = draw_start;
+        self.profiler.add(Section::Draw, draw_start.elapsed());
+        self.profiler.end_frame();
+        if let Some(stress_test) = self.stress_test.as_mut() {
+            stress_test.record_draw(draw_start.elapsed());
+        }
+        
+        self.limit_frame_rate();
+        Ok(())
+    }
+
+    fn key_down_event(&mut self, ctx: &mut Context, input: KeyInput, repeated: bool) -> GameResult {
+        // Any key dismisses the screensaver
+        if self.screensaver {
+            ctx.request_quit();
+            return Ok(());
+        }
+        
+        // Toggles fire once per press rather than every frame the key is held
+        if repeated {
+            return Ok(());
+        }
+        
+        if let Some(keycode) = input.keycode {
+            self.live_input(ctx, InputEvent::KeyDown(keycode, input.mods));
+        }
+        Ok(())
+    }
+    
+    fn key_up_event(&mut self, ctx: &mut Context, input: KeyInput) -> GameResult {
+        if let Some(keycode) = input.keycode {
+            self.live_input(ctx, InputEvent::KeyUp(keycode));
+        }
+        Ok(())
+    }
+
+    fn mouse_button_down_event(
+        &mut self,
+        ctx: &mut Context,
+        button: MouseButton,
+        x: f32,
+        y: f32,
+    ) -> GameResult {
+        if self.screensaver {
+            ctx.request_quit();
+            return Ok(());
+        }
+        
+        let mods = ctx.keyboard.active_mods();
+        self.live_input(ctx, InputEvent::MouseDown(button, glam::vec2(x, y), mods));
+        Ok(())
+    }
+
+    fn mouse_button_up_event(
+        &mut self,
+        ctx: &mut Context,
+        button: MouseButton,
+        x: f32,
+        y: f32,
+    ) -> GameResult {
+        self.live_input(ctx, InputEvent::MouseUp(button, glam::vec2(x, y)));
+        Ok(())
+    }
+
+    fn mouse_motion_event(&mut self, ctx: &mut Context, x: f32, y: f32, dx: f32, dy: f32) -> GameResult {
+        // Small jitters (and the event fired when the window opens) don't count
+        if self.screensaver {
+            self.screensaver_motion += dx.abs() + dy.abs();
+            if self.screensaver_motion > SCREENSAVER_MOTION_THRESHOLD {
+                ctx.request_quit();
+            }
+            return Ok(());
+        }
+        
+        self.live_input(ctx, InputEvent::MouseMove(glam::vec2(x, y), glam::vec2(dx, dy)));
+        Ok(())
+    }
+    
+    fn mouse_wheel_event(&mut self, ctx: &mut Context, _x: f32, y: f32) -> GameResult {
+        if self.screensaver {
+            ctx.request_quit();
+            return Ok(());
+        }
+        
+        self.live_input(ctx, InputEvent::Wheel(y));
+        Ok(())
+    }
+
+
+    fn resize_event(&mut self, ctx: &mut Context, width: f32, height: f32) -> GameResult {
+        self.join_sim_thread();
+        // Canvases take their screen coordinates from the frame, so one unit
+        // stays one screen pixel without setting anything here
+        self.camera.set_viewport(width, height);
+        self.blur_image = None;
+        
+        // Moving the window to another monitor can change the scale factor
+        let scale_factor = ctx.gfx.window().scale_factor() as f32;
+        if scale_factor != self.scale_factor {
+            self.set_ui_scale(scale_factor, self.config.display.ui_scale.unwrap_or(1.0));
+        }
         
-        graphics::present(ctx)
+        self.follow_window(glam::vec2(width, height));
+        Ok(())
+    }
+
+    // The event loop exits the process without dropping the state, so
+    // anything still buffered has to be written out here
+    fn quit_event(&mut self, _ctx: &mut Context) -> GameResult<bool> {
+        if self.recorder.is_some() {
+            self.toggle_recording();
+        }
+        if let Some(trajectories) = self.trajectories.as_mut() {
+            trajectories.finish();
+        }
+        if let Some(telemetry) = self.telemetry.as_mut() {
+            if let Err(err) = telemetry.flush() {
+                error!("Failed to flush telemetry: {}", err);
+            }
+        }
+        if let Some(recorder) = self.input_recorder.as_mut() {
+            if let Err(err) = recorder.flush() {
+                error!("Failed to flush the input log: {}", err);
+            }
+        }
+        Ok(false)
+    }
+}
+
+// Set up the GPU backend, staying on the CPU if there is no usable GPU
+#[cfg(feature = "gpu")]
+fn start_gpu() -> Option<GpuFlock> {
+    match GpuFlock::new() {
+        Ok(gpu) => {
+            info!("Simulating on {}", gpu.adapter_name);
+            Some(gpu)
+        }
+        Err(err) => {
+            warn!("GPU backend unavailable, using the CPU: {}", err);
+            None
+        }
     }
 }
 
 fn main() {
+    let mut options = match cli::Options::from_args(WORLD_WIDTH, WORLD_HEIGHT) {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("{}", message);
+            std::process::exit(2);
+        }
+    };
+    
+    // The terminal front end needs no window, key bindings or logging, which
+    // would print over it
+    if options.tui {
+        if let Err(err) = tui::run(&options, NUM_BOIDS) {
+            eprintln!("Terminal error: {}", err);
+            std::process::exit(1);
+        }
+        return;
+    }
+    
+    // Log verbosity comes from RUST_LOG, e.g. RUST_LOG=debug to see boids
+    // spawning and despawning
+    if let Err(err) = logging::init(options.log_file.as_deref()) {
+        eprintln!("Failed to open log file: {}", err);
+        std::process::exit(2);
+    }
+    
+    // A replay has to start from the seed it was recorded with
+    let playback = match options.replay_input.as_ref().map(|path| InputPlayback::load(path)).transpose() {
+        Ok(playback) => playback,
+        Err(message) => {
+            eprintln!("Invalid input log: {}", message);
+            std::process::exit(2);
+        }
+    };
+    if let Some(playback) = &playback {
+        options.seed = Some(playback.seed);
+    }
+    let scenario = match options.scenario.as_ref().map(|path| Scenario::load(path)).transpose() {
+        Ok(scenario) => scenario,
+        Err(message) => {
+            eprintln!("Invalid scenario: {}", message);
+            std::process::exit(2);
+        }
+    };
+    if let Some(seed) = scenario.as_ref().and_then(|scenario| scenario.seed) {
+        options.seed = options.seed.or(Some(seed));
+    }
+    if options.gpu && !cfg!(feature = "gpu") {
+        eprintln!("This build has no GPU backend, rebuild with --features gpu");
+        std::process::exit(2);
+    }
+    if options.serve.is_some() && !cfg!(feature = "server") {
+        eprintln!("This build has no live state server, rebuild with --features server");
+        std::process::exit(2);
+    }
+
+    // Key bindings and parameters come from the config file, if there is one.
+    // It is watched while running, so edits apply without a restart.
+    let config = match Config::load(&options.config) {
+        Ok(config) => config,
+        Err(message) => {
+            eprintln!("Invalid config: {}", message);
+            std::process::exit(2);
+        }
+    };
+    let input_map = match InputMap::new(&config.keys) {
+        Ok(input_map) => input_map,
+        Err(message) => {
+            eprintln!("Invalid config: {}", message);
+            std::process::exit(2);
+        }
+    };
+
+    // The screensaver covers the whole screen without decorations
+    let mut window_mode = conf::WindowMode::default()
+        .dimensions(WIDTH, HEIGHT)
+        .resizable(true);
+    if options.screensaver {
+        window_mode = window_mode
+            .fullscreen_type(conf::FullscreenType::Desktop)
+            .borderless(true);
+    }
+    
     // Create a context with MSAA anti-aliasing
     let (mut ctx, events_loop) = ContextBuilder::new("Boids", "Daniel Eisen")
-        .window_mode(conf::WindowMode::default().dimensions(WIDTH, HEIGHT))
+        .window_mode(window_mode)
         .window_setup(conf::WindowSetup::default()
             .title("Optimized Boids")
+            .vsync(config.display.vsync.unwrap_or(true))
             .samples(conf::NumSamples::Four)) // Reduced from Eight to Four for performance
         .build()
         .expect("Failed to create context");
 
-    let state = State::new(&mut ctx);
+    // Nobody is driving the mouse in demo mode
+    if options.demo {
+        ggez::input::mouse::set_cursor_hidden(&mut ctx, true);
+    }
+    
+    let mut state = State::new(&mut ctx, &options, input_map);
+    if let Some(path) = &options.obstacles {
+        if let Err(message) = state.sim.obstacles.load(path) {
+            eprintln!("Invalid obstacles: {}", message);
+            std::process::exit(2);
+        }
+    }
+    state.apply_config(&mut ctx, config);
+    state.playback = playback;
+    state.scenario = scenario;
     event::run(ctx, events_loop, state);
 }
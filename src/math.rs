@@ -0,0 +1,117 @@
+// math.rs
+// The transcendental functions the simulation core uses. std defers to the
+// platform's C math library, whose last bits differ between systems; with
+// the deterministic feature they come from the pure Rust libm crate instead,
+// so a seed plays out the same everywhere. The feature also switches the
+// motion math to f64, below.
+
+#[cfg(feature = "deterministic")]
+pub fn atan2(y: f32, x: f32) -> f32 {
+    libm::atan2f(y, x)
+}
+
+#[cfg(not(feature = "deterministic"))]
+pub fn atan2(y: f32, x: f32) -> f32 {
+    y.atan2(x)
+}
+
+// (sin, cos) of `angle`
+#[cfg(feature = "deterministic")]
+pub fn sin_cos(angle: f32) -> (f32, f32) {
+    (libm::sinf(angle), libm::cosf(angle))
+}
+
+#[cfg(not(feature = "deterministic"))]
+pub fn sin_cos(angle: f32) -> (f32, f32) {
+    angle.sin_cos()
+}
+
+#[cfg(feature = "deterministic")]
+pub fn powf(base: f32, exponent: f32) -> f32 {
+    libm::powf(base, exponent)
+}
+
+#[cfg(not(feature = "deterministic"))]
+pub fn powf(base: f32, exponent: f32) -> f32 {
+    base.powf(exponent)
+}
+
+// What positions and velocities are integrated in: f64 with the
+// deterministic feature, so long runs don't drift, f32 otherwise for speed.
+// Boids still store and show f32, see Boid::position_real.
+#[cfg(feature = "deterministic")]
+pub type Real = f64;
+#[cfg(feature = "deterministic")]
+pub type Vector = glam::DVec2;
+
+#[cfg(not(feature = "deterministic"))]
+pub type Real = f32;
+#[cfg(not(feature = "deterministic"))]
+pub type Vector = glam::Vec2;
+
+#[cfg(feature = "deterministic")]
+pub const PI: Real = std::f64::consts::PI;
+#[cfg(not(feature = "deterministic"))]
+pub const PI: Real = std::f32::consts::PI;
+
+#[cfg(feature = "deterministic")]
+pub fn real(value: f32) -> Real {
+    value as f64
+}
+
+#[cfg(not(feature = "deterministic"))]
+pub fn real(value: f32) -> Real {
+    value
+}
+
+// Round back to f32 for storing and drawing
+#[cfg(feature = "deterministic")]
+pub fn single(value: Real) -> f32 {
+    value as f32
+}
+
+#[cfg(not(feature = "deterministic"))]
+pub fn single(value: Real) -> f32 {
+    value
+}
+
+#[cfg(feature = "deterministic")]
+pub fn widen(v: glam::Vec2) -> Vector {
+    v.as_dvec2()
+}
+
+#[cfg(not(feature = "deterministic"))]
+pub fn widen(v: glam::Vec2) -> Vector {
+    v
+}
+
+#[cfg(feature = "deterministic")]
+pub fn narrow(v: Vector) -> glam::Vec2 {
+    v.as_vec2()
+}
+
+#[cfg(not(feature = "deterministic"))]
+pub fn narrow(v: Vector) -> glam::Vec2 {
+    v
+}
+
+// atan2 and sin_cos in the integration precision
+#[cfg(feature = "deterministic")]
+pub fn atan2_real(y: Real, x: Real) -> Real {
+    libm::atan2(y, x)
+}
+
+#[cfg(not(feature = "deterministic"))]
+pub fn atan2_real(y: Real, x: Real) -> Real {
+    atan2(y, x)
+}
+
+#[cfg(feature = "deterministic")]
+pub fn sin_cos_real(angle: Real) -> (Real, Real) {
+    (libm::sin(angle), libm::cos(angle))
+}
+
+#[cfg(not(feature = "deterministic"))]
+pub fn sin_cos_real(angle: Real) -> (Real, Real) {
+    sin_cos(angle)
+}
@@ -0,0 +1,160 @@
+// metrics.rs
+use crate::boid::{Boid, VISUAL_RANGE};
+use crate::neighbor_index::NeighborIndex;
+use crate::spatial_grid::SpatialGrid;
+
+// Aggregate statistics describing the whole flock at one moment
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FlockStats {
+    pub boid_count: usize,
+    pub average_speed: f32,
+    pub polarization: f32,              // |mean heading vector|, 0 = disordered, 1 = aligned
+    pub angular_momentum: f32,          // Rotation about the centroid, 0 = none, 1 = perfect mill
+    pub nearest_neighbor_distance: f32, // Mean over boids with a neighbor in visual range
+    pub cluster_count: usize,           // Groups of boids linked by visual range
+}
+
+impl FlockStats {
+    // `neighbor_lists` are the candidate lists from the neighbor index, one per boid
+    pub fn compute(boids: &[Boid], neighbor_lists: &[Vec<usize>]) -> FlockStats {
+        if boids.is_empty() {
+            return FlockStats::default();
+        }
+
+        let count = boids.len() as f32;
+        let centroid = boids
+            .iter()
+            .fold(glam::Vec2::ZERO, |sum, boid| sum + glam::vec2(boid.x, boid.y))
+            / count;
+
+        let mut speed_sum = 0.0;
+        let mut heading_sum = glam::Vec2::ZERO;
+        let mut moment_sum = 0.0;
+        let mut radius_sum = 0.0;
+        for boid in boids {
+            let velocity = glam::vec2(boid.dx, boid.dy);
+            let speed = velocity.length();
+            speed_sum += speed;
+            if speed > 0.0 {
+                let heading = velocity / speed;
+                heading_sum += heading;
+
+                // 2D cross product of the offset from the centroid and the heading
+                let offset = glam::vec2(boid.x, boid.y) - centroid;
+                moment_sum += offset.x * heading.y - offset.y * heading.x;
+                radius_sum += offset.length();
+            }
+        }
+
+        let mut nearest_sum = 0.0;
+        let mut nearest_count = 0;
+        let mut clusters = DisjointSet::new(boids.len());
+        for (i, (boid, neighbors)) in boids.iter().zip(neighbor_lists).enumerate() {
+            let mut nearest = VISUAL_RANGE * VISUAL_RANGE;
+            let mut found = false;
+            for &j in neighbors {
+                // Lists built before boids were removed can point past the end
+                if j == i || j >= boids.len() {
+                    continue;
+                }
+                let dist = boid.squared_distance(&boids[j]);
+                if dist < VISUAL_RANGE * VISUAL_RANGE {
+                    clusters.union(i, j);
+                    if dist < nearest {
+                        nearest = dist;
+                        found = true;
+                    }
+                }
+            }
+            if found {
+                nearest_sum += nearest.sqrt();
+                nearest_count += 1;
+            }
+        }
+
+        FlockStats {
+            boid_count: boids.len(),
+            average_speed: speed_sum / count,
+            polarization: heading_sum.length() / count,
+            angular_momentum: if radius_sum > 0.0 { moment_sum.abs() / radius_sum } else { 0.0 },
+            nearest_neighbor_distance: if nearest_count > 0 {
+                nearest_sum / nearest_count as f32
+            } else {
+                0.0
+            },
+            cluster_count: clusters.count(),
+        }
+    }
+
+    // Multi-line summary for the stats panel
+    pub fn panel_text(&self) -> String {
+        format!(
+            "Polarization: {:.2}\nAngular momentum: {:.2}\nNearest neighbor: {:.1}px\nFlocks: {}\nAverage speed: {:.0}px/s",
+            self.polarization,
+            self.angular_momentum,
+            self.nearest_neighbor_distance,
+            self.cluster_count,
+            self.average_speed,
+        )
+    }
+}
+
+// Connected components of the "within visual range" graph. Each boid gets the
+// ID of its flock in `Boid::flock`, numbered from 0 in boid order; returns the
+// number of flocks. `grid` must hold the current positions.
+pub fn label_flocks(boids: &mut [Boid], grid: &SpatialGrid) -> usize {
+    let mut components = DisjointSet::new(boids.len());
+    for (i, boid) in boids.iter().enumerate() {
+        for j in grid.get_neighbors(boid, VISUAL_RANGE) {
+            // Each pair only needs to be joined once
+            if j > i && boid.squared_distance(&boids[j]) < VISUAL_RANGE * VISUAL_RANGE {
+                components.union(i, j);
+            }
+        }
+    }
+
+    // Map the union-find roots to compact IDs
+    let mut ids = vec![u32::MAX; boids.len()];
+    let mut flock_count = 0;
+    for (i, boid) in boids.iter_mut().enumerate() {
+        let root = components.find(i);
+        if ids[root] == u32::MAX {
+            ids[root] = flock_count;
+            flock_count += 1;
+        }
+        boid.flock = ids[root];
+    }
+
+    flock_count as usize
+}
+
+// Union-find over boid indices for counting connected groups
+struct DisjointSet {
+    parent: Vec<usize>,
+}
+
+impl DisjointSet {
+    fn new(size: usize) -> Self {
+        DisjointSet { parent: (0..size).collect() }
+    }
+
+    fn find(&mut self, mut i: usize) -> usize {
+        while self.parent[i] != i {
+            // Path halving keeps the trees shallow
+            self.parent[i] = self.parent[self.parent[i]];
+            i = self.parent[i];
+        }
+        i
+    }
+
+    fn union(&mut self, a: usize, b: usize) {
+        let (root_a, root_b) = (self.find(a), self.find(b));
+        if root_a != root_b {
+            self.parent[root_a] = root_b;
+        }
+    }
+
+    fn count(&mut self) -> usize {
+        (0..self.parent.len()).filter(|&i| self.find(i) == i).count()
+    }
+}
@@ -0,0 +1,124 @@
+// midi.rs
+use crate::config::{self, MidiControl, Parameters};
+use midir::{MidiInput, MidiInputConnection};
+use std::collections::HashMap;
+use std::sync::mpsc::{self, Receiver};
+use tracing::warn;
+
+// Status byte of a control change, the low nibble is the channel
+const CONTROL_CHANGE: u8 = 0xB0;
+
+// A control change as received: channel (0-15), controller number and value (0-127)
+type ControlChange = (u8, u8, u8);
+
+// Knobs and faders on a MIDI controller mapped onto parameters by the
+// [midi] section of boids.toml. midir calls back on its own thread, so the
+// messages are passed over a channel and applied when polled.
+pub struct MidiKnobs {
+    _connection: MidiInputConnection<()>, // Closes the port when dropped
+    messages: Receiver<ControlChange>,
+    channel: Option<u8>,
+    controls: HashMap<String, MidiControl>,
+    levels: Vec<(String, f32)>, // Last value set by each control, for the HUD
+}
+
+impl MidiKnobs {
+    // Connect to the first input port whose name contains `settings.port`
+    pub fn connect(settings: &config::Midi) -> Result<MidiKnobs, String> {
+        let input = MidiInput::new("boids").map_err(|err| err.to_string())?;
+        let ports = input.ports();
+        let port = ports
+            .iter()
+            .find(|port| match (&settings.port, input.port_name(port)) {
+                (Some(wanted), Ok(name)) => name.contains(wanted.as_str()),
+                (None, _) => true,
+                (Some(_), Err(_)) => false,
+            })
+            .ok_or_else(|| match &settings.port {
+                Some(wanted) => format!("no MIDI input port matching '{}'", wanted),
+                None => "no MIDI input ports".to_string(),
+            })?;
+        let name = input.port_name(port).unwrap_or_default();
+
+        let (sender, messages) = mpsc::channel();
+        let connection = input
+            .connect(
+                port,
+                "boids-controls",
+                move |_, message, _| {
+                    if let &[status, controller, value] = message {
+                        if status & 0xF0 == CONTROL_CHANGE {
+                            let _ = sender.send((status & 0x0F, controller, value));
+                        }
+                    }
+                },
+                (),
+            )
+            .map_err(|err| format!("{}: {}", name, err))?;
+
+        let mut knobs = MidiKnobs {
+            _connection: connection,
+            messages,
+            channel: None,
+            controls: HashMap::new(),
+            levels: Vec::new(),
+        };
+        knobs.set_mapping(settings);
+        Ok(knobs)
+    }
+
+    // Take a new mapping, from a reloaded config. Unknown parameter names are skipped.
+    pub fn set_mapping(&mut self, settings: &config::Midi) {
+        self.channel = settings.channel.map(|channel| channel.saturating_sub(1));
+        self.controls = settings
+            .controls
+            .iter()
+            .filter(|(name, _)| {
+                let known = Parameters::default().set(name, 0.0);
+                if !known {
+                    warn!("Ignoring MIDI control for unknown parameter '{}'", name);
+                }
+                known
+            })
+            .map(|(name, control)| (name.clone(), *control))
+            .collect();
+        self.levels.retain(|(name, _)| settings.controls.contains_key(name));
+    }
+
+    // The parameters moved since the last poll, at their latest values
+    pub fn poll(&mut self) -> Option<Parameters> {
+        let mut parameters = Parameters::default();
+        let mut moved = false;
+        for (channel, controller, value) in self.messages.try_iter() {
+            if self.channel.map_or(false, |wanted| wanted != channel) {
+                continue;
+            }
+            for (name, control) in &self.controls {
+                if control.cc != controller {
+                    continue;
+                }
+                let level = control.min + (control.max - control.min) * value as f32 / 127.0;
+                parameters.set(name, level);
+                moved = true;
+                match self.levels.iter_mut().find(|(shown, _)| shown == name) {
+                    Some(entry) => entry.1 = level,
+                    None => self.levels.push((name.clone(), level)),
+                }
+            }
+        }
+        if moved {
+            Some(parameters)
+        } else {
+            None
+        }
+    }
+
+    // "name value" for every control that has been moved, in the order they were first touched
+    pub fn hud_text(&self) -> Option<String> {
+        if self.levels.is_empty() {
+            return None;
+        }
+        let levels: Vec<String> = self.levels.iter().map(|(name, level)| format!("{} {:.2}", name, level)).collect();
+        Some(format!("MIDI: {}", levels.join(" | ")))
+    }
+}
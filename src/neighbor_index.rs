@@ -0,0 +1,58 @@
+// neighbor_index.rs
+use crate::boid::Boid;
+
+// Common interface for the acceleration structures used to find nearby boids.
+// Implementations may return a superset of the true neighbors; callers are
+// expected to do their own distance checks.
+pub trait NeighborIndex {
+    fn clear(&mut self);
+
+    fn insert(&mut self, boid_index: usize, boid: &Boid);
+
+    // Make the inserts since the last call visible to queries. Indexes that
+    // place each boid as it is inserted have nothing to do.
+    fn finish(&mut self) {}
+
+    fn get_neighbors(&self, boid: &Boid, range: f32) -> Vec<usize>;
+
+    // Boids inside the rectangle from `min` to `max`, also a superset
+    fn query_rect(&self, min: glam::Vec2, max: glam::Vec2) -> Vec<usize>;
+
+    // Index and squared distance of every boid strictly within `radius` of
+    // `pos`. Unlike the other queries this is exact.
+    fn query_radius(&self, pos: glam::Vec2, radius: f32) -> Vec<(usize, f32)>;
+
+    // Rebuild the index from scratch for the given boids
+    fn rebuild(&mut self, boids: &[Boid]) {
+        self.clear();
+        for (i, boid) in boids.iter().enumerate() {
+            self.insert(i, boid);
+        }
+        self.finish();
+    }
+}
+
+// Which neighbor index is currently driving the simulation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IndexKind {
+    Grid,
+    QuadTree,
+}
+
+impl IndexKind {
+    pub const ALL: [IndexKind; 2] = [IndexKind::Grid, IndexKind::QuadTree];
+
+    pub fn next(self) -> IndexKind {
+        match self {
+            IndexKind::Grid => IndexKind::QuadTree,
+            IndexKind::QuadTree => IndexKind::Grid,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            IndexKind::Grid => "Grid",
+            IndexKind::QuadTree => "QuadTree",
+        }
+    }
+}
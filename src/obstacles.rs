@@ -0,0 +1,365 @@
+// obstacles.rs
+use crate::boid::{Boid, VISUAL_RANGE};
+use crate::spatial_grid::SpatialGrid;
+use crate::world::World;
+#[cfg(feature = "render-ggez")]
+use ggez::{graphics, GameResult};
+use std::fs;
+use std::path::Path;
+
+// Obstacle settings
+pub const OBSTACLES_PATH: &str = "obstacles.txt"; // Where the editor saves without --obstacles
+const AVOID_RANGE: f32 = 40.0;    // Pixels, boids start turning away this close
+const AVOID_WEIGHT: f32 = 40.0;   // Velocity change per step right at the surface
+const MIN_RADIUS: f32 = 4.0;      // Smaller circles are taken as a stray click
+const WALL_PIECE: f32 = 16.0;     // Walls are indexed in pieces at most this long
+#[cfg(feature = "render-ggez")]
+const WALL_COLOR: [f32; 4] = [0.9, 0.85, 0.7, 0.9];
+#[cfg(feature = "render-ggez")]
+const PREVIEW_COLOR: [f32; 4] = [0.9, 0.85, 0.7, 0.4];
+
+// Point on the segment from `a` to `b` closest to `p`
+pub fn closest_point_on_segment(p: glam::Vec2, a: glam::Vec2, b: glam::Vec2) -> glam::Vec2 {
+    let ab = b - a;
+    let length_squared = ab.length_squared();
+    if length_squared == 0.0 {
+        return a;
+    }
+    let t = ((p - a).dot(ab) / length_squared).max(0.0).min(1.0);
+    a + ab * t
+}
+
+// Whether segment p1-p2 crosses segment q1-q2. Touching counts, so a boid
+// can't slip through the joint between two walls.
+pub fn segments_intersect(p1: glam::Vec2, p2: glam::Vec2, q1: glam::Vec2, q2: glam::Vec2) -> bool {
+    let cross = |o: glam::Vec2, a: glam::Vec2, b: glam::Vec2| (a - o).perp_dot(b - o);
+    let d1 = cross(q1, q2, p1);
+    let d2 = cross(q1, q2, p2);
+    let d3 = cross(p1, p2, q1);
+    let d4 = cross(p1, p2, q2);
+    if ((d1 > 0.0 && d2 < 0.0) || (d1 < 0.0 && d2 > 0.0)) && ((d3 > 0.0 && d4 < 0.0) || (d3 < 0.0 && d4 > 0.0)) {
+        return true;
+    }
+
+    // Collinear and overlapping endpoints
+    let on_segment = |a: glam::Vec2, b: glam::Vec2, p: glam::Vec2| {
+        p.x >= a.x.min(b.x) && p.x <= a.x.max(b.x) && p.y >= a.y.min(b.y) && p.y <= a.y.max(b.y)
+    };
+    (d1 == 0.0 && on_segment(q1, q2, p1))
+        || (d2 == 0.0 && on_segment(q1, q2, p2))
+        || (d3 == 0.0 && on_segment(p1, p2, q1))
+        || (d4 == 0.0 && on_segment(p1, p2, q2))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Obstacle {
+    Wall(Vec<glam::Vec2>), // Polyline, at least two points
+    Circle { center: glam::Vec2, radius: f32 },
+}
+
+impl Obstacle {
+    // Wall pieces, none for a circle
+    pub fn segments(&self) -> impl Iterator<Item = (glam::Vec2, glam::Vec2)> + '_ {
+        let points: &[glam::Vec2] = match self {
+            Obstacle::Wall(points) => points,
+            Obstacle::Circle { .. } => &[],
+        };
+        points.windows(2).map(|pair| (pair[0], pair[1]))
+    }
+
+    // Point on the outline closest to `pos`
+    fn closest_point(&self, pos: glam::Vec2) -> glam::Vec2 {
+        match self {
+            Obstacle::Wall(_) => self
+                .segments()
+                .map(|(a, b)| closest_point_on_segment(pos, a, b))
+                .min_by(|a, b| a.distance_squared(pos).partial_cmp(&b.distance_squared(pos)).unwrap())
+                .unwrap_or(pos),
+            Obstacle::Circle { center, radius } => {
+                let offset = pos - *center;
+                if offset == glam::Vec2::ZERO {
+                    *center + glam::vec2(*radius, 0.0)
+                } else {
+                    *center + offset.normalize() * *radius
+                }
+            }
+        }
+    }
+
+    fn to_line(&self) -> String {
+        match self {
+            Obstacle::Wall(points) => {
+                let coordinates: Vec<String> = points.iter().map(|p| format!("{} {}", p.x, p.y)).collect();
+                format!("wall {}", coordinates.join(" "))
+            }
+            Obstacle::Circle { center, radius } => format!("circle {} {} {}", center.x, center.y, radius),
+        }
+    }
+
+    fn parse(line: &str) -> Option<Obstacle> {
+        let mut fields = line.split_whitespace();
+        let kind = fields.next()?;
+        let numbers: Vec<f32> = fields.map(|field| field.parse().ok()).collect::<Option<_>>()?;
+        match (kind, numbers.as_slice()) {
+            ("circle", &[x, y, radius]) => Some(Obstacle::Circle { center: glam::vec2(x, y), radius }),
+            ("wall", numbers) if numbers.len() >= 4 && numbers.len() % 2 == 0 => {
+                Some(Obstacle::Wall(numbers.chunks(2).map(|pair| glam::vec2(pair[0], pair[1])).collect()))
+            }
+            _ => None,
+        }
+    }
+}
+
+// Whether the segment from `from` to `to` passes through the circle
+fn segment_hits_circle(from: glam::Vec2, to: glam::Vec2, center: glam::Vec2, radius: f32) -> bool {
+    closest_point_on_segment(center, from, to).distance_squared(center) < radius * radius
+}
+
+// Impassable walls and circles, drawn in the editor (<e>). Boids steer away
+// from them, any move that would go through one is undone, and they block
+// the view between boids on either side.
+#[derive(Clone)]
+pub struct Obstacles {
+    items: Vec<Obstacle>,
+    pieces: Vec<(glam::Vec2, glam::Vec2)>, // Every wall cut into short pieces
+    piece_grid: SpatialGrid,               // The pieces by midpoint, for sight checks
+}
+
+impl Obstacles {
+    pub fn new(world: &World) -> Self {
+        Obstacles {
+            items: Vec::new(),
+            pieces: Vec::new(),
+            piece_grid: SpatialGrid::new(world.width, world.height, VISUAL_RANGE),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    pub fn items(&self) -> &[Obstacle] {
+        &self.items
+    }
+
+    pub fn push(&mut self, obstacle: Obstacle) {
+        self.items.push(obstacle);
+        self.index_walls();
+    }
+
+    pub fn pop(&mut self) {
+        self.items.pop();
+        self.index_walls();
+    }
+
+    // Move and stretch everything with a world rescaled by `scale`. Circles
+    // stay round, with the radius scaled by the mean of the two axes.
+    pub fn rescale(&mut self, scale: glam::Vec2) {
+        for obstacle in &mut self.items {
+            match obstacle {
+                Obstacle::Wall(points) => {
+                    for point in points {
+                        *point *= scale;
+                    }
+                }
+                Obstacle::Circle { center, radius } => {
+                    *center *= scale;
+                    *radius *= (scale.x * scale.y).sqrt();
+                }
+            }
+        }
+    }
+
+    pub fn resize(&mut self, world: &World) {
+        self.piece_grid = SpatialGrid::new(world.width, world.height, VISUAL_RANGE);
+        self.index_walls();
+    }
+
+    // Cut the walls into pieces no longer than WALL_PIECE and store them in
+    // the grid by midpoint. A piece can then only cross a sight line if its
+    // midpoint is within half a piece of the line's bounding circle.
+    fn index_walls(&mut self) {
+        self.pieces.clear();
+        for (a, b) in self.items.iter().flat_map(|obstacle| obstacle.segments()) {
+            let count = ((a.distance(b) / WALL_PIECE).ceil() as usize).max(1);
+            for i in 0..count {
+                let start = a.lerp(b, i as f32 / count as f32);
+                let end = a.lerp(b, (i + 1) as f32 / count as f32);
+                self.pieces.push((start, end));
+            }
+        }
+        let midpoints: Vec<glam::Vec2> = self.pieces.iter().map(|&(a, b)| (a + b) / 2.0).collect();
+        self.piece_grid.build(&midpoints);
+    }
+
+    // Whether a wall or circle stands between two points
+    pub fn blocks_sight(&self, from: glam::Vec2, to: glam::Vec2) -> bool {
+        let middle = (from + to) / 2.0;
+        let reach = from.distance(to) / 2.0 + WALL_PIECE / 2.0;
+        let walls = self
+            .piece_grid
+            .query_radius(middle, reach)
+            .into_iter()
+            .any(|(i, _)| segments_intersect(from, to, self.pieces[i].0, self.pieces[i].1));
+        walls
+            || self.items.iter().any(|obstacle| match obstacle {
+                Obstacle::Circle { center, radius } => segment_hits_circle(from, to, *center, *radius),
+                Obstacle::Wall(_) => false,
+            })
+    }
+
+    // Push away from every obstacle within AVOID_RANGE, harder the closer it is
+    pub fn avoid_force(&self, boid: &Boid) -> glam::Vec2 {
+        let pos = glam::vec2(boid.x, boid.y);
+        let mut force = glam::Vec2::ZERO;
+        for obstacle in &self.items {
+            let away = pos - obstacle.closest_point(pos);
+            let distance = away.length();
+            let inside = matches!(obstacle, Obstacle::Circle { center, radius } if pos.distance(*center) < *radius);
+            if distance < AVOID_RANGE && distance > 0.0 {
+                let direction = if inside { -away / distance } else { away / distance };
+                force += direction * AVOID_WEIGHT * (1.0 - distance / AVOID_RANGE);
+            }
+        }
+        force
+    }
+
+    // Undo a move from `from` to the boid's current position that ended
+    // inside a circle or crossed a wall, bouncing the boid off instead
+    pub fn resolve(&self, boid: &mut Boid, from: glam::Vec2) {
+        let to = glam::vec2(boid.x, boid.y);
+        let mut velocity = glam::vec2(boid.dx, boid.dy);
+        let mut position = to;
+
+        for obstacle in &self.items {
+            match obstacle {
+                Obstacle::Wall(_) => {
+                    for (a, b) in obstacle.segments() {
+                        if segments_intersect(from, position, a, b) {
+                            let normal = (b - a).perp().normalize_or_zero();
+                            velocity -= 2.0 * velocity.dot(normal) * normal;
+                            position = from;
+                        }
+                    }
+                }
+                Obstacle::Circle { center, radius } => {
+                    let offset = position - *center;
+                    if offset.length_squared() < radius * radius {
+                        let normal = if offset == glam::Vec2::ZERO { glam::Vec2::X } else { offset.normalize() };
+                        position = *center + normal * *radius;
+                        velocity -= velocity.dot(normal).min(0.0) * normal;
+                    }
+                }
+            }
+        }
+
+        if position != to || velocity != glam::vec2(boid.dx, boid.dy) {
+            boid.x = position.x;
+            boid.y = position.y;
+            boid.dx = velocity.x;
+            boid.dy = velocity.y;
+            boid.heading = velocity.y.atan2(velocity.x);
+        }
+    }
+
+    // One obstacle per line, "wall x1 y1 x2 y2 ..." or "circle x y radius"
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        let mut text = String::from("# boids obstacles\n");
+        for obstacle in &self.items {
+            text.push_str(&obstacle.to_line());
+            text.push('\n');
+        }
+        fs::write(path, text)
+    }
+
+    // Replace the obstacles with the ones saved in `path`
+    pub fn load(&mut self, path: &Path) -> Result<(), String> {
+        let text = fs::read_to_string(path).map_err(|err| format!("{}: {}", path.display(), err))?;
+        let mut items = Vec::new();
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let obstacle = Obstacle::parse(line)
+                .ok_or_else(|| format!("{} line {}: can't read '{}'", path.display(), number + 1, line))?;
+            items.push(obstacle);
+        }
+        self.items = items;
+        self.index_walls();
+        Ok(())
+    }
+
+    #[cfg(feature = "render-ggez")]
+    pub fn draw(&self, mb: &mut graphics::MeshBuilder, line_width: f32) -> GameResult {
+        for obstacle in &self.items {
+            match obstacle {
+                Obstacle::Wall(points) => {
+                    mb.line(points, 3.0 * line_width, WALL_COLOR.into())?;
+                }
+                Obstacle::Circle { center, radius } => {
+                    mb.circle(graphics::DrawMode::fill(), *center, *radius, 0.1, WALL_COLOR.into())?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+// Obstacle drawing, toggled with <e>. Clicks add points to a wall and
+// <return> or a right click finishes it; <shift> + drag draws a circle;
+// <backspace> removes the last obstacle.
+#[derive(Default)]
+pub struct ObstacleEditor {
+    pub active: bool,
+    wall: Vec<glam::Vec2>,             // Points of the wall being drawn
+    circle_center: Option<glam::Vec2>, // Set while a circle is being dragged out
+}
+
+impl ObstacleEditor {
+    pub fn press(&mut self, point: glam::Vec2, shift: bool) {
+        if shift {
+            self.circle_center = Some(point);
+        } else {
+            self.wall.push(point);
+        }
+    }
+
+    // The circle dragged out since the press, if any
+    pub fn release(&mut self, point: glam::Vec2) -> Option<Obstacle> {
+        let center = self.circle_center.take()?;
+        let radius = center.distance(point);
+        if radius < MIN_RADIUS {
+            return None;
+        }
+        Some(Obstacle::Circle { center, radius })
+    }
+
+    // The wall drawn so far, if it has at least one segment
+    pub fn finish(&mut self) -> Option<Obstacle> {
+        let points = std::mem::take(&mut self.wall);
+        if points.len() < 2 {
+            return None;
+        }
+        Some(Obstacle::Wall(points))
+    }
+
+    // The unfinished wall or circle, following the cursor
+    #[cfg(feature = "render-ggez")]
+    pub fn draw(&self, mb: &mut graphics::MeshBuilder, cursor: glam::Vec2, line_width: f32) -> GameResult {
+        if let Some(&last) = self.wall.last() {
+            let mut points = self.wall.clone();
+            if last != cursor {
+                points.push(cursor);
+            }
+            if points.len() >= 2 {
+                mb.line(&points, 3.0 * line_width, PREVIEW_COLOR.into())?;
+            }
+        }
+        if let Some(center) = self.circle_center {
+            let radius = center.distance(cursor).max(MIN_RADIUS);
+            mb.circle(graphics::DrawMode::fill(), center, radius, 0.1, PREVIEW_COLOR.into())?;
+        }
+        Ok(())
+    }
+}
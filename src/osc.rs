@@ -0,0 +1,103 @@
+// osc.rs
+use crate::config::Parameters;
+use crate::simulation;
+use crate::world::World;
+use rosc::{OscMessage, OscPacket, OscType};
+use std::io;
+use std::net::{SocketAddr, UdpSocket};
+use tracing::{debug, warn};
+
+// Largest datagram read in one go, plenty for a bundle of a few floats
+const MAX_PACKET: usize = 4096;
+
+// Most boids /boids/count can ask for, larger counts are clamped to it
+const MAX_COUNT: usize = 100_000;
+
+// What an OSC message asks for
+#[derive(Debug, Clone, PartialEq)]
+pub enum OscCommand {
+    Parameters(Parameters), // /boids/<name> <value>, names and ranges as in [parameters] of boids.toml
+    Count(usize),           // /boids/count <boids>, up to MAX_COUNT
+    World(f32, f32),        // /boids/world <width> <height>, each up to MAX_WORLD_SIZE, rescaling the flock
+}
+
+// Listens for OSC messages from controllers like TouchOSC or Max for Live,
+// enabled with --osc. Polled from the main thread, so nothing is shared.
+pub struct OscListener {
+    socket: UdpSocket,
+    buffer: Vec<u8>,
+}
+
+impl OscListener {
+    pub fn bind(address: SocketAddr) -> io::Result<OscListener> {
+        let socket = UdpSocket::bind(address)?;
+        socket.set_nonblocking(true)?;
+        Ok(OscListener {
+            socket,
+            buffer: vec![0; MAX_PACKET],
+        })
+    }
+
+    // Every command received since the last poll, in order
+    pub fn poll(&mut self) -> Vec<OscCommand> {
+        let mut commands = Vec::new();
+        loop {
+            let size = match self.socket.recv(&mut self.buffer) {
+                Ok(size) => size,
+                Err(err) if err.kind() == io::ErrorKind::WouldBlock => break,
+                Err(err) => {
+                    warn!("OSC receive failed: {}", err);
+                    break;
+                }
+            };
+            match rosc::decoder::decode_udp(&self.buffer[..size]) {
+                Ok((_, packet)) => collect(packet, &mut commands),
+                Err(err) => debug!("Ignoring a malformed OSC packet: {:?}", err),
+            }
+        }
+        commands
+    }
+}
+
+// Bundles are flattened, their timetags ignored
+fn collect(packet: OscPacket, commands: &mut Vec<OscCommand>) {
+    match packet {
+        OscPacket::Message(message) => match command(&message) {
+            Some(command) => commands.push(command),
+            None => debug!("Ignoring OSC message {} {:?}", message.addr, message.args),
+        },
+        OscPacket::Bundle(bundle) => {
+            for packet in bundle.content {
+                collect(packet, commands);
+            }
+        }
+    }
+}
+
+fn command(message: &OscMessage) -> Option<OscCommand> {
+    let name = message.addr.strip_prefix("/boids/")?;
+    // Controllers differ in which number type they send
+    let number = |arg: &OscType| match arg {
+        OscType::Float(value) => Some(*value),
+        OscType::Double(value) => Some(*value as f32),
+        OscType::Int(value) => Some(*value as f32),
+        OscType::Long(value) => Some(*value as f32),
+        _ => None,
+    };
+    // NaN and infinities would poison whatever they are applied to
+    let value = number(message.args.first()?).filter(|value| value.is_finite())?;
+
+    match name {
+        "count" => return Some(OscCommand::Count((value.max(0.0).round() as usize).min(MAX_COUNT))),
+        "world" => {
+            let height = number(message.args.get(1)?)?;
+            return if World::is_valid_size(value, height) { Some(OscCommand::World(value, height)) } else { None };
+        }
+        _ => {}
+    }
+    // The same ranges as every other source of parameters
+    let value = simulation::clamp_parameter(name, value)?;
+    let mut parameters = Parameters::default();
+    parameters.set(name, value);
+    Some(OscCommand::Parameters(parameters))
+}
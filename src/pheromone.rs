@@ -0,0 +1,158 @@
+// pheromone.rs
+use crate::boid::Boid;
+#[cfg(feature = "render-ggez")]
+use ggez::{graphics, GameResult};
+
+// Pheromone settings
+pub const PHEROMONE_CELL_SIZE: f32 = 16.0; // Pixels
+const DEPOSIT_RATE: f32 = 0.6;             // Added per boid per second to the cell it is in
+const DECAY_RATE: f32 = 0.25;              // Fraction lost per second
+const DIFFUSION_RATE: f32 = 2.0;           // How fast cells even out with their neighbors, per second
+const MAX_LEVEL: f32 = 4.0;                // Cells saturate here
+const FOLLOW_WEIGHT: f32 = 10.0;           // Velocity change per step up a steep gradient
+const GRADIENT_GAIN: f32 = 4.0;            // Gradients this much below 1 per cell still steer fully
+#[cfg(feature = "render-ggez")]
+const SHOWN_LEVEL: f32 = 0.02;             // Fainter cells aren't drawn
+
+// What the pheromone field is doing, cycled with <f5>
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PheromoneMode {
+    Off,
+    Deposit, // Boids lay trails, shown as an overlay
+    Follow,  // ...and steer up the gradient, so trails reinforce themselves
+}
+
+impl PheromoneMode {
+    pub fn next(self) -> PheromoneMode {
+        match self {
+            PheromoneMode::Off => PheromoneMode::Deposit,
+            PheromoneMode::Deposit => PheromoneMode::Follow,
+            PheromoneMode::Follow => PheromoneMode::Off,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            PheromoneMode::Off => "Off",
+            PheromoneMode::Deposit => "Deposit",
+            PheromoneMode::Follow => "Deposit and follow",
+        }
+    }
+}
+
+// Scalar field the boids deposit into as they fly, which spreads to
+// neighboring cells and fades over time
+#[derive(Clone)]
+pub struct PheromoneField {
+    pub mode: PheromoneMode,
+    cells: Vec<f32>,
+    scratch: Vec<f32>, // Next levels while diffusing
+    cell_size: f32,
+    width: usize,
+    height: usize,
+}
+
+impl PheromoneField {
+    pub fn new(world_width: f32, world_height: f32, cell_size: f32) -> Self {
+        let width = ((world_width / cell_size).ceil() as usize).max(1);
+        let height = ((world_height / cell_size).ceil() as usize).max(1);
+
+        PheromoneField {
+            mode: PheromoneMode::Off,
+            cells: vec![0.0; width * height],
+            scratch: vec![0.0; width * height],
+            cell_size,
+            width,
+            height,
+        }
+    }
+
+    pub fn clear(&mut self) {
+        for cell in &mut self.cells {
+            *cell = 0.0;
+        }
+    }
+
+    // Fit the field to a new world size, carrying the levels over the same
+    // way as FlowField::resize
+    pub fn resize(&mut self, world_width: f32, world_height: f32, scale: glam::Vec2) {
+        let mut resized = PheromoneField::new(world_width, world_height, self.cell_size);
+        resized.mode = self.mode;
+        for cell_y in 0..resized.height {
+            for cell_x in 0..resized.width {
+                // Cells that were outside the old world start empty
+                let old = (glam::vec2(cell_x as f32, cell_y as f32) + 0.5) / scale;
+                if old.x < self.width as f32 && old.y < self.height as f32 {
+                    resized.cells[cell_y * resized.width + cell_x] = self.level(old.x as usize, old.y as usize);
+                }
+            }
+        }
+        *self = resized;
+    }
+
+    // Cell coordinates of a world position, clamped to the field
+    fn cell_of(&self, x: f32, y: f32) -> (usize, usize) {
+        let cell_x = ((x / self.cell_size).max(0.0) as usize).min(self.width - 1);
+        let cell_y = ((y / self.cell_size).max(0.0) as usize).min(self.height - 1);
+        (cell_x, cell_y)
+    }
+
+    fn level(&self, cell_x: usize, cell_y: usize) -> f32 {
+        self.cells[cell_y * self.width + cell_x]
+    }
+
+    // Deposit under every boid, then diffuse and decay over `tick` seconds
+    pub fn update(&mut self, boids: &[Boid], tick: f32) {
+        for boid in boids {
+            let (cell_x, cell_y) = self.cell_of(boid.x, boid.y);
+            let cell = &mut self.cells[cell_y * self.width + cell_x];
+            *cell = (*cell + DEPOSIT_RATE * tick).min(MAX_LEVEL);
+        }
+
+        // Explicit diffusion towards the average of the four neighbors, with
+        // the edges mirrored. Capped so large ticks can't overshoot.
+        let spread = (DIFFUSION_RATE * tick).min(1.0);
+        let keep = (1.0 - DECAY_RATE * tick).max(0.0);
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let center = self.level(x, y);
+                let left = self.level(x.saturating_sub(1), y);
+                let right = self.level((x + 1).min(self.width - 1), y);
+                let up = self.level(x, y.saturating_sub(1));
+                let down = self.level(x, (y + 1).min(self.height - 1));
+                let average = (left + right + up + down) / 4.0;
+                self.scratch[y * self.width + x] = (center + spread * (average - center)) * keep;
+            }
+        }
+        std::mem::swap(&mut self.cells, &mut self.scratch);
+    }
+
+    // Steering up the local gradient, zero on flat ground
+    pub fn follow_force(&self, boid: &Boid) -> glam::Vec2 {
+        let (x, y) = self.cell_of(boid.x, boid.y);
+        let gradient = glam::vec2(
+            self.level((x + 1).min(self.width - 1), y) - self.level(x.saturating_sub(1), y),
+            self.level(x, (y + 1).min(self.height - 1)) - self.level(x, y.saturating_sub(1)),
+        );
+        (gradient * GRADIENT_GAIN).clamp_length_max(1.0) * FOLLOW_WEIGHT
+    }
+
+    // Cells tinted by their level
+    #[cfg(feature = "render-ggez")]
+    pub fn draw(&self, mb: &mut graphics::MeshBuilder) -> GameResult {
+        for (idx, &level) in self.cells.iter().enumerate() {
+            if level < SHOWN_LEVEL {
+                continue;
+            }
+            let rect = graphics::Rect::new(
+                (idx % self.width) as f32 * self.cell_size,
+                (idx / self.width) as f32 * self.cell_size,
+                self.cell_size,
+                self.cell_size,
+            );
+            let alpha = (level / MAX_LEVEL).sqrt() * 0.6;
+            mb.rectangle(graphics::DrawMode::fill(), rect, [0.9, 0.6, 1.0, alpha].into())?;
+        }
+        Ok(())
+    }
+}
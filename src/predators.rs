@@ -0,0 +1,237 @@
+// predators.rs
+use crate::boid::{Boid, SPEED_LIMIT};
+use crate::charts::PopulationChart;
+use crate::spatial_grid::SpatialGrid;
+use crate::spawn;
+use crate::world::World;
+#[cfg(feature = "render-ggez")]
+use ggez::{graphics, GameResult};
+use rand::Rng;
+
+// Predator-prey settings. Energy runs from 0 (starved) to BREED_ENERGY.
+const INITIAL_PREDATORS: usize = 6;
+const MAX_PREDATORS: usize = 100;
+const MAX_PREY: f32 = 4_000.0;        // Prey births slow to nothing at this population
+const PREY_BIRTH_RATE: f32 = 0.15;    // Births per prey per second while the population is small
+const PREDATOR_SPEED: f32 = SPEED_LIMIT * 1.1;
+const HUNT_RANGE: f32 = 160.0;        // Predators notice prey this close
+const CATCH_RADIUS: f32 = 8.0;        // Pixels, touching a boid catches it
+const CHASE_WEIGHT: f32 = 30.0;       // Velocity change per step towards the nearest prey
+const WANDER_WEIGHT: f32 = 20.0;      // Random velocity change per step with nothing in range
+const FLEE_RANGE: f32 = 80.0;         // Prey flee predators this close
+const FLEE_WEIGHT: f32 = 25.0;        // Velocity change per step away from a predator
+const HUNGER: f32 = 0.1;              // Energy lost per second
+const MEAL_ENERGY: f32 = 0.3;         // Energy gained per boid caught
+const BREED_ENERGY: f32 = 1.0;        // A predator this well fed splits in two
+#[cfg(feature = "render-ggez")]
+const PREDATOR_SIZE: f32 = 9.0;       // Pixels from the center to the nose
+
+const PREY_COLOR: [f32; 4] = [0.5, 0.8, 1.0, 0.9];
+const PREDATOR_COLOR: [f32; 4] = [1.0, 0.3, 0.2, 0.9];
+
+#[derive(Clone)]
+pub struct Predator {
+    pub pos: glam::Vec2,
+    pub vel: glam::Vec2,
+    pub energy: f32,
+}
+
+impl Predator {
+    fn new(pos: glam::Vec2, energy: f32, rng: &mut impl Rng) -> Self {
+        Predator {
+            pos,
+            vel: spawn::random_in_circle(rng).normalize_or_zero() * PREDATOR_SPEED * 0.5,
+            energy,
+        }
+    }
+}
+
+// What a step did to the flock. The caller removes the caught boids and
+// appends the newborn ones, so indices stay valid while the step runs.
+pub struct PopulationChanges {
+    pub caught: Vec<bool>, // One entry per boid
+    pub born: Vec<Boid>,
+}
+
+// Lotka-Volterra style predator-prey mode, toggled with <f4>. Predators
+// starve unless they catch boids and split once well fed; boids breed
+// logistically and flee predators. Both populations are charted.
+#[derive(Clone)]
+pub struct PredatorPrey {
+    pub active: bool,
+    pub predators: Vec<Predator>,
+    prey_grid: SpatialGrid, // The boids, rebuilt every step, in cells sized for catching
+    births_pending: f32,    // Fractional prey births owed from previous steps
+    pub chart: PopulationChart,
+}
+
+impl PredatorPrey {
+    pub fn new(world: &World) -> Self {
+        PredatorPrey {
+            active: false,
+            predators: Vec::new(),
+            prey_grid: SpatialGrid::new(world.width, world.height, 2.0 * CATCH_RADIUS),
+            births_pending: 0.0,
+            chart: PopulationChart::new(&[("Prey", PREY_COLOR), ("Predators", PREDATOR_COLOR)]),
+        }
+    }
+
+    // Release a few predators at random spots, or remove them all
+    pub fn toggle(&mut self, world: &World, rng: &mut impl Rng) {
+        self.active = !self.active;
+        self.predators.clear();
+        self.births_pending = 0.0;
+        self.chart.clear();
+        if self.active {
+            for _ in 0..INITIAL_PREDATORS {
+                let pos = glam::vec2(rng.gen::<f32>() * world.width, rng.gen::<f32>() * world.height);
+                self.predators.push(Predator::new(pos, BREED_ENERGY / 2.0, rng));
+            }
+        }
+    }
+
+    // Turn the mode on without releasing anyone, if it is off
+    fn start(&mut self) {
+        if !self.active {
+            self.active = true;
+            self.births_pending = 0.0;
+            self.chart.clear();
+        }
+    }
+
+    // Release `count` more predators, turning the mode on if it is off
+    pub fn spawn(&mut self, count: usize, world: &World, rng: &mut impl Rng) {
+        self.start();
+        for _ in 0..count.min(MAX_PREDATORS.saturating_sub(self.predators.len())) {
+            let pos = glam::vec2(rng.gen::<f32>() * world.width, rng.gen::<f32>() * world.height);
+            self.predators.push(Predator::new(pos, BREED_ENERGY / 2.0, rng));
+        }
+    }
+
+    // Turn boids into predators flying on from where they were. Returns how
+    // many were converted, the rest are past MAX_PREDATORS.
+    pub fn convert<'a>(&mut self, boids: impl Iterator<Item = &'a Boid>) -> usize {
+        self.start();
+        let room = MAX_PREDATORS.saturating_sub(self.predators.len());
+        let before = self.predators.len();
+        self.predators.extend(boids.take(room).map(|boid| Predator {
+            pos: glam::vec2(boid.x, boid.y),
+            vel: glam::vec2(boid.dx, boid.dy).clamp_length_max(PREDATOR_SPEED),
+            energy: BREED_ENERGY / 2.0,
+        }));
+        self.predators.len() - before
+    }
+
+    // Move the predators with a world rescaled by `scale`
+    pub fn rescale(&mut self, scale: glam::Vec2) {
+        for predator in &mut self.predators {
+            predator.pos *= scale;
+        }
+    }
+
+    pub fn resize(&mut self, world: &World) {
+        self.prey_grid = SpatialGrid::new(world.width, world.height, 2.0 * CATCH_RADIUS);
+        for predator in &mut self.predators {
+            predator.pos = predator.pos.max(glam::Vec2::ZERO).min(glam::vec2(world.width, world.height));
+        }
+    }
+
+    // Push a boid away from every predator within FLEE_RANGE
+    pub fn flee_force(&self, boid: &Boid) -> glam::Vec2 {
+        let pos = glam::vec2(boid.x, boid.y);
+        self.predators
+            .iter()
+            .map(|predator| pos - predator.pos)
+            .filter(|offset| offset.length_squared() < FLEE_RANGE * FLEE_RANGE)
+            .fold(glam::Vec2::ZERO, |force, offset| force + offset.normalize_or_zero() * FLEE_WEIGHT)
+    }
+
+    // Move the predators, let them hunt, starve and breed, and breed the prey
+    pub fn step(&mut self, boids: &[Boid], tick: f32, world: &World, rng: &mut impl Rng) -> PopulationChanges {
+        self.prey_grid.build(boids);
+        let mut caught = vec![false; boids.len()];
+
+        for predator in &mut self.predators {
+            // Chase the nearest boid in range, or roam
+            let nearest = self
+                .prey_grid
+                .query_radius(predator.pos, HUNT_RANGE)
+                .into_iter()
+                .filter(|&(i, _)| !caught[i])
+                .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap());
+            let steering = match nearest {
+                Some((i, _)) => (glam::vec2(boids[i].x, boids[i].y) - predator.pos).normalize_or_zero() * CHASE_WEIGHT,
+                None => spawn::random_in_circle(rng) * WANDER_WEIGHT,
+            };
+            predator.vel = (predator.vel + steering).clamp_length_max(PREDATOR_SPEED);
+            predator.pos += predator.vel * tick;
+
+            // Bounce off the world edges
+            if predator.pos.x < 0.0 || predator.pos.x > world.width {
+                predator.vel.x = -predator.vel.x;
+                predator.pos.x = predator.pos.x.max(0.0).min(world.width);
+            }
+            if predator.pos.y < 0.0 || predator.pos.y > world.height {
+                predator.vel.y = -predator.vel.y;
+                predator.pos.y = predator.pos.y.max(0.0).min(world.height);
+            }
+
+            for (i, _) in self.prey_grid.query_radius(predator.pos, CATCH_RADIUS) {
+                if !caught[i] {
+                    caught[i] = true;
+                    predator.energy += MEAL_ENERGY;
+                }
+            }
+            predator.energy -= HUNGER * tick;
+        }
+
+        // Starved predators die, well fed ones split their energy with a child
+        self.predators.retain(|predator| predator.energy > 0.0);
+        let mut children = Vec::new();
+        let mut count = self.predators.len();
+        for predator in &mut self.predators {
+            if predator.energy >= BREED_ENERGY && count < MAX_PREDATORS {
+                predator.energy /= 2.0;
+                children.push(Predator::new(predator.pos, predator.energy, rng));
+                count += 1;
+            }
+        }
+        self.predators.extend(children);
+
+        // Logistic growth: every surviving boid may have a child next to it
+        let survivors: Vec<usize> = (0..boids.len()).filter(|&i| !caught[i]).collect();
+        let population = survivors.len() as f32;
+        self.births_pending += PREY_BIRTH_RATE * population * (1.0 - population / MAX_PREY).max(0.0) * tick;
+        let mut born = Vec::new();
+        while self.births_pending >= 1.0 && !survivors.is_empty() {
+            self.births_pending -= 1.0;
+            let parent = &boids[survivors[rng.gen_range(0..survivors.len())]];
+            let mut child = Boid::new(world.width, world.height, rng);
+            let position = glam::vec2(parent.x, parent.y) + spawn::random_in_circle(rng) * CATCH_RADIUS;
+            spawn::place(&mut child, position, glam::vec2(parent.dx, parent.dy));
+            child.species = parent.species;
+            born.push(child);
+        }
+        if survivors.is_empty() {
+            self.births_pending = 0.0;
+        }
+
+        let predators = self.predators.len() as f32;
+        self.chart.sample(tick, || vec![population + born.len() as f32, predators]);
+        PopulationChanges { caught, born }
+    }
+
+    // Predators as triangles pointing the way they fly
+    #[cfg(feature = "render-ggez")]
+    pub fn draw(&self, mb: &mut graphics::MeshBuilder) -> GameResult {
+        for predator in &self.predators {
+            let forward = predator.vel.normalize_or_zero();
+            let forward = if forward == glam::Vec2::ZERO { glam::Vec2::Y } else { forward };
+            let side = forward.perp() * PREDATOR_SIZE * 0.6;
+            let nose = predator.pos + forward * PREDATOR_SIZE;
+            let tail = predator.pos - forward * PREDATOR_SIZE * 0.6;
+            mb.polygon(graphics::DrawMode::fill(), &[nose, tail + side, tail - side], PREDATOR_COLOR.into())?;
+        }
+        Ok(())
+    }
+}
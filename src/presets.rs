@@ -0,0 +1,59 @@
+// presets.rs
+use crate::behavior::{self, WeightedBehavior};
+
+// A named set of flocking parameters, selected with the number keys
+pub struct Preset {
+    pub name: &'static str,
+    pub separation: f32,
+    pub cohesion: f32,
+    pub alignment: f32,
+    pub wander: f32,
+}
+
+pub const PRESETS: [Preset; 5] = [
+    Preset {
+        name: "Classic",
+        separation: 0.5,
+        cohesion: 0.05,
+        alignment: 0.1,
+        wander: 3.0,
+    },
+    Preset {
+        name: "Tight murmuration",
+        separation: 0.3,
+        cohesion: 0.12,
+        alignment: 0.2,
+        wander: 1.0,
+    },
+    Preset {
+        name: "Loose swarm",
+        separation: 0.8,
+        cohesion: 0.02,
+        alignment: 0.03,
+        wander: 8.0,
+    },
+    Preset {
+        name: "Fish school",
+        separation: 0.5,
+        cohesion: 0.06,
+        alignment: 0.3,
+        wander: 2.0,
+    },
+    Preset {
+        name: "Chaotic",
+        separation: 1.0,
+        cohesion: 0.15,
+        alignment: 0.0,
+        wander: 15.0,
+    },
+];
+
+impl Preset {
+    // Apply the weights to the built-in behaviors, leaving custom ones alone
+    pub fn apply(&self, behaviors: &mut [WeightedBehavior], wander_strength: &mut f32) {
+        behavior::set_weight(behaviors, "separation", self.separation);
+        behavior::set_weight(behaviors, "cohesion", self.cohesion);
+        behavior::set_weight(behaviors, "alignment", self.alignment);
+        *wander_strength = self.wander;
+    }
+}
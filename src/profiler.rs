@@ -0,0 +1,216 @@
+// profiler.rs
+use ggez::{graphics, Context, GameResult};
+use std::collections::VecDeque;
+use std::fmt;
+use std::time::Duration;
+
+// Profiler settings
+const PROFILE_FRAMES: usize = 120;       // Frames shown in the overlay
+const STATS_FRAMES: usize = 600;         // Frames the percentiles are taken over, ~10s at 60 FPS
+const HITCH_FACTOR: f32 = 2.0;           // Frames this many times slower than the mean are flagged
+const BAR_WIDTH: f32 = 3.0;              // Pixels per frame
+const CHART_HEIGHT: f32 = 100.0;
+const CHART_BUDGET_MS: f32 = 1000.0 / 30.0; // Frame time at the top of the chart
+
+// Parts of a frame timed separately. The simulation sections are also
+// emitted as tracing spans, so any tracing subscriber can pick them up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Section {
+    GridRebuild,
+    Neighbors,
+    Behaviors,
+    Update, // The whole update, including the three above
+    Draw,
+}
+
+impl Section {
+    const COUNT: usize = 5;
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Section::GridRebuild => "grid rebuild",
+            Section::Neighbors => "neighbors",
+            Section::Behaviors => "behaviors",
+            Section::Update => "update",
+            Section::Draw => "draw",
+        }
+    }
+}
+
+// Time spent in each section during one frame
+#[derive(Debug, Clone, Copy, Default)]
+struct FrameTimes([Duration; Section::COUNT]);
+
+impl FrameTimes {
+    fn get(&self, section: Section) -> Duration {
+        self.0[section as usize]
+    }
+
+    // Update time not covered by the simulation sections
+    fn other_update(&self) -> Duration {
+        let simulation = self.get(Section::GridRebuild) + self.get(Section::Neighbors) + self.get(Section::Behaviors);
+        self.get(Section::Update).checked_sub(simulation).unwrap_or_default()
+    }
+
+    // Stacked segments from the bottom of a bar up, in milliseconds
+    fn segments(&self) -> [(f32, [f32; 4]); 5] {
+        let ms = |duration: Duration| duration.as_secs_f32() * 1000.0;
+        [
+            (ms(self.get(Section::GridRebuild)), [0.9, 0.5, 0.2, 0.9]),
+            (ms(self.get(Section::Neighbors)), [0.9, 0.8, 0.2, 0.9]),
+            (ms(self.get(Section::Behaviors)), [0.4, 0.8, 0.3, 0.9]),
+            (ms(self.other_update()), [0.5, 0.5, 0.5, 0.9]),
+            (ms(self.get(Section::Draw)), [0.3, 0.6, 1.0, 0.9]),
+        ]
+    }
+}
+
+// Mean and tail of a section's frame times, in microseconds
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FrameStats {
+    pub mean: f32,
+    pub p95: f32,
+    pub p99: f32,
+}
+
+impl fmt::Display for FrameStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:.0}/{:.0}/{:.0}μs", self.mean, self.p95, self.p99)
+    }
+}
+
+// Measured section times for the last few seconds. The FPS line shows the
+// mean and percentiles, and <f3> shows the last frames as stacked bars.
+pub struct Profiler {
+    current: FrameTimes,
+    history: VecDeque<FrameTimes>,
+}
+
+impl Profiler {
+    pub fn new() -> Self {
+        Profiler {
+            current: FrameTimes::default(),
+            history: VecDeque::with_capacity(STATS_FRAMES),
+        }
+    }
+
+    pub fn add(&mut self, section: Section, elapsed: Duration) {
+        self.current.0[section as usize] += elapsed;
+    }
+
+    // Close the current frame, call once per frame after drawing
+    pub fn end_frame(&mut self) {
+        if self.history.len() >= STATS_FRAMES {
+            self.history.pop_front();
+        }
+        self.history.push_back(self.current);
+        self.current = FrameTimes::default();
+    }
+
+    // Average time per frame in `section` over the last `frames` frames, in microseconds
+    pub fn average_micros(&self, section: Section, frames: usize) -> f32 {
+        let frames = frames.min(self.history.len());
+        if frames == 0 {
+            return 0.0;
+        }
+        let total: Duration = self.history.iter().rev().take(frames).map(|times| times.get(section)).sum();
+        total.as_micros() as f32 / frames as f32
+    }
+
+    // Mean, 95th and 99th percentile of `section` over the rolling window.
+    // Percentiles use the nearest rank, so with few frames p99 is the slowest.
+    pub fn stats(&self, section: Section) -> FrameStats {
+        let mut micros: Vec<f32> = self.history.iter().map(|times| times.get(section).as_secs_f32() * 1e6).collect();
+        if micros.is_empty() {
+            return FrameStats::default();
+        }
+        micros.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+        let percentile = |p: f32| micros[((p * micros.len() as f32).ceil() as usize).clamp(1, micros.len()) - 1];
+        FrameStats {
+            mean: micros.iter().sum::<f32>() / micros.len() as f32,
+            p95: percentile(0.95),
+            p99: percentile(0.99),
+        }
+    }
+
+    pub fn size(&self, ui_scale: f32) -> glam::Vec2 {
+        glam::vec2(PROFILE_FRAMES as f32 * BAR_WIDTH, CHART_HEIGHT + 80.0) * ui_scale
+    }
+
+    // Draw the bars and a legend with averages, in screen space
+    pub fn draw(&self, ctx: &Context, canvas: &mut graphics::Canvas, top_left: glam::Vec2, ui_scale: f32) -> GameResult {
+        let mb = &mut graphics::MeshBuilder::new();
+        let (bar_width, height) = (BAR_WIDTH * ui_scale, CHART_HEIGHT * ui_scale);
+        let width = PROFILE_FRAMES as f32 * bar_width;
+        let scale = height / CHART_BUDGET_MS;
+
+        mb.rectangle(
+            graphics::DrawMode::fill(),
+            graphics::Rect::new(top_left.x, top_left.y, width, height),
+            [0.0, 0.0, 0.0, 0.5].into(),
+        )?;
+
+        // Newest frame at the right edge. Hitches, frames well above the
+        // average, get a red mark along the top.
+        let shown = self.history.len().min(PROFILE_FRAMES);
+        let start_x = top_left.x + width - shown as f32 * bar_width;
+        let hitch_ms = HITCH_FACTOR * (self.stats(Section::Update).mean + self.stats(Section::Draw).mean) / 1000.0;
+        for (i, times) in self.history.iter().skip(self.history.len() - shown).enumerate() {
+            let x = start_x + i as f32 * bar_width;
+            let frame_ms = (times.get(Section::Update) + times.get(Section::Draw)).as_secs_f32() * 1000.0;
+            if frame_ms > hitch_ms {
+                mb.rectangle(
+                    graphics::DrawMode::fill(),
+                    graphics::Rect::new(x, top_left.y, bar_width, 3.0 * ui_scale),
+                    [1.0, 0.2, 0.2, 1.0].into(),
+                )?;
+            }
+            let mut bottom = top_left.y + height;
+            for &(ms, color) in &times.segments() {
+                let bar = (ms * scale).min(bottom - top_left.y);
+                if bar > 0.0 {
+                    mb.rectangle(
+                        graphics::DrawMode::fill(),
+                        graphics::Rect::new(x, bottom - bar, bar_width, bar),
+                        color.into(),
+                    )?;
+                    bottom -= bar;
+                }
+            }
+        }
+
+        // 60 FPS budget line
+        let budget_y = top_left.y + height - 1000.0 / 60.0 * scale;
+        mb.line(
+            &[glam::vec2(top_left.x, budget_y), glam::vec2(top_left.x + width, budget_y)],
+            1.0,
+            [1.0, 1.0, 1.0, 0.5].into(),
+        )?;
+
+        let legend = [
+            (Section::GridRebuild, [0.9, 0.5, 0.2, 1.0]),
+            (Section::Neighbors, [0.9, 0.8, 0.2, 1.0]),
+            (Section::Behaviors, [0.4, 0.8, 0.3, 1.0]),
+            (Section::Update, [0.5, 0.5, 0.5, 1.0]),
+            (Section::Draw, [0.3, 0.6, 1.0, 1.0]),
+        ];
+        canvas.draw(&graphics::Mesh::from_data(ctx, mb.build()), graphics::DrawParam::default());
+        for (row, &(section, color)) in legend.iter().enumerate() {
+            // Update and draw get the tail too, they're what hitches show up in
+            let text = match section {
+                Section::Update | Section::Draw => format!("{} mean/p95/p99: {}", section.name(), self.stats(section)),
+                _ => format!("{}: {:.0}μs", section.name(), self.average_micros(section, PROFILE_FRAMES)),
+            };
+            let text = graphics::Text::new(graphics::TextFragment {
+                text,
+                color: Some(color.into()),
+                font: None,
+                scale: Some(graphics::PxScale::from(12.0 * ui_scale)),
+            });
+            let pos = top_left + glam::vec2(0.0, height + (4.0 + row as f32 * 15.0) * ui_scale);
+            canvas.draw(&text, graphics::DrawParam::default().dest(pos));
+        }
+        Ok(())
+    }
+}
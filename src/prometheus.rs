@@ -0,0 +1,108 @@
+// prometheus.rs
+use crate::metrics::FlockStats;
+use crate::profiler::FrameStats;
+use std::fmt::Write as _;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use tracing::debug;
+
+// Scrapers that stall are dropped rather than holding up the next one
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(5);
+
+// What the main thread publishes once a second
+pub struct Sample<'a> {
+    pub fps: f32,
+    pub boids: usize,
+    pub update: FrameStats,
+    pub draw: FrameStats,
+    pub flock: &'a FlockStats,
+}
+
+// HTTP endpoint started with --metrics, serving the last published sample at
+// /metrics in the Prometheus text format. The page is rendered on the main
+// thread; the listener thread only copies it out, so scrapes never touch the
+// simulation.
+pub struct MetricsEndpoint {
+    page: Arc<Mutex<String>>,
+}
+
+impl MetricsEndpoint {
+    pub fn bind(address: SocketAddr) -> io::Result<MetricsEndpoint> {
+        let listener = TcpListener::bind(address)?;
+        let page = Arc::new(Mutex::new(String::new()));
+        let served = page.clone();
+        thread::Builder::new().name("metrics".to_string()).spawn(move || {
+            for stream in listener.incoming() {
+                let result = stream.and_then(|stream| respond(stream, &served));
+                if let Err(err) = result {
+                    debug!("Metrics request failed: {}", err);
+                }
+            }
+        })?;
+        Ok(MetricsEndpoint { page })
+    }
+
+    pub fn publish(&self, sample: &Sample) {
+        let mut page = String::new();
+        gauge(&mut page, "boids_fps", "Frames per second over the last second", sample.fps);
+        gauge(&mut page, "boids_count", "Boids in the flock", sample.boids as f32);
+        timings(&mut page, "boids_update_seconds", "Simulation update time per frame", sample.update);
+        timings(&mut page, "boids_draw_seconds", "Draw time per frame", sample.draw);
+        let flock = sample.flock;
+        gauge(&mut page, "boids_average_speed", "Mean boid speed in pixels per second", flock.average_speed);
+        gauge(&mut page, "boids_polarization", "Alignment of headings, 0 disordered to 1 aligned", flock.polarization);
+        gauge(&mut page, "boids_angular_momentum", "Rotation about the centroid, 0 none to 1 a perfect mill", flock.angular_momentum);
+        gauge(&mut page, "boids_nearest_neighbor_distance", "Mean distance to the nearest neighbor in pixels", flock.nearest_neighbor_distance);
+        gauge(&mut page, "boids_clusters", "Groups of boids linked by visual range", flock.cluster_count as f32);
+        if let Ok(mut served) = self.page.lock() {
+            *served = page;
+        }
+    }
+}
+
+fn gauge(page: &mut String, name: &str, help: &str, value: f32) {
+    let _ = writeln!(page, "# HELP {} {}\n# TYPE {} gauge\n{} {}", name, help, name, name, value);
+}
+
+// Profiler stats are in microseconds, Prometheus wants seconds
+fn timings(page: &mut String, name: &str, help: &str, stats: FrameStats) {
+    let _ = writeln!(page, "# HELP {} {}\n# TYPE {} gauge", name, help, name);
+    for (stat, micros) in [("mean", stats.mean), ("p95", stats.p95), ("p99", stats.p99)] {
+        let _ = writeln!(page, "{}{{stat=\"{}\"}} {}", name, stat, micros / 1e6);
+    }
+}
+
+// Answer a single request and close the connection
+fn respond(mut stream: TcpStream, page: &Mutex<String>) -> io::Result<()> {
+    stream.set_read_timeout(Some(CLIENT_TIMEOUT))?;
+    stream.set_write_timeout(Some(CLIENT_TIMEOUT))?;
+    let mut request_line = String::new();
+    let mut reader = BufReader::new(&stream);
+    reader.read_line(&mut request_line)?;
+    // Skip the headers, nothing in them changes the answer
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 2 {
+        header.clear();
+    }
+
+    let mut parts = request_line.split_whitespace();
+    let (status, content_type, body) = match (parts.next(), parts.next()) {
+        (Some("GET"), Some("/metrics")) => {
+            let body = page.lock().map(|page| page.clone()).unwrap_or_default();
+            ("200 OK", "text/plain; version=0.0.4", body)
+        }
+        (Some("GET"), Some(_)) => ("404 Not Found", "text/plain", "Not found, try /metrics\n".to_string()),
+        _ => ("405 Method Not Allowed", "text/plain", "Only GET is supported\n".to_string()),
+    };
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        content_type,
+        body.len(),
+        body
+    )
+}
@@ -0,0 +1,284 @@
+// python.rs
+// Python bindings, built with `maturin develop` (see pyproject.toml):
+//
+//     import boids
+//     sim = boids.Simulation(500, boids.SimParams(cohesion=0.08), seed=1)
+//     sim.step(100)
+//     xy = sim.positions()   # numpy array, one (x, y) row per boid
+//     sim.set_parameter("visual_range", 1.5)
+//
+// The flock is stepped by the same Simulation as the visualizer, so every
+// subsystem it runs and every parameter from boids.toml applies here too.
+use crate::behavior;
+use crate::boid::{Boid, AVOID_FACTOR, CENTERING_FACTOR, MATCHING_FACTOR, TRAIT_VARIANCE, WANDER_STRENGTH};
+use crate::epidemic::INFECTION_PROBABILITY;
+use crate::food::FOOD_RATE;
+use crate::simulation;
+use crate::spawn::SpawnPattern;
+use crate::waypoints::GOAL_WEIGHT;
+use crate::world::{ResizeMode, World};
+use crate::zones::EMIT_RATE;
+use numpy::PyArray2;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+// The [parameters] from boids.toml plus the stepping settings, all read and
+// writable from Python
+#[pyclass]
+#[derive(Debug, Clone)]
+pub struct SimParams {
+    #[pyo3(get, set)]
+    pub separation: f32,
+    #[pyo3(get, set)]
+    pub cohesion: f32,
+    #[pyo3(get, set)]
+    pub alignment: f32,
+    #[pyo3(get, set)]
+    pub wander: f32,
+    #[pyo3(get, set)]
+    pub goal_weight: f32,
+    #[pyo3(get, set)]
+    pub emit_rate: f32,
+    #[pyo3(get, set)]
+    pub food_rate: f32,
+    #[pyo3(get, set)]
+    pub infection_probability: f32,
+    #[pyo3(get, set)]
+    pub trait_variance: f32,
+    #[pyo3(get, set)]
+    pub speed: f32, // Multiplier on the speed limit
+    #[pyo3(get, set)]
+    pub visual_range: f32, // Multiplier on how far boids see
+    #[pyo3(get, set)]
+    pub long_range: f32, // Pull between distant sub-flocks
+    #[pyo3(get, set)]
+    pub neighbor_cap: usize, // Most neighbors each boid looks at, 0 for all
+    #[pyo3(get, set)]
+    pub tick: f32, // Simulated seconds per step
+    #[pyo3(get, set)]
+    pub width: f32,
+    #[pyo3(get, set)]
+    pub height: f32,
+}
+
+impl SimParams {
+    // Every parameter by its boids.toml name
+    fn named(&self) -> [(&'static str, f32); 13] {
+        [
+            ("separation", self.separation),
+            ("cohesion", self.cohesion),
+            ("alignment", self.alignment),
+            ("wander", self.wander),
+            ("goal_weight", self.goal_weight),
+            ("emit_rate", self.emit_rate),
+            ("food_rate", self.food_rate),
+            ("infection_probability", self.infection_probability),
+            ("trait_variance", self.trait_variance),
+            ("speed", self.speed),
+            ("visual_range", self.visual_range),
+            ("long_range", self.long_range),
+            ("neighbor_cap", self.neighbor_cap as f32),
+        ]
+    }
+}
+
+#[pymethods]
+impl SimParams {
+    #[new]
+    #[pyo3(signature = (
+        separation = AVOID_FACTOR,
+        cohesion = CENTERING_FACTOR,
+        alignment = MATCHING_FACTOR,
+        wander = WANDER_STRENGTH,
+        goal_weight = GOAL_WEIGHT,
+        emit_rate = EMIT_RATE,
+        food_rate = FOOD_RATE,
+        infection_probability = INFECTION_PROBABILITY,
+        trait_variance = TRAIT_VARIANCE,
+        speed = 1.0,
+        visual_range = 1.0,
+        long_range = 0.0,
+        neighbor_cap = 0,
+        tick = 1.0 / 60.0,
+        width = 1600.0,
+        height = 1200.0,
+    ))]
+    #[allow(clippy::too_many_arguments)]
+    fn new(
+        separation: f32,
+        cohesion: f32,
+        alignment: f32,
+        wander: f32,
+        goal_weight: f32,
+        emit_rate: f32,
+        food_rate: f32,
+        infection_probability: f32,
+        trait_variance: f32,
+        speed: f32,
+        visual_range: f32,
+        long_range: f32,
+        neighbor_cap: usize,
+        tick: f32,
+        width: f32,
+        height: f32,
+    ) -> Self {
+        SimParams {
+            separation,
+            cohesion,
+            alignment,
+            wander,
+            goal_weight,
+            emit_rate,
+            food_rate,
+            infection_probability,
+            trait_variance,
+            speed,
+            visual_range,
+            long_range,
+            neighbor_cap,
+            tick,
+            width,
+            height,
+        }
+    }
+
+    fn __repr__(&self) -> String {
+        format!("{:?}", self)
+    }
+}
+
+// A simulated world and how long each step lasts
+#[pyclass]
+pub struct Simulation {
+    sim: simulation::Simulation,
+    tick: f32,
+}
+
+impl Simulation {
+    // Apply `params`, resizing the world if its size changed
+    fn apply(&mut self, params: &SimParams) -> PyResult<()> {
+        if (params.width, params.height) != (self.sim.world.width, self.sim.world.height) {
+            self.sim
+                .resize(params.width, params.height, ResizeMode::Rescale)
+                .map_err(PyValueError::new_err)?;
+        }
+        for (name, value) in params.named() {
+            self.sim.set_parameter(name, value);
+        }
+        self.tick = params.tick;
+        Ok(())
+    }
+
+    // Rows of two values per boid, as a numpy array
+    fn pairs<'py>(&self, py: Python<'py>, pair: impl Fn(&Boid) -> [f32; 2]) -> PyResult<&'py PyArray2<f32>> {
+        let flat: Vec<f32> = self.sim.boids.iter().flat_map(pair).collect();
+        Ok(numpy::PyArray1::from_vec(py, flat).reshape([self.sim.boids.len(), 2])?)
+    }
+}
+
+#[pymethods]
+impl Simulation {
+    #[new]
+    #[pyo3(signature = (count = 500, params = None, seed = 0))]
+    fn new(count: usize, params: Option<SimParams>, seed: u64) -> PyResult<Self> {
+        let params = params.unwrap_or_else(|| {
+            SimParams::new(
+                AVOID_FACTOR,
+                CENTERING_FACTOR,
+                MATCHING_FACTOR,
+                WANDER_STRENGTH,
+                GOAL_WEIGHT,
+                EMIT_RATE,
+                FOOD_RATE,
+                INFECTION_PROBABILITY,
+                TRAIT_VARIANCE,
+                1.0,
+                1.0,
+                0.0,
+                0,
+                1.0 / 60.0,
+                1600.0,
+                1200.0,
+            )
+        });
+        if !World::is_valid_size(params.width, params.height) {
+            return Err(PyValueError::new_err(format!("can't simulate a {}x{} world", params.width, params.height)));
+        }
+
+        let world = World::new(params.width, params.height);
+        let mut simulation = Simulation { sim: simulation::Simulation::new(world, seed), tick: params.tick };
+        simulation.apply(&params)?;
+        simulation.sim.respawn(count, SpawnPattern::Uniform);
+        Ok(simulation)
+    }
+
+    // The current parameters. Changes only take effect once assigned back.
+    #[getter]
+    fn params(&self) -> SimParams {
+        let sim = &self.sim;
+        let weight = |name: &str| behavior::weight(&sim.behaviors, name).unwrap_or(0.0);
+        SimParams {
+            separation: weight("separation"),
+            cohesion: weight("cohesion"),
+            alignment: weight("alignment"),
+            wander: sim.wander_strength,
+            goal_weight: sim.waypoints.weight,
+            emit_rate: sim.zones.rate,
+            food_rate: sim.food.rate,
+            infection_probability: sim.epidemic.probability,
+            trait_variance: sim.trait_variance,
+            speed: sim.speed_scale,
+            visual_range: sim.range_scale,
+            long_range: sim.long_range.strength,
+            neighbor_cap: sim.neighbor_cap.unwrap_or(0),
+            tick: self.tick,
+            width: sim.world.width,
+            height: sim.world.height,
+        }
+    }
+
+    #[setter]
+    fn set_params(&mut self, params: SimParams) -> PyResult<()> {
+        self.apply(&params)
+    }
+
+    // Set one parameter by its boids.toml name
+    fn set_parameter(&mut self, name: &str, value: f32) -> PyResult<()> {
+        if self.sim.set_parameter(name, value) {
+            Ok(())
+        } else {
+            Err(PyValueError::new_err(format!("unknown parameter '{}'", name)))
+        }
+    }
+
+    // Advance the flock by `steps` ticks
+    #[pyo3(signature = (steps = 1))]
+    fn step(&mut self, steps: usize) {
+        // No cursor to avoid, so put it far outside the world
+        let cursor = glam::vec2(-1.0e6, -1.0e6);
+        for _ in 0..steps {
+            self.sim.step(self.tick, cursor);
+        }
+    }
+
+    // Array of shape (boids, 2) with every boid's x and y
+    fn positions<'py>(&self, py: Python<'py>) -> PyResult<&'py PyArray2<f32>> {
+        self.pairs(py, |boid| [boid.x, boid.y])
+    }
+
+    // Array of shape (boids, 2) with every boid's velocity, pixels per second
+    fn velocities<'py>(&self, py: Python<'py>) -> PyResult<&'py PyArray2<f32>> {
+        self.pairs(py, |boid| [boid.dx, boid.dy])
+    }
+
+    fn __len__(&self) -> usize {
+        self.sim.boids.len()
+    }
+}
+
+#[pymodule]
+fn boids(_py: Python, module: &PyModule) -> PyResult<()> {
+    module.add_class::<SimParams>()?;
+    module.add_class::<Simulation>()?;
+    Ok(())
+}
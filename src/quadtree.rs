@@ -0,0 +1,325 @@
+// quadtree.rs
+use crate::boid::Boid;
+use crate::neighbor_index::NeighborIndex;
+
+// Tuning constants for the tree shape
+const NODE_CAPACITY: usize = 16; // Points per leaf before it splits
+const MAX_DEPTH: usize = 10;     // Stops runaway splitting when boids stack up
+
+#[derive(Debug, Clone, Copy)]
+struct Bounds {
+    x: f32,
+    y: f32,
+    w: f32,
+    h: f32,
+}
+
+impl Bounds {
+    fn center(&self) -> (f32, f32) {
+        (self.x + self.w / 2.0, self.y + self.h / 2.0)
+    }
+
+    // Nodes on the edge of the tree also hold boids that drifted outside the
+    // root bounds, so the overlap test treats the outer edges as open
+    fn overlaps(&self, root: &Bounds, min_x: f32, min_y: f32, max_x: f32, max_y: f32) -> bool {
+        let left = if self.x <= root.x { f32::NEG_INFINITY } else { self.x };
+        let top = if self.y <= root.y { f32::NEG_INFINITY } else { self.y };
+        let right = if self.x + self.w >= root.x + root.w { f32::INFINITY } else { self.x + self.w };
+        let bottom = if self.y + self.h >= root.y + root.h { f32::INFINITY } else { self.y + self.h };
+
+        min_x <= right && max_x >= left && min_y <= bottom && max_y >= top
+    }
+}
+
+#[derive(Clone)]
+struct Node {
+    bounds: Bounds,
+    depth: usize,
+    // Index of the first of four consecutive children in `nodes`
+    children: Option<usize>,
+    points: Vec<(usize, f32, f32)>,
+}
+
+impl Node {
+    fn new(bounds: Bounds, depth: usize) -> Self {
+        Node {
+            bounds,
+            depth,
+            children: None,
+            points: Vec::new(),
+        }
+    }
+
+    // Quadrant (0..4) a point belongs to, ordered NW, NE, SW, SE
+    fn quadrant(&self, x: f32, y: f32) -> usize {
+        let (cx, cy) = self.bounds.center();
+        let east = (x >= cx) as usize;
+        let south = (y >= cy) as usize;
+        south * 2 + east
+    }
+}
+
+// Adaptive quadtree for neighbor lookups. Unlike the uniform grid it splits
+// only where boids actually are, so dense clusters stay cheap to query.
+#[derive(Clone)]
+pub struct QuadTree {
+    nodes: Vec<Node>,
+    bounds: Bounds,
+}
+
+impl QuadTree {
+    pub fn new(window_width: f32, window_height: f32) -> Self {
+        let bounds = Bounds {
+            x: 0.0,
+            y: 0.0,
+            w: window_width,
+            h: window_height,
+        };
+
+        QuadTree {
+            nodes: vec![Node::new(bounds, 0)],
+            bounds,
+        }
+    }
+
+    fn subdivide(&mut self, node_idx: usize) {
+        let Bounds { x, y, w, h } = self.nodes[node_idx].bounds;
+        let depth = self.nodes[node_idx].depth + 1;
+        let (hw, hh) = (w / 2.0, h / 2.0);
+
+        let first_child = self.nodes.len();
+        self.nodes.push(Node::new(Bounds { x, y, w: hw, h: hh }, depth));
+        self.nodes.push(Node::new(Bounds { x: x + hw, y, w: hw, h: hh }, depth));
+        self.nodes.push(Node::new(Bounds { x, y: y + hh, w: hw, h: hh }, depth));
+        self.nodes.push(Node::new(Bounds { x: x + hw, y: y + hh, w: hw, h: hh }, depth));
+        self.nodes[node_idx].children = Some(first_child);
+
+        // Push the points down into the new children
+        let points = std::mem::take(&mut self.nodes[node_idx].points);
+        for (i, px, py) in points {
+            let child = first_child + self.nodes[node_idx].quadrant(px, py);
+            self.nodes[child].points.push((i, px, py));
+        }
+    }
+
+    // Stored points inside the rectangle from `min` to `max`
+    fn points_in_rect(&self, min: glam::Vec2, max: glam::Vec2) -> Vec<(usize, f32, f32)> {
+        let mut found = Vec::new();
+
+        let (min_x, min_y) = (min.x, min.y);
+        let (max_x, max_y) = (max.x, max.y);
+
+        // Iterative traversal to avoid recursion overhead
+        let mut stack = vec![0];
+        while let Some(node_idx) = stack.pop() {
+            let node = &self.nodes[node_idx];
+            if !node.bounds.overlaps(&self.bounds, min_x, min_y, max_x, max_y) {
+                continue;
+            }
+
+            match node.children {
+                Some(first_child) => stack.extend(first_child..first_child + 4),
+                None => {
+                    for &(i, px, py) in &node.points {
+                        if px >= min_x && px <= max_x && py >= min_y && py <= max_y {
+                            found.push((i, px, py));
+                        }
+                    }
+                }
+            }
+        }
+
+        found
+    }
+}
+
+impl NeighborIndex for QuadTree {
+    fn clear(&mut self) {
+        self.nodes.clear();
+        self.nodes.push(Node::new(self.bounds, 0));
+    }
+
+    fn insert(&mut self, boid_index: usize, boid: &Boid) {
+        // Walk down to the leaf containing this point
+        let mut node_idx = 0;
+        while let Some(first_child) = self.nodes[node_idx].children {
+            node_idx = first_child + self.nodes[node_idx].quadrant(boid.x, boid.y);
+        }
+
+        self.nodes[node_idx].points.push((boid_index, boid.x, boid.y));
+
+        let node = &self.nodes[node_idx];
+        if node.points.len() > NODE_CAPACITY && node.depth < MAX_DEPTH {
+            self.subdivide(node_idx);
+        }
+    }
+
+    fn get_neighbors(&self, boid: &Boid, range: f32) -> Vec<usize> {
+        let pos = glam::vec2(boid.x, boid.y);
+        self.query_rect(pos - glam::Vec2::splat(range), pos + glam::Vec2::splat(range))
+    }
+
+    fn query_rect(&self, min: glam::Vec2, max: glam::Vec2) -> Vec<usize> {
+        self.points_in_rect(min, max).into_iter().map(|(i, _, _)| i).collect()
+    }
+
+    fn query_radius(&self, pos: glam::Vec2, radius: f32) -> Vec<(usize, f32)> {
+        let corner = glam::Vec2::splat(radius);
+        self.points_in_rect(pos - corner, pos + corner)
+            .into_iter()
+            .map(|(i, px, py)| (i, pos.distance_squared(glam::vec2(px, py))))
+            .filter(|&(_, squared_distance)| squared_distance < radius * radius)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    fn tree_with(points: &[glam::Vec2]) -> QuadTree {
+        let mut boid = Boid::new(100.0, 100.0, &mut StdRng::seed_from_u64(0));
+        let mut tree = QuadTree::new(100.0, 100.0);
+        for (i, point) in points.iter().enumerate() {
+            boid.x = point.x;
+            boid.y = point.y;
+            tree.insert(i, &boid);
+        }
+        tree
+    }
+
+    // Enough points in one corner to split it several levels deep
+    fn crowded_corner() -> Vec<glam::Vec2> {
+        (0..64).map(|i| glam::vec2((i % 8) as f32 * 3.0, (i / 8) as f32 * 3.0)).collect()
+    }
+
+    #[test]
+    fn splits_full_leaves() {
+        let tree = tree_with(&crowded_corner());
+
+        assert!(tree.nodes.len() > 1);
+        assert!(tree.nodes.iter().all(|node| node.points.len() <= NODE_CAPACITY || node.depth == MAX_DEPTH));
+    }
+
+    #[test]
+    fn radius_query_is_exact() {
+        let tree = tree_with(&[glam::vec2(50.0, 50.0), glam::vec2(53.0, 54.0), glam::vec2(58.0, 50.0)]);
+
+        let mut found = tree.query_radius(glam::vec2(50.0, 50.0), 6.0);
+        found.sort_by_key(|&(i, _)| i);
+        assert_eq!(found, vec![(0, 0.0), (1, 25.0)]);
+    }
+
+    #[test]
+    fn points_on_quadrant_boundaries_are_found_from_both_sides() {
+        let mut points = crowded_corner();
+        points.extend(&[glam::vec2(50.0, 50.0), glam::vec2(25.0, 10.0), glam::vec2(10.0, 25.0)]);
+        let tree = tree_with(&points);
+
+        for &point in &points[64..] {
+            let index = points.iter().position(|&p| p == point).unwrap();
+            for &side in &[glam::vec2(-1.0, 0.0), glam::vec2(1.0, 0.0), glam::vec2(0.0, -1.0), glam::vec2(0.0, 1.0)] {
+                let found = tree.query_radius(point + side, 1.5);
+                assert!(found.iter().any(|&(i, _)| i == index), "missed {:?} from {:?}", point, point + side);
+            }
+        }
+    }
+
+    #[test]
+    fn points_outside_the_root_land_in_edge_nodes() {
+        let mut points = crowded_corner();
+        points.extend(&[glam::vec2(-20.0, 50.0), glam::vec2(150.0, 150.0)]);
+        let tree = tree_with(&points);
+
+        assert_eq!(tree.query_rect(glam::vec2(-30.0, 40.0), glam::vec2(-10.0, 60.0)), vec![64]);
+        assert_eq!(tree.query_rect(glam::vec2(140.0, 140.0), glam::vec2(160.0, 160.0)), vec![65]);
+    }
+
+    #[test]
+    fn rect_query_includes_its_edges() {
+        let tree = tree_with(&[glam::vec2(10.0, 10.0), glam::vec2(40.0, 40.0), glam::vec2(85.0, 85.0)]);
+
+        let mut found = tree.query_rect(glam::vec2(10.0, 10.0), glam::vec2(40.0, 40.0));
+        found.sort_unstable();
+        assert_eq!(found, vec![0, 1]);
+    }
+
+    #[test]
+    fn clear_empties_the_tree() {
+        let mut tree = tree_with(&crowded_corner());
+        tree.clear();
+
+        assert_eq!(tree.nodes.len(), 1);
+        assert!(tree.query_rect(glam::vec2(-10.0, -10.0), glam::vec2(110.0, 110.0)).is_empty());
+    }
+
+    mod properties {
+        use super::*;
+        use proptest::prelude::*;
+
+        // Mostly anywhere in or just around the 100x100 root, but often exactly
+        // on a quadrant boundary a few levels down or a window edge
+        fn coordinate() -> impl Strategy<Value = f32> {
+            prop_oneof![
+                -5.0f32..105.0,
+                (0u32..=16).prop_map(|step| step as f32 * 6.25),
+                prop_oneof![Just(0.0f32), Just(99.999), Just(100.0), Just(-0.001)],
+            ]
+        }
+
+        fn point() -> impl Strategy<Value = glam::Vec2> {
+            (coordinate(), coordinate()).prop_map(|(x, y)| glam::vec2(x, y))
+        }
+
+        fn within(points: &[glam::Vec2], center: glam::Vec2, range: f32) -> Vec<usize> {
+            (0..points.len())
+                .filter(|&i| points[i].distance_squared(center) < range * range)
+                .collect()
+        }
+
+        fn inside(points: &[glam::Vec2], min: glam::Vec2, max: glam::Vec2) -> Vec<usize> {
+            (0..points.len())
+                .filter(|&i| points[i].cmpge(min).all() && points[i].cmple(max).all())
+                .collect()
+        }
+
+        proptest! {
+            #[test]
+            fn radius_query_matches_brute_force(
+                points in prop::collection::vec(point(), 1..300),
+                center in point(),
+                range in 1.0f32..40.0,
+            ) {
+                let tree = tree_with(&points);
+                let mut found: Vec<usize> = tree.query_radius(center, range).into_iter().map(|(i, _)| i).collect();
+                found.sort_unstable();
+
+                prop_assert_eq!(found, within(&points, center, range));
+            }
+
+            #[test]
+            fn rect_query_matches_brute_force(
+                points in prop::collection::vec(point(), 1..300),
+                corner in point(),
+                other in point(),
+            ) {
+                let (min, max) = (corner.min(other), corner.max(other));
+                let tree = tree_with(&points);
+                let mut found = tree.query_rect(min, max);
+                found.sort_unstable();
+
+                prop_assert_eq!(found, inside(&points, min, max));
+            }
+
+            #[test]
+            fn every_point_is_stored_once(points in prop::collection::vec(point(), 0..300)) {
+                let tree = tree_with(&points);
+                let mut found = tree.query_rect(glam::vec2(-10.0, -10.0), glam::vec2(110.0, 110.0));
+                found.sort_unstable();
+
+                prop_assert_eq!(found, (0..points.len()).collect::<Vec<_>>());
+            }
+        }
+    }
+}
@@ -0,0 +1,174 @@
+// recorder.rs
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::sync::mpsc::{self, Receiver, SyncSender, TrySendError};
+use std::thread::{self, JoinHandle};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::{error, info};
+
+// Recording settings
+pub const RECORDINGS_DIR: &str = "recordings";
+const RECORDING_FPS: u32 = 30;
+const FRAME_QUEUE_LENGTH: usize = 120; // Frames buffered before new ones get dropped
+
+// A captured frame, tightly packed RGBA rows from top to bottom
+pub struct Frame {
+    pub width: u32,
+    pub height: u32,
+    pub rgba: Vec<u8>,
+}
+
+// Where frames end up, decided by the worker when the first frame arrives
+enum Sink {
+    Png { dir: PathBuf, next_index: usize },
+    Ffmpeg { child: Child },
+}
+
+impl Sink {
+    fn open(name: &str, frame: &Frame) -> io::Result<Sink> {
+        fs::create_dir_all(RECORDINGS_DIR)?;
+
+        // Prefer a single video file when ffmpeg is installed
+        if ffmpeg_available() {
+            let path = Path::new(RECORDINGS_DIR).join(format!("{}.mp4", name));
+            let child = Command::new("ffmpeg")
+                .args(&["-y", "-loglevel", "error", "-f", "rawvideo", "-pixel_format", "rgba"])
+                .args(&["-video_size", &format!("{}x{}", frame.width, frame.height)])
+                .args(&["-framerate", &RECORDING_FPS.to_string(), "-i", "-"])
+                .args(&["-pix_fmt", "yuv420p"])
+                .arg(&path)
+                .stdin(Stdio::piped())
+                .spawn()?;
+            info!("Recording video to {}", path.display());
+            return Ok(Sink::Ffmpeg { child });
+        }
+
+        let dir = Path::new(RECORDINGS_DIR).join(name);
+        fs::create_dir_all(&dir)?;
+        info!("Recording PNG frames to {}", dir.display());
+        Ok(Sink::Png { dir, next_index: 0 })
+    }
+
+    fn write(&mut self, frame: &Frame) -> io::Result<()> {
+        match self {
+            Sink::Png { dir, next_index } => {
+                let path = dir.join(format!("frame_{:06}.png", next_index));
+                *next_index += 1;
+                image::save_buffer(&path, &frame.rgba, frame.width, frame.height, image::ColorType::Rgba8)
+                    .map_err(|err| io::Error::new(io::ErrorKind::Other, err.to_string()))
+            }
+            Sink::Ffmpeg { child } => match child.stdin.as_mut() {
+                Some(stdin) => stdin.write_all(&frame.rgba),
+                None => Err(io::Error::new(io::ErrorKind::BrokenPipe, "ffmpeg stdin closed")),
+            },
+        }
+    }
+
+    fn close(self) -> io::Result<()> {
+        if let Sink::Ffmpeg { mut child } = self {
+            // Closing stdin lets ffmpeg finish writing the file
+            drop(child.stdin.take());
+            child.wait()?;
+        }
+        Ok(())
+    }
+}
+
+fn ffmpeg_available() -> bool {
+    Command::new("ffmpeg")
+        .arg("-version")
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .map(|status| status.success())
+        .unwrap_or(false)
+}
+
+// Records rendered frames on a background thread so encoding never stalls the
+// simulation. If the writer falls behind, frames are dropped rather than queued
+// without bound.
+pub struct Recorder {
+    sender: Option<SyncSender<Frame>>,
+    worker: Option<JoinHandle<()>>,
+    pub frames: usize,
+    pub dropped: usize,
+}
+
+impl Recorder {
+    pub fn start() -> Recorder {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs())
+            .unwrap_or(0);
+        let name = format!("boids-{}", timestamp);
+
+        let (sender, receiver) = mpsc::sync_channel(FRAME_QUEUE_LENGTH);
+        let worker = thread::spawn(move || write_frames(&name, receiver));
+
+        Recorder {
+            sender: Some(sender),
+            worker: Some(worker),
+            frames: 0,
+            dropped: 0,
+        }
+    }
+
+    // Queue a frame for writing without blocking
+    pub fn push(&mut self, frame: Frame) {
+        if let Some(sender) = &self.sender {
+            match sender.try_send(frame) {
+                Ok(()) => self.frames += 1,
+                Err(TrySendError::Full(_)) => self.dropped += 1,
+                Err(TrySendError::Disconnected(_)) => {
+                    // The worker gave up after an error, stop feeding it
+                    self.sender = None;
+                }
+            }
+        }
+    }
+
+    // Flush the remaining frames and wait for the writer to finish
+    pub fn stop(&mut self) {
+        self.sender = None;
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
+
+impl Drop for Recorder {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+fn write_frames(name: &str, receiver: Receiver<Frame>) {
+    let mut sink: Option<Sink> = None;
+
+    for frame in receiver {
+        if sink.is_none() {
+            match Sink::open(name, &frame) {
+                Ok(opened) => sink = Some(opened),
+                Err(err) => {
+                    error!("Failed to start recording: {}", err);
+                    return;
+                }
+            }
+        }
+
+        if let Some(sink) = sink.as_mut() {
+            if let Err(err) = sink.write(&frame) {
+                error!("Failed to write frame: {}", err);
+                return;
+            }
+        }
+    }
+
+    if let Some(sink) = sink {
+        if let Err(err) = sink.close() {
+            error!("Failed to finish recording: {}", err);
+        }
+    }
+}
@@ -0,0 +1,73 @@
+// render.rs
+use crate::boid::Boid;
+use crate::world::World;
+#[cfg(feature = "render-ggez")]
+use ggez::graphics;
+
+// What a front end provides to show the flock. The terminal (--tui),
+// macroquad and ggez front ends implement it; the ggez visualizer draws its
+// many overlays itself.
+pub trait Renderer {
+    // Start a frame that shows all of `world`
+    fn begin_frame(&mut self, world: &World);
+
+    // One boid at `pos`, pointing along `heading` in radians
+    fn draw_boid(&mut self, pos: glam::Vec2, heading: f32, color: [f32; 4]);
+
+    // A line of status text for the corner of the view
+    fn draw_status(&mut self, text: &str);
+
+    fn end_frame(&mut self);
+}
+
+// Draw every boid in its own color and the status line as one frame
+pub fn draw_flock(renderer: &mut impl Renderer, boids: &[Boid], world: &World, status: &str) {
+    draw_boids(renderer, boids, 0..boids.len(), |i| boids[i].color, world, status);
+}
+
+// draw_flock for only the boids at the `visible` indices, each in `color(index)`
+pub fn draw_boids(
+    renderer: &mut impl Renderer,
+    boids: &[Boid],
+    visible: impl IntoIterator<Item = usize>,
+    color: impl Fn(usize) -> [f32; 4],
+    world: &World,
+    status: &str,
+) {
+    renderer.begin_frame(world);
+    for i in visible {
+        let boid = &boids[i];
+        renderer.draw_boid(glam::vec2(boid.x, boid.y), boid.heading, color(i));
+    }
+    renderer.draw_status(status);
+    renderer.end_frame();
+}
+
+// Fills an instance array for drawing one boid mesh many times, as the ggez
+// visualizer does
+#[cfg(feature = "render-ggez")]
+pub struct GgezRenderer<'a> {
+    pub instances: &'a mut graphics::InstanceArray,
+}
+
+#[cfg(feature = "render-ggez")]
+impl Renderer for GgezRenderer<'_> {
+    fn begin_frame(&mut self, _world: &World) {
+        self.instances.clear();
+    }
+
+    fn draw_boid(&mut self, pos: glam::Vec2, heading: f32, color: [f32; 4]) {
+        // The mesh points up the screen, a quarter turn from a heading of zero
+        self.instances.push(
+            graphics::DrawParam::new()
+                .dest(pos)
+                .rotation(heading + std::f32::consts::FRAC_PI_2)
+                .color(color),
+        );
+    }
+
+    // The visualizer's HUD shows the status
+    fn draw_status(&mut self, _text: &str) {}
+
+    fn end_frame(&mut self) {}
+}
@@ -0,0 +1,108 @@
+// rewind.rs
+use crate::boid::Boid;
+use std::collections::VecDeque;
+
+// Rewind settings
+pub const REWIND_SECONDS: f32 = 10.0;             // History kept by default
+pub const REWIND_MEMORY_MB: usize = 256;          // Upper bound on snapshot memory
+const SNAPSHOT_INTERVAL: f32 = 1.0 / 30.0;        // Simulation seconds between snapshots
+
+// Ring buffer of past boid states for scrubbing backwards through time
+pub struct RewindBuffer {
+    snapshots: VecDeque<Vec<Boid>>,
+    max_seconds: f32,
+    max_bytes: usize,
+    since_snapshot: f32, // Simulation time since the last snapshot was taken
+    rewind_time: f32,    // Real time spent rewinding not yet consumed
+}
+
+impl RewindBuffer {
+    pub fn new(max_seconds: f32, max_megabytes: usize) -> Self {
+        RewindBuffer {
+            snapshots: VecDeque::new(),
+            max_seconds,
+            max_bytes: max_megabytes * 1024 * 1024,
+            since_snapshot: 0.0,
+            rewind_time: 0.0,
+        }
+    }
+
+    // An empty buffer with the same limits, for another simulation
+    pub fn empty_copy(&self) -> Self {
+        RewindBuffer {
+            snapshots: VecDeque::new(),
+            max_seconds: self.max_seconds,
+            max_bytes: self.max_bytes,
+            since_snapshot: 0.0,
+            rewind_time: 0.0,
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.snapshots.clear();
+        self.since_snapshot = 0.0;
+        self.rewind_time = 0.0;
+    }
+
+    // Seconds of history currently available
+    pub fn seconds(&self) -> f32 {
+        self.snapshots.len() as f32 * SNAPSHOT_INTERVAL
+    }
+
+    // Maximum snapshots allowed for the given population, bounded by both time and memory
+    fn capacity(&self, boid_count: usize) -> usize {
+        let by_time = (self.max_seconds / SNAPSHOT_INTERVAL).ceil() as usize;
+        let snapshot_bytes = (boid_count * std::mem::size_of::<Boid>()).max(1);
+        by_time.min(self.max_bytes / snapshot_bytes).max(1)
+    }
+
+    // Called after each simulation step with the simulated time that passed
+    pub fn record(&mut self, boids: &[Boid], sim_dt: f32) {
+        self.rewind_time = 0.0;
+        self.since_snapshot += sim_dt;
+        if self.since_snapshot < SNAPSHOT_INTERVAL && !self.snapshots.is_empty() {
+            return;
+        }
+        self.since_snapshot = 0.0;
+
+        // Reuse the oldest allocation once the buffer is full
+        let capacity = self.capacity(boids.len());
+        let mut snapshot = if self.snapshots.len() >= capacity {
+            self.snapshots.pop_front().unwrap_or_default()
+        } else {
+            Vec::with_capacity(boids.len())
+        };
+        while self.snapshots.len() >= capacity {
+            self.snapshots.pop_front();
+        }
+
+        snapshot.clear();
+        snapshot.extend_from_slice(boids);
+        self.snapshots.push_back(snapshot);
+    }
+
+    // Step backwards in time at real-time speed. Returns the state to restore,
+    // or None if not enough time has passed or the history is used up.
+    pub fn rewind(&mut self, dt: f32) -> Option<Vec<Boid>> {
+        self.rewind_time += dt;
+
+        // The newest remaining snapshot is always the state being shown, and
+        // the oldest one is kept so there is somewhere to resume from
+        let mut moved = false;
+        while self.rewind_time >= SNAPSHOT_INTERVAL && self.snapshots.len() > 1 {
+            self.rewind_time -= SNAPSHOT_INTERVAL;
+            self.snapshots.pop_back();
+            moved = true;
+        }
+
+        if self.snapshots.len() <= 1 {
+            self.rewind_time = 0.0;
+        }
+
+        if moved {
+            self.snapshots.back().cloned()
+        } else {
+            None
+        }
+    }
+}
@@ -0,0 +1,130 @@
+// scenario.rs
+use crate::config::Parameters;
+use crate::world::World;
+use std::fs;
+use std::path::Path;
+
+// Something a scenario does at a set time
+#[derive(Debug, Clone, PartialEq)]
+pub enum ScenarioEvent {
+    SpawnPredators(usize),
+    SpawnBoids(usize),
+    Count(usize),
+    World(f32, f32),           // New world size, the flock rescaled to fit
+    Wind(glam::Vec2),          // Pixels per second the air carries the boids
+    Set(String, f32),          // Any parameter from the [parameters] section of boids.toml
+    Reset,                     // A fresh flock, as if restarted from the menu
+    Pause,
+    Quit,
+}
+
+// Timed events loaded with --scenario, for demos and experiments that play
+// out the same way every run. One event per line, times in simulated seconds
+// from the start, blank lines and # comments ignored:
+//
+//     seed 42
+//     at 10s spawn 2 predators
+//     at 20s spawn 100 boids
+//     at 30s set wind 50 0
+//     at 40s set alignment 2.0
+//     at 50s count 500
+//     at 55s world 1600 900
+//     at 60s reset
+//     at 90s quit
+//
+// The optional seed line is used unless --seed is given. Time only passes
+// while the simulation is playing.
+pub struct Scenario {
+    pub seed: Option<u64>,
+    events: Vec<(f32, ScenarioEvent)>, // Sorted by time
+    next: usize,
+    time: f32,
+}
+
+impl Scenario {
+    pub fn load(path: &Path) -> Result<Scenario, String> {
+        let text = fs::read_to_string(path).map_err(|err| format!("{}: {}", path.display(), err))?;
+        Scenario::parse(&text).map_err(|err| format!("{}: {}", path.display(), err))
+    }
+
+    pub fn parse(text: &str) -> Result<Scenario, String> {
+        let mut seed = None;
+        let mut events = Vec::new();
+        for (number, line) in text.lines().enumerate() {
+            // Wind may be written as a tuple, (50, 0)
+            let line = line.split('#').next().unwrap_or_default();
+            let cleaned = line.replace(['(', ')', ','], " ");
+            let fields: Vec<&str> = cleaned.split_whitespace().collect();
+            let invalid = || format!("line {}: can't read '{}'", number + 1, line.trim());
+            match fields.as_slice() {
+                [] => {}
+                ["seed", value] => seed = Some(value.parse().map_err(|_| invalid())?),
+                ["at", time, event @ ..] => {
+                    let time = parse_time(time).ok_or_else(invalid)?;
+                    events.push((time, parse_event(event).ok_or_else(invalid)?));
+                }
+                _ => return Err(invalid()),
+            }
+        }
+        // Stable, so events at the same time keep their order in the file
+        events.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        Ok(Scenario { seed, events, next: 0, time: 0.0 })
+    }
+
+    // Move the clock on by `sim_dt` simulated seconds and return the events now due
+    pub fn advance(&mut self, sim_dt: f32) -> Vec<ScenarioEvent> {
+        self.time += sim_dt;
+        let due = self.events[self.next..].iter().take_while(|(time, _)| *time <= self.time).count();
+        let events = self.events[self.next..self.next + due].iter().map(|(_, event)| event.clone()).collect();
+        self.next += due;
+        events
+    }
+
+    pub fn time(&self) -> f32 {
+        self.time
+    }
+}
+
+// "10", "10s", "t=10s" or "1.5m"
+fn parse_time(text: &str) -> Option<f32> {
+    let text = text.strip_prefix("t=").unwrap_or(text);
+    let seconds = match text.strip_suffix('m') {
+        Some(minutes) => minutes.parse::<f32>().ok()? * 60.0,
+        None => text.strip_suffix('s').unwrap_or(text).parse::<f32>().ok()?,
+    };
+    if seconds.is_finite() && seconds >= 0.0 {
+        Some(seconds)
+    } else {
+        None
+    }
+}
+
+fn parse_event(fields: &[&str]) -> Option<ScenarioEvent> {
+    let event = match fields {
+        ["spawn", count, "predator" | "predators"] => ScenarioEvent::SpawnPredators(count.parse().ok()?),
+        ["spawn", count, "boid" | "boids"] => ScenarioEvent::SpawnBoids(count.parse().ok()?),
+        ["count", count] => ScenarioEvent::Count(count.parse().ok()?),
+        ["world", width, height] => {
+            let (width, height): (f32, f32) = (width.parse().ok()?, height.parse().ok()?);
+            if !World::is_valid_size(width, height) {
+                return None;
+            }
+            ScenarioEvent::World(width, height)
+        }
+        ["set", "wind", x, y] => ScenarioEvent::Wind(glam::vec2(x.parse().ok()?, y.parse().ok()?)),
+        ["set", name, value] => {
+            let value: f32 = value.parse().ok()?;
+            // Only names boids.toml knows
+            if !Parameters::default().set(name, value) {
+                return None;
+            }
+            ScenarioEvent::Set(name.to_string(), value)
+        }
+        ["reset"] => ScenarioEvent::Reset,
+        ["pause"] => ScenarioEvent::Pause,
+        ["quit"] => ScenarioEvent::Quit,
+        _ => return None,
+    };
+    Some(event)
+}
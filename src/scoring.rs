@@ -0,0 +1,166 @@
+// scoring.rs
+use crate::boid::Boid;
+use crate::world::World;
+#[cfg(feature = "render-ggez")]
+use ggez::{graphics, GameResult};
+use serde::Deserialize;
+
+#[cfg(feature = "render-ggez")]
+const ZONE_COLOR: [f32; 4] = [1.0, 0.9, 0.3, 0.7];
+
+// A [[scoring]] section of boids.toml, a rectangle counting the boids that
+// fly through it
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ScoringArea {
+    pub name: String,
+    pub min: [f32; 2], // Corners as fractions of the world size
+    pub max: [f32; 2],
+}
+
+// One of those rectangles fitted to the world, with its counts
+#[derive(Clone)]
+pub struct ScoringZone {
+    pub name: String,
+    area: (glam::Vec2, glam::Vec2), // Corners as fractions of the world size
+    min: glam::Vec2,                // Corners in world pixels
+    max: glam::Vec2,
+    pub inside: usize,              // Boids inside on the last step
+    pub entered: u64,               // Boids that flew in since the counts were cleared
+    pub dwell: f32,                 // Boid-seconds spent inside since then
+    was_inside: Vec<bool>,          // Per boid on the last step, to spot the entries
+}
+
+impl ScoringZone {
+    fn contains(&self, boid: &Boid) -> bool {
+        boid.x >= self.min.x && boid.x <= self.max.x && boid.y >= self.min.y && boid.y <= self.max.y
+    }
+
+    fn fit(&mut self, world: &World) {
+        let size = glam::vec2(world.width, world.height);
+        let (a, b) = self.area;
+        self.min = a.min(b) * size;
+        self.max = a.max(b) * size;
+    }
+}
+
+// Zones that count the boids passing through and dwelling inside, for the
+// HUD and telemetry. Nothing steers towards them.
+#[derive(Clone)]
+pub struct ScoringZones {
+    zones: Vec<ScoringZone>,
+}
+
+impl ScoringZones {
+    pub fn new() -> Self {
+        ScoringZones { zones: Vec::new() }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.zones.is_empty()
+    }
+
+    pub fn zones(&self) -> &[ScoringZone] {
+        &self.zones
+    }
+
+    // Take the zones from a (re)loaded config. Zones whose name and corners
+    // are unchanged keep counting.
+    pub fn set_areas(&mut self, areas: &[ScoringArea], world: &World) {
+        let mut old = std::mem::take(&mut self.zones);
+        for area in areas {
+            let corners = (glam::Vec2::from(area.min), glam::Vec2::from(area.max));
+            let zone = match old.iter().position(|zone| zone.name == area.name && zone.area == corners) {
+                Some(i) => old.swap_remove(i),
+                None => ScoringZone {
+                    name: area.name.clone(),
+                    area: corners,
+                    min: glam::Vec2::ZERO,
+                    max: glam::Vec2::ZERO,
+                    inside: 0,
+                    entered: 0,
+                    dwell: 0.0,
+                    was_inside: Vec::new(),
+                },
+            };
+            self.zones.push(zone);
+        }
+        self.resize(world);
+    }
+
+    pub fn resize(&mut self, world: &World) {
+        for zone in &mut self.zones {
+            zone.fit(world);
+            zone.was_inside.clear();
+        }
+    }
+
+    // Start counting from zero, for a new flock
+    pub fn clear(&mut self) {
+        for zone in &mut self.zones {
+            zone.inside = 0;
+            zone.entered = 0;
+            zone.dwell = 0.0;
+            zone.was_inside.clear();
+        }
+    }
+
+    // Follow boids being removed, `kept` has one flag per boid before the removal
+    pub fn retain_kept(&mut self, kept: &[bool]) {
+        for zone in &mut self.zones {
+            let mut flags = kept.iter();
+            zone.was_inside.retain(|_| flags.next().copied().unwrap_or(false));
+        }
+    }
+
+    // Count the boids inside after a step of `tick` seconds. Boids added since
+    // the last step aren't counted as entering, they were placed there.
+    pub fn update(&mut self, boids: &[Boid], tick: f32) {
+        for zone in &mut self.zones {
+            zone.was_inside.truncate(boids.len());
+            let known = zone.was_inside.len();
+            let mut inside = 0;
+            for (i, boid) in boids.iter().enumerate() {
+                let now = zone.contains(boid);
+                if i < known {
+                    if now && !zone.was_inside[i] {
+                        zone.entered += 1;
+                    }
+                    zone.was_inside[i] = now;
+                } else {
+                    zone.was_inside.push(now);
+                }
+                inside += now as usize;
+            }
+            zone.inside = inside;
+            zone.dwell += inside as f32 * tick;
+        }
+    }
+
+    // One line per zone for the HUD
+    pub fn hud_text(&self) -> Option<String> {
+        if self.zones.is_empty() {
+            return None;
+        }
+        let lines: Vec<String> = self
+            .zones
+            .iter()
+            .map(|zone| format!("{}: {} inside | {} entered | {:.0} boid-s", zone.name, zone.inside, zone.entered, zone.dwell))
+            .collect();
+        Some(lines.join("\n"))
+    }
+
+    #[cfg(feature = "render-ggez")]
+    pub fn draw(&self, mb: &mut graphics::MeshBuilder, line_width: f32) -> GameResult {
+        for zone in &self.zones {
+            let size = zone.max - zone.min;
+            if size.x <= 0.0 || size.y <= 0.0 {
+                continue;
+            }
+            let rect = graphics::Rect::new(zone.min.x, zone.min.y, size.x, size.y);
+            mb.rectangle(graphics::DrawMode::fill(), rect, [1.0, 0.9, 0.3, 0.06].into())?;
+            mb.rectangle(graphics::DrawMode::stroke(2.0 * line_width), rect, ZONE_COLOR.into())?;
+        }
+        Ok(())
+    }
+}
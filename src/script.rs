@@ -0,0 +1,159 @@
+// script.rs
+use crate::behavior::{SteeringBehavior, WeightedBehavior};
+use crate::boid::{Boid, MIN_DISTANCE};
+use crate::world::World;
+use rhai::{Dynamic, Engine, Map, Scope, AST};
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tracing::warn;
+
+// Scripting settings
+pub const SCRIPT_PATH: &str = "custom_behavior.rhai";
+const SCRIPT_WEIGHT: f32 = 1.0;
+const MAX_OPERATIONS: u64 = 100_000; // Per boid per step, so a runaway loop can't hang the window
+const ENTRY_POINT: &str = "steer";
+
+// A steering rule written in Rhai. The script defines
+//
+//     fn steer(boid, neighbors) { ... }
+//
+// where `boid` is #{x, y, dx, dy} and `neighbors` summarises the boids in
+// range as #{count, center_x, center_y, heading_x, heading_y, away_x, away_y}
+// (the average position and velocity, and the sum of offsets from the ones
+// closer than the minimum distance). It returns the force as [x, y] or #{x, y}.
+// Built with --features scripting. A gentle pull towards the neighbors:
+//
+//     fn steer(boid, neighbors) {
+//         [(neighbors.center_x - boid.x) * 0.02, (neighbors.center_y - boid.y) * 0.02]
+//     }
+pub struct ScriptedBehavior {
+    engine: Engine,
+    ast: AST,
+    failed: AtomicBool, // Runtime errors are logged once, not for every boid
+}
+
+impl ScriptedBehavior {
+    pub fn compile(source: &str) -> Result<ScriptedBehavior, String> {
+        let mut engine = Engine::new();
+        engine.set_max_operations(MAX_OPERATIONS);
+        let ast = engine.compile(source).map_err(|err| err.to_string())?;
+        if !ast.iter_functions().any(|function| function.name == ENTRY_POINT && function.params.len() == 2) {
+            return Err(format!("no {}(boid, neighbors) function", ENTRY_POINT));
+        }
+        Ok(ScriptedBehavior { engine, ast, failed: AtomicBool::new(false) })
+    }
+
+    fn call(&self, boid: &Boid, neighbors: &[usize], boids: &[Boid]) -> Result<glam::Vec2, String> {
+        let result: Dynamic = self
+            .engine
+            .call_fn(&mut Scope::new(), &self.ast, ENTRY_POINT, (boid_map(boid), neighbor_summary(boid, neighbors, boids)))
+            .map_err(|err| err.to_string())?;
+
+        let (x, y) = if let Some(array) = result.clone().try_cast::<rhai::Array>() {
+            match array.as_slice() {
+                [x, y] => (x.clone(), y.clone()),
+                _ => return Err(format!("{} returned an array of {} values, not [x, y]", ENTRY_POINT, array.len())),
+            }
+        } else if let Some(mut map) = result.try_cast::<Map>() {
+            match (map.remove("x"), map.remove("y")) {
+                (Some(x), Some(y)) => (x, y),
+                _ => return Err(format!("{} returned a map without x and y", ENTRY_POINT)),
+            }
+        } else {
+            return Err(format!("{} must return [x, y] or #{{x, y}}", ENTRY_POINT));
+        };
+        Ok(glam::vec2(number(&x)?, number(&y)?))
+    }
+}
+
+impl SteeringBehavior for ScriptedBehavior {
+    fn name(&self) -> &'static str {
+        "custom"
+    }
+
+    fn force(&self, boid: &Boid, neighbors: &[usize], boids: &[Boid], _world: &World) -> glam::Vec2 {
+        match self.call(boid, neighbors, boids) {
+            Ok(force) if force.is_finite() => force,
+            Ok(_) => glam::Vec2::ZERO,
+            Err(message) => {
+                if !self.failed.swap(true, Ordering::Relaxed) {
+                    warn!("{}: {}", SCRIPT_PATH, message);
+                }
+                glam::Vec2::ZERO
+            }
+        }
+    }
+}
+
+// Load the script into `behaviors`, replacing the version already there.
+// Returns whether a script is now running: a missing file removes it, a broken
+// one is an error and leaves the running version alone.
+pub fn reload(behaviors: &mut Vec<WeightedBehavior>, path: &Path) -> Result<bool, String> {
+    let scripted = match fs::read_to_string(path) {
+        Ok(source) => Some(ScriptedBehavior::compile(&source).map_err(|err| format!("{}: {}", path.display(), err))?),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => None,
+        Err(err) => return Err(format!("{}: {}", path.display(), err)),
+    };
+
+    let weight = crate::behavior::weight(behaviors, "custom").unwrap_or(SCRIPT_WEIGHT);
+    behaviors.retain(|weighted| weighted.behavior.name() != "custom");
+    let loaded = scripted.is_some();
+    if let Some(scripted) = scripted {
+        behaviors.push(WeightedBehavior::new(scripted, weight));
+    }
+    Ok(loaded)
+}
+
+fn boid_map(boid: &Boid) -> Map {
+    let mut map = Map::new();
+    map.insert("x".into(), Dynamic::from_float(boid.x as rhai::FLOAT));
+    map.insert("y".into(), Dynamic::from_float(boid.y as rhai::FLOAT));
+    map.insert("dx".into(), Dynamic::from_float(boid.dx as rhai::FLOAT));
+    map.insert("dy".into(), Dynamic::from_float(boid.dy as rhai::FLOAT));
+    map
+}
+
+fn neighbor_summary(boid: &Boid, neighbors: &[usize], boids: &[Boid]) -> Map {
+    let mut center = glam::Vec2::ZERO;
+    let mut heading = glam::Vec2::ZERO;
+    let mut away = glam::Vec2::ZERO;
+    for &idx in neighbors {
+        let other = &boids[idx];
+        center += glam::vec2(other.x, other.y);
+        heading += glam::vec2(other.dx, other.dy);
+        if boid.squared_distance(other) < MIN_DISTANCE * MIN_DISTANCE {
+            away += glam::vec2(boid.x - other.x, boid.y - other.y);
+        }
+    }
+    // With no neighbors the averages are the boid's own position and velocity
+    let (center, heading) = if neighbors.is_empty() {
+        (glam::vec2(boid.x, boid.y), glam::vec2(boid.dx, boid.dy))
+    } else {
+        (center / neighbors.len() as f32, heading / neighbors.len() as f32)
+    };
+
+    let mut map = Map::new();
+    map.insert("count".into(), Dynamic::from_int(neighbors.len() as rhai::INT));
+    for (name, value) in [
+        ("center_x", center.x),
+        ("center_y", center.y),
+        ("heading_x", heading.x),
+        ("heading_y", heading.y),
+        ("away_x", away.x),
+        ("away_y", away.y),
+    ] {
+        map.insert(name.into(), Dynamic::from_float(value as rhai::FLOAT));
+    }
+    map
+}
+
+// Scripts may return integers or floats
+fn number(value: &Dynamic) -> Result<f32, String> {
+    value
+        .as_float()
+        .map(|value| value as f32)
+        .or_else(|_| value.as_int().map(|value| value as f32))
+        .map_err(|type_name| format!("{} returned a {} where a number was expected", ENTRY_POINT, type_name))
+}
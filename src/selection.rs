@@ -0,0 +1,194 @@
+// selection.rs
+use crate::boid::Boid;
+use crate::neighbor_index::NeighborIndex;
+#[cfg(feature = "render-ggez")]
+use ggez::{graphics, GameResult};
+
+// Group selection settings
+const GOAL_WEIGHT: f32 = 8.0;       // Velocity change per step towards the group's goal
+const ARRIVAL_RADIUS: f32 = 40.0;   // Selected boids this close to the goal stop being pulled
+#[cfg(feature = "render-ggez")]
+const HIGHLIGHT_RADIUS: f32 = 10.0; // Pixels, ring drawn around every selected boid
+#[cfg(feature = "render-ggez")]
+const SELECTION_COLOR: [f32; 4] = [0.4, 1.0, 0.8, 0.8];
+
+// Boids picked by dragging a box with the left mouse button. Group actions
+// apply to them until a click on empty space clears the selection.
+#[derive(Clone)]
+pub struct Selection {
+    members: Vec<usize>,                    // Sorted boid indices
+    drag: Option<(glam::Vec2, glam::Vec2)>, // World corners of the box being dragged
+    goal: Option<glam::Vec2>,               // Where the group flies, set with a right click
+}
+
+impl Selection {
+    pub fn new() -> Self {
+        Selection {
+            members: Vec::new(),
+            drag: None,
+            goal: None,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.members.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.members.len()
+    }
+
+    pub fn members(&self) -> &[usize] {
+        &self.members
+    }
+
+    pub fn contains(&self, i: usize) -> bool {
+        self.members.binary_search(&i).is_ok()
+    }
+
+    pub fn clear(&mut self) {
+        self.members.clear();
+        self.goal = None;
+    }
+
+    // One flag per boid, set for the selected ones
+    pub fn mask(&self, count: usize) -> Vec<bool> {
+        let mut mask = vec![false; count];
+        for &i in self.members.iter().filter(|&&i| i < count) {
+            mask[i] = true;
+        }
+        mask
+    }
+
+    pub fn start_drag(&mut self, point: glam::Vec2) {
+        self.drag = Some((point, point));
+    }
+
+    pub fn drag_to(&mut self, point: glam::Vec2) {
+        if let Some((_, end)) = self.drag.as_mut() {
+            *end = point;
+        }
+    }
+
+    pub fn is_dragging(&self) -> bool {
+        self.drag.is_some()
+    }
+
+    // End the drag, selecting the boids inside the box if it is wider or taller
+    // than `min_size`. Returns whether it was big enough to count as a box.
+    pub fn finish_drag(&mut self, boids: &[Boid], index: &dyn NeighborIndex, min_size: f32) -> bool {
+        let (start, end) = match self.drag.take() {
+            Some(drag) => drag,
+            None => return false,
+        };
+        let (min, max) = (start.min(end), start.max(end));
+        if (max - min).max_element() < min_size {
+            return false;
+        }
+
+        // The index returns a superset, so the box is checked exactly
+        self.members = index.query_rect(min, max);
+        self.members.retain(|&i| {
+            boids.get(i).map_or(false, |boid| {
+                boid.x >= min.x && boid.x <= max.x && boid.y >= min.y && boid.y <= max.y
+            })
+        });
+        self.members.sort_unstable();
+        self.members.dedup();
+        self.goal = None;
+        true
+    }
+
+    pub fn set_goal(&mut self, point: glam::Vec2) {
+        self.goal = Some(point);
+    }
+
+    // Move the goal and the box being dragged with a world rescaled by `scale`
+    pub fn rescale(&mut self, scale: glam::Vec2) {
+        if let Some(goal) = self.goal.as_mut() {
+            *goal *= scale;
+        }
+        if let Some((start, end)) = self.drag.as_mut() {
+            *start *= scale;
+            *end *= scale;
+        }
+    }
+
+    // Follow boids being removed, `kept` has one flag per boid before the removal
+    pub fn retain_kept(&mut self, kept: &[bool]) {
+        if self.members.is_empty() {
+            return;
+        }
+        let mut new_index = Vec::with_capacity(kept.len());
+        let mut count = 0;
+        for &keep in kept {
+            new_index.push(if keep { Some(count) } else { None });
+            count += keep as usize;
+        }
+        self.members = self.members.iter().filter_map(|&i| new_index.get(i).copied().flatten()).collect();
+        if self.members.is_empty() {
+            self.goal = None;
+        }
+    }
+
+    // Drop selected boids beyond the end of a shrunk flock
+    pub fn truncate(&mut self, count: usize) {
+        self.members.retain(|&i| i < count);
+        if self.members.is_empty() {
+            self.goal = None;
+        }
+    }
+
+    // Pull of boid `i` towards the group's goal, zero if it isn't selected or has arrived
+    pub fn steer(&self, i: usize, boid: &Boid) -> glam::Vec2 {
+        let goal = match self.goal {
+            Some(goal) if self.contains(i) => goal,
+            _ => return glam::Vec2::ZERO,
+        };
+        let offset = goal - glam::vec2(boid.x, boid.y);
+        if offset.length_squared() < ARRIVAL_RADIUS * ARRIVAL_RADIUS {
+            glam::Vec2::ZERO
+        } else {
+            offset.normalize() * GOAL_WEIGHT
+        }
+    }
+
+    // Rings around the selected boids, the goal and the box being dragged
+    #[cfg(feature = "render-ggez")]
+    pub fn draw(&self, mb: &mut graphics::MeshBuilder, boids: &[Boid], line_width: f32) -> GameResult {
+        for boid in self.members.iter().filter_map(|&i| boids.get(i)) {
+            mb.circle(
+                graphics::DrawMode::stroke(line_width),
+                glam::vec2(boid.x, boid.y),
+                HIGHLIGHT_RADIUS,
+                0.5,
+                SELECTION_COLOR.into(),
+            )?;
+        }
+
+        if let Some(goal) = self.goal {
+            mb.circle(graphics::DrawMode::stroke(2.0 * line_width), goal, ARRIVAL_RADIUS, 0.1, SELECTION_COLOR.into())?;
+            mb.line(
+                &[goal - glam::vec2(8.0, 0.0), goal + glam::vec2(8.0, 0.0)],
+                line_width,
+                SELECTION_COLOR.into(),
+            )?;
+            mb.line(
+                &[goal - glam::vec2(0.0, 8.0), goal + glam::vec2(0.0, 8.0)],
+                line_width,
+                SELECTION_COLOR.into(),
+            )?;
+        }
+
+        if let Some((start, end)) = self.drag {
+            let (min, max) = (start.min(end), start.max(end));
+            if max.x > min.x && max.y > min.y {
+                let rect = graphics::Rect::new(min.x, min.y, max.x - min.x, max.y - min.y);
+                mb.rectangle(graphics::DrawMode::fill(), rect, [0.4, 1.0, 0.8, 0.1].into())?;
+                mb.rectangle(graphics::DrawMode::stroke(line_width), rect, SELECTION_COLOR.into())?;
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,216 @@
+// server.rs
+use crate::boid::Boid;
+use crate::config::Parameters;
+use crate::metrics::FlockStats;
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::mpsc::{self, Receiver, Sender, TryRecvError};
+use std::thread;
+use std::time::Duration;
+use tracing::{debug, info, warn};
+use tungstenite::protocol::WebSocketConfig;
+use tungstenite::{Message, WebSocket};
+
+// A client that hasn't finished its handshake by then is given up on
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(5);
+// Snapshots queued for a client that isn't reading. One that falls this far
+// behind is dropped rather than buffered without end.
+const MAX_QUEUED_BYTES: usize = 16 << 20;
+
+// How a client wants its snapshots encoded, JSON text until it asks otherwise
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Encoding {
+    Json,
+    MsgPack,
+}
+
+// What a client can send, as JSON text or MessagePack binary frames:
+// {"parameters": {"alignment": 2.0}} changes the same values as the
+// [parameters] section of boids.toml, {"encoding": "msgpack"} switches the
+// snapshots that client receives
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Command {
+    Parameters(Parameters),
+    Encoding(Encoding),
+}
+
+// The flock metrics sent with every snapshot
+#[derive(Serialize)]
+struct Metrics {
+    average_speed: f32,
+    polarization: f32,
+    angular_momentum: f32,
+    nearest_neighbor_distance: f32,
+    clusters: usize,
+}
+
+// One tick of the simulation as broadcast to every client
+#[derive(Serialize)]
+struct Snapshot {
+    time: f32, // Simulation seconds since the server started
+    boids: usize,
+    metrics: Metrics,
+    positions: Vec<[f32; 2]>,
+    velocities: Vec<[f32; 2]>,
+}
+
+struct Client {
+    socket: WebSocket<TcpStream>,
+    address: SocketAddr,
+    encoding: Encoding,
+}
+
+// Live state server started with --serve. A thread accepts connections and
+// hands each to a thread of its own for the handshake, so a slow client
+// can't hold up the others; everything else happens on the main thread,
+// which broadcasts a snapshot every tick and collects parameter changes.
+pub struct Server {
+    clients: Vec<Client>,
+    accepted: Receiver<Client>,
+    time: f32,
+}
+
+impl Server {
+    pub fn bind(address: SocketAddr) -> io::Result<Server> {
+        let listener = TcpListener::bind(address)?;
+        let (sender, accepted) = mpsc::channel();
+        thread::Builder::new().name("server".to_string()).spawn(move || {
+            for stream in listener.incoming() {
+                let stream = match stream {
+                    Ok(stream) => stream,
+                    Err(err) => {
+                        warn!("Rejected a server connection: {}", err);
+                        continue;
+                    }
+                };
+                let sender = sender.clone();
+                let spawned = thread::Builder::new()
+                    .name("server handshake".to_string())
+                    .spawn(move || handshake(stream, sender));
+                if let Err(err) = spawned {
+                    warn!("Rejected a server connection: {}", err);
+                }
+            }
+        })?;
+
+        Ok(Server {
+            clients: Vec::new(),
+            accepted,
+            time: 0.0,
+        })
+    }
+
+    pub fn has_clients(&self) -> bool {
+        !self.clients.is_empty()
+    }
+
+    // Pick up new clients and return the parameter changes sent since the last poll
+    pub fn poll(&mut self) -> Vec<Parameters> {
+        loop {
+            match self.accepted.try_recv() {
+                Ok(client) => {
+                    info!("Server client connected from {}", client.address);
+                    self.clients.push(client);
+                }
+                Err(TryRecvError::Empty) | Err(TryRecvError::Disconnected) => break,
+            }
+        }
+
+        let mut changes = Vec::new();
+        self.clients.retain_mut(|client| loop {
+            let command = match client.socket.read() {
+                Ok(Message::Text(text)) => serde_json::from_str::<Command>(&text).map_err(|err| err.to_string()),
+                Ok(Message::Binary(bytes)) => rmp_serde::from_slice::<Command>(&bytes).map_err(|err| err.to_string()),
+                Ok(_) => continue, // Pings are answered by tungstenite
+                Err(tungstenite::Error::Io(err)) if err.kind() == io::ErrorKind::WouldBlock => return true,
+                Err(err) => {
+                    info!("Server client {} disconnected: {}", client.address, err);
+                    return false;
+                }
+            };
+            match command {
+                Ok(Command::Parameters(parameters)) => changes.push(parameters),
+                Ok(Command::Encoding(encoding)) => client.encoding = encoding,
+                Err(message) => warn!("Invalid command from {}: {}", client.address, message),
+            }
+        });
+        changes
+    }
+
+    // Send every client the state after a tick of `sim_dt` seconds
+    pub fn broadcast(&mut self, boids: &[Boid], stats: &FlockStats, sim_dt: f32) {
+        self.time += sim_dt;
+        if self.clients.is_empty() {
+            return;
+        }
+
+        let snapshot = Snapshot {
+            time: self.time,
+            boids: boids.len(),
+            metrics: Metrics {
+                average_speed: stats.average_speed,
+                polarization: stats.polarization,
+                angular_momentum: stats.angular_momentum,
+                nearest_neighbor_distance: stats.nearest_neighbor_distance,
+                clusters: stats.cluster_count,
+            },
+            positions: boids.iter().map(|boid| [boid.x, boid.y]).collect(),
+            velocities: boids.iter().map(|boid| [boid.dx, boid.dy]).collect(),
+        };
+
+        // Each encoding is only built if some client asked for it
+        let mut json = None;
+        let mut msgpack = None;
+        self.clients.retain_mut(|client| {
+            let message = match client.encoding {
+                Encoding::Json => json
+                    .get_or_insert_with(|| Message::Text(serde_json::to_string(&snapshot).unwrap_or_default()))
+                    .clone(),
+                Encoding::MsgPack => msgpack
+                    .get_or_insert_with(|| Message::Binary(rmp_serde::to_vec_named(&snapshot).unwrap_or_default()))
+                    .clone(),
+            };
+            match client.socket.send(message) {
+                Ok(()) => true,
+                // Queued until the socket drains, a later send flushes it
+                Err(tungstenite::Error::Io(err)) if err.kind() == io::ErrorKind::WouldBlock => true,
+                Err(tungstenite::Error::WriteBufferFull(_)) => {
+                    info!("Dropping server client {}: not keeping up", client.address);
+                    false
+                }
+                Err(err) => {
+                    debug!("Dropping server client {}: {}", client.address, err);
+                    false
+                }
+            }
+        });
+    }
+}
+
+// Upgrade a new connection and pass it to the main thread
+fn handshake(stream: TcpStream, sender: Sender<Client>) {
+    match upgrade(stream) {
+        // Only fails once the server is gone
+        Ok(client) => {
+            let _ = sender.send(client);
+        }
+        Err(message) => warn!("Rejected a server connection: {}", message),
+    }
+}
+
+fn upgrade(stream: TcpStream) -> Result<Client, String> {
+    let address = stream.peer_addr().map_err(|err| err.to_string())?;
+    stream.set_read_timeout(Some(HANDSHAKE_TIMEOUT)).map_err(|err| err.to_string())?;
+    let config = WebSocketConfig {
+        max_write_buffer_size: MAX_QUEUED_BYTES,
+        ..WebSocketConfig::default()
+    };
+    let socket = tungstenite::accept_with_config(stream, Some(config)).map_err(|err| err.to_string())?;
+    // Reads on the main thread must not wait for the client
+    socket.get_ref().set_read_timeout(None).map_err(|err| err.to_string())?;
+    socket.get_ref().set_nonblocking(true).map_err(|err| err.to_string())?;
+    Ok(Client { socket, address, encoding: Encoding::Json })
+}
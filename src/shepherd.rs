@@ -0,0 +1,186 @@
+// shepherd.rs
+use crate::boid::Boid;
+use crate::world::World;
+#[cfg(feature = "render-ggez")]
+use ggez::input::gamepad::gilrs::Axis;
+#[cfg(feature = "render-ggez")]
+use ggez::{graphics, Context, GameResult};
+
+// Shepherding game settings
+const DOG_SPEED: f32 = 360.0;        // Pixels per second at full stick or with a key held
+#[cfg(feature = "render-ggez")]
+const DOG_SIZE: f32 = 14.0;          // Pixels, radius as drawn
+const SCARE_RANGE: f32 = 140.0;      // Boids closer than this run from the dog
+const SCARE_WEIGHT: f32 = 30.0;      // Velocity change per step right next to the dog
+const PEN_FRACTION: f32 = 0.2;       // Pen width and height as a fraction of the world's
+const TARGET_FRACTION: f32 = 0.6;    // Share of the flock that has to be penned at once
+const TIME_LIMIT: f32 = 120.0;       // Seconds
+const POINTS_PER_SECOND: f32 = 10.0; // Bonus for every second left when the flock is penned
+#[cfg(feature = "render-ggez")]
+const STICK_DEAD_ZONE: f32 = 0.2;
+
+#[cfg(feature = "render-ggez")]
+const DOG_COLOR: [f32; 4] = [1.0, 0.7, 0.2, 1.0];
+#[cfg(feature = "render-ggez")]
+const PEN_COLOR: [f32; 4] = [0.4, 0.9, 0.4, 0.8];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Playing,
+    Won,
+    Lost,
+}
+
+// Herding game, toggled with <f1>. The player steers a sheepdog with the
+// pan keys or a gamepad's left stick; boids flee it, and the round is won
+// once TARGET_FRACTION of the flock is inside the pen before time runs out.
+// <f1> after a round starts the next one.
+#[derive(Clone)]
+pub struct Shepherd {
+    pub active: bool,
+    dog: glam::Vec2,
+    heading: glam::Vec2, // Stick or key direction from the last frame, at most unit length
+    pen_min: glam::Vec2,
+    pen_max: glam::Vec2,
+    time_left: f32,
+    penned: usize,       // Boids inside the pen on the last step
+    flock: usize,
+    outcome: Outcome,
+    score: u32,
+    best: u32,           // Best score since the game was first started
+}
+
+impl Shepherd {
+    pub fn new(world: &World) -> Self {
+        let mut shepherd = Shepherd {
+            active: false,
+            dog: glam::Vec2::ZERO,
+            heading: glam::Vec2::ZERO,
+            pen_min: glam::Vec2::ZERO,
+            pen_max: glam::Vec2::ZERO,
+            time_left: TIME_LIMIT,
+            penned: 0,
+            flock: 0,
+            outcome: Outcome::Playing,
+            score: 0,
+            best: 0,
+        };
+        shepherd.resize(world);
+        shepherd
+    }
+
+    // Start a new round, or end the one being played
+    pub fn toggle(&mut self, world: &World) {
+        self.active = !(self.active && self.outcome == Outcome::Playing);
+        if self.active {
+            self.resize(world);
+            self.dog = world.center() * 0.5;
+            self.heading = glam::Vec2::ZERO;
+            self.time_left = TIME_LIMIT;
+            self.penned = 0;
+            self.outcome = Outcome::Playing;
+            self.score = 0;
+        }
+    }
+
+    // Put the pen in the bottom-right corner of a new world
+    // Move the dog with a world rescaled by `scale`, the pen follows in resize
+    pub fn rescale(&mut self, scale: glam::Vec2) {
+        self.dog *= scale;
+    }
+
+    pub fn resize(&mut self, world: &World) {
+        let size = glam::vec2(world.width, world.height);
+        self.pen_max = size;
+        self.pen_min = size * (1.0 - PEN_FRACTION);
+        self.dog = self.dog.max(glam::Vec2::ZERO).min(size);
+    }
+
+    // Direction the dog runs in until the next frame
+    pub fn steer(&mut self, direction: glam::Vec2) {
+        self.heading = direction.clamp_length_max(1.0);
+    }
+
+    // Push a boid away from the dog, stronger the closer it is
+    pub fn scare_force(&self, boid: &Boid) -> glam::Vec2 {
+        let offset = glam::vec2(boid.x, boid.y) - self.dog;
+        let distance = offset.length();
+        if distance == 0.0 || distance > SCARE_RANGE {
+            return glam::Vec2::ZERO;
+        }
+        offset / distance * SCARE_WEIGHT * (1.0 - distance / SCARE_RANGE)
+    }
+
+    fn in_pen(&self, boid: &Boid) -> bool {
+        boid.x >= self.pen_min.x && boid.x <= self.pen_max.x && boid.y >= self.pen_min.y && boid.y <= self.pen_max.y
+    }
+
+    // Move the dog, count the penned boids and run the clock. Returns the
+    // outcome when the round has just ended.
+    pub fn update(&mut self, boids: &[Boid], tick: f32, world: &World) -> Option<Outcome> {
+        let bounds = glam::vec2(world.width, world.height);
+        self.dog = (self.dog + self.heading * DOG_SPEED * tick).max(glam::Vec2::ZERO).min(bounds);
+        if self.outcome != Outcome::Playing {
+            return None;
+        }
+
+        self.penned = boids.iter().filter(|boid| self.in_pen(boid)).count();
+        self.flock = boids.len();
+        self.time_left = (self.time_left - tick).max(0.0);
+        if self.flock > 0 && self.penned as f32 >= TARGET_FRACTION * self.flock as f32 {
+            self.outcome = Outcome::Won;
+            self.score = self.penned as u32 + (self.time_left * POINTS_PER_SECOND) as u32;
+            self.best = self.best.max(self.score);
+            Some(Outcome::Won)
+        } else if self.time_left <= 0.0 {
+            self.outcome = Outcome::Lost;
+            self.score = self.penned as u32;
+            self.best = self.best.max(self.score);
+            Some(Outcome::Lost)
+        } else {
+            None
+        }
+    }
+
+    // Score line for the HUD
+    pub fn hud_text(&self) -> String {
+        let target = (TARGET_FRACTION * self.flock as f32).ceil() as usize;
+        let status = match self.outcome {
+            Outcome::Playing => format!("{:.0}s left", self.time_left.ceil()),
+            Outcome::Won => format!("Penned! Score {}", self.score),
+            Outcome::Lost => format!("Time's up! Score {}", self.score),
+        };
+        format!("Herd: {} / {} penned | {} | Best {}", self.penned, target, status, self.best)
+    }
+
+    // The pen, the dog and the range it scares boids from
+    #[cfg(feature = "render-ggez")]
+    pub fn draw(&self, mb: &mut graphics::MeshBuilder, line_width: f32) -> GameResult {
+        let size = self.pen_max - self.pen_min;
+        let pen = graphics::Rect::new(self.pen_min.x, self.pen_min.y, size.x, size.y);
+        mb.rectangle(graphics::DrawMode::fill(), pen, [0.4, 0.9, 0.4, 0.1].into())?;
+        mb.rectangle(graphics::DrawMode::stroke(3.0 * line_width), pen, PEN_COLOR.into())?;
+
+        mb.circle(
+            graphics::DrawMode::stroke(line_width),
+            self.dog,
+            SCARE_RANGE,
+            0.5,
+            [1.0, 0.7, 0.2, 0.25].into(),
+        )?;
+        mb.circle(graphics::DrawMode::fill(), self.dog, DOG_SIZE, 0.1, DOG_COLOR.into())?;
+        Ok(())
+    }
+}
+
+// Left stick of the first gamepad that is being pushed, y down like the screen
+#[cfg(feature = "render-ggez")]
+pub fn gamepad_stick(ctx: &Context) -> glam::Vec2 {
+    for (_, gamepad) in ctx.gamepad.gamepads() {
+        let stick = glam::vec2(gamepad.value(Axis::LeftStickX), -gamepad.value(Axis::LeftStickY));
+        if stick.length() > STICK_DEAD_ZONE {
+            return stick;
+        }
+    }
+    glam::Vec2::ZERO
+}
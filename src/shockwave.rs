@@ -0,0 +1,87 @@
+// shockwave.rs
+use crate::boid::Boid;
+#[cfg(feature = "render-ggez")]
+use ggez::{graphics, GameResult};
+
+// Shockwave settings
+const SHOCKWAVE_SPEED: f32 = 500.0;     // Pixels per second the ring expands
+const SHOCKWAVE_MAX_RADIUS: f32 = 400.0;
+const SHOCKWAVE_WIDTH: f32 = 24.0;      // Thickness of the band that pushes boids
+const SHOCKWAVE_STRENGTH: f32 = 40.0;   // Velocity change per step at full strength
+
+// Expanding rings that push boids outwards as they pass, started with <alt> + left click
+#[derive(Clone)]
+pub struct Shockwaves {
+    waves: Vec<(glam::Vec2, f32)>, // Center and current radius
+}
+
+impl Shockwaves {
+    pub fn new() -> Self {
+        Shockwaves { waves: Vec::new() }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.waves.is_empty()
+    }
+
+    // Move the centers with a world rescaled by `scale`
+    pub fn rescale(&mut self, scale: glam::Vec2) {
+        for (center, _) in &mut self.waves {
+            *center *= scale;
+        }
+    }
+
+    pub fn start(&mut self, center: glam::Vec2) {
+        self.waves.push((center, 0.0));
+    }
+
+    // Grow every ring, dropping the ones that have faded out
+    pub fn update(&mut self, tick: f32) {
+        for (_, radius) in &mut self.waves {
+            *radius += SHOCKWAVE_SPEED * tick;
+        }
+        self.waves.retain(|&(_, radius)| radius < SHOCKWAVE_MAX_RADIUS);
+    }
+
+    // Radial push on a boid currently inside any ring's band
+    pub fn force(&self, boid: &Boid) -> glam::Vec2 {
+        let pos = glam::vec2(boid.x, boid.y);
+        let mut force = glam::Vec2::ZERO;
+
+        for &(center, radius) in &self.waves {
+            let offset = pos - center;
+            let distance = offset.length();
+            if distance == 0.0 || (distance - radius).abs() > SHOCKWAVE_WIDTH {
+                continue;
+            }
+
+            // Weaker the further the ring has travelled
+            let strength = SHOCKWAVE_STRENGTH * fade(radius);
+            force += offset / distance * strength;
+        }
+
+        force
+    }
+
+    #[cfg(feature = "render-ggez")]
+    pub fn draw(&self, mb: &mut graphics::MeshBuilder, line_width: f32) -> GameResult {
+        for &(center, radius) in &self.waves {
+            if radius <= 0.0 {
+                continue;
+            }
+            mb.circle(
+                graphics::DrawMode::stroke(3.0 * line_width),
+                center,
+                radius,
+                0.5,
+                [1.0, 1.0, 1.0, 0.8 * fade(radius)].into(),
+            )?;
+        }
+        Ok(())
+    }
+}
+
+// 1 when a ring starts, falling to 0 at its maximum radius
+fn fade(radius: f32) -> f32 {
+    1.0 - (radius / SHOCKWAVE_MAX_RADIUS).min(1.0)
+}
@@ -0,0 +1,174 @@
+// sim_thread.rs
+use crate::boid::Boid;
+use crate::simulation::{Simulation, StepReport};
+use std::sync::mpsc::{self, Receiver, Sender, TryRecvError};
+use std::thread::{self, JoinHandle};
+
+// One batch of steps for the worker
+struct Job {
+    sim: Simulation,
+    generation: u64,
+    cursor: glam::Vec2,
+    tick: f32,
+    steps: usize,
+    keep_flocks: bool,
+}
+
+// The simulation after stepping, with the report from every step
+struct Done {
+    sim: Simulation,
+    view: Simulation,
+    generation: u64,
+    reports: Vec<StepReport>,
+    flocks: Vec<Vec<Boid>>,
+    tick: f32,
+}
+
+// What a job gave back
+pub struct Stepped {
+    pub sim: Simulation,
+    pub reports: Vec<StepReport>, // One per step
+    pub flocks: Vec<Vec<Boid>>,   // The flock after each step, when asked for
+    pub tick: f32,                // Length of each step in seconds
+}
+
+// Runs the simulation on a worker thread, enabled with --sim-thread. The
+// render thread hands the simulation over at the end of each update and
+// draws a view of it while the worker steps it. The worker makes the view of
+// each result on its own thread, to be drawn while the next job runs. The
+// stepped simulation is taken back before anything reads or edits it again,
+// so edits land on the latest state rather than being thrown away. Jobs are
+// numbered and only the result of the last one sent is taken back. Edits
+// made between taking a result and sending the next job show from the
+// following result on.
+pub struct SimThread {
+    jobs: Option<Sender<Job>>,
+    results: Receiver<Done>,
+    worker: Option<JoinHandle<()>>,
+    generation: u64, // Of the last job sent
+    in_flight: bool,
+    view: Option<Simulation>, // Of the last result, drawn during the next job
+    stepped: Option<Stepped>, // Finished and not taken yet
+}
+
+impl SimThread {
+    pub fn spawn() -> SimThread {
+        let (jobs, job_queue) = mpsc::channel::<Job>();
+        let (result_queue, results) = mpsc::channel();
+
+        let worker = thread::Builder::new()
+            .name("simulation".to_string())
+            .spawn(move || {
+                for Job { mut sim, generation, cursor, tick, steps, keep_flocks } in job_queue {
+                    let mut reports = Vec::with_capacity(steps);
+                    let mut flocks = Vec::new();
+                    for _ in 0..steps {
+                        reports.push(sim.step(tick, cursor));
+                        if keep_flocks {
+                            flocks.push(sim.boids.clone());
+                        }
+                    }
+                    let view = sim.render_view();
+                    let done = Done { sim, view, generation, reports, flocks, tick };
+                    if result_queue.send(done).is_err() {
+                        break;
+                    }
+                }
+            })
+            .expect("Failed to start the simulation thread");
+
+        SimThread {
+            jobs: Some(jobs),
+            results,
+            worker: Some(worker),
+            generation: 0,
+            in_flight: false,
+            view: None,
+            stepped: None,
+        }
+    }
+
+    // Step `sim` `steps` times of `tick` seconds on the worker, with the mouse
+    // at `cursor`, leaving the view of the last result in its place. With
+    // `keep_flocks` the flock after every step comes back too. The result of
+    // any earlier job still in flight is skipped. False, with `sim` left as it
+    // was, if the worker has stopped.
+    pub fn send(&mut self, sim: &mut Simulation, cursor: glam::Vec2, tick: f32, steps: usize, keep_flocks: bool) -> bool {
+        let jobs = match self.jobs.as_ref() {
+            Some(jobs) => jobs,
+            None => return false,
+        };
+
+        // Only the first job has no view from the worker yet
+        let view = self.view.take().unwrap_or_else(|| sim.render_view());
+        let sim_to_step = std::mem::replace(sim, view);
+        self.generation += 1;
+        let job = Job { sim: sim_to_step, generation: self.generation, cursor, tick, steps, keep_flocks };
+        match jobs.send(job) {
+            Ok(()) => {
+                self.in_flight = true;
+                true
+            }
+            Err(err) => {
+                *sim = err.0.sim;
+                false
+            }
+        }
+    }
+
+    // Whether a job is still being worked on, without waiting. A finished
+    // result is kept for take.
+    pub fn busy(&mut self) -> bool {
+        if !self.in_flight {
+            return false;
+        }
+        loop {
+            match self.results.try_recv() {
+                Ok(done) if done.generation == self.generation => {
+                    self.finish(done);
+                    return false;
+                }
+                Ok(_) => {}
+                Err(TryRecvError::Empty) => return true,
+                Err(TryRecvError::Disconnected) => {
+                    self.in_flight = false;
+                    return false;
+                }
+            }
+        }
+    }
+
+    // Wait for the job in flight and return what it gave back. None when
+    // nothing was sent or the worker stopped.
+    pub fn take(&mut self) -> Option<Stepped> {
+        if self.in_flight {
+            if let Some(done) = self.results.iter().find(|done| done.generation == self.generation) {
+                self.finish(done);
+            }
+            self.in_flight = false;
+        }
+        self.stepped.take()
+    }
+
+    // Make the next job draw a fresh copy of what it is sent, for when the
+    // simulation was replaced by a different one since the last result
+    pub fn discard_view(&mut self) {
+        self.view = None;
+    }
+
+    fn finish(&mut self, done: Done) {
+        self.in_flight = false;
+        self.view = Some(done.view);
+        self.stepped = Some(Stepped { sim: done.sim, reports: done.reports, flocks: done.flocks, tick: done.tick });
+    }
+}
+
+impl Drop for SimThread {
+    fn drop(&mut self) {
+        // Closing the job queue ends the worker loop
+        self.jobs = None;
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
@@ -0,0 +1,172 @@
+// simd_kernel.rs
+// Neighbor sums behind the classic flocking rules, computed several neighbors
+// at a time when built with `--features simd`. This file only depends on glam
+// and wide so the benchmark can include it directly.
+
+// Neighbor positions and velocities laid out as structure of arrays, so a
+// run of lanes can be loaded straight into a vector register
+#[derive(Debug, Default, Clone)]
+pub struct NeighborLanes {
+    pub x: Vec<f32>,
+    pub y: Vec<f32>,
+    pub dx: Vec<f32>,
+    pub dy: Vec<f32>,
+}
+
+impl NeighborLanes {
+    pub fn push(&mut self, x: f32, y: f32, dx: f32, dy: f32) {
+        self.x.push(x);
+        self.y.push(y);
+        self.dx.push(dx);
+        self.dy.push(dy);
+    }
+
+    fn len(&self) -> usize {
+        self.x.len()
+    }
+}
+
+// Everything separation, cohesion and alignment need from the neighbors
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct FlockSums {
+    pub separation: glam::Vec2,   // Sum of offsets away from neighbors closer than the minimum distance
+    pub position_sum: glam::Vec2,
+    pub velocity_sum: glam::Vec2,
+    pub count: usize,
+}
+
+impl FlockSums {
+    // Steering towards the center of the neighbors
+    pub fn cohesion(&self, pos: glam::Vec2) -> glam::Vec2 {
+        if self.count == 0 {
+            return glam::Vec2::ZERO;
+        }
+        self.position_sum / self.count as f32 - pos
+    }
+
+    // Steering towards the average velocity of the neighbors
+    pub fn alignment(&self, vel: glam::Vec2) -> glam::Vec2 {
+        if self.count == 0 {
+            return glam::Vec2::ZERO;
+        }
+        self.velocity_sum / self.count as f32 - vel
+    }
+}
+
+// One neighbor at a time, the reference for the vector version
+pub fn flock_sums_scalar(pos: glam::Vec2, lanes: &NeighborLanes, min_distance: f32) -> FlockSums {
+    let mut sums = FlockSums::default();
+    accumulate(&mut sums, pos, lanes, 0, min_distance);
+    sums
+}
+
+// Eight neighbors at a time, the leftovers one at a time
+#[cfg(feature = "simd")]
+pub fn flock_sums_simd(pos: glam::Vec2, lanes: &NeighborLanes, min_distance: f32) -> FlockSums {
+    use std::convert::TryInto;
+    use wide::{f32x8, CmpLt};
+
+    // Not even one full vector, skip the setup
+    if lanes.len() < 8 {
+        return flock_sums_scalar(pos, lanes, min_distance);
+    }
+
+    let load = |values: &[f32], start: usize| -> f32x8 {
+        let chunk: [f32; 8] = values[start..start + 8].try_into().unwrap();
+        f32x8::from(chunk)
+    };
+    let sum = |values: f32x8| -> f32 { values.to_array().iter().sum() };
+
+    let (px, py) = (f32x8::splat(pos.x), f32x8::splat(pos.y));
+    let min_squared = f32x8::splat(min_distance * min_distance);
+    let zero = f32x8::splat(0.0);
+    let (mut sep_x, mut sep_y) = (zero, zero);
+    let (mut sum_x, mut sum_y) = (zero, zero);
+    let (mut sum_dx, mut sum_dy) = (zero, zero);
+
+    let full = lanes.len() / 8 * 8;
+    for start in (0..full).step_by(8) {
+        let (x, y) = (load(&lanes.x, start), load(&lanes.y, start));
+        let (away_x, away_y) = (px - x, py - y);
+        let close = (away_x * away_x + away_y * away_y).cmp_lt(min_squared);
+
+        sep_x += close.blend(away_x, zero);
+        sep_y += close.blend(away_y, zero);
+        sum_x += x;
+        sum_y += y;
+        sum_dx += load(&lanes.dx, start);
+        sum_dy += load(&lanes.dy, start);
+    }
+
+    let mut sums = FlockSums {
+        separation: glam::vec2(sum(sep_x), sum(sep_y)),
+        position_sum: glam::vec2(sum(sum_x), sum(sum_y)),
+        velocity_sum: glam::vec2(sum(sum_dx), sum(sum_dy)),
+        count: full,
+    };
+    accumulate(&mut sums, pos, lanes, full, min_distance);
+    sums
+}
+
+// Add the lanes from `start` onwards to `sums`
+fn accumulate(sums: &mut FlockSums, pos: glam::Vec2, lanes: &NeighborLanes, start: usize, min_distance: f32) {
+    for i in start..lanes.len() {
+        let other = glam::vec2(lanes.x[i], lanes.y[i]);
+        let away = pos - other;
+        if away.length_squared() < min_distance * min_distance {
+            sums.separation += away;
+        }
+        sums.position_sum += other;
+        sums.velocity_sum += glam::vec2(lanes.dx[i], lanes.dy[i]);
+        sums.count += 1;
+    }
+}
+
+#[cfg(all(test, feature = "simd"))]
+mod tests {
+    #[test]
+    fn vector_sums_match_scalar_sums() {
+        use super::*;
+
+        // Neighbors spread around `pos`, every third one inside the minimum
+        // distance. Kept inside the test, the benchmark includes this file.
+        fn lanes_around(pos: glam::Vec2, count: usize) -> NeighborLanes {
+            let mut lanes = NeighborLanes::default();
+            for i in 0..count {
+                let angle = i as f32 * 2.4;
+                let distance = if i % 3 == 0 { 3.0 + i as f32 % 15.0 } else { 25.0 + i as f32 * 1.7 };
+                let offset = glam::vec2(angle.cos(), angle.sin()) * distance;
+                lanes.push(pos.x + offset.x, pos.y + offset.y, (i as f32 * 0.7).sin() * 3.0, (i as f32 * 1.3).cos() * 3.0);
+            }
+            lanes
+        }
+
+        let pos = glam::vec2(400.0, 300.0);
+        let min_distance = 20.0;
+        for &count in &[0, 7, 8, 9, 100] {
+            let lanes = lanes_around(pos, count);
+            let simd = flock_sums_simd(pos, &lanes, min_distance);
+            let scalar = flock_sums_scalar(pos, &lanes, min_distance);
+
+            assert_eq!(simd.count, scalar.count);
+            if count > 0 {
+                assert_ne!(scalar.separation, glam::Vec2::ZERO);
+            }
+            for (what, simd, scalar) in &[
+                ("separation", simd.separation, scalar.separation),
+                ("position sum", simd.position_sum, scalar.position_sum),
+                ("velocity sum", simd.velocity_sum, scalar.velocity_sum),
+            ] {
+                let tolerance = 1e-5 * scalar.length().max(1.0);
+                assert!(
+                    (*simd - *scalar).length() <= tolerance,
+                    "{} differs with {} lanes: {} vs {}",
+                    what,
+                    count,
+                    simd,
+                    scalar
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,770 @@
+// simulation.rs
+use crate::barnes_hut::BarnesHut;
+use crate::behavior::{self, SteeringForces, WeightedBehavior};
+use crate::boid::{Boid, VISUAL_RANGE, WANDER_STRENGTH, TRAIT_VARIANCE};
+use crate::collisions::Collisions;
+use crate::energy;
+use crate::epidemic::{Epidemic, INFECTION_PROBABILITY};
+use crate::evolution::{Evolution, GenerationStats};
+use crate::flow_field::{FlowField, FLOW_CELL_SIZE, FLOW_STRENGTH};
+use crate::food::{Food, FOOD_RATE};
+use crate::inspect::Inspector;
+use crate::leaders::{self, LeaderMode, NUM_LEADERS};
+use crate::lod::Lod;
+use crate::metrics;
+use crate::neighbor_index::{IndexKind, NeighborIndex};
+use crate::obstacles::Obstacles;
+use crate::pheromone::{PheromoneField, PheromoneMode, PHEROMONE_CELL_SIZE};
+use crate::predators::PredatorPrey;
+use crate::quadtree::QuadTree;
+use crate::scoring::ScoringZones;
+use crate::selection::Selection;
+use crate::shepherd::{Outcome, Shepherd};
+use crate::shockwave::Shockwaves;
+use crate::spatial_grid::SpatialGrid;
+use crate::spawn::{self, SpawnPattern};
+use crate::trails::Trails;
+use crate::waypoints::{Waypoints, GOAL_WEIGHT};
+use crate::world::{Arena, Boundary, BoundaryMode, ResizeMode, World, MAX_WORLD_SIZE};
+use crate::zones::{Zones, EMIT_RATE};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::borrow::Cow;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tracing::{debug, info};
+
+// Steps between flock labeling passes, for coloring by flock
+const FLOCK_LABEL_INTERVAL: usize = 10;
+
+const CELL_SIZE: f32 = VISUAL_RANGE; // Starting cell size for spatial partitioning, tuned by the visualizer
+
+// Lowest and highest value of every parameter set_parameter takes, by its
+// boids.toml name. Whatever the source (boids.toml, the server, OSC, MIDI,
+// scenarios), values are clamped to these, so a remote client can't make a
+// step spawn without end or look at every boid.
+pub const PARAMETER_RANGES: [(&str, f32, f32); 13] = [
+    ("separation", 0.0, 10.0),
+    ("cohesion", 0.0, 10.0),
+    ("alignment", 0.0, 10.0),
+    ("wander", 0.0, 60.0),               // Up to MAX_FORCE
+    ("goal_weight", 0.0, 60.0),
+    ("emit_rate", 0.0, 1_000.0),         // Boids per second from each emitter
+    ("food_rate", 0.0, 1_000.0),         // Items per second
+    ("infection_probability", 0.0, 1.0),
+    ("trait_variance", 0.0, 0.9),        // Boid::vary_traits caps it here anyway
+    ("speed", 0.0, 10.0),
+    ("visual_range", 0.0, 4.0),
+    ("long_range", 0.0, 60.0),
+    ("neighbor_cap", 0.0, 10_000.0),
+];
+
+// `value` clamped to the range of the named parameter, None for an unknown
+// name. NaN gives the lowest value.
+pub fn clamp_parameter(name: &str, value: f32) -> Option<f32> {
+    PARAMETER_RANGES
+        .iter()
+        .find(|&&(known, _, _)| known == name)
+        .map(|&(_, min, max)| value.max(min).min(max))
+}
+
+// Time spent in each part of a step, and what happened in it that the front
+// end may want to show or log
+#[derive(Debug, Default)]
+pub struct StepReport {
+    pub grid_rebuild: Duration,
+    pub neighbors: Duration,
+    pub behaviors: Duration,
+    pub bred: Option<GenerationStats>, // Set when evolution bred a new generation
+}
+
+// Everything that belongs to one simulated world: the flock, the space it
+// flies in, the rules it follows and every subsystem acting on it. The ggez
+// visualizer keeps one per <tab> world, the other front ends just one.
+#[derive(Clone)]
+pub struct Simulation {
+    pub boids: Vec<Boid>,
+    pub world: World,
+    pub arena: Arena,                   // Shape from boids.toml, fitted to the world as `boundary`
+    pub boundary: Arc<dyn Boundary>,
+    pub boundary_mode: BoundaryMode,
+    pub behaviors: Vec<WeightedBehavior>, // Steering rules applied to every follower boid
+    pub forces: Vec<SteeringForces>,    // Per-boid rule contributions from the last step
+    pub neighbor_lists: Vec<Vec<usize>>, // Boids in range of each boid on the last step, see refresh_neighbor_lists
+    steps_since_labeling: usize,        // Steps since flock IDs were last assigned
+    pub inspector: Inspector,           // Boid selected with a left click
+    pub selection: Selection,           // Group boxed with a left drag
+    pub trails: Trails,
+    pub flow_field: FlowField,          // Painted with the right mouse button
+    pub waypoints: Waypoints,
+    pub zones: Zones,                   // Emitters and sinks
+    pub scoring: ScoringZones,          // Counting areas from the [[scoring]] sections of boids.toml
+    pub food: Food,
+    pub energy_model: bool,             // Boids tire with speed and recover by eating or resting
+    pub collisions: Collisions,
+    pub evolution: Evolution,           // Breeds behavior weights
+    pub epidemic: Epidemic,             // SIR infection spreading through the flock
+    pub predator_prey: PredatorPrey,    // Hunting and breeding populations
+    pub shepherd: Shepherd,             // Herding game
+    pub pheromones: PheromoneField,     // Trails the boids lay and may follow
+    pub obstacles: Obstacles,           // Walls and circles
+    pub shockwaves: Shockwaves,
+    pub leader_mode: LeaderMode,
+    pub wander_strength: f32,
+    pub trait_variance: f32,            // Spread of the traits of boids spawned from now on
+    pub speed_scale: f32,               // Multiplier on the speed limit, the speed parameter
+    pub separation_gain: f32,           // Extra multipliers the music puts on the separation weight...
+    pub speed_gain: f32,                // ...and speed limit, left at 1 without audio
+    pub range_scale: f32,               // Multiplier on the visual range, the visual_range parameter
+    pub long_range: BarnesHut,          // Attraction between distant sub-flocks, the long_range parameter
+    pub lod: Lod,                       // Slower steering updates far from the camera
+    pub neighbor_cap: Option<usize>,    // Nearest neighbors each boid steers by, the neighbor_cap parameter
+    pub wind: glam::Vec2,               // Drift in pixels per second
+    pub rng: StdRng,                    // Every random choice, so a seed replays the same run
+    pub spatial_grid: SpatialGrid,
+    pub quadtree: QuadTree,
+    pub index_kind: IndexKind,          // Neighbor index used for lookups
+    pub population: u64,                // Bumped whenever boids are added, removed or replaced, see new_population
+}
+
+impl Simulation {
+    // An empty world with the default parameters. Boids are added with
+    // respawn or resize_flock.
+    pub fn new(world: World, seed: u64) -> Simulation {
+        let arena = Arena::default();
+        Simulation {
+            boids: Vec::new(),
+            world,
+            boundary: arena.fit(&world),
+            arena,
+            boundary_mode: BoundaryMode::default(),
+            behaviors: behavior::default_behaviors(),
+            forces: Vec::new(),
+            neighbor_lists: Vec::new(),
+            steps_since_labeling: FLOCK_LABEL_INTERVAL,
+            inspector: Inspector::new(),
+            selection: Selection::new(),
+            trails: Trails::new(),
+            flow_field: FlowField::new(world.width, world.height, FLOW_CELL_SIZE),
+            waypoints: Waypoints::new(GOAL_WEIGHT),
+            zones: Zones::new(EMIT_RATE),
+            scoring: ScoringZones::new(),
+            food: Food::new(&world, FOOD_RATE),
+            energy_model: false,
+            collisions: Collisions::new(&world),
+            evolution: Evolution::new(),
+            epidemic: Epidemic::new(INFECTION_PROBABILITY),
+            predator_prey: PredatorPrey::new(&world),
+            shepherd: Shepherd::new(&world),
+            pheromones: PheromoneField::new(world.width, world.height, PHEROMONE_CELL_SIZE),
+            obstacles: Obstacles::new(&world),
+            shockwaves: Shockwaves::new(),
+            leader_mode: LeaderMode::Off,
+            wander_strength: WANDER_STRENGTH,
+            trait_variance: TRAIT_VARIANCE,
+            speed_scale: 1.0,
+            separation_gain: 1.0,
+            speed_gain: 1.0,
+            range_scale: 1.0,
+            long_range: BarnesHut::new(),
+            lod: Lod::new(),
+            neighbor_cap: None,
+            wind: glam::Vec2::ZERO,
+            rng: StdRng::seed_from_u64(seed),
+            spatial_grid: SpatialGrid::new(world.width, world.height, CELL_SIZE),
+            quadtree: QuadTree::new(world.width, world.height),
+            index_kind: IndexKind::Grid,
+            population: 0,
+        }
+    }
+
+    // Another world of the same size and arena, with these parameters but
+    // none of the boids or anything placed in it
+    pub fn empty_copy(&self, seed: u64) -> Simulation {
+        let mut copy = Simulation::new(self.world, seed);
+        copy.set_arena(self.arena.clone());
+        copy.boundary_mode = self.boundary_mode;
+        copy.behaviors = self.behaviors.clone();
+        copy.scoring = self.scoring.clone();
+        copy.scoring.clear();
+        copy.waypoints.weight = self.waypoints.weight;
+        copy.zones.rate = self.zones.rate;
+        copy.food.rate = self.food.rate;
+        copy.energy_model = self.energy_model;
+        copy.epidemic.probability = self.epidemic.probability;
+        copy.wander_strength = self.wander_strength;
+        copy.trait_variance = self.trait_variance;
+        copy.speed_scale = self.speed_scale;
+        copy.range_scale = self.range_scale;
+        copy.long_range.strength = self.long_range.strength;
+        copy.lod.active = self.lod.active;
+        copy.neighbor_cap = self.neighbor_cap;
+        copy.spatial_grid = SpatialGrid::new(self.world.width, self.world.height, self.spatial_grid.cell_size());
+        copy.index_kind = self.index_kind;
+        copy
+    }
+
+    // Set one parameter by its boids.toml name, clamped to its range in
+    // PARAMETER_RANGES, false for an unknown name
+    pub fn set_parameter(&mut self, name: &str, value: f32) -> bool {
+        let value = match clamp_parameter(name, value) {
+            Some(value) => value,
+            None => return false,
+        };
+        match name {
+            "separation" | "cohesion" | "alignment" => behavior::set_weight(&mut self.behaviors, name, value),
+            "wander" => self.wander_strength = value,
+            "goal_weight" => self.waypoints.weight = value,
+            "emit_rate" => self.zones.rate = value,
+            "food_rate" => self.food.rate = value,
+            "infection_probability" => self.epidemic.probability = value,
+            "trait_variance" => self.trait_variance = value,
+            "speed" => self.speed_scale = value,
+            "visual_range" => self.range_scale = value,
+            "long_range" => self.long_range.strength = value,
+            "neighbor_cap" => self.neighbor_cap = Some(value as usize).filter(|&cap| cap > 0),
+            _ => return false,
+        }
+        true
+    }
+
+    // Change the arena shape, fitted to the current world
+    pub fn set_arena(&mut self, arena: Arena) {
+        self.boundary = arena.fit(&self.world);
+        self.arena = arena;
+    }
+
+    // Replace the flock with `count` new boids laid out by `pattern`,
+    // forgetting everything that referred to the old ones
+    pub fn respawn(&mut self, count: usize, pattern: SpawnPattern) {
+        self.boids = pattern.spawn(count, &self.world, &mut self.rng);
+        self.vary_traits_from(0);
+        self.trails.clear();
+        self.inspector.clear();
+        self.selection.clear();
+        self.scoring.clear();
+        self.lod.clear();
+    }
+
+    // Change the world bounds mid-run, rebuilding everything sized to the
+    // world. With ResizeMode::Rescale everything in the world moves and
+    // stretches with it; with Clamp it all stays put and boids left outside
+    // are pulled in to the edge.
+    pub fn resize(&mut self, width: f32, height: f32, mode: ResizeMode) -> Result<(), String> {
+        if !World::is_valid_size(width, height) {
+            return Err(format!(
+                "{}x{} is not a world size, each side has to be between 1 and {}",
+                width, height, MAX_WORLD_SIZE
+            ));
+        }
+        if width == self.world.width && height == self.world.height {
+            return Ok(());
+        }
+
+        let scale = match mode {
+            ResizeMode::Rescale => glam::vec2(width / self.world.width, height / self.world.height),
+            ResizeMode::Clamp => glam::Vec2::ONE,
+        };
+        if mode == ResizeMode::Rescale {
+            self.obstacles.rescale(scale);
+            self.zones.rescale(scale);
+            self.waypoints.rescale(scale);
+            self.food.rescale(scale);
+            self.predator_prey.rescale(scale);
+            self.shepherd.rescale(scale);
+            self.selection.rescale(scale);
+            self.shockwaves.rescale(scale);
+            self.trails.rescale(scale);
+        } else {
+            self.trails.clear();
+        }
+
+        self.world = World::new(width, height);
+        self.spatial_grid = SpatialGrid::new(width, height, self.spatial_grid.cell_size());
+        self.quadtree = QuadTree::new(width, height);
+        self.flow_field.resize(width, height, scale);
+        self.pheromones.resize(width, height, scale);
+        self.food.resize(&self.world);
+        self.predator_prey.resize(&self.world);
+        self.shepherd.resize(&self.world);
+        self.scoring.resize(&self.world);
+        self.obstacles.resize(&self.world);
+        self.collisions.resize(&self.world);
+        self.boundary = self.arena.fit(&self.world);
+        for boid in &mut self.boids {
+            boid.x = (boid.x * scale.x).max(0.0).min(width);
+            boid.y = (boid.y * scale.y).max(0.0).min(height);
+        }
+        Ok(())
+    }
+
+    // Grow or shrink the population to `count` without disturbing the boids
+    // that stay
+    pub fn resize_flock(&mut self, count: usize) {
+        let from = self.boids.len();
+        if count < from {
+            self.remove_random_boids(from - count);
+        } else if count > from {
+            self.add_boids_sparse(count - from);
+        }
+        debug!(from, to = count, "Resized flock");
+    }
+
+    // Add `count` boids in the emptiest grid cells, flying in random
+    // directions, so they fill the gaps rather than piling onto the flocks
+    fn add_boids_sparse(&mut self, count: usize) {
+        self.spatial_grid.build(&self.boids);
+        let cell_size = self.spatial_grid.cell_size();
+        let corner = glam::vec2(self.world.width, self.world.height);
+        let before = self.boids.len();
+        // Only cells centered inside the arena, and the center itself when
+        // the random point lands outside it
+        let boundary = &self.boundary;
+        let cells = self.spatial_grid.sparsest_cells(count, |center| boundary.signed_distance(center) > 0.0);
+        for (cell_x, cell_y) in cells {
+            let mut boid = Boid::new(self.world.width, self.world.height, &mut self.rng);
+            let cell = glam::vec2(cell_x as f32, cell_y as f32);
+            let mut point = ((cell + glam::vec2(self.rng.gen(), self.rng.gen())) * cell_size).min(corner);
+            if self.boundary.signed_distance(point) <= 0.0 {
+                point = (cell + 0.5) * cell_size;
+            }
+            let velocity = glam::vec2(boid.dx, boid.dy);
+            spawn::place(&mut boid, point, velocity);
+            self.boids.push(boid);
+        }
+        self.vary_traits_from(before);
+    }
+
+    // Remove `count` boids picked at random
+    fn remove_random_boids(&mut self, count: usize) {
+        let count = count.min(self.boids.len());
+        let mut removing = vec![false; self.boids.len()];
+        for i in rand::seq::index::sample(&mut self.rng, self.boids.len(), count).iter() {
+            removing[i] = true;
+        }
+        self.remove_boids(|i, _| removing[i]);
+    }
+
+    // Sample traits for the boids from `first` on, which were just added
+    pub fn vary_traits_from(&mut self, first: usize) {
+        self.new_population();
+        for boid in &mut self.boids[first..] {
+            boid.vary_traits(self.trait_variance, &mut self.rng);
+        }
+    }
+
+    // Note that the boids have changed, so per-boid results worked out
+    // elsewhere for the old flock, like GPU steering still in flight, are
+    // dropped rather than applied to different boids at the same indices
+    pub fn new_population(&mut self) {
+        self.population += 1;
+    }
+
+    // Drop every boid for which `remove(index, boid)` holds, keeping the
+    // selections on the same boids
+    pub fn remove_boids(&mut self, mut remove: impl FnMut(usize, &Boid) -> bool) {
+        self.new_population();
+        let selected = self.inspector.selected;
+        let mut new_selected = None;
+        let mut index = 0;
+        let mut kept = 0;
+        let mut kept_flags = Vec::with_capacity(self.boids.len());
+        self.boids.retain(|boid| {
+            let keep = !remove(index, boid);
+            kept_flags.push(keep);
+            if keep {
+                if selected == Some(index) {
+                    new_selected = Some(kept);
+                }
+                kept += 1;
+            }
+            index += 1;
+            keep
+        });
+
+        self.inspector.selected = new_selected;
+        self.selection.retain_kept(&kept_flags);
+        self.trails.retain_kept(&kept_flags);
+        self.scoring.retain_kept(&kept_flags);
+        self.lod.retain_kept(&kept_flags);
+        debug!(removed = index - kept, remaining = kept, "Removed boids");
+    }
+
+    // Remove every boid in the group selection
+    pub fn delete_selection(&mut self) {
+        let selected = self.selection.mask(self.boids.len());
+        self.remove_boids(|i, _| selected[i]);
+    }
+
+    // Give the group selection one new random color
+    pub fn recolor_selection(&mut self) {
+        let color = [
+            (self.rng.gen::<f32>() * 128.0 + 128.0) / 255.0,
+            (self.rng.gen::<f32>() * 128.0 + 128.0) / 255.0,
+            (self.rng.gen::<f32>() * 128.0 + 128.0) / 255.0,
+            0.5,
+        ];
+        for &i in self.selection.members() {
+            if let Some(boid) = self.boids.get_mut(i) {
+                boid.color = color;
+            }
+        }
+    }
+
+    // Replace the group selection with predators, as many as there is room
+    // for, returning how many were converted
+    pub fn selection_to_predators(&mut self) -> usize {
+        let (members, boids) = (self.selection.members(), &self.boids);
+        let converted = self.predator_prey.convert(members.iter().filter_map(|&i| boids.get(i)));
+        let mut converting = vec![false; self.boids.len()];
+        for &i in members.iter().take(converted) {
+            converting[i] = true;
+        }
+        self.remove_boids(|i, _| converting[i]);
+        converted
+    }
+
+    // Add one boid at `point`, flying with the average velocity of the boids
+    // around it, or in a random direction if it is alone
+    pub fn spawn_boid_at(&mut self, point: glam::Vec2) {
+        let mut boid = Boid::new(self.world.width, self.world.height, &mut self.rng);
+        boid.vary_traits(self.trait_variance, &mut self.rng);
+
+        let (sum, count) = self.boids
+            .iter()
+            .filter(|other| (glam::vec2(other.x, other.y) - point).length_squared() < VISUAL_RANGE * VISUAL_RANGE)
+            .fold((glam::Vec2::ZERO, 0), |(sum, count), other| (sum + glam::vec2(other.dx, other.dy), count + 1));
+        let velocity = if count > 0 { sum / count as f32 } else { glam::vec2(boid.dx, boid.dy) };
+
+        spawn::place(&mut boid, point, velocity);
+        self.boids.push(boid);
+        self.new_population();
+        debug!(x = point.x, y = point.y, boids = self.boids.len(), "Spawned boid");
+    }
+
+    // The neighbor index currently selected for lookups
+    pub fn active_index(&self) -> &dyn NeighborIndex {
+        match self.index_kind {
+            IndexKind::Grid => &self.spatial_grid,
+            IndexKind::QuadTree => &self.quadtree,
+        }
+    }
+
+    // Update the active neighbor index with current boid positions
+    pub fn rebuild_index(&mut self) {
+        let index: &mut dyn NeighborIndex = match self.index_kind {
+            IndexKind::Grid => &mut self.spatial_grid,
+            IndexKind::QuadTree => &mut self.quadtree,
+        };
+        index.rebuild(&self.boids);
+    }
+
+    // Get neighbor lists for all boids using spatial partitioning. The lists
+    // hold every boid within the boid's own visual range, including itself.
+    fn get_all_neighbor_lists(&self) -> Vec<Vec<usize>> {
+        let index = self.active_index();
+        self.boids.iter()
+            .map(|boid| {
+                index.query_radius(glam::vec2(boid.x, boid.y), VISUAL_RANGE * boid.traits.range * self.range_scale)
+                    .into_iter()
+                    .map(|(i, _)| i)
+                    .collect()
+            })
+            .collect()
+    }
+
+    // Group boids into flocks, reusing the spatial grid for the neighbor queries
+    fn label_flocks(&mut self) {
+        // The grid is only kept current while it is the active index
+        if self.index_kind != IndexKind::Grid {
+            self.spatial_grid.rebuild(&self.boids);
+        }
+        metrics::label_flocks(&mut self.boids, &self.spatial_grid);
+    }
+
+    // Advance the simulation by a single step of `tick` seconds, with the
+    // mouse at `cursor` in world coordinates
+    pub fn step(&mut self, tick: f32, cursor: glam::Vec2) -> StepReport {
+        self.advance(tick, cursor, None)
+    }
+
+    // step, with the registered behaviors' steering and the neighbor count of
+    // each boid worked out elsewhere, like on the GPU. Ignored unless there is
+    // one of each per boid. The CPU neighbor search is then skipped, unless
+    // the energy model or the epidemic needs the neighbor lists.
+    #[cfg(feature = "gpu")]
+    pub fn step_with_steering(
+        &mut self,
+        tick: f32,
+        cursor: glam::Vec2,
+        steering: &[glam::Vec2],
+        neighbor_counts: &[u32],
+    ) -> StepReport {
+        self.advance(tick, cursor, Some((steering, neighbor_counts)))
+    }
+
+    // Build the neighbor lists if the last step skipped them, for whatever
+    // reads them between steps, like the flock statistics
+    pub fn refresh_neighbor_lists(&mut self) {
+        if self.neighbor_lists.len() != self.boids.len() {
+            self.neighbor_lists = self.get_all_neighbor_lists();
+        }
+    }
+
+    // A copy for drawing, without the neighbor lists, which are the largest
+    // part and only rebuilt on demand by refresh_neighbor_lists
+    pub fn render_view(&mut self) -> Simulation {
+        let neighbor_lists = std::mem::take(&mut self.neighbor_lists);
+        let view = self.clone();
+        self.neighbor_lists = neighbor_lists;
+        view
+    }
+
+    fn advance(&mut self, tick: f32, cursor: glam::Vec2, steering: Option<(&[glam::Vec2], &[u32])>) -> StepReport {
+        let mut report = StepReport::default();
+
+        // Update spatial grid
+        let index_start = Instant::now();
+        {
+            let _span = tracing::trace_span!("grid_rebuild").entered();
+            self.rebuild_index();
+        }
+        let rebuilt = Instant::now();
+        report.grid_rebuild = rebuilt - index_start;
+
+        if self.lod.active {
+            self.lod.plan(self.boids.iter().map(|boid| glam::vec2(boid.x, boid.y)));
+        }
+
+        let count = self.boids.len();
+        let steering = steering.filter(|(steering, counts)| steering.len() == count && counts.len() == count);
+        let needs_lists = steering.is_none() || self.energy_model || self.epidemic.active;
+
+        // Get neighbor lists for all boids, kept around for the metrics
+        if !needs_lists {
+            self.neighbor_lists.clear();
+        } else {
+            let _span = tracing::trace_span!("neighbors").entered();
+            self.neighbor_lists = self.get_all_neighbor_lists();
+
+            // Boids can't see each other through obstacles
+            if !self.obstacles.is_empty() {
+                let (boids, obstacles) = (&self.boids, &self.obstacles);
+                for (boid, neighbors) in boids.iter().zip(&mut self.neighbor_lists) {
+                    let pos = glam::vec2(boid.x, boid.y);
+                    neighbors.retain(|&j| !obstacles.blocks_sight(pos, glam::vec2(boids[j].x, boids[j].y)));
+                }
+            }
+        }
+        report.neighbors = rebuilt.elapsed();
+
+        // Flock IDs only need refreshing every so often
+        self.steps_since_labeling += 1;
+        if self.steps_since_labeling >= FLOCK_LABEL_INTERVAL {
+            self.steps_since_labeling = 0;
+            self.label_flocks();
+        }
+
+        let behaviors_start = Instant::now();
+        let behaviors_span = tracing::trace_span!("behaviors").entered();
+
+        // The music's gain goes on top of the separation weight for this step only
+        let behaviors = if self.separation_gain == 1.0 {
+            Cow::Borrowed(&self.behaviors)
+        } else {
+            let mut scaled = self.behaviors.clone();
+            let separation = behavior::weight(&scaled, "separation").unwrap_or(0.0);
+            behavior::set_weight(&mut scaled, "separation", separation * self.separation_gain);
+            Cow::Owned(scaled)
+        };
+
+        // Leaders are lost whenever the population is regenerated
+        if self.leader_mode != LeaderMode::Off && !self.boids.iter().any(|boid| boid.leader) {
+            leaders::assign_leaders(&mut self.boids, NUM_LEADERS, &mut self.rng);
+        }
+        let leader_positions = leaders::leader_positions(&self.boids);
+        if self.long_range.active() {
+            self.long_range.build(&self.boids);
+        }
+
+        // Update boids movement - non-parallel version
+        self.forces.resize(self.boids.len(), SteeringForces::default());
+        for i in 0..self.boids.len() {
+            // Make a copy of the boid to work with
+            let mut boid = self.boids[i];
+            if boid.leader {
+                // Leaders ignore the flock and do their own thing
+                boid.apply_force(self.leader_mode.leader_force(&boid, cursor, &mut self.rng));
+                self.forces[i] = SteeringForces::default();
+            } else if !self.lod.due(i) {
+                // Far from the camera, steer as at the last full update
+                boid.apply_force(self.lod.steering(i));
+                boid.wander(&mut self.rng, self.wander_strength);
+            } else {
+                let before = glam::vec2(boid.ax, boid.ay);
+                self.forces[i] = match steering {
+                    Some((steering, neighbor_counts)) => {
+                        boid.apply_force(steering[i]);
+                        boid.neighbor_count = neighbor_counts[i];
+                        SteeringForces::default()
+                    }
+                    None => boid.calculate_behaviors_capped(
+                        i,
+                        &self.neighbor_lists[i],
+                        &self.boids,
+                        &behaviors,
+                        &self.world,
+                        self.neighbor_cap,
+                    ),
+                };
+                if self.lod.active {
+                    self.lod.store(i, glam::vec2(boid.ax, boid.ay) - before);
+                }
+
+                boid.apply_force(leaders::follow_force(&boid, &leader_positions));
+                if self.long_range.active() {
+                    boid.apply_force(self.long_range.force(&boid));
+                }
+                boid.wander(&mut self.rng, self.wander_strength);
+
+                if self.energy_model {
+                    boid.apply_force(energy::drafting_force(&boid, &self.neighbor_lists[i], &self.boids));
+                }
+            }
+
+            // Push along any painted flow
+            if !self.flow_field.is_empty() {
+                boid.apply_force(self.flow_field.sample(boid.x, boid.y) * FLOW_STRENGTH);
+            }
+
+            // Weak attraction towards the current waypoint
+            boid.apply_force(self.waypoints.steer(&boid));
+            boid.apply_force(self.selection.steer(i, &boid));
+
+            if self.food.active {
+                boid.apply_force(self.food.steer(&boid));
+            }
+            if self.predator_prey.active {
+                boid.apply_force(self.predator_prey.flee_force(&boid));
+            }
+            if self.shepherd.active {
+                boid.apply_force(self.shepherd.scare_force(&boid));
+            }
+            if self.pheromones.mode == PheromoneMode::Follow {
+                boid.apply_force(self.pheromones.follow_force(&boid));
+            }
+            if !self.obstacles.is_empty() {
+                boid.apply_force(self.obstacles.avoid_force(&boid));
+            }
+
+            if !self.shockwaves.is_empty() {
+                boid.apply_force(self.shockwaves.force(&boid));
+            }
+
+            boid.apply_acceleration();
+            boid.limit_speed_scaled(self.speed_scale * self.speed_gain);
+            let from = glam::vec2(boid.x, boid.y);
+            boid.update_position(tick);
+            // Wind carries the boids along on top of their own flight
+            boid.x += self.wind.x * tick;
+            boid.y += self.wind.y * tick;
+            if !self.obstacles.is_empty() {
+                self.obstacles.resolve(&mut boid, from);
+            }
+            // Store the modified boid back in the collection
+            self.boids[i] = boid;
+        }
+
+        // Handle boundary checks and mouse interactions
+        for boid in &mut self.boids {
+            boid.keep_within_bounds(cursor.into(), self.boundary.as_ref(), self.boundary_mode);
+        }
+
+        if self.collisions.active {
+            self.collisions.resolve(&mut self.boids);
+        }
+
+        drop(behaviors_span);
+        report.behaviors = behaviors_start.elapsed();
+
+        self.waypoints.update(&self.boids);
+        self.shockwaves.update(tick);
+        if self.shepherd.active {
+            match self.shepherd.update(&self.boids, tick, &self.world) {
+                Some(Outcome::Won) => info!("Herding round won: {}", self.shepherd.hud_text()),
+                Some(Outcome::Lost) => info!("Herding round lost: {}", self.shepherd.hud_text()),
+                _ => {}
+            }
+        }
+
+        if self.pheromones.mode != PheromoneMode::Off {
+            self.pheromones.update(&self.boids, tick);
+        }
+        if self.energy_model && self.neighbor_lists.len() == self.boids.len() {
+            energy::update(&mut self.boids, &self.neighbor_lists, tick);
+        }
+        if self.epidemic.active && self.neighbor_lists.len() == self.boids.len() {
+            self.epidemic.step(&mut self.boids, &self.neighbor_lists, tick, &mut self.rng);
+        }
+        if self.evolution.is_active() {
+            let predators: Vec<glam::Vec2> = self.predator_prey.predators.iter().map(|predator| predator.pos).collect();
+            report.bred = self.evolution.step(&mut self.boids, &predators, tick, &mut self.rng);
+            if let Some(stats) = &report.bred {
+                info!(
+                    generation = stats.generation,
+                    best = stats.best_fitness,
+                    mean = stats.mean_fitness,
+                    "Generation bred"
+                );
+            }
+        }
+
+        if self.food.active {
+            let eaters = self.food.eat(&self.boids);
+            if !eaters.is_empty() {
+                debug!(eaten = eaters.len(), left = self.food.items.len(), "Food eaten");
+            }
+            if self.energy_model {
+                energy::feed(&mut self.boids, &eaters);
+            }
+            self.food.respawn(tick, &self.world, &mut self.rng);
+        }
+
+        // Caught boids are removed and newborn ones appended only after the
+        // whole population has been looked at, so indices hold during the step
+        if self.predator_prey.active {
+            let changes = self.predator_prey.step(&self.boids, tick, &self.world, &mut self.rng);
+            let caught = changes.caught.iter().filter(|&&caught| caught).count();
+            if caught > 0 {
+                self.remove_boids(|i, _| changes.caught[i]);
+            }
+            if caught > 0 || !changes.born.is_empty() {
+                debug!(caught, born = changes.born.len(), predators = self.predator_prey.predators.len(), "Population changed");
+            }
+            let before = self.boids.len();
+            self.boids.extend(changes.born);
+            self.vary_traits_from(before);
+        }
+
+        // Boids enter and leave through the zones. The neighbor index is rebuilt
+        // from scratch at the start of every step, so it stays consistent.
+        if !self.zones.is_empty() {
+            let sunk: Vec<bool> = self.boids.iter().map(|boid| self.zones.in_sink(boid)).collect();
+            if sunk.contains(&true) {
+                self.remove_boids(|i, _| sunk[i]);
+            }
+            let before = self.boids.len();
+            self.zones.emit(&mut self.boids, tick, &self.world, &mut self.rng);
+            if self.boids.len() > before {
+                debug!(emitted = self.boids.len() - before, "Emitted boids");
+                self.vary_traits_from(before);
+            }
+        }
+
+        // Counted last, once the population is settled for this step
+        if !self.scoring.is_empty() {
+            self.scoring.update(&self.boids, tick);
+        }
+
+        report
+    }
+}
@@ -0,0 +1,68 @@
+// skin.rs
+use ggez::graphics;
+use ggez::{Context, GameError, GameResult};
+use std::path::Path;
+
+// A user supplied image drawn in place of the boid polygon. The image should
+// point up, like the polygon does. Animated skins are sprite sheets with the
+// frames side by side, picked by each boid's wing phase.
+pub struct Skin {
+    instances: graphics::InstanceArray,
+    height: f32, // Pixels, used to scale the sprite to the boid size
+    frames: u32,
+}
+
+impl Skin {
+    // Any PNG works, it is scaled so its height matches the boid size
+    pub fn load(ctx: &Context, path: &Path, frames: u32) -> GameResult<Skin> {
+        let rgba = image::open(path)
+            .map_err(|err| GameError::ResourceLoadError(format!("{}: {}", path.display(), err)))?
+            .to_rgba8();
+        let (width, height) = rgba.dimensions();
+        if frames == 0 || width < frames {
+            return Err(GameError::ResourceLoadError(format!(
+                "{}: can't split {} pixels into {} frames",
+                path.display(),
+                width,
+                frames
+            )));
+        }
+
+        let image = graphics::Image::from_pixels(ctx, &rgba, graphics::ImageFormat::Rgba8UnormSrgb, width, height);
+        Ok(Skin {
+            instances: graphics::InstanceArray::new(ctx, image),
+            height: height as f32,
+            frames,
+        })
+    }
+
+    // Draw one sprite per boid, `size` pixels tall and tinted by `colors`.
+    // Each sprite is given as position, rotation and wing phase.
+    pub fn draw(
+        &mut self,
+        canvas: &mut graphics::Canvas,
+        sprites: impl Iterator<Item = (glam::Vec2, f32, f32)>,
+        colors: &[[f32; 4]],
+        size: f32,
+        param: graphics::DrawParam,
+    ) -> GameResult {
+        let scale = size / self.height;
+        let frame_width = 1.0 / self.frames as f32;
+
+        self.instances.clear();
+        for ((pos, rotation, phase), color) in sprites.zip(colors) {
+            let frame = ((phase * self.frames as f32) as u32).min(self.frames - 1);
+            self.instances.push(
+                graphics::DrawParam::new()
+                    .src(graphics::Rect::new(frame as f32 * frame_width, 0.0, frame_width, 1.0))
+                    .dest(pos)
+                    .rotation(rotation)
+                    .offset(glam::vec2(0.5, 0.5))
+                    .scale(glam::vec2(scale, scale))
+                    .color(*color),
+            );
+        }
+        canvas.draw(&self.instances, param);
+        Ok(())
+    }
+}
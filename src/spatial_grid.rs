@@ -1,74 +1,555 @@
 // spatial_grid.rs
 use crate::boid::Boid;
+use crate::neighbor_index::NeighborIndex;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::sync::atomic::{AtomicUsize, Ordering};
 
-// Spatial grid for faster neighbor lookups
+// Cells per side of the coarse blocks that long-range queries scan
+const BLOCK: usize = 4;
+// Queries spanning more cells than this per side scan blocks instead
+const MAX_CELL_SPAN: isize = 7;
+
+// Anything with a position in world space can be stored in the grid: boids,
+// obstacles, attractors, food...
+pub trait Position2D {
+    fn position(&self) -> glam::Vec2;
+}
+
+impl Position2D for glam::Vec2 {
+    fn position(&self) -> glam::Vec2 {
+        *self
+    }
+}
+
+// Query counters since the last call to take_stats
+#[derive(Debug, Clone, Copy, Default)]
+pub struct QueryStats {
+    pub queries: usize,
+    pub candidates: usize, // Items looked at in the scanned cells
+    pub matches: usize,    // Of those, items actually within the radius
+}
+
+// Spatial grid for faster neighbor lookups. Items are stored by index into
+// whatever slice the caller keeps them in, along with their position.
+//
+// Rather than a Vec per cell, the entries live in one flat array sorted by
+// cell with a counting sort, so a cell is a contiguous slice and rebuilding
+// every frame doesn't touch the allocator.
+//
+// The cells are numbered block by block, BLOCK x BLOCK cells at a time, so
+// each block is a contiguous slice as well. That makes a second, coarser grid
+// for free: a 200px predator query on a 16px grid scans a few blocks rather
+// than hundreds of cells, while short-range queries keep the fine cells.
 pub struct SpatialGrid {
-    cells: Vec<Vec<usize>>,
+    cell_start: Vec<usize>,            // Cell c holds entries[cell_start[c]..cell_start[c + 1]]
+    entries: Vec<(usize, glam::Vec2)>, // Index and position, grouped by cell
+    items: Vec<(usize, glam::Vec2)>,   // Everything inserted, in insertion order
+    item_cells: Vec<usize>,            // Cell of each of `items`, scratch for the sort
+    unsorted: bool,                    // Items were inserted since the last sort
     cell_size: f32,
     width: usize,
     height: usize,
+    blocks_wide: usize,
+    blocks_high: usize,
+    queries: AtomicUsize,
+    candidates: AtomicUsize,
+    matches: AtomicUsize,
+}
+
+// The counters are atomics so queries can count through a shared reference,
+// which rules out deriving Clone
+impl Clone for SpatialGrid {
+    fn clone(&self) -> Self {
+        SpatialGrid {
+            cell_start: self.cell_start.clone(),
+            entries: self.entries.clone(),
+            items: self.items.clone(),
+            item_cells: self.item_cells.clone(),
+            unsorted: self.unsorted,
+            cell_size: self.cell_size,
+            width: self.width,
+            height: self.height,
+            blocks_wide: self.blocks_wide,
+            blocks_high: self.blocks_high,
+            queries: AtomicUsize::new(self.queries.load(Ordering::Relaxed)),
+            candidates: AtomicUsize::new(self.candidates.load(Ordering::Relaxed)),
+            matches: AtomicUsize::new(self.matches.load(Ordering::Relaxed)),
+        }
+    }
 }
 
 impl SpatialGrid {
     pub fn new(window_width: f32, window_height: f32, cell_size: f32) -> Self {
-        let width = (window_width / cell_size).ceil() as usize;
-        let height = (window_height / cell_size).ceil() as usize;
-        let cells = vec![Vec::new(); width * height];
-        
+        let width = ((window_width / cell_size).ceil() as usize).max(1);
+        let height = ((window_height / cell_size).ceil() as usize).max(1);
+        let blocks_wide = (width + BLOCK - 1) / BLOCK;
+        let blocks_high = (height + BLOCK - 1) / BLOCK;
         SpatialGrid {
-            cells,
+            cell_start: vec![0; blocks_wide * blocks_high * BLOCK * BLOCK + 1],
+            entries: Vec::new(),
+            items: Vec::new(),
+            item_cells: Vec::new(),
+            unsorted: false,
             cell_size,
             width,
             height,
+            blocks_wide,
+            blocks_high,
+            queries: AtomicUsize::new(0),
+            candidates: AtomicUsize::new(0),
+            matches: AtomicUsize::new(0),
+        }
+    }
+
+    pub fn cell_size(&self) -> f32 {
+        self.cell_size
+    }
+
+    // Number of cells horizontally and vertically
+    pub fn dimensions(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+
+    // Number of items stored in the given cell
+    pub fn occupancy(&self, cell_x: usize, cell_y: usize) -> usize {
+        if cell_x >= self.width || cell_y >= self.height {
+            return 0;
+        }
+        self.cell(self.cell_index(cell_x, cell_y)).len()
+    }
+
+    // Average items per non-empty cell
+    pub fn average_occupancy(&self) -> f32 {
+        let (items, occupied) = self
+            .cell_start
+            .windows(2)
+            .map(|bounds| bounds[1] - bounds[0])
+            .filter(|&count| count > 0)
+            .fold((0, 0), |(items, occupied), count| (items + count, occupied + 1));
+        if occupied == 0 {
+            0.0
+        } else {
+            items as f32 / occupied as f32
+        }
+    }
+
+    // Cells for `count` new items that even out the density: each goes in the
+    // emptiest cell, counting the ones placed before it. Equally empty cells
+    // are taken in a scrambled order, so a sparse grid fills evenly instead
+    // of row by row. Only cells whose center passes `accept` are used, such
+    // as those inside the arena.
+    pub fn sparsest_cells(&self, count: usize, accept: impl Fn(glam::Vec2) -> bool) -> Vec<(usize, usize)> {
+        let center = |cell_x: usize, cell_y: usize| (glam::vec2(cell_x as f32, cell_y as f32) + 0.5) * self.cell_size;
+        let mut heap: BinaryHeap<Reverse<(usize, u32, usize, usize)>> = (0..self.height)
+            .flat_map(|cell_y| (0..self.width).map(move |cell_x| (cell_x, cell_y)))
+            .filter(|&(cell_x, cell_y)| accept(center(cell_x, cell_y)))
+            .map(|(cell_x, cell_y)| Reverse((self.occupancy(cell_x, cell_y), scramble(cell_x, cell_y), cell_x, cell_y)))
+            .collect();
+        let mut cells = Vec::with_capacity(count);
+        while cells.len() < count {
+            let Reverse((occupancy, order, cell_x, cell_y)) = match heap.pop() {
+                Some(cell) => cell,
+                None => break,
+            };
+            cells.push((cell_x, cell_y));
+            heap.push(Reverse((occupancy + 1, order, cell_x, cell_y)));
+        }
+        cells
+    }
+
+    // Counters for the radius queries since the last call, then reset them
+    pub fn take_stats(&self) -> QueryStats {
+        QueryStats {
+            queries: self.queries.swap(0, Ordering::Relaxed),
+            candidates: self.candidates.swap(0, Ordering::Relaxed),
+            matches: self.matches.swap(0, Ordering::Relaxed),
         }
     }
-    
+
     pub fn clear(&mut self) {
-        for cell in &mut self.cells {
-            cell.clear();
-        }
-    }
-    
-    pub fn insert(&mut self, boid_index: usize, boid: &Boid) {
-        let cell_x = (boid.x / self.cell_size).floor() as usize;
-        let cell_y = (boid.y / self.cell_size).floor() as usize;
-        
-        // Clamp to ensure we don't go out of bounds
-        let cell_x = cell_x.min(self.width - 1);
-        let cell_y = cell_y.min(self.height - 1);
-        
-        let idx = cell_y * self.width + cell_x;
-        if idx < self.cells.len() {
-            self.cells[idx].push(boid_index);
-        }
-    }
-    
-    pub fn get_neighbors(&self, boid: &Boid, range: f32) -> Vec<usize> {
-        let mut neighbors = Vec::new();
-        
-        // Calculate the cell range to check
-        let cell_range = (range / self.cell_size).ceil() as usize + 1;
-        let cx = (boid.x / self.cell_size).floor() as isize;
-        let cy = (boid.y / self.cell_size).floor() as isize;
-        
-        // Check all cells in range
-        for y in (cy - cell_range as isize)..=(cy + cell_range as isize) {
-            if y < 0 || y >= self.height as isize {
-                continue;
-            }
-            
-            for x in (cx - cell_range as isize)..=(cx + cell_range as isize) {
-                if x < 0 || x >= self.width as isize {
-                    continue;
+        self.items.clear();
+        self.entries.clear();
+        self.unsorted = false;
+        for start in &mut self.cell_start {
+            *start = 0;
+        }
+    }
+
+    // Replace the contents with `items`, each stored under its index in the
+    // slice. Items outside the grid go in the nearest edge cell.
+    pub fn build<P: Position2D>(&mut self, items: &[P]) {
+        self.items.clear();
+        self.items.extend(items.iter().enumerate().map(|(i, item)| (i, item.position())));
+        self.sort();
+    }
+
+    // Add a single item. It only reaches its cell on the next sort_pending,
+    // so a frame's worth of inserts costs one sort.
+    pub fn insert_item<P: Position2D>(&mut self, index: usize, item: &P) {
+        self.items.push((index, item.position()));
+        self.unsorted = true;
+    }
+
+    // Sort the items inserted since the last sort into their cells
+    pub fn sort_pending(&mut self) {
+        if self.unsorted {
+            self.sort();
+        }
+    }
+
+    // Counting sort of `items` into `entries` by cell
+    fn sort(&mut self) {
+        let cell_count = self.cell_start.len() - 1;
+        self.unsorted = false;
+
+        // Count the items per cell, shifted by one so the prefix sum gives the starts
+        self.item_cells.clear();
+        self.cell_start.iter_mut().for_each(|start| *start = 0);
+        for &(_, pos) in &self.items {
+            let (cell_x, cell_y) = self.cell_of(pos);
+            let cell = self.cell_index(cell_x, cell_y);
+            self.item_cells.push(cell);
+            self.cell_start[cell + 1] += 1;
+        }
+        for cell in 0..cell_count {
+            self.cell_start[cell + 1] += self.cell_start[cell];
+        }
+
+        // Place each item at the next free slot of its cell. The starts are
+        // bumped while filling and shifted back afterwards.
+        self.entries.clear();
+        self.entries.resize(self.items.len(), (0, glam::Vec2::ZERO));
+        for (&item, &cell) in self.items.iter().zip(&self.item_cells) {
+            self.entries[self.cell_start[cell]] = item;
+            self.cell_start[cell] += 1;
+        }
+        for cell in (1..=cell_count).rev() {
+            self.cell_start[cell] = self.cell_start[cell - 1];
+        }
+        self.cell_start[0] = 0;
+    }
+
+    // Position of a cell in the block by block numbering
+    fn cell_index(&self, cell_x: usize, cell_y: usize) -> usize {
+        let block = (cell_y / BLOCK) * self.blocks_wide + cell_x / BLOCK;
+        (block * BLOCK + cell_y % BLOCK) * BLOCK + cell_x % BLOCK
+    }
+
+    // Entries stored in the cell with the given index
+    fn cell(&self, cell: usize) -> &[(usize, glam::Vec2)] {
+        debug_assert!(!self.unsorted, "query before sort_pending");
+        &self.entries[self.cell_start[cell]..self.cell_start[cell + 1]]
+    }
+
+    // Entries stored in any cell of the given block
+    fn block(&self, block_x: usize, block_y: usize) -> &[(usize, glam::Vec2)] {
+        debug_assert!(!self.unsorted, "query before sort_pending");
+        let first = (block_y * self.blocks_wide + block_x) * BLOCK * BLOCK;
+        &self.entries[self.cell_start[first]..self.cell_start[first + BLOCK * BLOCK]]
+    }
+
+    // Indices of the items in every cell within `range` of `item`. This is a
+    // superset of the items actually in range.
+    pub fn items_near<P: Position2D>(&self, item: &P, range: f32) -> Vec<usize> {
+        self.cells_near(item.position(), range).map(|&(i, _)| i).collect()
+    }
+
+    // Index and squared distance of every item strictly within `radius` of `pos`
+    pub fn query_radius(&self, pos: glam::Vec2, radius: f32) -> Vec<(usize, f32)> {
+        let mut candidates = 0;
+        let found: Vec<(usize, f32)> = self
+            .cells_near(pos, radius)
+            .inspect(|_| candidates += 1)
+            .map(|&(i, other)| (i, pos.distance_squared(other)))
+            .filter(|&(_, squared_distance)| squared_distance < radius * radius)
+            .collect();
+
+        self.queries.fetch_add(1, Ordering::Relaxed);
+        self.candidates.fetch_add(candidates, Ordering::Relaxed);
+        self.matches.fetch_add(found.len(), Ordering::Relaxed);
+        found
+    }
+
+    // Side length of the square scanned by a query of the given radius
+    pub fn scan_width(cell_size: f32, radius: f32) -> f32 {
+        let cell_range = (radius / cell_size).ceil() + 1.0;
+        if cell_range as isize > MAX_CELL_SPAN {
+            let block_size = cell_size * BLOCK as f32;
+            return ((2.0 * radius / block_size).ceil() + 1.0) * block_size;
+        }
+        (2.0 * cell_range + 1.0) * cell_size
+    }
+
+    // Everything stored in the cells within `range` of `pos`, or in the
+    // blocks around it for long ranges
+    fn cells_near(&self, pos: glam::Vec2, range: f32) -> impl Iterator<Item = &(usize, glam::Vec2)> + '_ {
+        let cell_range = (range / self.cell_size).ceil() as isize + 1;
+        let (columns, rows, blocks) = if cell_range > MAX_CELL_SPAN {
+            // Exactly the blocks the circle's bounding box touches, clamped
+            // like cell_of so items outside the grid are still found
+            let block_size = self.cell_size * BLOCK as f32;
+            let block = |value: f32, count: usize| ((value / block_size).floor().max(0.0) as usize).min(count - 1);
+            let columns = block(pos.x - range, self.blocks_wide)..=block(pos.x + range, self.blocks_wide);
+            let rows = block(pos.y - range, self.blocks_high)..=block(pos.y + range, self.blocks_high);
+            (columns, rows, true)
+        } else {
+            let cx = (pos.x / self.cell_size).floor() as isize;
+            let cy = (pos.y / self.cell_size).floor() as isize;
+            let span = |center: isize, count: usize| {
+                let first = (center - cell_range).max(0);
+                let last = (center + cell_range).min(count as isize - 1);
+                // An empty range when the query is wholly off one side
+                if first > last {
+                    1..=0
+                } else {
+                    first as usize..=last as usize
                 }
-                
-                let idx = (y as usize) * self.width + (x as usize);
-                if idx < self.cells.len() {
-                    neighbors.extend_from_slice(&self.cells[idx]);
+            };
+            (span(cx, self.width), span(cy, self.height), false)
+        };
+
+        rows.flat_map(move |y| {
+            columns.clone().flat_map(move |x| {
+                if blocks {
+                    self.block(x, y).iter()
+                } else {
+                    self.cell(self.cell_index(x, y)).iter()
                 }
+            })
+        })
+    }
+
+    // Indices of the items in every cell overlapping the rectangle from `min` to `max`
+    pub fn items_in_rect(&self, min: glam::Vec2, max: glam::Vec2) -> Vec<usize> {
+        // Edge cells also hold items outside the grid, so clamp rather than skip
+        let (first_x, first_y) = self.cell_of(min);
+        let (last_x, last_y) = self.cell_of(max);
+
+        let mut found = Vec::new();
+        for y in first_y..=last_y {
+            for x in first_x..=last_x {
+                found.extend(self.cell(self.cell_index(x, y)).iter().map(|&(i, _)| i));
             }
         }
-        
-        neighbors
+
+        found
+    }
+
+    // Cell containing `pos`, clamped to the grid
+    fn cell_of(&self, pos: glam::Vec2) -> (usize, usize) {
+        let cell = |value: f32, count: usize| ((value / self.cell_size).floor().max(0.0) as usize).min(count - 1);
+        (cell(pos.x, self.width), cell(pos.y, self.height))
+    }
+}
+
+impl NeighborIndex for SpatialGrid {
+    fn clear(&mut self) {
+        SpatialGrid::clear(self);
+    }
+
+    fn insert(&mut self, boid_index: usize, boid: &Boid) {
+        self.insert_item(boid_index, boid);
+    }
+
+    fn finish(&mut self) {
+        self.sort_pending();
+    }
+
+    fn rebuild(&mut self, boids: &[Boid]) {
+        self.build(boids);
+    }
+
+    fn get_neighbors(&self, boid: &Boid, range: f32) -> Vec<usize> {
+        self.items_near(boid, range)
+    }
+
+    fn query_rect(&self, min: glam::Vec2, max: glam::Vec2) -> Vec<usize> {
+        self.items_in_rect(min, max)
+    }
+
+    fn query_radius(&self, pos: glam::Vec2, radius: f32) -> Vec<(usize, f32)> {
+        SpatialGrid::query_radius(self, pos, radius)
+    }
+}
+
+// Fixed pseudo-random rank of a cell, a multiplicative hash of its coordinates
+fn scramble(cell_x: usize, cell_y: usize) -> u32 {
+    ((cell_x as u32).wrapping_mul(0x9E37_79B9) ^ (cell_y as u32).wrapping_mul(0x85EB_CA6B)).wrapping_mul(0xC2B2_AE35)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grid_with(points: &[glam::Vec2]) -> SpatialGrid {
+        let mut grid = SpatialGrid::new(100.0, 100.0, 10.0);
+        grid.build(points);
+        grid
+    }
+
+    #[test]
+    fn dimensions_round_up() {
+        let grid = SpatialGrid::new(95.0, 41.0, 10.0);
+        assert_eq!(grid.dimensions(), (10, 5));
+    }
+
+    #[test]
+    fn finds_nearby_items_and_skips_distant_ones() {
+        let grid = grid_with(&[glam::vec2(50.0, 50.0), glam::vec2(55.0, 52.0), glam::vec2(95.0, 5.0)]);
+
+        let found = grid.items_near(&glam::vec2(51.0, 51.0), 5.0);
+        assert!(found.contains(&0));
+        assert!(found.contains(&1));
+        assert!(!found.contains(&2));
+    }
+
+    #[test]
+    fn radius_query_is_exact() {
+        let grid = grid_with(&[glam::vec2(50.0, 50.0), glam::vec2(53.0, 54.0), glam::vec2(58.0, 50.0)]);
+
+        let mut found = grid.query_radius(glam::vec2(50.0, 50.0), 6.0);
+        found.sort_by_key(|&(i, _)| i);
+        assert_eq!(found, vec![(0, 0.0), (1, 25.0)]);
+    }
+
+    #[test]
+    fn items_outside_the_grid_land_in_edge_cells() {
+        let grid = grid_with(&[glam::vec2(-20.0, 50.0), glam::vec2(150.0, 150.0)]);
+
+        assert_eq!(grid.occupancy(0, 5), 1);
+        assert_eq!(grid.occupancy(9, 9), 1);
+        assert!(grid.items_near(&glam::vec2(2.0, 50.0), 5.0).contains(&0));
+    }
+
+    #[test]
+    fn rect_query_covers_overlapping_cells() {
+        let grid = grid_with(&[glam::vec2(15.0, 15.0), glam::vec2(35.0, 35.0), glam::vec2(85.0, 85.0)]);
+
+        let mut found = grid.items_in_rect(glam::vec2(10.0, 10.0), glam::vec2(40.0, 40.0));
+        found.sort_unstable();
+        assert_eq!(found, vec![0, 1]);
+    }
+
+    #[test]
+    fn single_inserts_match_a_bulk_build() {
+        let points = [glam::vec2(15.0, 15.0), glam::vec2(16.0, 14.0), glam::vec2(85.0, 85.0)];
+        let mut grid = SpatialGrid::new(100.0, 100.0, 10.0);
+        for (i, point) in points.iter().enumerate() {
+            grid.insert_item(i, point);
+        }
+        grid.sort_pending();
+
+        assert_eq!(grid.occupancy(1, 1), 2);
+        assert_eq!(grid.occupancy(8, 8), 1);
+        assert_eq!(grid.average_occupancy(), 1.5);
+    }
+
+    #[test]
+    fn long_range_queries_scan_blocks_and_stay_exact() {
+        let grid = grid_with(&[glam::vec2(5.0, 5.0), glam::vec2(60.0, 70.0), glam::vec2(95.0, 95.0)]);
+
+        let mut found = grid.query_radius(glam::vec2(10.0, 10.0), 90.0);
+        found.sort_by_key(|&(i, _)| i);
+        assert_eq!(found.iter().map(|&(i, _)| i).collect::<Vec<_>>(), vec![0, 1]);
+    }
+
+    #[test]
+    fn clear_empties_every_cell() {
+        let mut grid = grid_with(&[glam::vec2(15.0, 15.0), glam::vec2(85.0, 85.0)]);
+        grid.clear();
+
+        assert!(grid.items_in_rect(glam::Vec2::ZERO, glam::vec2(100.0, 100.0)).is_empty());
+    }
+
+    mod properties {
+        use super::*;
+        use proptest::prelude::*;
+
+        // Mostly anywhere in or just around the 100x100 grid, but often exactly
+        // on a cell boundary or a window edge where rounding bites
+        fn coordinate() -> impl Strategy<Value = f32> {
+            prop_oneof![
+                -5.0f32..105.0,
+                (0u32..=10).prop_map(|cell| cell as f32 * 10.0),
+                prop_oneof![Just(0.0f32), Just(99.999), Just(100.0), Just(-0.001)],
+            ]
+        }
+
+        fn point() -> impl Strategy<Value = glam::Vec2> {
+            (coordinate(), coordinate()).prop_map(|(x, y)| glam::vec2(x, y))
+        }
+
+        fn within(points: &[glam::Vec2], center: glam::Vec2, range: f32) -> Vec<usize> {
+            (0..points.len())
+                .filter(|&i| points[i].distance_squared(center) < range * range)
+                .collect()
+        }
+
+        proptest! {
+            #[test]
+            fn items_near_never_misses_a_neighbor(
+                points in prop::collection::vec(point(), 1..200),
+                center in point(),
+                range in 1.0f32..40.0,
+            ) {
+                let grid = grid_with(&points);
+                let found = grid.items_near(&center, range);
+
+                for i in within(&points, center, range) {
+                    prop_assert!(found.contains(&i), "missed {:?} near {:?}", points[i], center);
+                }
+            }
+
+            #[test]
+            fn radius_query_matches_brute_force(
+                points in prop::collection::vec(point(), 1..200),
+                center in point(),
+                range in 1.0f32..40.0,
+            ) {
+                let grid = grid_with(&points);
+                let mut found: Vec<usize> = grid.query_radius(center, range).into_iter().map(|(i, _)| i).collect();
+                found.sort_unstable();
+
+                prop_assert_eq!(found, within(&points, center, range));
+            }
+
+            #[test]
+            fn every_item_is_stored_once(points in prop::collection::vec(point(), 0..200)) {
+                let grid = grid_with(&points);
+                let mut found = grid.items_in_rect(glam::vec2(-10.0, -10.0), glam::vec2(110.0, 110.0));
+                found.sort_unstable();
+
+                prop_assert_eq!(found, (0..points.len()).collect::<Vec<_>>());
+            }
+        }
+    }
+
+    #[test]
+    fn sparsest_cells_even_out_the_density() {
+        // Three items crowd the top-left of a 10x10 cell grid
+        let grid = grid_with(&[glam::vec2(5.0, 5.0); 3]);
+
+        // The 99 empty cells are filled once each before any gets a second item
+        let mut cells = grid.sparsest_cells(99, |_| true);
+        assert!(!cells.contains(&(0, 0)));
+        cells.sort_unstable();
+        cells.dedup();
+        assert_eq!(cells.len(), 99);
+
+        // The crowded cell only gets more once the rest have caught up
+        let cells = grid.sparsest_cells(3 * 99, |_| true);
+        assert!(!cells.contains(&(0, 0)));
+        assert!(cells.iter().all(|cell| cells.iter().filter(|&other| other == cell).count() == 3));
+    }
+
+    #[test]
+    fn sparsest_cells_skip_rejected_cells() {
+        let grid = grid_with(&[glam::vec2(5.0, 5.0)]);
+
+        // Only the left half of the grid is accepted, however many are asked for
+        let cells = grid.sparsest_cells(200, |center| center.x < 50.0);
+        assert_eq!(cells.len(), 200);
+        assert!(cells.iter().all(|&(cell_x, _)| cell_x < 5));
     }
 }
@@ -0,0 +1,118 @@
+// spawn.rs
+use crate::boid::{Boid, SPEED_LIMIT};
+use crate::math;
+use crate::world::World;
+use rand::Rng;
+use std::f32::consts::PI;
+
+// Spawn settings
+const RING_RADIUS: f32 = 0.35;     // Fraction of the smaller world dimension
+const GROUP_RADIUS: f32 = 0.15;    // Fraction of the smaller world dimension
+const BURST_JITTER: f32 = 2.0;     // Pixels, keeps burst boids from overlapping exactly
+pub const INITIAL_SPEED: f32 = SPEED_LIMIT * 0.5;
+
+// Initial arrangement of a new flock, cycled with <o> in the setup menu
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpawnPattern {
+    Blob,           // Random positions in the middle of the world
+    Uniform,        // Random positions anywhere in the world
+    Ring,           // Evenly spaced on a circle, circling
+    Grid,           // Evenly spaced rows and columns
+    OpposingGroups, // Two clusters flying at each other
+    Burst,          // Everyone from one point, flying outwards
+}
+
+impl SpawnPattern {
+    pub fn next(self) -> SpawnPattern {
+        match self {
+            SpawnPattern::Blob => SpawnPattern::Uniform,
+            SpawnPattern::Uniform => SpawnPattern::Ring,
+            SpawnPattern::Ring => SpawnPattern::Grid,
+            SpawnPattern::Grid => SpawnPattern::OpposingGroups,
+            SpawnPattern::OpposingGroups => SpawnPattern::Burst,
+            SpawnPattern::Burst => SpawnPattern::Blob,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            SpawnPattern::Blob => "Blob",
+            SpawnPattern::Uniform => "Uniform",
+            SpawnPattern::Ring => "Ring",
+            SpawnPattern::Grid => "Grid",
+            SpawnPattern::OpposingGroups => "Opposing groups",
+            SpawnPattern::Burst => "Burst",
+        }
+    }
+
+    pub fn spawn(self, count: usize, world: &World, rng: &mut impl Rng) -> Vec<Boid> {
+        let center = world.center();
+        let min_dimension = world.width.min(world.height);
+
+        // Grid layout matching the world's aspect ratio
+        let columns = ((count as f32 * world.width / world.height).sqrt().ceil() as usize).max(1);
+        let rows = (count + columns - 1) / columns.max(1);
+
+        (0..count)
+            .map(|i| {
+                // Start from a random boid so the other fields are filled in as usual
+                let mut boid = Boid::new(world.width, world.height, rng);
+                match self {
+                    SpawnPattern::Blob => {}
+                    SpawnPattern::Uniform => {
+                        boid.x = rng.gen::<f32>() * world.width;
+                        boid.y = rng.gen::<f32>() * world.height;
+                    }
+                    SpawnPattern::Ring => {
+                        let angle = i as f32 / count as f32 * 2.0 * PI;
+                        let (sin, cos) = math::sin_cos(angle);
+                        let offset = glam::vec2(cos, sin);
+                        let position = center + offset * min_dimension * RING_RADIUS;
+                        let tangent = glam::vec2(-offset.y, offset.x);
+                        place(&mut boid, position, tangent * INITIAL_SPEED);
+                    }
+                    SpawnPattern::Grid => {
+                        let spacing = glam::vec2(
+                            world.width / (columns + 1) as f32,
+                            world.height / (rows + 1) as f32,
+                        );
+                        boid.x = ((i % columns) + 1) as f32 * spacing.x;
+                        boid.y = ((i / columns) + 1) as f32 * spacing.y;
+                    }
+                    SpawnPattern::OpposingGroups => {
+                        // Even boids on the left flying right, odd ones the other way
+                        let side = if i % 2 == 0 { -1.0 } else { 1.0 };
+                        let group_center = center + glam::vec2(side * world.width / 4.0, 0.0);
+                        let position = group_center + random_in_circle(rng) * min_dimension * GROUP_RADIUS;
+                        place(&mut boid, position, glam::vec2(-side * INITIAL_SPEED, 0.0));
+                    }
+                    SpawnPattern::Burst => {
+                        let angle = rng.gen::<f32>() * 2.0 * PI;
+                        let (sin, cos) = math::sin_cos(angle);
+                        let direction = glam::vec2(cos, sin);
+                        let position = center + random_in_circle(rng) * BURST_JITTER;
+                        place(&mut boid, position, direction * INITIAL_SPEED);
+                    }
+                }
+                boid
+            })
+            .collect()
+    }
+}
+
+// Move a boid to `position` flying with `velocity`
+pub fn place(boid: &mut Boid, position: glam::Vec2, velocity: glam::Vec2) {
+    boid.x = position.x;
+    boid.y = position.y;
+    boid.dx = velocity.x;
+    boid.dy = velocity.y;
+    boid.heading = math::atan2(velocity.y, velocity.x);
+}
+
+// Uniformly distributed point in the unit circle
+pub fn random_in_circle(rng: &mut impl Rng) -> glam::Vec2 {
+    let angle = rng.gen::<f32>() * 2.0 * PI;
+    let radius = rng.gen::<f32>().sqrt();
+    let (sin, cos) = math::sin_cos(angle);
+    glam::vec2(cos, sin) * radius
+}
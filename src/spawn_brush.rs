@@ -0,0 +1,92 @@
+// spawn_brush.rs
+use crate::boid::Boid;
+use crate::spawn::{self, INITIAL_SPEED};
+use crate::world::World;
+use ggez::{graphics, GameResult};
+use rand::Rng;
+use tracing::info;
+
+// Brush settings
+const BRUSH_RADIUS: f32 = 32.0;        // Pixels, in world space
+const BRUSH_RATE: f32 = 200.0;         // Boids per second while painting
+const BRUSH_RADIUS_RANGE: (f32, f32) = (4.0, 256.0);
+const BRUSH_RATE_RANGE: (f32, f32) = (10.0, 5000.0);
+const BRUSH_ADJUST_STEP: f32 = 1.25;   // Multiplier per key press
+
+// Paints boids along the mouse path while <z> is held
+pub struct SpawnBrush {
+    pub radius: f32,
+    pub rate: f32,
+    pending: f32,                     // Fractional boids owed from previous frames
+    last_point: Option<glam::Vec2>,   // Cursor position on the previous painted frame
+}
+
+impl SpawnBrush {
+    pub fn new() -> Self {
+        SpawnBrush {
+            radius: BRUSH_RADIUS,
+            rate: BRUSH_RATE,
+            pending: 0.0,
+            last_point: None,
+        }
+    }
+
+    pub fn adjust_radius(&mut self, larger: bool) {
+        let factor = if larger { BRUSH_ADJUST_STEP } else { 1.0 / BRUSH_ADJUST_STEP };
+        self.radius = (self.radius * factor).max(BRUSH_RADIUS_RANGE.0).min(BRUSH_RADIUS_RANGE.1);
+        info!("Brush radius: {:.0}px", self.radius);
+    }
+
+    pub fn adjust_rate(&mut self, faster: bool) {
+        let factor = if faster { BRUSH_ADJUST_STEP } else { 1.0 / BRUSH_ADJUST_STEP };
+        self.rate = (self.rate * factor).max(BRUSH_RATE_RANGE.0).min(BRUSH_RATE_RANGE.1);
+        info!("Brush rate: {:.0} boids/s", self.rate);
+    }
+
+    // Called when the brush key is released so the next stroke starts fresh
+    pub fn release(&mut self) {
+        self.pending = 0.0;
+        self.last_point = None;
+    }
+
+    // Spawn the boids due over `dt` seconds, spread along the path from the
+    // previous cursor position to `point`. They fly in the direction of the stroke.
+    pub fn paint(
+        &mut self,
+        boids: &mut Vec<Boid>,
+        point: glam::Vec2,
+        dt: f32,
+        world: &World,
+        rng: &mut impl Rng,
+    ) {
+        let from = self.last_point.unwrap_or(point);
+        self.last_point = Some(point);
+        let stroke = point - from;
+
+        self.pending += self.rate * dt;
+        while self.pending >= 1.0 {
+            self.pending -= 1.0;
+
+            let mut boid = Boid::new(world.width, world.height, rng);
+            let position = from + stroke * rng.gen::<f32>() + spawn::random_in_circle(rng) * self.radius;
+            let velocity = if stroke == glam::Vec2::ZERO {
+                glam::vec2(boid.dx, boid.dy)
+            } else {
+                stroke.normalize() * INITIAL_SPEED
+            };
+            spawn::place(&mut boid, position, velocity);
+            boids.push(boid);
+        }
+    }
+
+    pub fn draw(&self, mb: &mut graphics::MeshBuilder, center: glam::Vec2, line_width: f32) -> GameResult {
+        mb.circle(
+            graphics::DrawMode::stroke(line_width),
+            center,
+            self.radius,
+            0.1,
+            [0.6, 1.0, 0.6, 0.7].into(),
+        )?;
+        Ok(())
+    }
+}
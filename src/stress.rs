@@ -0,0 +1,136 @@
+// stress.rs
+use std::fs;
+use std::io;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tracing::info;
+
+// Stress test settings
+pub const STRESS_START_COUNT: usize = 1000;
+pub const STRESS_THRESHOLD: f32 = 30.0; // FPS below which the test stops
+const STRESS_STEP: usize = 1000;   // Boids added per phase
+const PHASE_SECONDS: f32 = 10.0;
+const WARMUP_SECONDS: f32 = 1.0;   // Skipped at the start of a phase while the flock settles
+
+// Averages measured over one phase at a fixed boid count
+struct Phase {
+    boid_count: usize,
+    fps: f32,
+    update_ms: f32,
+    draw_ms: f32,
+}
+
+// Ramps the boid count up until the frame rate drops below a threshold, then
+// reports the largest count that held it
+pub struct StressTest {
+    threshold: f32,
+    boid_count: usize,
+    elapsed: f32,
+    frames: usize,
+    update_time: Duration,
+    draw_time: Duration,
+    phases: Vec<Phase>,
+}
+
+// What the caller should do after a frame
+pub enum StressAction {
+    Continue,
+    Resize(usize),
+    Finished,
+}
+
+impl StressTest {
+    pub fn new(threshold: f32) -> Self {
+        StressTest {
+            threshold,
+            boid_count: STRESS_START_COUNT,
+            elapsed: 0.0,
+            frames: 0,
+            update_time: Duration::new(0, 0),
+            draw_time: Duration::new(0, 0),
+            phases: Vec::new(),
+        }
+    }
+
+    pub fn record_draw(&mut self, duration: Duration) {
+        if self.elapsed >= WARMUP_SECONDS {
+            self.draw_time += duration;
+        }
+    }
+
+    // Feed one frame of `dt` real seconds whose update took `update_duration`
+    pub fn update(&mut self, dt: f32, update_duration: Duration) -> StressAction {
+        self.elapsed += dt;
+        if self.elapsed < WARMUP_SECONDS {
+            return StressAction::Continue;
+        }
+
+        self.frames += 1;
+        self.update_time += update_duration;
+        if self.elapsed < PHASE_SECONDS {
+            return StressAction::Continue;
+        }
+
+        let frames = self.frames.max(1) as f32;
+        let phase = Phase {
+            boid_count: self.boid_count,
+            fps: self.frames as f32 / (self.elapsed - WARMUP_SECONDS),
+            update_ms: self.update_time.as_secs_f32() * 1000.0 / frames,
+            draw_ms: self.draw_time.as_secs_f32() * 1000.0 / frames,
+        };
+        info!(
+            "Stress phase: {} boids at {:.1} FPS (update {:.2}ms, draw {:.2}ms)",
+            phase.boid_count, phase.fps, phase.update_ms, phase.draw_ms
+        );
+        let below_threshold = phase.fps < self.threshold;
+        self.phases.push(phase);
+
+        self.elapsed = 0.0;
+        self.frames = 0;
+        self.update_time = Duration::new(0, 0);
+        self.draw_time = Duration::new(0, 0);
+
+        if below_threshold {
+            StressAction::Finished
+        } else {
+            self.boid_count += STRESS_STEP;
+            StressAction::Resize(self.boid_count)
+        }
+    }
+
+    // The largest boid count that stayed at or above the threshold
+    pub fn max_sustainable(&self) -> Option<usize> {
+        self.phases
+            .iter()
+            .filter(|phase| phase.fps >= self.threshold)
+            .map(|phase| phase.boid_count)
+            .max()
+    }
+
+    pub fn report(&self) -> String {
+        let mut report = format!("Boids stress test, threshold {:.1} FPS\n", self.threshold);
+        match self.max_sustainable() {
+            Some(count) => report.push_str(&format!("Max sustainable boid count: {}\n\n", count)),
+            None => report.push_str("Max sustainable boid count: none\n\n"),
+        }
+
+        report.push_str("boids,fps,update_ms,draw_ms\n");
+        for phase in &self.phases {
+            report.push_str(&format!(
+                "{},{:.1},{:.3},{:.3}\n",
+                phase.boid_count, phase.fps, phase.update_ms, phase.draw_ms
+            ));
+        }
+        report
+    }
+
+    // Write the report to the working directory, returning the file name
+    pub fn write_report(&self) -> io::Result<String> {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs())
+            .unwrap_or(0);
+        let path = format!("stress-report-{}.txt", timestamp);
+        fs::write(&path, self.report())?;
+        Ok(path)
+    }
+}
@@ -0,0 +1,150 @@
+// telemetry.rs
+use crate::metrics::FlockStats;
+use crate::scoring::ScoringZone;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+// Telemetry settings
+pub const TELEMETRY_INTERVAL: f32 = 0.1; // Simulation seconds between samples
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TelemetryFormat {
+    Csv,
+    JsonLines,
+}
+
+impl TelemetryFormat {
+    // Pick the format from the file extension, defaulting to CSV
+    pub fn from_path(path: &Path) -> TelemetryFormat {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("json") | Some("jsonl") => TelemetryFormat::JsonLines,
+            _ => TelemetryFormat::Csv,
+        }
+    }
+}
+
+// Periodically appends flock statistics to a file for offline analysis
+pub struct Telemetry {
+    writer: BufWriter<File>,
+    format: TelemetryFormat,
+    interval: f32,
+    time: f32,           // Simulation seconds since logging started
+    since_sample: f32,
+    columns: Option<Vec<String>>, // Scoring zones in the CSV header, written with the first sample
+}
+
+impl Telemetry {
+    pub fn create(path: &Path, interval: f32) -> io::Result<Telemetry> {
+        let format = TelemetryFormat::from_path(path);
+        let writer = BufWriter::new(File::create(path)?);
+
+        Ok(Telemetry {
+            writer,
+            format,
+            interval,
+            time: 0.0,
+            // Take the first sample right away
+            since_sample: interval,
+            columns: None,
+        })
+    }
+
+    // Advance the clock by `sim_dt` and report whether a sample should be taken
+    pub fn due(&mut self, sim_dt: f32) -> bool {
+        self.time += sim_dt;
+        self.since_sample += sim_dt;
+        if self.since_sample >= self.interval {
+            self.since_sample = 0.0;
+            true
+        } else {
+            false
+        }
+    }
+
+    // One sample of the flock and every scoring zone. CSV columns are fixed
+    // by the zones of the first sample; zones added later are left out and
+    // ones removed leave their columns empty.
+    pub fn write(&mut self, stats: &FlockStats, zones: &[ScoringZone]) -> io::Result<()> {
+        match self.format {
+            TelemetryFormat::Csv => {
+                if self.columns.is_none() {
+                    let names: Vec<String> = zones.iter().map(|zone| zone.name.clone()).collect();
+                    write!(self.writer, "time,boids,average_speed,polarization,angular_momentum,nearest_neighbor_distance,clusters")?;
+                    for name in &names {
+                        write!(self.writer, ",{0}_inside,{0}_entered,{0}_dwell", name)?;
+                    }
+                    writeln!(self.writer)?;
+                    self.columns = Some(names);
+                }
+                write!(
+                    self.writer,
+                    "{:.3},{},{:.3},{:.4},{:.4},{:.3},{}",
+                    self.time,
+                    stats.boid_count,
+                    stats.average_speed,
+                    stats.polarization,
+                    stats.angular_momentum,
+                    stats.nearest_neighbor_distance,
+                    stats.cluster_count,
+                )?;
+                for name in self.columns.iter().flatten() {
+                    match zones.iter().find(|zone| &zone.name == name) {
+                        Some(zone) => write!(self.writer, ",{},{},{:.2}", zone.inside, zone.entered, zone.dwell)?,
+                        None => write!(self.writer, ",,,")?,
+                    }
+                }
+                writeln!(self.writer)
+            }
+            TelemetryFormat::JsonLines => {
+                write!(
+                    self.writer,
+                    "{{\"time\":{:.3},\"boids\":{},\"average_speed\":{:.3},\"polarization\":{:.4},\"angular_momentum\":{:.4},\"nearest_neighbor_distance\":{:.3},\"clusters\":{}",
+                    self.time,
+                    stats.boid_count,
+                    stats.average_speed,
+                    stats.polarization,
+                    stats.angular_momentum,
+                    stats.nearest_neighbor_distance,
+                    stats.cluster_count,
+                )?;
+                if !zones.is_empty() {
+                    let entries: Vec<String> = zones
+                        .iter()
+                        .map(|zone| {
+                            format!(
+                                "{}:{{\"inside\":{},\"entered\":{},\"dwell\":{:.2}}}",
+                                json_string(&zone.name),
+                                zone.inside,
+                                zone.entered,
+                                zone.dwell,
+                            )
+                        })
+                        .collect();
+                    write!(self.writer, ",\"zones\":{{{}}}", entries.join(","))?;
+                }
+                writeln!(self.writer, "}}")
+            }
+        }
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+// `text` as a quoted JSON string
+fn json_string(text: &str) -> String {
+    let mut quoted = String::with_capacity(text.len() + 2);
+    quoted.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            c if c.is_control() => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
@@ -0,0 +1,124 @@
+// theme.rs
+use ggez::{graphics, Context, GameResult};
+use serde::Deserialize;
+
+// Look of the background, boids and HUD, cycled with <y> or set in boids.toml
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Theme {
+    Dark,
+    Light,
+    Sunset,
+    Ocean,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme::Dark
+    }
+}
+
+impl Theme {
+    pub fn next(self) -> Theme {
+        match self {
+            Theme::Dark => Theme::Light,
+            Theme::Light => Theme::Sunset,
+            Theme::Sunset => Theme::Ocean,
+            Theme::Ocean => Theme::Dark,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Theme::Dark => "Dark",
+            Theme::Light => "Light",
+            Theme::Sunset => "Sunset",
+            Theme::Ocean => "Ocean",
+        }
+    }
+
+    // Background colors at the top and bottom of the screen
+    fn background(self) -> ([f32; 4], [f32; 4]) {
+        match self {
+            Theme::Dark => ([0.15, 0.2, 0.22, 1.0], [0.15, 0.2, 0.22, 1.0]),
+            Theme::Light => ([0.92, 0.92, 0.88, 1.0], [0.92, 0.92, 0.88, 1.0]),
+            Theme::Sunset => ([0.25, 0.15, 0.35, 1.0], [0.95, 0.5, 0.3, 1.0]),
+            Theme::Ocean => ([0.1, 0.35, 0.5, 1.0], [0.02, 0.08, 0.18, 1.0]),
+        }
+    }
+
+    // Text and overlay color that reads well on the background
+    pub fn hud_color(self) -> graphics::Color {
+        match self {
+            Theme::Light => [0.1, 0.1, 0.1, 1.0].into(),
+            _ => graphics::Color::WHITE,
+        }
+    }
+
+    // Same as hud_color with a different alpha, for translucent overlays
+    pub fn overlay_color(self, alpha: f32) -> graphics::Color {
+        let mut color = self.hud_color();
+        color.a = alpha;
+        color
+    }
+
+    // Shift a color from the current ColorMode into this theme's palette
+    pub fn boid_color(self, [r, g, b, a]: [f32; 4]) -> [f32; 4] {
+        match self {
+            Theme::Dark => [r, g, b, a],
+            // Pale colors vanish on a light background, so darken and firm them up
+            Theme::Light => [r * 0.55, g * 0.55, b * 0.55, (a * 1.6).min(1.0)],
+            Theme::Sunset => [r * 0.6 + 0.4, g * 0.6 + 0.25, b * 0.5 + 0.1, a],
+            Theme::Ocean => [r * 0.4 + 0.1, g * 0.6 + 0.35, b * 0.5 + 0.5, a],
+        }
+    }
+
+    // Color the frame is cleared to before anything is drawn
+    pub fn clear_color(self) -> graphics::Color {
+        self.background().0.into()
+    }
+
+    // Paint the vertical gradient over the cleared frame for the themes that have one
+    pub fn draw_background(self, ctx: &Context, canvas: &mut graphics::Canvas, width: f32, height: f32) -> GameResult {
+        let (top, bottom) = self.background();
+        if top == bottom {
+            return Ok(());
+        }
+        self.fade_background(ctx, canvas, width, height, 1.0)
+    }
+
+    // Cover the screen with a translucent background, fading what was drawn before
+    pub fn fade_background(
+        self,
+        ctx: &Context,
+        canvas: &mut graphics::Canvas,
+        width: f32,
+        height: f32,
+        alpha: f32,
+    ) -> GameResult {
+        let (mut top, mut bottom) = self.background();
+        top[3] = alpha;
+        bottom[3] = alpha;
+
+        let vertex = |x: f32, y: f32, color: [f32; 4]| graphics::Vertex {
+            position: [x, y],
+            uv: [0.0, 0.0],
+            color,
+        };
+        let vertices = [
+            vertex(0.0, 0.0, top),
+            vertex(width, 0.0, top),
+            vertex(width, height, bottom),
+            vertex(0.0, height, bottom),
+        ];
+        let gradient = graphics::Mesh::from_data(
+            ctx,
+            graphics::MeshData {
+                vertices: &vertices,
+                indices: &[0, 1, 2, 0, 2, 3],
+            },
+        );
+        canvas.draw(&gradient, graphics::DrawParam::new());
+        Ok(())
+    }
+}
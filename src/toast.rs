@@ -0,0 +1,64 @@
+// toast.rs
+use ggez::{graphics, Context, GameResult};
+
+// Toast settings
+const TOAST_SECONDS: f32 = 4.0;
+const TOAST_FADE_SECONDS: f32 = 1.0; // Fades out over the end of its lifetime
+
+// Short-lived message shown at the top of the screen
+pub struct Toast {
+    text: String,
+    remaining: f32,
+}
+
+impl Toast {
+    pub fn new() -> Self {
+        Toast {
+            text: String::new(),
+            remaining: 0.0,
+        }
+    }
+
+    pub fn show(&mut self, text: String) {
+        self.text = text;
+        self.remaining = TOAST_SECONDS;
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        self.remaining = (self.remaining - dt).max(0.0);
+    }
+
+    // Centered horizontally, `top` pixels from the top of a `screen_width` wide screen
+    pub fn draw(&self, ctx: &Context, canvas: &mut graphics::Canvas, screen_width: f32, top: f32, ui_scale: f32) -> GameResult {
+        if self.remaining <= 0.0 {
+            return Ok(());
+        }
+
+        let alpha = (self.remaining / TOAST_FADE_SECONDS).min(1.0);
+        let text = graphics::Text::new(graphics::TextFragment {
+            text: self.text.clone(),
+            color: Some([1.0, 1.0, 0.8, alpha].into()),
+            font: None,
+            scale: Some(graphics::PxScale::from(18.0 * ui_scale)),
+        });
+
+        let measured = text.measure(ctx)?;
+        let size = glam::vec2(measured.x, measured.y);
+        let pos = glam::vec2((screen_width - size.x) / 2.0, top);
+        let padding = glam::vec2(8.0, 6.0) * ui_scale;
+        let background = graphics::Mesh::new_rectangle(
+            ctx,
+            graphics::DrawMode::fill(),
+            graphics::Rect::new(
+                pos.x - padding.x,
+                pos.y - padding.y,
+                size.x + 2.0 * padding.x,
+                size.y + 2.0 * padding.y,
+            ),
+            [0.0, 0.0, 0.0, 0.6 * alpha].into(),
+        )?;
+        canvas.draw(&background, graphics::DrawParam::default());
+        canvas.draw(&text, graphics::DrawParam::default().dest(pos));
+        Ok(())
+    }
+}
@@ -0,0 +1,120 @@
+// trails.rs
+use crate::boid::Boid;
+#[cfg(feature = "render-ggez")]
+use ggez::{graphics, GameResult};
+
+// Trail settings
+pub const TRAIL_LENGTH: usize = 20;   // Positions remembered per boid
+#[cfg(feature = "render-ggez")]
+const MAX_SEGMENT_LENGTH: f32 = 64.0; // Longer jumps are respawns, not motion
+
+// Ring buffer of recent boid positions, one frame-slot per recorded tick
+#[derive(Clone)]
+pub struct Trails {
+    positions: Vec<glam::Vec2>, // TRAIL_LENGTH slots of boid_count positions
+    boid_count: usize,
+    head: usize,                // Slot the next frame will be written to
+    filled: usize,              // Number of valid slots
+}
+
+impl Trails {
+    pub fn new() -> Self {
+        Trails {
+            positions: Vec::new(),
+            boid_count: 0,
+            head: 0,
+            filled: 0,
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.filled = 0;
+        self.head = 0;
+    }
+
+    // Move the recorded positions with a world rescaled by `scale`
+    pub fn rescale(&mut self, scale: glam::Vec2) {
+        for pos in &mut self.positions {
+            *pos *= scale;
+        }
+    }
+
+    // Follow boids being removed, `kept` has one flag per boid before the removal
+    pub fn retain_kept(&mut self, kept: &[bool]) {
+        if self.boid_count == 0 || kept.len() != self.boid_count {
+            return; // Already out of step, the next record starts over
+        }
+        let remaining = kept.iter().filter(|&&keep| keep).count();
+        let mut positions = Vec::with_capacity(TRAIL_LENGTH * remaining);
+        for slot in self.positions.chunks_exact(self.boid_count) {
+            positions.extend(slot.iter().zip(kept).filter(|(_, &keep)| keep).map(|(&pos, _)| pos));
+        }
+        self.positions = positions;
+        self.boid_count = remaining;
+    }
+
+    // Store the current positions of all boids
+    pub fn record(&mut self, boids: &[Boid]) {
+        // Boids appended since the last frame start with a history of standing
+        // still, which draws nothing
+        if boids.len() > self.boid_count && self.boid_count > 0 {
+            let added: Vec<glam::Vec2> = boids[self.boid_count..].iter().map(|boid| glam::vec2(boid.x, boid.y)).collect();
+            let mut positions = Vec::with_capacity(TRAIL_LENGTH * boids.len());
+            for slot in self.positions.chunks_exact(self.boid_count) {
+                positions.extend_from_slice(slot);
+                positions.extend_from_slice(&added);
+            }
+            self.positions = positions;
+            self.boid_count = boids.len();
+        }
+
+        // Otherwise the old history no longer lines up
+        if boids.len() != self.boid_count {
+            self.boid_count = boids.len();
+            self.positions = vec![glam::Vec2::ZERO; TRAIL_LENGTH * self.boid_count];
+            self.clear();
+        }
+
+        let start = self.head * self.boid_count;
+        for (slot, boid) in self.positions[start..start + self.boid_count].iter_mut().zip(boids) {
+            *slot = glam::vec2(boid.x, boid.y);
+        }
+
+        self.head = (self.head + 1) % TRAIL_LENGTH;
+        self.filled = (self.filled + 1).min(TRAIL_LENGTH);
+    }
+
+    // Position of boid `i`, `age` frames ago
+    #[cfg(feature = "render-ggez")]
+    fn position(&self, i: usize, age: usize) -> glam::Vec2 {
+        let slot = (self.head + TRAIL_LENGTH - 1 - age) % TRAIL_LENGTH;
+        self.positions[slot * self.boid_count + i]
+    }
+
+    // Add fading segments for every boid trail to the mesh, using one color per boid
+    #[cfg(feature = "render-ggez")]
+    pub fn draw(&self, mb: &mut graphics::MeshBuilder, colors: &[[f32; 4]], line_width: f32) -> GameResult {
+        if self.filled < 2 || colors.len() != self.boid_count {
+            return Ok(());
+        }
+
+        for (i, color) in colors.iter().enumerate() {
+            for age in 0..self.filled - 1 {
+                let from = self.position(i, age);
+                let to = self.position(i, age + 1);
+
+                let length_sq = (to - from).length_squared();
+                if length_sq == 0.0 || length_sq > MAX_SEGMENT_LENGTH * MAX_SEGMENT_LENGTH {
+                    continue;
+                }
+
+                // Older segments fade out linearly
+                let fade = 1.0 - (age as f32 + 1.0) / self.filled as f32;
+                let [r, g, b, a] = *color;
+                mb.line(&[from, to], line_width, [r, g, b, a * fade].into())?;
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,120 @@
+// trajectories.rs
+//
+// Binary trajectory log: every boid's position and velocity on every
+// simulation step, stored column by column so it loads straight into numpy:
+//
+//   header: b"BOIDTRJ1"
+//   block:  tick u64, time f32, count u32,
+//           x[count] f32, y[count] f32, dx[count] f32, dy[count] f32
+//
+// All values are little-endian. Ticks are numbered consecutively, so a gap
+// means blocks were dropped because the writer fell behind.
+use crate::boid::Boid;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, SyncSender, TrySendError};
+use std::thread::{self, JoinHandle};
+use tracing::{error, warn};
+
+const MAGIC: &[u8; 8] = b"BOIDTRJ1";
+const BLOCK_QUEUE_LENGTH: usize = 600; // Steps buffered before new ones get dropped
+
+// One simulation step in columnar layout
+struct Block {
+    tick: u64,
+    time: f32,
+    columns: Vec<f32>, // x, then y, dx and dy, each `count` long
+}
+
+// Streams trajectories to disk on a background thread
+pub struct TrajectoryWriter {
+    sender: Option<SyncSender<Block>>,
+    worker: Option<JoinHandle<()>>,
+    tick: u64,
+    time: f32,
+    pub dropped: usize,
+}
+
+impl TrajectoryWriter {
+    pub fn create(path: &Path) -> io::Result<TrajectoryWriter> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        writer.write_all(MAGIC)?;
+
+        let path = path.to_path_buf();
+        let (sender, receiver) = mpsc::sync_channel(BLOCK_QUEUE_LENGTH);
+        let worker = thread::spawn(move || write_blocks(&path, writer, receiver));
+
+        Ok(TrajectoryWriter {
+            sender: Some(sender),
+            worker: Some(worker),
+            tick: 0,
+            time: 0.0,
+            dropped: 0,
+        })
+    }
+
+    // Queue the state after a step of `tick` seconds without blocking
+    pub fn record(&mut self, boids: &[Boid], tick: f32) {
+        self.time += tick;
+        let block_tick = self.tick;
+        self.tick += 1;
+
+        let sender = match &self.sender {
+            Some(sender) => sender,
+            None => return,
+        };
+
+        let mut columns = Vec::with_capacity(boids.len() * 4);
+        columns.extend(boids.iter().map(|boid| boid.x));
+        columns.extend(boids.iter().map(|boid| boid.y));
+        columns.extend(boids.iter().map(|boid| boid.dx));
+        columns.extend(boids.iter().map(|boid| boid.dy));
+
+        let block = Block { tick: block_tick, time: self.time, columns };
+        match sender.try_send(block) {
+            Ok(()) => {}
+            Err(TrySendError::Full(_)) => self.dropped += 1,
+            Err(TrySendError::Disconnected(_)) => self.sender = None,
+        }
+    }
+
+    // Flush the remaining blocks and wait for the writer to finish
+    pub fn finish(&mut self) {
+        self.sender = None;
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+            if self.dropped > 0 {
+                warn!("Trajectory export dropped {} steps", self.dropped);
+            }
+        }
+    }
+}
+
+impl Drop for TrajectoryWriter {
+    fn drop(&mut self) {
+        self.finish();
+    }
+}
+
+fn write_blocks(path: &PathBuf, mut writer: BufWriter<File>, receiver: Receiver<Block>) {
+    let result = receiver
+        .iter()
+        .try_for_each(|block| write_block(&mut writer, &block))
+        .and_then(|()| writer.flush());
+
+    if let Err(err) = result {
+        error!("Failed to write {}: {}", path.display(), err);
+    }
+}
+
+fn write_block(writer: &mut impl Write, block: &Block) -> io::Result<()> {
+    let count = (block.columns.len() / 4) as u32;
+    writer.write_all(&block.tick.to_le_bytes())?;
+    writer.write_all(&block.time.to_le_bytes())?;
+    writer.write_all(&count.to_le_bytes())?;
+    for value in &block.columns {
+        writer.write_all(&value.to_le_bytes())?;
+    }
+    Ok(())
+}
@@ -0,0 +1,131 @@
+// tui.rs
+use crate::cli::Options;
+use crate::config::{Config, Parameters};
+use crate::render::{self, Renderer};
+use crate::simulation::Simulation;
+use crate::spawn::SpawnPattern;
+use crate::world::World;
+use crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use crossterm::execute;
+use crossterm::terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen};
+use ratatui::backend::CrosstermBackend;
+use ratatui::style::Color;
+use ratatui::symbols::Marker;
+use ratatui::widgets::canvas::{Canvas, Points};
+use ratatui::widgets::{Block, Borders};
+use ratatui::Terminal;
+use std::io::{self, Stdout};
+use std::time::{Duration, Instant};
+
+// Terminal front end settings
+const FRAME_TIME: Duration = Duration::from_millis(33); // About 30 redraws a second
+const MAX_TICK: f32 = 0.1;                              // Longest step after a stall, seconds
+const BOID_STEP: usize = 100;                           // Boids added or removed per key press
+
+// One frame for the Braille canvas, collected through the Renderer trait
+#[derive(Default)]
+struct BrailleFrame {
+    height: f32,
+    coords: Vec<(f64, f64)>,
+    title: String,
+}
+
+impl Renderer for BrailleFrame {
+    fn begin_frame(&mut self, world: &World) {
+        self.height = world.height;
+        self.coords.clear();
+    }
+
+    // Braille dots have no direction or color to show
+    fn draw_boid(&mut self, pos: glam::Vec2, _heading: f32, _color: [f32; 4]) {
+        // The canvas counts y upwards, the world downwards
+        self.coords.push((pos.x as f64, (self.height - pos.y) as f64));
+    }
+
+    fn draw_status(&mut self, text: &str) {
+        self.title = format!(" {} ", text);
+    }
+
+    fn end_frame(&mut self) {}
+}
+
+// Runs the flock in the terminal instead of a window, started with --tui.
+// Boids are drawn as Braille dots, several to a character cell. The same
+// Simulation as the window steps the flock, with the options and the
+// [parameters] from boids.toml applied.
+pub fn run(options: &Options, count: usize) -> io::Result<()> {
+    let world = World::new(options.world_width, options.world_height);
+    let mut sim = Simulation::new(world, options.seed.unwrap_or_else(rand::random));
+    sim.set_parameter("goal_weight", options.goal_weight);
+    sim.set_parameter("emit_rate", options.emit_rate);
+    sim.set_parameter("food_rate", options.food_rate);
+    sim.set_parameter("infection_probability", options.infection_probability);
+    sim.set_parameter("wander", options.wander_strength);
+    sim.set_parameter("trait_variance", options.trait_variance);
+    match Config::load(&options.config) {
+        Ok(config) => {
+            for (name, value) in config.parameters.changes(&Parameters::default()) {
+                sim.set_parameter(name, value);
+            }
+        }
+        Err(err) => eprintln!("Using the default parameters: {}", err),
+    }
+    sim.respawn(count, SpawnPattern::Blob);
+
+    enable_raw_mode()?;
+    execute!(io::stdout(), EnterAlternateScreen)?;
+    let mut terminal = Terminal::new(CrosstermBackend::new(io::stdout()))?;
+
+    let result = event_loop(&mut terminal, &mut sim);
+
+    // Put the terminal back even if drawing failed
+    disable_raw_mode()?;
+    execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+    terminal.show_cursor()?;
+    result
+}
+
+fn event_loop(terminal: &mut Terminal<CrosstermBackend<Stdout>>, sim: &mut Simulation) -> io::Result<()> {
+    let mut paused = false;
+    let mut last_step = Instant::now();
+    let mut braille = BrailleFrame::default();
+    loop {
+        let status = format!(
+            "Boids: {}{} | <space> pause | <up>/<down> boids | <q> quit",
+            sim.boids.len(),
+            if paused { " (paused)" } else { "" }
+        );
+        render::draw_flock(&mut braille, &sim.boids, &sim.world, &status);
+        let world = sim.world;
+        terminal.draw(|frame| {
+            let canvas = Canvas::default()
+                .block(Block::default().borders(Borders::ALL).title(braille.title.as_str()))
+                .marker(Marker::Braille)
+                .x_bounds([0.0, world.width as f64])
+                .y_bounds([0.0, world.height as f64])
+                .paint(|ctx| ctx.draw(&Points { coords: &braille.coords, color: Color::Cyan }));
+            frame.render_widget(canvas, frame.size());
+        })?;
+
+        if event::poll(FRAME_TIME)? {
+            if let Event::Key(key) = event::read()? {
+                if key.kind == KeyEventKind::Press {
+                    match key.code {
+                        KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+                        KeyCode::Char(' ') => paused = !paused,
+                        KeyCode::Up => sim.resize_flock(sim.boids.len() + BOID_STEP),
+                        KeyCode::Down => sim.resize_flock(sim.boids.len().saturating_sub(BOID_STEP)),
+                        _ => {}
+                    }
+                }
+            }
+        }
+
+        let tick = last_step.elapsed().as_secs_f32().min(MAX_TICK);
+        last_step = Instant::now();
+        if !paused {
+            // No mouse to avoid, so the cursor sits far outside the world
+            sim.step(tick, glam::vec2(-1.0e6, -1.0e6));
+        }
+    }
+}
@@ -0,0 +1,104 @@
+// waypoints.rs
+use crate::boid::Boid;
+#[cfg(feature = "render-ggez")]
+use ggez::{graphics, GameResult};
+
+// Goal seeking settings
+pub const GOAL_WEIGHT: f32 = 4.0;      // Velocity change per step towards the goal
+const WAYPOINT_RADIUS: f32 = 80.0;     // Flock center within this distance counts as arrived
+
+// Ordered list of goals the flock migrates between, looping at the end.
+// A single waypoint acts as a fixed target point.
+#[derive(Clone)]
+pub struct Waypoints {
+    points: Vec<glam::Vec2>,
+    current: usize,
+    pub weight: f32,
+}
+
+impl Waypoints {
+    pub fn new(weight: f32) -> Self {
+        Waypoints {
+            points: Vec::new(),
+            current: 0,
+            weight,
+        }
+    }
+
+    pub fn add(&mut self, point: glam::Vec2) {
+        self.points.push(point);
+    }
+
+    pub fn clear(&mut self) {
+        self.points.clear();
+        self.current = 0;
+    }
+
+    // Move the waypoints with a world rescaled by `scale`
+    pub fn rescale(&mut self, scale: glam::Vec2) {
+        for point in &mut self.points {
+            *point *= scale;
+        }
+    }
+
+    // The waypoint the flock is currently heading for
+    pub fn current(&self) -> Option<glam::Vec2> {
+        self.points.get(self.current).copied()
+    }
+
+    // Move on to the next waypoint once the flock's center has reached the current one
+    pub fn update(&mut self, boids: &[Boid]) {
+        let goal = match self.current() {
+            Some(goal) => goal,
+            None => return,
+        };
+        if boids.is_empty() || self.points.len() < 2 {
+            return;
+        }
+
+        let sum = boids
+            .iter()
+            .fold(glam::Vec2::ZERO, |sum, boid| sum + glam::vec2(boid.x, boid.y));
+        let center = sum / boids.len() as f32;
+
+        if (center - goal).length_squared() < WAYPOINT_RADIUS * WAYPOINT_RADIUS {
+            self.current = (self.current + 1) % self.points.len();
+        }
+    }
+
+    // Weak pull of a boid towards the current waypoint
+    pub fn steer(&self, boid: &Boid) -> glam::Vec2 {
+        match self.current() {
+            Some(goal) => {
+                let offset = goal - glam::vec2(boid.x, boid.y);
+                if offset == glam::Vec2::ZERO {
+                    glam::Vec2::ZERO
+                } else {
+                    offset.normalize() * self.weight
+                }
+            }
+            None => glam::Vec2::ZERO,
+        }
+    }
+
+    // Path through all waypoints with the current one highlighted
+    #[cfg(feature = "render-ggez")]
+    pub fn draw(&self, mb: &mut graphics::MeshBuilder, line_width: f32) -> GameResult {
+        if self.points.len() >= 2 {
+            let mut path = self.points.clone();
+            path.push(self.points[0]);
+            mb.line(&path, line_width, [1.0, 0.8, 0.3, 0.3].into())?;
+        }
+
+        for (i, &point) in self.points.iter().enumerate() {
+            let color = if i == self.current {
+                [1.0, 0.8, 0.3, 0.9]
+            } else {
+                [1.0, 0.8, 0.3, 0.4]
+            };
+            mb.circle(graphics::DrawMode::stroke(2.0 * line_width), point, 8.0, 0.1, color.into())?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,313 @@
+// world.rs
+use std::sync::Arc;
+
+// Largest world side accepted from outside: --world, OSC and scenarios. The
+// grids and fields are allocated per cell of the world, so a huge one would
+// run out of memory.
+pub const MAX_WORLD_SIZE: f32 = 20_000.0; // Pixels
+
+// Dimensions of the simulated space, independent of the window size
+#[derive(Debug, Clone, Copy)]
+pub struct World {
+    pub width: f32,
+    pub height: f32,
+}
+
+impl World {
+    pub fn new(width: f32, height: f32) -> Self {
+        World { width, height }
+    }
+
+    pub fn center(&self) -> glam::Vec2 {
+        glam::vec2(self.width / 2.0, self.height / 2.0)
+    }
+
+    // Whether a requested size can be simulated: finite, at least a pixel
+    // and at most MAX_WORLD_SIZE on each side
+    pub fn is_valid_size(width: f32, height: f32) -> bool {
+        let valid = |side: f32| side.is_finite() && (1.0..=MAX_WORLD_SIZE).contains(&side);
+        valid(width) && valid(height)
+    }
+}
+
+// What happens to the boids when the world changes size mid-run
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResizeMode {
+    Clamp,   // Stay where they are, those now outside are pulled in to the edge
+    Rescale, // Move with the world, so the flock keeps its layout
+}
+
+pub const DEFAULT_RESTITUTION: f32 = 0.9; // Fraction of the speed into a wall kept by a bounce
+
+// How boids are kept inside the boundary
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BoundaryMode {
+    Turn,                        // Steer back gradually within EDGE_BUFFER of the edge
+    Bounce { restitution: f32 }, // Reflect off the edge like a billiard ball
+}
+
+impl Default for BoundaryMode {
+    fn default() -> Self {
+        BoundaryMode::Turn
+    }
+}
+
+// Mirror the part of `vel` heading against the inward `normal`, scaled by
+// `restitution`. Velocities already heading inside are left alone.
+fn reflect(vel: glam::Vec2, normal: glam::Vec2, restitution: f32) -> glam::Vec2 {
+    let into_wall = vel.dot(normal);
+    if into_wall < 0.0 {
+        vel - normal * into_wall * (1.0 + restitution)
+    } else {
+        vel
+    }
+}
+
+// Shape the boids are kept inside, see Boid::keep_within_bounds. The world
+// rectangle is the default; the others sit inside it.
+pub trait Boundary: Send + Sync {
+    // Distance from `pos` to the nearest edge, negative outside
+    fn signed_distance(&self, pos: glam::Vec2) -> f32;
+
+    // Direction to steer a boid at `pos` back towards the middle, zero while
+    // it is more than `buffer` from every edge
+    fn inward(&self, pos: glam::Vec2, buffer: f32) -> glam::Vec2;
+
+    // Put a point that has left back on the edge and reflect its velocity
+    fn bounce(&self, pos: &mut glam::Vec2, vel: &mut glam::Vec2, restitution: f32);
+
+    // Corners to draw the edge through, in order
+    fn outline(&self) -> Vec<glam::Vec2>;
+}
+
+// Shape of the arena regardless of the world size, see [arena] in boids.toml
+#[derive(Debug, Clone, PartialEq)]
+pub enum Arena {
+    Rectangle,
+    Circle,
+    Polygon(Vec<glam::Vec2>), // Corners as fractions of the world size
+}
+
+impl Default for Arena {
+    fn default() -> Self {
+        Arena::Rectangle
+    }
+}
+
+impl Arena {
+    // The boundary for `world`, falling back to the world rectangle if the
+    // polygon isn't convex
+    pub fn fit(&self, world: &World) -> Arc<dyn Boundary> {
+        match self {
+            Arena::Rectangle => Arc::new(Rectangle::new(world)),
+            Arena::Circle => Arc::new(Circle::new(world)),
+            Arena::Polygon(corners) => {
+                let size = glam::vec2(world.width, world.height);
+                match ConvexPolygon::new(corners.iter().map(|&corner| corner * size).collect()) {
+                    Some(polygon) => Arc::new(polygon),
+                    None => Arc::new(Rectangle::new(world)),
+                }
+            }
+        }
+    }
+}
+
+// The whole world, with each axis handled on its own so boids in a corner
+// are pushed away from both edges
+pub struct Rectangle {
+    pub width: f32,
+    pub height: f32,
+}
+
+impl Rectangle {
+    pub fn new(world: &World) -> Self {
+        Rectangle { width: world.width, height: world.height }
+    }
+}
+
+impl Boundary for Rectangle {
+    fn signed_distance(&self, pos: glam::Vec2) -> f32 {
+        pos.x.min(self.width - pos.x).min(pos.y).min(self.height - pos.y)
+    }
+
+    fn inward(&self, pos: glam::Vec2, buffer: f32) -> glam::Vec2 {
+        let axis = |p: f32, size: f32| {
+            if p < buffer {
+                1.0
+            } else if p > size - buffer {
+                -1.0
+            } else {
+                0.0
+            }
+        };
+        glam::vec2(axis(pos.x, self.width), axis(pos.y, self.height))
+    }
+
+    // Each axis flips on its own, so a boid leaving through a corner comes
+    // back along the way it went in
+    fn bounce(&self, pos: &mut glam::Vec2, vel: &mut glam::Vec2, restitution: f32) {
+        let axis = |p: &mut f32, v: &mut f32, size: f32| {
+            if *p < 0.0 {
+                *p = 0.0;
+                *v = reflect(glam::vec2(*v, 0.0), glam::Vec2::X, restitution).x;
+            } else if *p > size {
+                *p = size;
+                *v = reflect(glam::vec2(*v, 0.0), -glam::Vec2::X, restitution).x;
+            }
+        };
+        axis(&mut pos.x, &mut vel.x, self.width);
+        axis(&mut pos.y, &mut vel.y, self.height);
+    }
+
+    fn outline(&self) -> Vec<glam::Vec2> {
+        vec![
+            glam::vec2(0.0, 0.0),
+            glam::vec2(self.width, 0.0),
+            glam::vec2(self.width, self.height),
+            glam::vec2(0.0, self.height),
+        ]
+    }
+}
+
+// Round arena, by default the largest circle that fits in the world
+pub struct Circle {
+    pub center: glam::Vec2,
+    pub radius: f32,
+}
+
+impl Circle {
+    pub fn new(world: &World) -> Self {
+        Circle { center: world.center(), radius: world.width.min(world.height) / 2.0 }
+    }
+}
+
+impl Boundary for Circle {
+    fn signed_distance(&self, pos: glam::Vec2) -> f32 {
+        self.radius - pos.distance(self.center)
+    }
+
+    fn inward(&self, pos: glam::Vec2, buffer: f32) -> glam::Vec2 {
+        if self.signed_distance(pos) < buffer {
+            (self.center - pos).normalize_or_zero()
+        } else {
+            glam::Vec2::ZERO
+        }
+    }
+
+    fn bounce(&self, pos: &mut glam::Vec2, vel: &mut glam::Vec2, restitution: f32) {
+        if self.signed_distance(*pos) >= 0.0 {
+            return;
+        }
+        let normal = (self.center - *pos).normalize_or_zero();
+        *pos = self.center - normal * self.radius;
+        *vel = reflect(*vel, normal, restitution);
+    }
+
+    fn outline(&self) -> Vec<glam::Vec2> {
+        const SEGMENTS: usize = 64;
+        (0..SEGMENTS)
+            .map(|i| {
+                let angle = i as f32 / SEGMENTS as f32 * std::f32::consts::TAU;
+                self.center + glam::vec2(angle.cos(), angle.sin()) * self.radius
+            })
+            .collect()
+    }
+}
+
+// Convex polygon arena. Corners are stored so the inside is on the perp()
+// side of every edge.
+pub struct ConvexPolygon {
+    corners: Vec<glam::Vec2>,
+}
+
+impl ConvexPolygon {
+    // None unless there are at least three corners forming a convex shape.
+    // Either winding is accepted.
+    pub fn new(mut corners: Vec<glam::Vec2>) -> Option<Self> {
+        if corners.len() < 3 {
+            return None;
+        }
+        let turns: Vec<f32> = (0..corners.len())
+            .map(|i| {
+                let (a, b, c) = (corners[i], corners[(i + 1) % corners.len()], corners[(i + 2) % corners.len()]);
+                (b - a).perp_dot(c - b)
+            })
+            .collect();
+        if turns.iter().all(|&turn| turn < 0.0) {
+            corners.reverse();
+        } else if !turns.iter().all(|&turn| turn > 0.0) {
+            return None;
+        }
+        Some(ConvexPolygon { corners })
+    }
+
+    fn edges(&self) -> impl Iterator<Item = (glam::Vec2, glam::Vec2)> + '_ {
+        let next = self.corners.iter().cycle().skip(1);
+        self.corners.iter().copied().zip(next.copied())
+    }
+
+    // Point on the edge closest to `pos`
+    fn closest_point(&self, pos: glam::Vec2) -> glam::Vec2 {
+        self.edges()
+            .map(|(a, b)| {
+                let edge = b - a;
+                let t = ((pos - a).dot(edge) / edge.length_squared()).max(0.0).min(1.0);
+                a + edge * t
+            })
+            .min_by(|a, b| a.distance_squared(pos).total_cmp(&b.distance_squared(pos)))
+            .unwrap_or(pos)
+    }
+
+    fn centroid(&self) -> glam::Vec2 {
+        self.corners.iter().fold(glam::Vec2::ZERO, |sum, &corner| sum + corner) / self.corners.len() as f32
+    }
+}
+
+impl Boundary for ConvexPolygon {
+    // Inside a convex polygon the distance to the boundary is the distance
+    // to the nearest edge line; outside it is the distance to the shape
+    fn signed_distance(&self, pos: glam::Vec2) -> f32 {
+        let inside = self
+            .edges()
+            .map(|(a, b)| (b - a).perp().normalize_or_zero().dot(pos - a))
+            .fold(f32::INFINITY, f32::min);
+        if inside >= 0.0 {
+            inside
+        } else {
+            -pos.distance(self.closest_point(pos))
+        }
+    }
+
+    fn inward(&self, pos: glam::Vec2, buffer: f32) -> glam::Vec2 {
+        let distance = self.signed_distance(pos);
+        if distance >= buffer {
+            return glam::Vec2::ZERO;
+        }
+        if distance < 0.0 {
+            return (self.centroid() - pos).normalize_or_zero();
+        }
+
+        // Away from the nearest edge along its normal
+        self.edges()
+            .map(|(a, b)| {
+                let normal = (b - a).perp().normalize_or_zero();
+                (normal.dot(pos - a), normal)
+            })
+            .min_by(|a, b| a.0.total_cmp(&b.0))
+            .map_or(glam::Vec2::ZERO, |(_, normal)| normal)
+    }
+
+    fn bounce(&self, pos: &mut glam::Vec2, vel: &mut glam::Vec2, restitution: f32) {
+        if self.signed_distance(*pos) >= 0.0 {
+            return;
+        }
+        let edge = self.closest_point(*pos);
+        let normal = (edge - *pos).normalize_or_zero();
+        *pos = edge;
+        *vel = reflect(*vel, normal, restitution);
+    }
+
+    fn outline(&self) -> Vec<glam::Vec2> {
+        self.corners.clone()
+    }
+}
@@ -0,0 +1,130 @@
+// zones.rs
+use crate::boid::Boid;
+use crate::spawn::{self, INITIAL_SPEED};
+use crate::world::World;
+#[cfg(feature = "render-ggez")]
+use ggez::{graphics, GameResult};
+use rand::Rng;
+
+// Zone settings
+pub const EMIT_RATE: f32 = 20.0;  // Boids per second from each emitter
+const ZONE_RADIUS: f32 = 48.0;    // Pixels
+const MAX_ZONE_BOIDS: usize = 20_000; // Emitters pause once the population reaches this
+
+// Spawns boids at a steady rate, all starting in the same direction
+#[derive(Clone)]
+pub struct Emitter {
+    pub center: glam::Vec2,
+    pub heading: f32,  // Radians
+    pending: f32,      // Fractional boids owed from previous steps
+}
+
+// Removes any boid that flies into it
+#[derive(Clone)]
+pub struct Sink {
+    pub center: glam::Vec2,
+}
+
+impl Sink {
+    pub fn contains(&self, boid: &Boid) -> bool {
+        (glam::vec2(boid.x, boid.y) - self.center).length_squared() < ZONE_RADIUS * ZONE_RADIUS
+    }
+}
+
+// Emitters placed with <insert> and sinks with <q>, cleared with <delete>
+#[derive(Clone)]
+pub struct Zones {
+    pub emitters: Vec<Emitter>,
+    pub sinks: Vec<Sink>,
+    pub rate: f32,
+}
+
+impl Zones {
+    pub fn new(rate: f32) -> Self {
+        Zones {
+            emitters: Vec::new(),
+            sinks: Vec::new(),
+            rate,
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.emitters.clear();
+        self.sinks.clear();
+    }
+
+    // Move the zones with a world rescaled by `scale`
+    pub fn rescale(&mut self, scale: glam::Vec2) {
+        for emitter in &mut self.emitters {
+            emitter.center *= scale;
+        }
+        for sink in &mut self.sinks {
+            sink.center *= scale;
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.emitters.is_empty() && self.sinks.is_empty()
+    }
+
+    // New emitters aim at the middle of the world, so one near each edge
+    // sends a stream across it
+    pub fn add_emitter(&mut self, center: glam::Vec2, world: &World) {
+        let aim = world.center() - center;
+        let heading = if aim == glam::Vec2::ZERO { 0.0 } else { aim.y.atan2(aim.x) };
+        self.emitters.push(Emitter { center, heading, pending: 0.0 });
+    }
+
+    pub fn add_sink(&mut self, center: glam::Vec2) {
+        self.sinks.push(Sink { center });
+    }
+
+    // Whether a boid has flown into any sink
+    pub fn in_sink(&self, boid: &Boid) -> bool {
+        self.sinks.iter().any(|sink| sink.contains(boid))
+    }
+
+    // Append the boids due from every emitter over `tick` seconds
+    pub fn emit(&mut self, boids: &mut Vec<Boid>, tick: f32, world: &World, rng: &mut impl Rng) {
+        for emitter in &mut self.emitters {
+            emitter.pending += self.rate * tick;
+            // Whole boids only, carrying the fraction over to the next step
+            let due = emitter.pending.floor();
+            emitter.pending -= due;
+            let room = MAX_ZONE_BOIDS.saturating_sub(boids.len());
+            for _ in 0..(due as usize).min(room) {
+                let mut boid = Boid::new(world.width, world.height, rng);
+                let position = emitter.center + spawn::random_in_circle(rng) * ZONE_RADIUS;
+                let direction = glam::vec2(emitter.heading.cos(), emitter.heading.sin());
+                spawn::place(&mut boid, position, direction * INITIAL_SPEED);
+                boids.push(boid);
+            }
+        }
+    }
+
+    #[cfg(feature = "render-ggez")]
+    pub fn draw(&self, mb: &mut graphics::MeshBuilder, line_width: f32) -> GameResult {
+        for emitter in &self.emitters {
+            let color = [0.4, 1.0, 0.5, 0.6];
+            let direction = glam::vec2(emitter.heading.cos(), emitter.heading.sin());
+            mb.circle(graphics::DrawMode::stroke(2.0 * line_width), emitter.center, ZONE_RADIUS, 0.1, color.into())?;
+            mb.line(
+                &[emitter.center, emitter.center + direction * ZONE_RADIUS * 1.5],
+                2.0 * line_width,
+                color.into(),
+            )?;
+        }
+
+        for sink in &self.sinks {
+            mb.circle(
+                graphics::DrawMode::stroke(2.0 * line_width),
+                sink.center,
+                ZONE_RADIUS,
+                0.1,
+                [1.0, 0.4, 0.4, 0.6].into(),
+            )?;
+        }
+
+        Ok(())
+    }
+}
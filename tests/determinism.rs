@@ -0,0 +1,79 @@
+// determinism.rs
+// Runs the same Simulation as the visualizer headless from a fixed seed and
+// checks two runs agree. With the deterministic feature it also compares a
+// hash of every position against tests/golden/step_hash.txt, so refactors
+// can show they leave the simulation untouched. After an intended behavior
+// change, record the new value with
+// `BOIDS_BLESS=1 cargo test --features deterministic --test determinism`.
+
+#[cfg(all(feature = "deterministic", not(feature = "simd")))]
+use std::fs;
+#[cfg(all(feature = "deterministic", not(feature = "simd")))]
+use std::path::Path;
+
+use boids_core::boid::Boid;
+use boids_core::simulation::Simulation;
+use boids_core::spawn::SpawnPattern;
+use boids_core::world::World;
+
+const SEED: u64 = 1234;
+const BOIDS: usize = 500;
+const STEPS: usize = 1_000;
+const TICK: f32 = 1.0 / 60.0;
+#[cfg(all(feature = "deterministic", not(feature = "simd")))]
+const GOLDEN: &str = "tests/golden/step_hash.txt";
+
+// FNV-1a over the bits of every position and velocity. Hand-rolled because
+// std's hasher may change between Rust releases.
+fn hash_flock(boids: &[Boid]) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for boid in boids {
+        for value in &[boid.x, boid.y, boid.dx, boid.dy] {
+            for byte in &value.to_bits().to_le_bytes() {
+                hash ^= *byte as u64;
+                hash = hash.wrapping_mul(0x0100_0000_01b3);
+            }
+        }
+    }
+    hash
+}
+
+fn run() -> u64 {
+    let mut sim = Simulation::new(World::new(1200.0, 900.0), SEED);
+    sim.respawn(BOIDS, SpawnPattern::Uniform);
+
+    let cursor = glam::vec2(-1000.0, -1000.0);
+    for _ in 0..STEPS {
+        sim.step(TICK, cursor);
+    }
+    hash_flock(&sim.boids)
+}
+
+#[test]
+fn same_seed_same_flock() {
+    assert_eq!(run(), run());
+}
+
+// The default build uses the platform's trig, which may round differently
+// elsewhere, so only the deterministic build has a golden value. The vector
+// kernel adds neighbors up in a different order and would not match it.
+#[cfg(all(feature = "deterministic", not(feature = "simd")))]
+#[test]
+fn matches_golden_hash() {
+    let hash = format!("{:016x}", run());
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join(GOLDEN);
+
+    if std::env::var_os("BOIDS_BLESS").is_some() {
+        fs::write(&path, format!("{}\n", hash)).expect("Failed to write the golden hash");
+        eprintln!("Recorded golden hash {} in {}", hash, GOLDEN);
+        return;
+    }
+
+    let golden = fs::read_to_string(&path)
+        .unwrap_or_else(|err| panic!("Failed to read {} ({}); record it with BOIDS_BLESS=1", GOLDEN, err));
+    assert_eq!(
+        hash,
+        golden.trim(),
+        "simulation results changed; if that was intended, rerun with BOIDS_BLESS=1"
+    );
+}